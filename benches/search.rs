@@ -0,0 +1,47 @@
+//! Pattern search benchmark: `cargo bench --bench search`
+//!
+//! Compares the shared search core against the naive `windows().position()`
+//! scan it replaced, on data where the pattern only appears at the very end.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use ehx::search;
+
+const SIZE: usize = 256 * 1024 * 1024;
+const ROUNDS: u32 = 3;
+
+fn naive_find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len()).position(|w| w == pattern)
+}
+
+fn measure(name: &str, mut f: impl FnMut() -> Option<usize>) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        black_box(f());
+        best = best.min(start.elapsed());
+    }
+    let mib_per_sec = SIZE as f64 / (1024.0 * 1024.0) / best.as_secs_f64();
+    println!("{:<10} {:>10.2?} {:>10.0} MiB/s", name, best, mib_per_sec);
+    best
+}
+
+fn main() {
+    // 0x00-0x7F only, so the pattern's 0xDE never appears before the end
+    let mut data: Vec<u8> = (0..SIZE).map(|i| (i % 251) as u8 & 0x7F).collect();
+    let pattern = b"\xDE\xAD\xBE\xEF\x00\x01";
+    data[SIZE - pattern.len()..].copy_from_slice(pattern);
+
+    println!(
+        "searching {} MiB for {} bytes",
+        SIZE / (1024 * 1024),
+        pattern.len()
+    );
+    let naive = measure("naive", || naive_find(black_box(&data), pattern));
+    let fast = measure("memmem", || search::find(black_box(&data), pattern, 0));
+    println!(
+        "speedup    {:>10.1}x",
+        naive.as_secs_f64() / fast.as_secs_f64()
+    );
+}
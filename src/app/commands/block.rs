@@ -0,0 +1,116 @@
+//! ブロックのコピーと移動のコマンド
+
+use super::{Command, CommandKind};
+use crate::app::{App, EditMode};
+
+impl App {
+    /// copy-to / move-to コマンド: 選択範囲を指定アドレスへ複製・移動する（1回の Undo で戻せる）
+    /// 挿入モードではアドレスに挿入、上書きモードではアドレスから上書きし、
+    /// move-to の移動元は fill バイトで埋める（ファイル長は変わらない）
+    fn cmd_block(&mut self, arg: &str, move_block: bool) {
+        let parts: Vec<&str> = arg.split_whitespace().collect();
+        let (dest, fill) = match parts.as_slice() {
+            [dest] => (*dest, Some(0u8)),
+            [dest, fill] if move_block => (*dest, Self::parse_byte(fill)),
+            _ => {
+                let usage = if move_block {
+                    "Usage: move-to <address> [fill byte]"
+                } else {
+                    "Usage: copy-to <address>"
+                };
+                self.status_message = Some(usage.to_string());
+                return;
+            }
+        };
+        let Some(fill) = fill else {
+            self.status_message = Some("Invalid byte value".to_string());
+            return;
+        };
+        let len = self.document.len();
+        let Some(dest) = Self::parse_address(dest)
+            .and_then(|addr| usize::try_from(addr).ok())
+            .filter(|&dest| dest <= len)
+        else {
+            self.status_message = Some("Invalid address".to_string());
+            return;
+        };
+        let Some((start, bytes)) = self.selected_bytes() else {
+            return;
+        };
+        let end = start + bytes.len();
+
+        // 移動先（挿入モードで移動元より後ろなら、削除した分だけ前にずれる）
+        let target = match self.edit_mode {
+            EditMode::Insert if move_block && dest > start && dest < end => {
+                self.status_message = Some("Destination is inside the selection".to_string());
+                return;
+            }
+            EditMode::Insert if move_block && dest >= end => dest - bytes.len(),
+            _ => dest,
+        };
+        if move_block && target == start {
+            self.status_message = Some(format!("Block is already at 0x{:X}", start));
+            return;
+        }
+
+        self.document.begin_group();
+        let result = match (self.edit_mode, move_block) {
+            (EditMode::Insert, false) => self.document.insert_bytes(dest, &bytes),
+            (EditMode::Insert, true) => self
+                .document
+                .delete_range(start, end)
+                .and_then(|_| self.document.insert_bytes(target, &bytes)),
+            (EditMode::Overwrite, _) => {
+                // 先に移動元を埋めてから書き込むので、重なった部分は移動先の内容になる
+                let result = if move_block {
+                    self.document.set_range(start, &vec![fill; bytes.len()])
+                } else {
+                    Ok(())
+                };
+                if result.is_ok() {
+                    self.write_bytes(dest, &bytes);
+                }
+                result
+            }
+        };
+        self.document.end_group();
+        if let Err(e) = result {
+            self.status_message = Some(format!("Edit failed: {}", e));
+            return;
+        }
+
+        self.clear_selection();
+        self.cursor = target.min(self.document.len());
+        self.ensure_cursor_visible();
+        let verb = if move_block { "Moved" } else { "Copied" };
+        self.status_message =
+            Some(format!("{} {} bytes to 0x{:X}", verb, bytes.len(), target));
+    }
+}
+
+/// ブロックのコマンド
+pub(super) const COMMANDS: &[Command] = &[
+    Command {
+        name: "copy-to",
+        aliases: &[],
+        help: "Copy the selection to an address (insert or overwrite per edit mode)",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "Copy block to address:".to_string(),
+            run: |app, arg| app.cmd_block(arg, false),
+        },
+    },
+    Command {
+        name: "move-to",
+        aliases: &[],
+        help: "Move the selection to an address (insert or overwrite per edit mode)",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |app| match app.edit_mode {
+                EditMode::Overwrite => "Move block to address ([byte] to fill source):".to_string(),
+                EditMode::Insert => "Move block to address:".to_string(),
+            },
+            run: |app, arg| app.cmd_block(arg, true),
+        },
+    },
+];
@@ -0,0 +1,107 @@
+//! チェックサムのコマンド
+
+use super::{Command, CommandKind};
+use crate::app::App;
+use crate::checksum::Algorithm;
+
+impl App {
+    /// checksum コマンド
+    /// `<algorithm> [copy]`: 選択範囲のチェックサムを表示（copy でクリップボードにも）
+    /// `write [le|be]`: 最後に計算した値をカーソル位置に書き込む（編集モードに従う）
+    fn cmd_checksum(&mut self, arg: &str) {
+        const USAGE: &str = "Usage: <algorithm> [copy] | write [le|be]";
+        let mut words = arg.split_whitespace().map(|w| w.to_ascii_lowercase());
+        let Some(first) = words.next() else {
+            self.status_message = Some(USAGE.to_string());
+            return;
+        };
+        if first == "write" {
+            let big_endian = match words.next().as_deref() {
+                None | Some("le") => false,
+                Some("be") => true,
+                Some(_) => {
+                    self.status_message = Some(USAGE.to_string());
+                    return;
+                }
+            };
+            self.write_checksum(big_endian);
+            return;
+        }
+        let algorithm = match Algorithm::parse(&first) {
+            Ok(algorithm) => algorithm,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                return;
+            }
+        };
+        let copy = match words.next().as_deref() {
+            None => false,
+            Some("copy") => true,
+            Some(_) => {
+                self.status_message = Some(USAGE.to_string());
+                return;
+            }
+        };
+        if !self.check_selection() {
+            return;
+        }
+        let Some((_, bytes)) = self.selected_bytes() else {
+            return;
+        };
+        let checksum = algorithm.compute(&bytes);
+        let hex = checksum.hex();
+        let mut message = format!("{} of {} bytes: {}", algorithm.name(), bytes.len(), hex);
+        self.last_checksum = Some(checksum);
+        if copy {
+            if !self.copy_to_clipboard(&hex) {
+                return;
+            }
+            message.push_str(" (copied)");
+        }
+        self.status_message = Some(message);
+    }
+
+    /// 最後に計算したチェックサムをカーソル位置に書き込む
+    fn write_checksum(&mut self, big_endian: bool) {
+        let Some(checksum) = self.last_checksum.clone() else {
+            self.status_message = Some("No checksum yet (run checksum <algorithm>)".to_string());
+            return;
+        };
+        if !self.check_writable() {
+            return;
+        }
+        self.clear_selection();
+        let pos = self.cursor;
+        self.write_bytes(pos, &checksum.to_bytes(big_endian));
+        let order = if !checksum.algorithm.is_integer() {
+            ""
+        } else if big_endian {
+            " (big-endian)"
+        } else {
+            " (little-endian)"
+        };
+        self.status_message = Some(format!(
+            "Wrote {} {} at {:08X}{}",
+            checksum.algorithm.name(),
+            checksum.hex(),
+            pos,
+            order
+        ));
+    }
+}
+
+/// チェックサムのコマンド
+pub(super) const COMMANDS: &[Command] = &[
+    Command {
+        name: "checksum",
+        aliases: &["hash"],
+        help: "Checksum of the selection (show, copy, or write at cursor)",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |_| {
+                format!("Checksum ({}) [copy]; or write [le|be]:", Algorithm::names().join("/"))
+            },
+            run: App::cmd_checksum,
+        },
+    },
+];
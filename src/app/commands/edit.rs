@@ -0,0 +1,480 @@
+//! 挿入・塗りつぶし・切り詰めなど、バイトを書き換えるコマンド
+
+use super::{Command, CommandKind};
+use crate::app::state::PromptMode;
+use crate::app::{App, EditMode};
+use crate::clipboard::HexFormat;
+use crate::encoding::{CharEncoding, Terminator};
+use crate::transform;
+
+impl App {
+    /// fill コマンド: 選択範囲を指定バイトで埋める
+    fn cmd_fill(&mut self, arg: &str) {
+        let arg = arg.trim();
+
+        // バイト値をパース
+        let byte = if arg.starts_with("0x") || arg.starts_with("0X") {
+            u8::from_str_radix(&arg[2..], 16).ok()
+        } else if arg.len() == 2 && arg.chars().all(|c| c.is_ascii_hexdigit()) {
+            u8::from_str_radix(arg, 16).ok()
+        } else {
+            arg.parse().ok()
+        };
+
+        let Some(byte) = byte else {
+            self.status_message = Some("Invalid byte value".to_string());
+            return;
+        };
+
+        let Some((start, end)) = self.selection else {
+            self.status_message = Some("No selection".to_string());
+            return;
+        };
+
+        // 選択範囲を埋める（EOF位置は除く）
+        let end = (end + 1).min(self.document.len());
+        let count = end.saturating_sub(start);
+        let _ = self.document.set_range(start, &vec![byte; count]);
+
+        self.status_message = Some(format!("Filled {} bytes with {:02X}", count, byte));
+        self.clear_selection();
+    }
+
+    /// insert コマンド: 指定サイズのバイトを挿入
+    fn cmd_insert(&mut self, arg: &str) {
+        // フォーマット: "count byte" or "count" (デフォルト 00)
+        let parts: Vec<&str> = arg.split_whitespace().collect();
+
+        let (count, byte) = match parts.len() {
+            1 => {
+                let count = Self::parse_number(parts[0]);
+                (count, Some(0u8))
+            }
+            2 => {
+                let count = Self::parse_number(parts[0]);
+                let byte = Self::parse_byte(parts[1]);
+                (count, byte)
+            }
+            _ => {
+                self.status_message = Some("Usage: insert <count> [byte]".to_string());
+                return;
+            }
+        };
+
+        let Some(count) = count else {
+            self.status_message = Some("Invalid count".to_string());
+            return;
+        };
+
+        let Some(byte) = byte else {
+            self.status_message = Some("Invalid byte value".to_string());
+            return;
+        };
+
+        if count == 0 {
+            self.status_message = Some("Count must be > 0".to_string());
+            return;
+        }
+
+        // カーソル位置に挿入
+        if let Err(e) = self.document.insert_fill(self.cursor, count, byte) {
+            self.status_message = Some(format!("Insert failed: {}", e));
+            return;
+        }
+
+        self.status_message = Some(format!("Inserted {} bytes of {:02X}", count, byte));
+    }
+
+    /// insert-string コマンド: 文字列を受け取り、エンコーディングと終端の入力に進む
+    fn cmd_insert_string(&mut self, text: &str) {
+        if text.is_empty() {
+            self.status_message = Some("No text".to_string());
+            return;
+        }
+        self.pending_string = Some(text.to_string());
+        self.current_command = Some(&INSERT_STRING_OPTIONS);
+        self.prompt_mode = PromptMode::CommandArg;
+        self.prompt_input.clear();
+    }
+
+    /// insert-string の続き: 指定のエンコーディング・終端でカーソル位置に書き込む
+    fn finish_insert_string(&mut self, arg: &str) {
+        let Some(text) = self.pending_string.take() else {
+            return;
+        };
+        let mut encoding = self.encoding;
+        let mut terminator = Terminator::None;
+        for word in arg.split_whitespace() {
+            if let Some(t) = Terminator::from_name(word) {
+                terminator = t;
+            } else if let Some(e) = CharEncoding::from_name(word) {
+                encoding = e;
+            } else {
+                self.status_message = Some(format!("Unknown encoding or terminator: {}", word));
+                return;
+            }
+        }
+        let Some(bytes) = self.encode_text(&text, encoding) else {
+            self.status_message = Some(format!("Text cannot be encoded in {}", encoding.name()));
+            return;
+        };
+        let Some(bytes) = terminator.apply(bytes, encoding) else {
+            self.status_message =
+                Some(format!("Text is too long for a {}", terminator.describe()));
+            return;
+        };
+        let pos = self.cursor;
+        self.write_bytes(pos, &bytes);
+        self.clear_selection();
+        self.cursor = (pos + bytes.len()).min(self.document.len());
+        self.ensure_cursor_visible();
+        let verb = match self.edit_mode {
+            EditMode::Overwrite => "Wrote",
+            EditMode::Insert => "Inserted",
+        };
+        self.status_message = Some(format!(
+            "{} {} bytes ({}, {})",
+            verb,
+            bytes.len(),
+            encoding.name(),
+            terminator.describe()
+        ));
+    }
+
+    /// random コマンド: 暗号論的乱数のバイト列をカーソル位置に書き込む（編集モードに従う）
+    fn cmd_random(&mut self, arg: &str) {
+        let count = match Self::parse_number(arg.trim()) {
+            Some(0) => {
+                self.status_message = Some("Count must be > 0".to_string());
+                return;
+            }
+            Some(count) => count,
+            None => {
+                self.status_message = Some("Invalid count".to_string());
+                return;
+            }
+        };
+        let bytes = match transform::random_bytes(count) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::OutOfMemory => {
+                self.status_message = Some(format!("Not enough memory for {} bytes", count));
+                return;
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Random source unavailable: {}", e));
+                return;
+            }
+        };
+        self.write_bytes(self.cursor, &bytes);
+        self.status_message = Some(match self.edit_mode {
+            EditMode::Overwrite => format!("Wrote {} random bytes", count),
+            EditMode::Insert => format!("Inserted {} random bytes", count),
+        });
+    }
+
+    /// pad コマンド: ファイル末尾（選択中なら選択範囲の終端）を fill バイトで埋めて
+    /// アラインメントの倍数、または `=size` の位置まで伸ばす
+    fn cmd_pad(&mut self, arg: &str) {
+        // フォーマット: "alignment [byte]" or "=size [byte]" (デフォルト 00)
+        let parts: Vec<&str> = arg.split_whitespace().collect();
+        let (target, byte) = match parts.as_slice() {
+            [target] => (*target, Some(0u8)),
+            [target, byte] => (*target, Self::parse_byte(byte)),
+            _ => {
+                self.status_message = Some("Usage: pad <alignment>|=<size> [byte]".to_string());
+                return;
+            }
+        };
+        let Some(byte) = byte else {
+            self.status_message = Some("Invalid byte value".to_string());
+            return;
+        };
+        let (absolute, number) = match target.strip_prefix('=') {
+            Some(size) => (true, Self::parse_number(size)),
+            None => (false, Self::parse_number(target)),
+        };
+        let Some(number) = number else {
+            self.status_message = Some("Invalid size".to_string());
+            return;
+        };
+        if !absolute && number == 0 {
+            self.status_message = Some("Alignment must be > 0".to_string());
+            return;
+        }
+
+        let len = self.document.len();
+        let end = self.selection.map_or(len, |(_, end)| (end + 1).min(len));
+        let target = if absolute { number } else { end.next_multiple_of(number) };
+        if target <= end {
+            self.status_message = Some(if target == end {
+                format!("Already at 0x{:X}", end)
+            } else {
+                format!("End 0x{:X} is already past 0x{:X}", end, target)
+            });
+            return;
+        }
+
+        let count = target - end;
+        let result = if end == len {
+            self.document.resize(target, byte)
+        } else {
+            self.document.insert_fill(end, count, byte)
+        };
+        if let Err(e) = result {
+            self.status_message = Some(format!("Pad failed: {}", e));
+            return;
+        }
+        self.clear_selection();
+        self.status_message = Some(format!(
+            "Padded {} bytes of {:02X} up to 0x{:X}",
+            count, byte, target
+        ));
+    }
+
+    /// truncate コマンド: 長さを変更（伸ばす場合は指定バイトで埋める）
+    fn cmd_truncate(&mut self, arg: &str) {
+        // フォーマット: "length byte" or "length" (デフォルト 00)
+        let parts: Vec<&str> = arg.split_whitespace().collect();
+
+        let (length, byte) = match parts.len() {
+            1 => (Self::parse_number(parts[0]), Some(0u8)),
+            2 => (Self::parse_number(parts[0]), Self::parse_byte(parts[1])),
+            _ => {
+                self.status_message = Some("Usage: truncate <length> [byte]".to_string());
+                return;
+            }
+        };
+
+        let Some(length) = length else {
+            self.status_message = Some("Invalid length".to_string());
+            return;
+        };
+
+        let Some(byte) = byte else {
+            self.status_message = Some("Invalid byte value".to_string());
+            return;
+        };
+
+        let old_len = self.document.len();
+        if let Err(e) = self.document.resize(length, byte) {
+            self.status_message = Some(format!("Truncate failed: {}", e));
+            return;
+        }
+
+        // カーソルと選択範囲を新しい長さに収める
+        self.cursor = self.cursor.min(length);
+        self.clear_selection();
+        self.ensure_cursor_visible();
+        self.status_message = Some(if length < old_len {
+            format!("Truncated to {} bytes (removed {})", length, old_len - length)
+        } else if length > old_len {
+            format!("Extended to {} bytes with {:02X}", length, byte)
+        } else {
+            format!("Length unchanged ({} bytes)", length)
+        });
+    }
+}
+
+/// insert-string の2段目（エンコーディングと終端を入力する。一覧には出さない）
+static INSERT_STRING_OPTIONS: Command = Command {
+    name: "insert-string",
+    aliases: &[],
+    help: "",
+    kind: CommandKind::Arg {
+        ready: |_| true,
+        prompt: |app| {
+            format!(
+                "Encoding and terminator (empty: {}; none/nul/len8/len16[be]/len32[be]):",
+                app.encoding.name()
+            )
+        },
+        run: App::finish_insert_string,
+    },
+};
+
+/// 編集のコマンド
+pub(super) const COMMANDS: &[Command] = &[
+    Command {
+        name: "copy-as",
+        aliases: &[],
+        help: "Copy the selection as a C/Rust array, Python bytes, C string, base64 or HEX",
+        kind: CommandKind::Arg {
+            ready: |app| {
+                if app.selection.is_none() {
+                    app.status_message = Some("No selection".to_string());
+                }
+                app.selection.is_some()
+            },
+            prompt: |_| {
+                let keys: Vec<&str> = HexFormat::ALL.iter().map(|f| f.key()).collect();
+                format!("Copy as ({}):", keys.join("/"))
+            },
+            run: |app, arg| match HexFormat::from_key(arg) {
+                Some(format) => app.copy_as(format),
+                None => app.status_message = Some(format!("Unknown format: {}", arg.trim())),
+            },
+        },
+    },
+    Command {
+        name: "copy-hex",
+        aliases: &[],
+        help: "Copy the selection (or the byte at the cursor) as spaced, compact, 0x or C HEX",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |_| {
+                let keys: Vec<&str> = HexFormat::HEX.iter().map(|f| f.key()).collect();
+                format!("Copy as HEX ({}; empty: hex):", keys.join("/"))
+            },
+            run: |app, arg| {
+                let format = if arg.trim().is_empty() {
+                    Some(HexFormat::Spaced)
+                } else {
+                    HexFormat::from_key(arg).filter(|f| HexFormat::HEX.contains(f))
+                };
+                match format {
+                    Some(format) => app.copy_hex(format),
+                    None => app.status_message = Some(format!("Unknown format: {}", arg.trim())),
+                }
+            },
+        },
+    },
+    Command {
+        name: "copy-as-base64",
+        aliases: &["b64"],
+        help: "Copy the selection as a base64 string",
+        kind: CommandKind::Run(|app| app.copy_as(HexFormat::Base64)),
+    },
+    Command {
+        name: "copy-as-text",
+        aliases: &[],
+        help: "Copy the selection decoded as text in the current encoding",
+        kind: CommandKind::Run(App::copy_as_text),
+    },
+    Command {
+        name: "paste-as",
+        aliases: &[],
+        help: "Paste the clipboard as raw text, HEX, base64 or an escaped string",
+        kind: CommandKind::Arg {
+            ready: App::prepare_paste_as,
+            prompt: |_| "Paste as (raw/hex/base64/string; empty: auto):".to_string(),
+            run: App::paste_as,
+        },
+    },
+    Command {
+        name: "delete-region",
+        aliases: &["dr"],
+        help: "Delete the selection without copying it to the clipboard",
+        kind: CommandKind::Run(|app| {
+            if app.check_selection() {
+                app.delete_region();
+            }
+        }),
+    },
+    Command {
+        name: "fill",
+        aliases: &["f"],
+        help: "Fill the selection with a byte",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "Fill with byte (hex):".to_string(),
+            run: App::cmd_fill,
+        },
+    },
+    Command {
+        name: "insert",
+        aliases: &["i"],
+        help: "Insert count bytes at cursor",
+        kind: CommandKind::Arg {
+            ready: App::check_writable,
+            prompt: |_| "Insert (count [byte]):".to_string(),
+            run: App::cmd_insert,
+        },
+    },
+    Command {
+        name: "insert-string",
+        aliases: &["istr"],
+        help: "Write text at cursor in a chosen encoding, optionally NUL/length-terminated",
+        kind: CommandKind::Arg {
+            ready: App::check_writable,
+            prompt: |_| "Insert string:".to_string(),
+            run: App::cmd_insert_string,
+        },
+    },
+    Command {
+        name: "random",
+        aliases: &[],
+        help: "Write count random bytes at cursor (per edit mode)",
+        kind: CommandKind::Arg {
+            ready: App::check_writable,
+            prompt: |_| "Random bytes (count):".to_string(),
+            run: App::cmd_random,
+        },
+    },
+    Command {
+        name: "truncate",
+        aliases: &["t"],
+        help: "Truncate or extend the file",
+        kind: CommandKind::Arg {
+            ready: App::check_writable,
+            prompt: |_| "New length ([byte] to pad):".to_string(),
+            run: App::cmd_truncate,
+        },
+    },
+    Command {
+        name: "pad",
+        aliases: &[],
+        help: "Pad the file (or selection end) to an alignment or =size",
+        kind: CommandKind::Arg {
+            ready: App::check_writable,
+            prompt: |_| "Pad to alignment or =size ([byte] to fill):".to_string(),
+            run: App::cmd_pad,
+        },
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::app::Action;
+    use crate::app::tests::app_with;
+
+    #[test]
+    fn test_truncate_and_undo() {
+        let mut app = app_with(b"abcdef");
+        app.cursor = 5;
+        app.cmd_truncate("2");
+        assert_eq!(app.document.data(), b"ab");
+        assert_eq!(app.cursor, 2);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Truncated to 2 bytes (removed 4)")
+        );
+        app.cmd_truncate("0x4 ff");
+        assert_eq!(app.document.data(), b"ab\xFF\xFF");
+        app.execute(Action::Undo);
+        app.execute(Action::Undo);
+        assert_eq!(app.document.data(), b"abcdef");
+
+        app.cmd_truncate("big");
+        assert_eq!(app.status_message.as_deref(), Some("Invalid length"));
+        app.cmd_truncate("2 zz");
+        assert_eq!(app.status_message.as_deref(), Some("Invalid byte value"));
+        app.document.set_readonly(true);
+        app.cmd_truncate("2");
+        assert_eq!(app.document.data(), b"abcdef");
+        assert!(app.status_message.as_deref().is_some_and(|m| m.starts_with("Truncate failed")));
+    }
+
+    #[test]
+    fn test_pad_selection() {
+        let mut app = app_with(b"abcdef");
+        app.selection = Some((0, 2));
+        app.cmd_pad("4 ff");
+        assert_eq!(app.document.data(), b"abc\xFFdef");
+
+        // 確保できない長さは異常終了せずにエラー
+        app.selection = Some((0, 2));
+        app.cmd_pad(&format!("={}", usize::MAX));
+        assert_eq!(app.document.data(), b"abc\xFFdef");
+        assert!(app.status_message.as_deref().is_some_and(|m| m.starts_with("Pad failed")));
+    }
+}
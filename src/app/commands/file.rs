@@ -0,0 +1,54 @@
+//! 保存・終了・比較のコマンド
+
+use super::{Command, CommandKind};
+use crate::app::state::{ConfirmMode, PromptMode};
+use crate::app::{Action, App};
+
+/// ファイルを扱うコマンド
+pub(super) const COMMANDS: &[Command] = &[
+    Command {
+        name: "save",
+        aliases: &["s"],
+        help: "Save",
+        kind: CommandKind::Run(|app| app.save_document(None, ConfirmMode::Off)),
+    },
+    Command {
+        name: "quit",
+        aliases: &["q"],
+        help: "Quit",
+        kind: CommandKind::Run(|app| app.execute(Action::Quit)),
+    },
+    Command {
+        name: "compare",
+        aliases: &[],
+        help: "Compare with another file side by side",
+        kind: CommandKind::Run(|app| {
+            app.prompt_mode = PromptMode::CompareFile;
+            app.prompt_input.clear();
+        }),
+    },
+    Command {
+        name: "compare-file",
+        aliases: &["baseline"],
+        help: "Highlight differences from a baseline file without splitting",
+        kind: CommandKind::Run(|app| {
+            app.prompt_mode = PromptMode::BaselineFile;
+            app.prompt_input.clear();
+        }),
+    },
+    Command {
+        name: "compare-off",
+        aliases: &[],
+        help: "Leave compare mode",
+        kind: CommandKind::Run(App::close_compare),
+    },
+    Command {
+        name: "backup",
+        aliases: &[],
+        help: "Cycle backup policy",
+        kind: CommandKind::Run(|app| {
+            let policy = app.config.backup.next();
+            app.set_backup_policy(policy);
+        }),
+    },
+];
@@ -0,0 +1,129 @@
+//! M-x コマンドの表と、その実行に使う処理（分野ごとにモジュールを分ける）
+
+mod block;
+mod checksum;
+mod edit;
+mod file;
+mod navigation;
+mod transform;
+mod view;
+
+use super::App;
+
+/// M-x コマンドの実行方法
+#[derive(Clone, Copy)]
+pub enum CommandKind {
+    /// そのまま実行
+    Run(fn(&mut App)),
+    /// 引数を入力してから実行
+    Arg {
+        /// 入力を始められるか（できなければ理由をステータスに出して false）
+        ready: fn(&mut App) -> bool,
+        /// 入力欄の見出し
+        prompt: fn(&App) -> String,
+        run: fn(&mut App, &str),
+    },
+}
+
+/// M-x コマンド
+pub struct Command {
+    pub name: &'static str,
+    /// 別名（省略形など）
+    pub aliases: &'static [&'static str],
+    /// 候補一覧とヘルプに表示する説明
+    pub help: &'static str,
+    pub kind: CommandKind,
+}
+
+impl Command {
+    /// 候補一覧などに表示する名前（別名付き）
+    pub fn label(&self) -> String {
+        if self.aliases.is_empty() {
+            self.name.to_string()
+        } else {
+            format!("{} ({})", self.name, self.aliases.join(" "))
+        }
+    }
+}
+
+/// 名前か別名が完全に一致するコマンド
+pub fn find_command(name: &str) -> Option<&'static Command> {
+    all().find(|command| command.name == name || command.aliases.contains(&name))
+}
+
+/// 入力にあいまいに一致するコマンド（よく一致する順、空なら全部）
+pub fn complete_commands(query: &str) -> Vec<&'static Command> {
+    let mut found: Vec<(usize, &Command)> = all()
+        .filter_map(|command| {
+            std::iter::once(command.name)
+                .chain(command.aliases.iter().copied())
+                .filter_map(|name| fuzzy_score(query, name))
+                .min()
+                .map(|score| (score, command))
+        })
+        .collect();
+    // 同じ点数なら表の順
+    found.sort_by_key(|&(score, _)| score);
+    found.into_iter().map(|(_, command)| command).collect()
+}
+
+/// あいまい一致の点数（小さいほどよく一致: 前方一致 < 部分一致 < 飛び飛びの一致）
+fn fuzzy_score(query: &str, name: &str) -> Option<usize> {
+    if name.starts_with(query) {
+        return Some(0);
+    }
+    if let Some(pos) = name.find(query) {
+        return Some(1 + pos);
+    }
+    // 入力の文字が順に含まれていれば、間に挟まった文字数を点数にする
+    let mut gaps = 0;
+    let mut chars = name.chars();
+    for q in query.chars() {
+        loop {
+            match chars.next() {
+                Some(c) if c == q => break,
+                Some(_) => gaps += 1,
+                None => return None,
+            }
+        }
+    }
+    Some(100 + gaps)
+}
+
+/// 分野ごとの表（候補一覧とヘルプはこの順に並ぶ）
+const TABLES: [&[Command]; 7] = [
+    file::COMMANDS,
+    navigation::COMMANDS,
+    edit::COMMANDS,
+    transform::COMMANDS,
+    checksum::COMMANDS,
+    block::COMMANDS,
+    view::COMMANDS,
+];
+
+/// M-x で実行できるコマンド（別名の解決・候補一覧・ヘルプはこの表から行う）
+pub(super) fn all() -> impl Iterator<Item = &'static Command> {
+    TABLES.iter().flat_map(|table| table.iter())
+}
+
+impl App {
+    /// 数値をパース（0x prefix または 10進数）
+    fn parse_number(s: &str) -> Option<usize> {
+        if s.starts_with("0x") || s.starts_with("0X") {
+            usize::from_str_radix(&s[2..], 16).ok()
+        } else {
+            s.parse().ok()
+        }
+    }
+
+    /// バイト値をパース
+    fn parse_byte(s: &str) -> Option<u8> {
+        if s.starts_with("0x") || s.starts_with("0X") {
+            u8::from_str_radix(&s[2..], 16).ok()
+        } else if s.len() <= 2 && s.chars().all(|c| c.is_ascii_hexdigit()) {
+            u8::from_str_radix(s, 16).ok()
+        } else {
+            s.parse().ok()
+        }
+    }
+}
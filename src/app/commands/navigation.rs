@@ -0,0 +1,88 @@
+//! 移動・検索・ブックマークのコマンド
+
+use super::{Command, CommandKind};
+use crate::app::state::{Panel, PromptMode};
+use crate::app::App;
+
+/// 移動のコマンド
+pub(super) const COMMANDS: &[Command] = &[
+    Command {
+        name: "goto",
+        aliases: &["g"],
+        help: "Go to address",
+        kind: CommandKind::Run(|app| {
+            app.prompt_mode = PromptMode::GotoAddress;
+            app.prompt_input.clear();
+        }),
+    },
+    Command {
+        name: "next-buffer",
+        aliases: &["next-tab"],
+        help: "Next buffer",
+        kind: CommandKind::Run(|app| app.switch_buffer(true)),
+    },
+    Command {
+        name: "prev-buffer",
+        aliases: &["previous-buffer", "prev-tab"],
+        help: "Previous buffer",
+        kind: CommandKind::Run(|app| app.switch_buffer(false)),
+    },
+    Command {
+        name: "search-value",
+        aliases: &["sv"],
+        help: "Search for a number as u8..u64 / i8..i64 / f32 / f64",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |_| "Search value (e.g. 500 u16, 0x1F4 u32be, 1.5 f32):".to_string(),
+            run: App::search_value,
+        },
+    },
+    Command {
+        name: "mark-all-matches",
+        aliases: &["mc"],
+        help: "Put a cursor at every search match",
+        kind: CommandKind::Run(App::mark_all_matches),
+    },
+    Command {
+        name: "next-change",
+        aliases: &[],
+        help: "Next unsaved change",
+        kind: CommandKind::Run(|app| app.goto_modified(true)),
+    },
+    Command {
+        name: "prev-change",
+        aliases: &[],
+        help: "Previous unsaved change",
+        kind: CommandKind::Run(|app| app.goto_modified(false)),
+    },
+    Command {
+        name: "next-diff",
+        aliases: &[],
+        help: "Next difference (compare)",
+        kind: CommandKind::Run(|app| app.goto_diff(true)),
+    },
+    Command {
+        name: "prev-diff",
+        aliases: &[],
+        help: "Previous difference (compare)",
+        kind: CommandKind::Run(|app| app.goto_diff(false)),
+    },
+    Command {
+        name: "set-bookmark",
+        aliases: &["bookmark"],
+        help: "Set / clear bookmark",
+        kind: CommandKind::Run(App::toggle_bookmark),
+    },
+    Command {
+        name: "jump-to-bookmark",
+        aliases: &[],
+        help: "Jump to next bookmark",
+        kind: CommandKind::Run(App::jump_bookmark),
+    },
+    Command {
+        name: "list-bookmarks",
+        aliases: &[],
+        help: "List bookmarks",
+        kind: CommandKind::Run(|app| app.open_panel(Panel::Bookmarks)),
+    },
+];
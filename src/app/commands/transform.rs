@@ -0,0 +1,346 @@
+//! 選択範囲のビット演算・バイト順の入れ替え・エンコードのコマンド
+
+use super::{Command, CommandKind};
+use crate::app::App;
+use crate::transform::{self, BitOp, Codec, Shift};
+
+/// inflate で展開できる最大のバイト数（圧縮率の極端なデータでメモリを使い果たさないため）
+const INFLATE_LIMIT: usize = 256 * 1024 * 1024;
+
+impl App {
+    /// 選択範囲を bytes で置き換えて選択を解除する（長さが変わっても1回の Undo で戻せる）
+    /// 戻り値: 書き換えられたか（失敗時はメッセージを出す）
+    fn replace_selection(&mut self, start: usize, old_len: usize, bytes: &[u8]) -> bool {
+        let result = if bytes.len() == old_len {
+            self.document.set_range(start, bytes)
+        } else {
+            self.document.begin_group();
+            let result = self
+                .document
+                .delete_range(start, start + old_len)
+                .and_then(|_| self.document.insert_bytes(start, bytes));
+            self.document.end_group();
+            result
+        };
+        self.clear_selection();
+        self.cursor = self.cursor.min(self.document.len());
+        self.ensure_cursor_visible();
+        if let Err(e) = result {
+            self.status_message = Some(format!("Edit failed: {}", e));
+            return false;
+        }
+        true
+    }
+
+    /// 選択範囲のバイト列を f で書き換える（1回の Undo で戻せる）
+    /// 戻り値: 書き換えたバイト数
+    fn transform_selection(&mut self, f: impl FnOnce(&mut [u8])) -> Option<usize> {
+        let Some((start, mut bytes)) = self.selected_bytes() else {
+            self.status_message = Some("No selection".to_string());
+            return None;
+        };
+        f(&mut bytes);
+        self.replace_selection(start, bytes.len(), &bytes).then_some(bytes.len())
+    }
+
+    /// xor / and / or コマンド: 選択範囲をキー（1バイトまたは繰り返すバイト列）で演算
+    fn cmd_bitwise(&mut self, arg: &str, op: BitOp) {
+        let key = match transform::parse_key(arg) {
+            Ok(key) => key,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                return;
+            }
+        };
+        let count = self.transform_selection(|bytes| transform::apply_key(bytes, &key, op));
+        if let Some(count) = count {
+            let key: Vec<String> = key.iter().map(|b| format!("{:02X}", b)).collect();
+            self.status_message =
+                Some(format!("{} {} bytes with {}", op.name(), count, key.join(" ")));
+        }
+    }
+
+    /// not コマンド: 選択範囲のビットを反転
+    fn cmd_not(&mut self) {
+        if self.check_selection()
+            && let Some(count) = self.transform_selection(transform::not)
+        {
+            self.status_message = Some(format!("Inverted {} bytes", count));
+        }
+    }
+
+    /// swap-nibbles コマンド: 選択範囲の各バイトの上位・下位ニブルを入れ替える
+    fn cmd_swap_nibbles(&mut self) {
+        if self.check_selection()
+            && let Some(count) = self.transform_selection(transform::swap_nibbles)
+        {
+            self.status_message = Some(format!("Swapped nibbles of {} bytes", count));
+        }
+    }
+
+    /// swap16 / swap32 / swap64 コマンド: size バイトごとにバイト順を入れ替える
+    fn cmd_swap(&mut self, size: usize) {
+        if !self.check_selection() {
+            return;
+        }
+        let Some((start, mut bytes)) = self.selected_bytes() else {
+            return;
+        };
+        if let Err(e) = transform::swap_bytes(&mut bytes, size) {
+            self.status_message = Some(e.to_string());
+            return;
+        }
+        if self.replace_selection(start, bytes.len(), &bytes) {
+            self.status_message =
+                Some(format!("Swapped {} bytes in {}-byte groups", bytes.len(), size));
+        }
+    }
+
+    /// shl / shr / rol / ror コマンド: 選択範囲の各バイトをシフト・ローテート
+    fn cmd_shift(&mut self, arg: &str, shift: Shift) {
+        let bits = match transform::parse_bits(arg) {
+            Ok(bits) => bits,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                return;
+            }
+        };
+        let (verb, direction) = match shift {
+            Shift::Left => ("Shifted", "left"),
+            Shift::Right => ("Shifted", "right"),
+            Shift::RotateLeft => ("Rotated", "left"),
+            Shift::RotateRight => ("Rotated", "right"),
+        };
+        let count = self.transform_selection(|bytes| transform::shift(bytes, shift, bits));
+        if let Some(count) = count {
+            self.status_message =
+                Some(format!("{} {} bytes {} by {}", verb, count, direction, bits));
+        }
+    }
+
+    /// base64-encode / base64-decode コマンド: 選択範囲を base64 で変換して置き換える
+    fn cmd_base64(&mut self, decode: bool) {
+        if !self.check_selection() {
+            return;
+        }
+        let Some((start, bytes)) = self.selected_bytes() else {
+            return;
+        };
+        let result = if decode {
+            transform::base64_decode(&bytes)
+        } else {
+            Ok(transform::base64_encode(&bytes))
+        };
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                return;
+            }
+        };
+        if self.replace_selection(start, bytes.len(), &output) {
+            let verb = if decode { "Decoded" } else { "Encoded" };
+            self.status_message = Some(format!(
+                "{} base64: {} bytes -> {} bytes",
+                verb,
+                bytes.len(),
+                output.len()
+            ));
+        }
+    }
+
+    /// deflate コマンド: 選択範囲を圧縮して置き換える（形式省略時は zlib）
+    fn cmd_deflate(&mut self, arg: &str) {
+        let codec = match Codec::parse(arg) {
+            Ok(codec) => codec.unwrap_or(Codec::Zlib),
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                return;
+            }
+        };
+        let Some((start, bytes)) = self.selected_bytes() else {
+            return;
+        };
+        let output = transform::compress(&bytes, codec);
+        if self.replace_selection(start, bytes.len(), &output) {
+            self.status_message = Some(format!(
+                "Deflated {}: {} bytes -> {} bytes",
+                codec.name(),
+                bytes.len(),
+                output.len()
+            ));
+        }
+    }
+
+    /// inflate コマンド: 選択範囲先頭の圧縮ストリームを展開して置き換える
+    /// （形式省略時はヘッダから判定、ストリームの後ろに続くバイトはそのまま残す）
+    fn cmd_inflate(&mut self, arg: &str) {
+        let Some((start, bytes)) = self.selected_bytes() else {
+            return;
+        };
+        let codec = match Codec::parse(arg) {
+            Ok(Some(codec)) => Ok(codec),
+            Ok(None) => Codec::detect(&bytes),
+            Err(e) => Err(e),
+        };
+        let result = codec.and_then(|codec| {
+            transform::decompress(&bytes, codec, INFLATE_LIMIT).map(|inflated| (codec, inflated))
+        });
+        let (codec, (output, consumed)) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                return;
+            }
+        };
+        if self.replace_selection(start, consumed, &output) {
+            let trailing = bytes.len() - consumed;
+            let mut message = format!(
+                "Inflated {}: {} bytes -> {} bytes",
+                codec.name(),
+                consumed,
+                output.len()
+            );
+            if trailing > 0 {
+                message.push_str(&format!(" ({} trailing bytes kept)", trailing));
+            }
+            self.status_message = Some(message);
+        }
+    }
+}
+
+/// 変換のコマンド
+pub(super) const COMMANDS: &[Command] = &[
+    Command {
+        name: "xor",
+        aliases: &[],
+        help: "XOR the selection with a byte or repeating hex key",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "XOR with key (hex):".to_string(),
+            run: |app, arg| app.cmd_bitwise(arg, BitOp::Xor),
+        },
+    },
+    Command {
+        name: "and",
+        aliases: &[],
+        help: "AND the selection with a byte or repeating hex mask",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "AND with mask (hex):".to_string(),
+            run: |app, arg| app.cmd_bitwise(arg, BitOp::And),
+        },
+    },
+    Command {
+        name: "or",
+        aliases: &[],
+        help: "OR the selection with a byte or repeating hex mask",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "OR with mask (hex):".to_string(),
+            run: |app, arg| app.cmd_bitwise(arg, BitOp::Or),
+        },
+    },
+    Command {
+        name: "not",
+        aliases: &[],
+        help: "Invert every bit of the selection",
+        kind: CommandKind::Run(App::cmd_not),
+    },
+    Command {
+        name: "swap16",
+        aliases: &["swap-pairs"],
+        help: "Swap byte order of each 2-byte group in the selection",
+        kind: CommandKind::Run(|app| app.cmd_swap(2)),
+    },
+    Command {
+        name: "swap-nibbles",
+        aliases: &["nibswap"],
+        help: "Swap the high and low nibbles of each selected byte",
+        kind: CommandKind::Run(App::cmd_swap_nibbles),
+    },
+    Command {
+        name: "swap32",
+        aliases: &[],
+        help: "Swap byte order of each 4-byte group in the selection",
+        kind: CommandKind::Run(|app| app.cmd_swap(4)),
+    },
+    Command {
+        name: "swap64",
+        aliases: &[],
+        help: "Swap byte order of each 8-byte group in the selection",
+        kind: CommandKind::Run(|app| app.cmd_swap(8)),
+    },
+    Command {
+        name: "shl",
+        aliases: &[],
+        help: "Shift each selected byte left",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "Shift left by bits (1-7):".to_string(),
+            run: |app, arg| app.cmd_shift(arg, Shift::Left),
+        },
+    },
+    Command {
+        name: "shr",
+        aliases: &[],
+        help: "Shift each selected byte right",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "Shift right by bits (1-7):".to_string(),
+            run: |app, arg| app.cmd_shift(arg, Shift::Right),
+        },
+    },
+    Command {
+        name: "rol",
+        aliases: &[],
+        help: "Rotate each selected byte left",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "Rotate left by bits (1-7):".to_string(),
+            run: |app, arg| app.cmd_shift(arg, Shift::RotateLeft),
+        },
+    },
+    Command {
+        name: "ror",
+        aliases: &[],
+        help: "Rotate each selected byte right",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "Rotate right by bits (1-7):".to_string(),
+            run: |app, arg| app.cmd_shift(arg, Shift::RotateRight),
+        },
+    },
+    Command {
+        name: "base64-encode",
+        aliases: &["b64e"],
+        help: "Replace the selection with its base64 encoding",
+        kind: CommandKind::Run(|app| app.cmd_base64(false)),
+    },
+    Command {
+        name: "base64-decode",
+        aliases: &["b64d"],
+        help: "Decode the selected base64 text into raw bytes",
+        kind: CommandKind::Run(|app| app.cmd_base64(true)),
+    },
+    Command {
+        name: "deflate",
+        aliases: &["compress"],
+        help: "Compress the selection (zlib, gzip, raw deflate or zstd)",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "Deflate format (zlib/gzip/raw/zstd; empty: zlib):".to_string(),
+            run: App::cmd_deflate,
+        },
+    },
+    Command {
+        name: "inflate",
+        aliases: &["decompress"],
+        help: "Decompress the stream at the start of the selection",
+        kind: CommandKind::Arg {
+            ready: App::check_selection,
+            prompt: |_| "Inflate format (zlib/gzip/raw/zstd; empty: detect):".to_string(),
+            run: App::cmd_inflate,
+        },
+    },
+];
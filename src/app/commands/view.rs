@@ -0,0 +1,187 @@
+//! 表示・パネル・エンコーディングの切り替えのコマンド
+
+use super::{Command, CommandKind};
+use crate::app::state::{expand_path, Panel};
+use crate::app::App;
+#[cfg(feature = "disasm")]
+use crate::disasm::Arch;
+use crate::ui::{ByteFormat, MinimapMode};
+
+impl App {
+    /// load-table コマンド
+    fn cmd_load_table(&mut self, arg: &str) {
+        let arg = arg.trim();
+        if arg.is_empty() {
+            self.status_message = Some("No file specified".to_string());
+            return;
+        }
+        let path = expand_path(arg);
+        self.status_message = Some(match self.load_char_table(&path) {
+            Ok(count) => format!("Loaded {} table entries from {}", count, path.display()),
+            Err(e) => format!("{}: {}", path.display(), e),
+        });
+    }
+}
+
+/// 表示のコマンド
+pub(super) const COMMANDS: &[Command] = &[
+    Command {
+        name: "undo-history",
+        aliases: &[],
+        help: "Undo history",
+        kind: CommandKind::Run(|app| app.open_panel(Panel::UndoHistory)),
+    },
+    Command {
+        name: "inspector",
+        aliases: &[],
+        help: "Data inspector",
+        kind: CommandKind::Run(|app| app.open_panel(Panel::Inspector)),
+    },
+    Command {
+        name: "strings",
+        aliases: &[],
+        help: "List strings in the file",
+        kind: CommandKind::Run(|app| app.open_panel(Panel::Strings)),
+    },
+    #[cfg(feature = "disasm")]
+    Command {
+        name: "disasm",
+        aliases: &[],
+        help: "Disassemble from the cursor (x86_64 / arm64 / arm / riscv64 / riscv32)",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |app| {
+                let names: Vec<&str> = Arch::ALL.iter().map(|arch| arch.name()).collect();
+                format!("Architecture ({}; empty: {}):", names.join("/"), app.disasm_arch.name())
+            },
+            run: |app, arg| {
+                let arg = if arg.trim().is_empty() { app.disasm_arch.name() } else { arg };
+                app.set_disasm_arch(arg)
+            },
+        },
+    },
+    Command {
+        name: "template",
+        aliases: &[],
+        help: "Parse with a binary template",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |_| "Template (file or name; empty: reopen):".to_string(),
+            run: App::load_template,
+        },
+    },
+    Command {
+        name: "minimap",
+        aliases: &[],
+        help: "Toggle the entropy minimap",
+        kind: CommandKind::Run(|app| app.toggle_minimap(MinimapMode::Entropy)),
+    },
+    Command {
+        name: "minimap-class",
+        aliases: &[],
+        help: "Toggle the byte-class minimap",
+        kind: CommandKind::Run(|app| app.toggle_minimap(MinimapMode::ByteClass)),
+    },
+    Command {
+        name: "group",
+        aliases: &[],
+        help: "Group hex bytes by 1 / 2 / 4 / 8",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |_| "Group bytes (1/2/4/8; empty: next):".to_string(),
+            run: App::set_group,
+        },
+    },
+    Command {
+        name: "set-encoding",
+        aliases: &["encoding"],
+        help: "Set the character encoding (F2 cycles)",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |_| "Encoding (e.g. sjis, utf-16le, cp437; empty: next):".to_string(),
+            run: App::set_encoding,
+        },
+    },
+    Command {
+        name: "load-table",
+        aliases: &["tbl"],
+        help: "Load a character table (.tbl) and use it as the encoding",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |_| "Table file:".to_string(),
+            run: App::cmd_load_table,
+        },
+    },
+    Command {
+        name: "radix",
+        aliases: &["addr-radix"],
+        help: "Toggle hex / decimal addresses",
+        kind: CommandKind::Run(App::toggle_addr_radix),
+    },
+    Command {
+        name: "base",
+        aliases: &[],
+        help: "Set the base address",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |app| format!("Address of {:08X} (empty: 0):", app.cursor),
+            run: App::set_base,
+        },
+    },
+    Command {
+        name: "base-off",
+        aliases: &[],
+        help: "Clear the base address",
+        kind: CommandKind::Run(|app| {
+            app.base = None;
+            app.status_message = Some("Base address cleared".to_string());
+        }),
+    },
+    Command {
+        name: "absolute-column",
+        aliases: &[],
+        help: "Toggle the file offset column",
+        kind: CommandKind::Run(|app| {
+            app.absolute_column = !app.absolute_column;
+            app.status_message = Some(if app.absolute_column {
+                "Offset column: on".to_string()
+            } else {
+                "Offset column: off".to_string()
+            });
+        }),
+    },
+    Command {
+        name: "hex",
+        aliases: &[],
+        help: "Show bytes in hex",
+        kind: CommandKind::Run(|app| app.set_byte_format(ByteFormat::Hex)),
+    },
+    Command {
+        name: "octal",
+        aliases: &["oct"],
+        help: "Show bytes in octal",
+        kind: CommandKind::Run(|app| app.set_byte_format(ByteFormat::Octal)),
+    },
+    Command {
+        name: "binary",
+        aliases: &["bin"],
+        help: "Show bytes in binary",
+        kind: CommandKind::Run(|app| app.set_byte_format(ByteFormat::Binary)),
+    },
+    Command {
+        name: "theme",
+        aliases: &[],
+        help: "Switch color theme",
+        kind: CommandKind::Arg {
+            ready: |_| true,
+            prompt: |app| format!("Theme ({}; empty: next):", app.theme_names().join(" ")),
+            run: App::set_theme,
+        },
+    },
+    Command {
+        name: "help",
+        aliases: &["?", "h"],
+        help: "Key and command reference",
+        kind: CommandKind::Run(|app| app.help = Some(0)),
+    },
+];
@@ -1,9 +1,13 @@
+mod commands;
 mod state;
 
+pub use commands::{complete_commands, find_command, Command, CommandKind};
 pub use state::App;
 
 use crossterm::event::KeyCode;
 
+use crate::clipboard::HexFormat;
+
 /// 編集モード
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum EditMode {
@@ -12,13 +16,13 @@ pub enum EditMode {
     Insert,
 }
 
-/// 入力状態（HEX入力は2桁で1バイト）
+/// 入力状態（HEX入力は2桁、8進は3桁、2進は8桁で1バイト）
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum InputState {
     #[default]
     Normal,
-    /// HEX入力の1桁目を入力済み
-    HexFirstDigit(u8),
+    /// 1バイト分の途中まで入力済み（入力した値, 桁数）
+    Digits(u8, usize),
 }
 
 /// プレフィックスキー状態（Emacs 2ストローク用）
@@ -28,6 +32,10 @@ pub enum PrefixKey {
     None,
     /// C-x を押した状態
     CtrlX,
+    /// C-x r を押した状態
+    CtrlXR,
+    /// C-x w を押した状態
+    CtrlXW,
 }
 
 /// アプリケーションアクション
@@ -42,6 +50,8 @@ pub enum Action {
     CursorDown,
     CursorLeft,
     CursorRight,
+    DigitLeft,      // HEXの1桁（ニブル）左へ
+    DigitRight,     // HEXの1桁（ニブル）右へ
     CursorHome,
     CursorEnd,
     PageUp,
@@ -57,6 +67,8 @@ pub enum Action {
     Backspace,
     ToggleMode,         // HEX <-> ASCII
     ToggleEditMode,     // Insert <-> Overwrite
+    Increment,          // C-x +: カーソル位置の値を増やす
+    Decrement,          // C-x -: カーソル位置の値を減らす
 
     // 選択
     StartSelection,
@@ -69,14 +81,20 @@ pub enum Action {
     SelectRight,
 
     // クリップボード
-    Copy,       // M-w: コピー
-    CopyHex,    // HEX形式でコピー
+    Copy,       // M-w: バイト列を文字列のままコピー
+    CopyHex,    // C-x h: HEX形式を選んでコピー
     Cut,        // C-w: カット (kill-region)
     Paste,      // C-y: ペースト
-    PasteHex,
+    PasteHex,   // C-x y: HEXとして解釈してペースト
+    PasteAs,            // M-y: 解釈の仕方を選んでペースト
+    EnterCtrlXW,        // C-x w を押した
+    CopyAs(HexFormat),  // C-x w c など: ソースコード形式でコピー
+    CopyAsText,         // C-x w t: 現在のエンコーディングでデコードした文字列をコピー
 
     // 表示
     ToggleEncoding,
+    ToggleByteFormat,   // HEX -> 8進 -> 2進
+    ToggleAddrRadix,    // アドレス表示 16進 <-> 10進
     SetBytesPerRow(usize),
 
     // 検索
@@ -96,6 +114,7 @@ pub enum Action {
     // プレフィックスキー
     EnterCtrlX,  // C-x を押した
     Cancel,      // C-g でキャンセル
+    Argument(usize), // M-0..M-9: 次のコマンドの数値引数
 
     // コマンド
     ExecuteCommand,  // M-x: コマンド実行
@@ -104,7 +123,26 @@ pub enum Action {
     StartGoto,   // M-g: アドレスジャンプ
     OpenFile,    // C-x C-f: ファイルを開く
     SaveAs,      // C-x C-w: 別名保存
+    InsertFile,  // C-x i: ファイルを挿入
     KillBuffer,  // C-x k: バッファを閉じる
+    NextBuffer,  // C-x →: 次のバッファ
+    PrevBuffer,  // C-x ←: 前のバッファ
+
+    // ブックマーク
+    EnterCtrlXR,    // C-x r を押した
+    SetBookmark,    // C-x r m: カーソル位置のブックマークを設定/解除
+    JumpBookmark,   // C-x r b: 次のブックマークへ
+    ListBookmarks,  // C-x r l: ブックマーク一覧
+
+    // パネル
+    Help,        // F1 / C-h: ヘルプ
+    UndoHistory, // C-x u: Undo履歴パネル
+    Inspector,   // M-i: データインスペクタ
+    Minimap,     // M-m: ミニマップでジャンプ先を選ぶ
+
+    // 比較モード
+    NextDiff,    // M-n: 次の差分
+    PrevDiff,    // M-p: 前の差分
 
     None,
 }
@@ -118,123 +156,310 @@ pub struct KeyMod {
 }
 
 impl Action {
+    /// バッファを変更するアクションかどうか（読み取り専用時は拒否）
+    pub fn is_edit(&self) -> bool {
+        matches!(
+            self,
+            Action::InputHex(_)
+                | Action::InputAscii(_)
+                | Action::Delete
+                | Action::Backspace
+                | Action::Cut
+                | Action::Paste
+                | Action::PasteHex
+                | Action::PasteAs
+                | Action::Undo
+                | Action::Redo
+                | Action::StartReplace
+                | Action::InsertFile
+                | Action::Increment
+                | Action::Decrement
+        )
+    }
+
     /// キーコードからアクションに変換（Emacsキーバインド）
     pub fn from_key(key: KeyCode, mods: KeyMod) -> Self {
-        let KeyMod { ctrl, shift, alt } = mods;
-
-        match (key, ctrl, alt, shift) {
-            // === Emacsプレフィックスキー ===
-            // C-x: プレフィックスキーモードへ
-            (KeyCode::Char('x'), true, false, false) => Action::EnterCtrlX,
-
-            // C-g: キャンセル
-            (KeyCode::Char('g'), true, false, false) => Action::Cancel,
-            (KeyCode::Esc, _, _, _) => Action::Cancel,
-
-            // === Emacsカーソル移動 ===
-            // Ctrl+F: 右
-            (KeyCode::Char('f'), true, false, false) => Action::CursorRight,
-            // Ctrl+B: 左
-            (KeyCode::Char('b'), true, false, false) => Action::CursorLeft,
-            // Ctrl+N: 下
-            (KeyCode::Char('n'), true, false, false) => Action::CursorDown,
-            // Ctrl+P: 上
-            (KeyCode::Char('p'), true, false, false) => Action::CursorUp,
-            // Ctrl+A: 行頭
-            (KeyCode::Char('a'), true, false, false) => Action::CursorHome,
-            // Ctrl+E: 行末
-            (KeyCode::Char('e'), true, false, false) => Action::CursorEnd,
-            // Ctrl+V: ページダウン
-            (KeyCode::Char('v'), true, false, false) => Action::PageDown,
-            // Alt+V: ページアップ
-            (KeyCode::Char('v'), false, true, false) => Action::PageUp,
-            // M-< : バッファ先頭
-            (KeyCode::Char('<'), false, true, _) => Action::GotoBeginning,
-            // M-> : バッファ末尾（EOF）
-            (KeyCode::Char('>'), false, true, _) => Action::GotoEnd,
-
-            // 矢印キー（修飾キーなし）
-            (KeyCode::Up, false, false, false) => Action::CursorUp,
-            (KeyCode::Down, false, false, false) => Action::CursorDown,
-            (KeyCode::Left, false, false, false) => Action::CursorLeft,
-            (KeyCode::Right, false, false, false) => Action::CursorRight,
-            // Shift+矢印キー: 選択
-            (KeyCode::Up, false, false, true) => Action::SelectUp,
-            (KeyCode::Down, false, false, true) => Action::SelectDown,
-            (KeyCode::Left, false, false, true) => Action::SelectLeft,
-            (KeyCode::Right, false, false, true) => Action::SelectRight,
-            (KeyCode::Home, _, _, _) => Action::CursorHome,
-            (KeyCode::End, _, _, _) => Action::CursorEnd,
-            (KeyCode::PageUp, _, _, _) => Action::PageUp,
-            (KeyCode::PageDown, _, _, _) => Action::PageDown,
-
-            // モード切替
-            (KeyCode::Tab, false, false, _) => Action::ToggleMode,
-            (KeyCode::Insert, false, false, _) => Action::ToggleEditMode,
-
-            // === Emacs編集 ===
-            // Ctrl+D: 削除（カーソル位置）
-            (KeyCode::Char('d'), true, false, false) => Action::Delete,
-            (KeyCode::Delete, false, false, _) => Action::Delete,
-            (KeyCode::Backspace, false, false, _) => Action::Backspace,
-
-            // === Emacsクリップボード ===
-            // Ctrl+Space: 選択開始（マーク設定）
-            (KeyCode::Char(' '), true, false, false) => Action::StartSelection,
-            // C-w: カット (kill-region)
-            (KeyCode::Char('w'), true, false, false) => Action::Cut,
-            // M-w: コピー (kill-ring-save)
-            (KeyCode::Char('w'), false, true, false) => Action::Copy,
-            // Ctrl+Y: ペースト (yank)
-            (KeyCode::Char('y'), true, false, false) => Action::Paste,
-
-            // Undo: C-u (ze style)
-            (KeyCode::Char('u'), true, false, false) => Action::Undo,
-            // Redo: C-/ (ze style)
-            (KeyCode::Char('/'), true, false, false) => Action::Redo,
-
-            // 検索: C-s (前方), C-r (後方)
-            (KeyCode::Char('s'), true, false, false) => Action::StartSearch,
-            (KeyCode::Char('r'), true, false, false) => Action::StartSearchBack,
-
-            // 置換: M-% (query-replace)
-            (KeyCode::Char('%'), false, true, _) => Action::StartReplace,
-
-            // コマンド: M-x
-            (KeyCode::Char('x'), false, true, false) => Action::ExecuteCommand,
-
-            // ジャンプ: M-g (goto-address)
-            (KeyCode::Char('g'), false, true, false) => Action::StartGoto,
-
-            // エンコーディング切替: F2
-            (KeyCode::F(2), false, false, _) => Action::ToggleEncoding,
-
-            _ => Action::None,
-        }
+        lookup(KEYMAP, key, mods).unwrap_or(Action::None)
     }
 
-    /// C-x の後のキーを処理
+    /// C-x の後のキーを処理（未定義のキーはキャンセル）
     pub fn from_key_after_ctrl_x(key: KeyCode, mods: KeyMod) -> Self {
-        let KeyMod { ctrl, .. } = mods;
-
-        match (key, ctrl) {
-            // C-x C-c: 終了
-            (KeyCode::Char('c'), true) => Action::Quit,
-            // C-x C-s: 保存
-            (KeyCode::Char('s'), true) => Action::Save,
-            // C-x C-f: ファイルを開く
-            (KeyCode::Char('f'), true) => Action::OpenFile,
-            // C-x C-w: 別名保存
-            (KeyCode::Char('w'), true) => Action::SaveAs,
-            // C-x k: バッファを閉じる
-            (KeyCode::Char('k'), false) => Action::KillBuffer,
-
-            // C-g: キャンセル
-            (KeyCode::Char('g'), true) => Action::Cancel,
-            (KeyCode::Esc, _) => Action::Cancel,
-
-            // その他は無効
-            _ => Action::Cancel,
+        lookup(CTRL_X_KEYMAP, key, mods).unwrap_or(Action::Cancel)
+    }
+
+    /// C-x r の後のキーを処理（未定義のキーはキャンセル）
+    pub fn from_key_after_ctrl_x_r(key: KeyCode, mods: KeyMod) -> Self {
+        lookup(CTRL_X_R_KEYMAP, key, mods).unwrap_or(Action::Cancel)
+    }
+
+    /// C-x w の後のキーを処理（未定義のキーはキャンセル）
+    pub fn from_key_after_ctrl_x_w(key: KeyCode, mods: KeyMod) -> Self {
+        lookup(CTRL_X_W_KEYMAP, key, mods).unwrap_or(Action::Cancel)
+    }
+}
+
+/// キーバインドの修飾キー（None なら押していてもいなくてもよい）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mods {
+    pub ctrl: Option<bool>,
+    pub alt: Option<bool>,
+    pub shift: Option<bool>,
+}
+
+impl Mods {
+    /// 修飾キーなし
+    const NONE: Self = Self { ctrl: Some(false), alt: Some(false), shift: Some(false) };
+    /// Ctrl / Alt なし（Shift は問わない）
+    const PLAIN: Self = Self { ctrl: Some(false), alt: Some(false), shift: None };
+    const CTRL: Self = Self { ctrl: Some(true), alt: Some(false), shift: Some(false) };
+    const ALT: Self = Self { ctrl: Some(false), alt: Some(true), shift: Some(false) };
+    /// Alt（Shift は問わない。M-< のように Shift で入力する記号用）
+    const META: Self = Self { ctrl: Some(false), alt: Some(true), shift: None };
+    const SHIFT: Self = Self { ctrl: Some(false), alt: Some(false), shift: Some(true) };
+    /// 修飾キーを問わない
+    const ANY: Self = Self { ctrl: None, alt: None, shift: None };
+
+    fn matches(self, mods: KeyMod) -> bool {
+        self.ctrl.is_none_or(|ctrl| ctrl == mods.ctrl)
+            && self.alt.is_none_or(|alt| alt == mods.alt)
+            && self.shift.is_none_or(|shift| shift == mods.shift)
+    }
+}
+
+/// キーバインドの1項目
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub code: KeyCode,
+    pub mods: Mods,
+    pub action: Action,
+    /// ヘルプに表示する説明
+    pub help: &'static str,
+}
+
+const fn bind(code: KeyCode, mods: Mods, action: Action, help: &'static str) -> Binding {
+    Binding { code, mods, action, help }
+}
+
+impl Binding {
+    /// キーの表示（例: "C-f", "M-<", "S-Up"）
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.mods.ctrl == Some(true) {
+            label.push_str("C-");
+        }
+        if self.mods.alt == Some(true) {
+            label.push_str("M-");
         }
+        if self.mods.shift == Some(true) {
+            label.push_str("S-");
+        }
+        match self.code {
+            KeyCode::Char(' ') => label.push_str("Space"),
+            KeyCode::Char(c) => label.push(c),
+            KeyCode::F(n) => label.push_str(&format!("F{}", n)),
+            KeyCode::PageUp => label.push_str("PgUp"),
+            KeyCode::PageDown => label.push_str("PgDn"),
+            code => label.push_str(&format!("{:?}", code)),
+        }
+        label
+    }
+}
+
+/// キーに対応するアクションを探す
+fn lookup(keymap: &[Binding], key: KeyCode, mods: KeyMod) -> Option<Action> {
+    keymap
+        .iter()
+        .find(|binding| binding.code == key && binding.mods.matches(mods))
+        .map(|binding| binding.action.clone())
+}
+
+/// キーバインド（上から順に探す。ヘルプもこの表から作る）
+pub const KEYMAP: &[Binding] = &[
+    // === Emacsプレフィックスキー ===
+    bind(KeyCode::Char('x'), Mods::CTRL, Action::EnterCtrlX, "Prefix key"),
+    bind(KeyCode::Char('g'), Mods::CTRL, Action::Cancel, "Cancel"),
+    bind(KeyCode::Esc, Mods::ANY, Action::Cancel, "Cancel"),
+    bind(KeyCode::Char('0'), Mods::ALT, Action::Argument(0), "Numeric argument"),
+    bind(KeyCode::Char('1'), Mods::ALT, Action::Argument(1), "Numeric argument"),
+    bind(KeyCode::Char('2'), Mods::ALT, Action::Argument(2), "Numeric argument"),
+    bind(KeyCode::Char('3'), Mods::ALT, Action::Argument(3), "Numeric argument"),
+    bind(KeyCode::Char('4'), Mods::ALT, Action::Argument(4), "Numeric argument"),
+    bind(KeyCode::Char('5'), Mods::ALT, Action::Argument(5), "Numeric argument"),
+    bind(KeyCode::Char('6'), Mods::ALT, Action::Argument(6), "Numeric argument"),
+    bind(KeyCode::Char('7'), Mods::ALT, Action::Argument(7), "Numeric argument"),
+    bind(KeyCode::Char('8'), Mods::ALT, Action::Argument(8), "Numeric argument"),
+    bind(KeyCode::Char('9'), Mods::ALT, Action::Argument(9), "Numeric argument"),
+    // === Emacsカーソル移動 ===
+    bind(KeyCode::Char('f'), Mods::CTRL, Action::CursorRight, "Cursor right"),
+    bind(KeyCode::Char('b'), Mods::CTRL, Action::CursorLeft, "Cursor left"),
+    bind(KeyCode::Char('n'), Mods::CTRL, Action::CursorDown, "Cursor down"),
+    bind(KeyCode::Char('p'), Mods::CTRL, Action::CursorUp, "Cursor up"),
+    bind(KeyCode::Char('a'), Mods::CTRL, Action::CursorHome, "Beginning of row"),
+    bind(KeyCode::Char('e'), Mods::CTRL, Action::CursorEnd, "End of row"),
+    bind(KeyCode::Char('v'), Mods::CTRL, Action::PageDown, "Page down"),
+    bind(KeyCode::Char('v'), Mods::ALT, Action::PageUp, "Page up"),
+    bind(KeyCode::Char('<'), Mods::META, Action::GotoBeginning, "Beginning of buffer"),
+    bind(KeyCode::Char('>'), Mods::META, Action::GotoEnd, "End of buffer (EOF)"),
+    // 矢印キー（修飾キーなし）
+    bind(KeyCode::Up, Mods::NONE, Action::CursorUp, "Cursor up"),
+    bind(KeyCode::Down, Mods::NONE, Action::CursorDown, "Cursor down"),
+    bind(KeyCode::Left, Mods::NONE, Action::CursorLeft, "Cursor left"),
+    bind(KeyCode::Right, Mods::NONE, Action::CursorRight, "Cursor right"),
+    bind(KeyCode::Left, Mods::CTRL, Action::DigitLeft, "Previous hex digit (nibble)"),
+    bind(KeyCode::Right, Mods::CTRL, Action::DigitRight, "Next hex digit (nibble)"),
+    // Shift+矢印キー: 選択
+    bind(KeyCode::Up, Mods::SHIFT, Action::SelectUp, "Select up"),
+    bind(KeyCode::Down, Mods::SHIFT, Action::SelectDown, "Select down"),
+    bind(KeyCode::Left, Mods::SHIFT, Action::SelectLeft, "Select left"),
+    bind(KeyCode::Right, Mods::SHIFT, Action::SelectRight, "Select right"),
+    bind(KeyCode::Home, Mods::ANY, Action::CursorHome, "Beginning of row"),
+    bind(KeyCode::End, Mods::ANY, Action::CursorEnd, "End of row"),
+    bind(KeyCode::PageDown, Mods::CTRL, Action::NextBuffer, "Next buffer (tab)"),
+    bind(KeyCode::PageUp, Mods::CTRL, Action::PrevBuffer, "Previous buffer (tab)"),
+    bind(KeyCode::PageUp, Mods::ANY, Action::PageUp, "Page up"),
+    bind(KeyCode::PageDown, Mods::ANY, Action::PageDown, "Page down"),
+    // モード切替
+    bind(KeyCode::Tab, Mods::PLAIN, Action::ToggleMode, "Switch HEX / ASCII column"),
+    bind(KeyCode::Insert, Mods::PLAIN, Action::ToggleEditMode, "Toggle insert / overwrite"),
+    // === Emacs編集 ===
+    bind(KeyCode::Char('d'), Mods::CTRL, Action::Delete, "Delete byte at cursor or selection"),
+    bind(KeyCode::Delete, Mods::PLAIN, Action::Delete, "Delete byte at cursor or selection"),
+    bind(
+        KeyCode::Backspace,
+        Mods::PLAIN,
+        Action::Backspace,
+        "Delete byte before cursor or selection",
+    ),
+    // === Emacsクリップボード ===
+    bind(KeyCode::Char(' '), Mods::CTRL, Action::StartSelection, "Set mark (start selection)"),
+    bind(KeyCode::Char('w'), Mods::CTRL, Action::Cut, "Cut (kill-region)"),
+    bind(KeyCode::Char('w'), Mods::ALT, Action::Copy, "Copy as text (kill-ring-save)"),
+    bind(KeyCode::Char('y'), Mods::CTRL, Action::Paste, "Paste (yank)"),
+    bind(KeyCode::Char('y'), Mods::ALT, Action::PasteAs, "Paste as raw / hex / base64 / string"),
+    // Undo / Redo (ze style)
+    bind(KeyCode::Char('u'), Mods::CTRL, Action::Undo, "Undo"),
+    bind(KeyCode::Char('/'), Mods::CTRL, Action::Redo, "Redo"),
+    // 検索・置換
+    bind(KeyCode::Char('s'), Mods::CTRL, Action::StartSearch, "Search forward"),
+    bind(KeyCode::Char('r'), Mods::CTRL, Action::StartSearchBack, "Search backward"),
+    bind(KeyCode::Char('%'), Mods::META, Action::StartReplace, "Query replace"),
+    // コマンド・ジャンプ
+    bind(KeyCode::Char('x'), Mods::ALT, Action::ExecuteCommand, "Run command (M-x)"),
+    bind(KeyCode::Char('g'), Mods::ALT, Action::StartGoto, "Go to address"),
+    // 比較モード
+    bind(KeyCode::Char('n'), Mods::ALT, Action::NextDiff, "Next difference (compare)"),
+    bind(KeyCode::Char('p'), Mods::ALT, Action::PrevDiff, "Previous difference (compare)"),
+    // パネル
+    bind(KeyCode::Char('i'), Mods::ALT, Action::Inspector, "Data inspector"),
+    bind(KeyCode::Char('m'), Mods::ALT, Action::Minimap, "Jump with the minimap"),
+    bind(KeyCode::F(1), Mods::PLAIN, Action::Help, "This help"),
+    bind(KeyCode::Char('h'), Mods::CTRL, Action::Help, "This help"),
+    // 表示
+    bind(KeyCode::F(2), Mods::PLAIN, Action::ToggleEncoding, "Cycle encoding"),
+    bind(KeyCode::F(3), Mods::PLAIN, Action::ToggleByteFormat, "Cycle hex / octal / binary"),
+    bind(KeyCode::F(4), Mods::PLAIN, Action::ToggleAddrRadix, "Toggle hex / decimal addresses"),
+];
+
+/// C-x の後のキー
+pub const CTRL_X_KEYMAP: &[Binding] = &[
+    bind(KeyCode::Char('c'), Mods::CTRL, Action::Quit, "Quit"),
+    bind(KeyCode::Char('s'), Mods::CTRL, Action::Save, "Save"),
+    bind(KeyCode::Char('f'), Mods::CTRL, Action::OpenFile, "Open file"),
+    bind(KeyCode::Char('w'), Mods::CTRL, Action::SaveAs, "Save as"),
+    bind(KeyCode::Char('i'), Mods::PLAIN, Action::InsertFile, "Insert file at cursor"),
+    bind(KeyCode::Char('k'), Mods::PLAIN, Action::KillBuffer, "Close buffer"),
+    bind(KeyCode::Right, Mods::ANY, Action::NextBuffer, "Next buffer"),
+    bind(KeyCode::Left, Mods::ANY, Action::PrevBuffer, "Previous buffer"),
+    bind(KeyCode::Char('u'), Mods::PLAIN, Action::UndoHistory, "Undo history"),
+    bind(KeyCode::Char('r'), Mods::PLAIN, Action::EnterCtrlXR, "Bookmark prefix"),
+    bind(KeyCode::Char('w'), Mods::PLAIN, Action::EnterCtrlXW, "Copy-as prefix"),
+    bind(KeyCode::Char('h'), Mods::PLAIN, Action::CopyHex, "Copy as HEX (choose format)"),
+    bind(KeyCode::Char('y'), Mods::PLAIN, Action::PasteHex, "Paste clipboard as HEX"),
+    bind(KeyCode::Char('+'), Mods::PLAIN, Action::Increment, "Increment value at cursor"),
+    bind(KeyCode::Char('-'), Mods::PLAIN, Action::Decrement, "Decrement value at cursor"),
+];
+
+/// C-x r の後のキー
+pub const CTRL_X_R_KEYMAP: &[Binding] = &[
+    bind(KeyCode::Char('m'), Mods::PLAIN, Action::SetBookmark, "Set / clear bookmark"),
+    bind(KeyCode::Char('b'), Mods::PLAIN, Action::JumpBookmark, "Jump to next bookmark"),
+    bind(KeyCode::Char('l'), Mods::PLAIN, Action::ListBookmarks, "List bookmarks"),
+];
+
+/// C-x w の後のキー
+pub const CTRL_X_W_KEYMAP: &[Binding] = &[
+    bind(KeyCode::Char('c'), Mods::PLAIN, Action::CopyAs(HexFormat::CArray), "Copy as C array"),
+    bind(
+        KeyCode::Char('r'),
+        Mods::PLAIN,
+        Action::CopyAs(HexFormat::RustArray),
+        "Copy as Rust array",
+    ),
+    bind(
+        KeyCode::Char('p'),
+        Mods::PLAIN,
+        Action::CopyAs(HexFormat::PythonBytes),
+        "Copy as Python bytes",
+    ),
+    bind(KeyCode::Char('s'), Mods::PLAIN, Action::CopyAs(HexFormat::CString), "Copy as C string"),
+    bind(KeyCode::Char('h'), Mods::PLAIN, Action::CopyAs(HexFormat::Spaced), "Copy as HEX"),
+    bind(
+        KeyCode::Char('x'),
+        Mods::PLAIN,
+        Action::CopyAs(HexFormat::Continuous),
+        "Copy as compact HEX",
+    ),
+    bind(
+        KeyCode::Char('0'),
+        Mods::PLAIN,
+        Action::CopyAs(HexFormat::Prefixed),
+        "Copy as 0x-prefixed HEX",
+    ),
+    bind(KeyCode::Char('b'), Mods::PLAIN, Action::CopyAs(HexFormat::Base64), "Copy as base64"),
+    bind(KeyCode::Char('t'), Mods::PLAIN, Action::CopyAsText, "Copy as decoded text"),
+];
+
+/// ヘルプに表示する行（キーバインドとコマンドの表から作る）
+pub fn help_lines() -> Vec<String> {
+    let mut lines = Vec::new();
+    for (title, prefix, keymap) in [
+        ("Keys", "", KEYMAP),
+        ("C-x prefix", "C-x ", CTRL_X_KEYMAP),
+        ("C-x r prefix (bookmarks)", "C-x r ", CTRL_X_R_KEYMAP),
+        ("C-x w prefix (copy as)", "C-x w ", CTRL_X_W_KEYMAP),
+    ] {
+        lines.push(title.to_string());
+        // 同じ説明のキーは最初に出てきた位置で1行にまとめる
+        let mut seen = Vec::new();
+        for binding in keymap {
+            if seen.contains(&binding.help) {
+                continue;
+            }
+            seen.push(binding.help);
+            let labels: Vec<String> = keymap
+                .iter()
+                .filter(|other| other.help == binding.help)
+                .map(|other| format!("{}{}", prefix, other.label()))
+                .collect();
+            lines.push(format!("  {:<16} {}", labels.join(" / "), binding.help));
+        }
+        lines.push(String::new());
+    }
+    lines.push("M-x commands".to_string());
+    for command in commands::all() {
+        lines.push(format!("  {:<20} {}", command.label(), command.help));
+    }
+    lines
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::App;
+
+    /// バイト列を開いた状態の App
+    pub(crate) fn app_with(data: &[u8]) -> App {
+        let mut app = App::new();
+        app.load_bytes(data.to_vec());
+        app
     }
 }
@@ -1,15 +1,21 @@
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
-    widgets::Paragraph,
+    widgets::{Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame,
 };
 
 use super::{Action, EditMode, InputState, KeyMod, PrefixKey};
+use super::{complete_commands, find_command, help_lines, Command, CommandKind};
 
 /// 置換モード状態
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -35,6 +41,14 @@ pub enum PromptMode {
     OpenFile,
     /// ファイルパス入力中（別名保存）
     SaveAs,
+    /// ファイルパス入力中（カーソル位置に挿入）
+    InsertFile,
+    /// インスペクタで選択した型の値を入力中
+    InspectorValue,
+    /// ファイルパス入力中（比較対象）
+    CompareFile,
+    /// ファイルパス入力中（差分を調べる基準。分割表示はしない）
+    BaselineFile,
     /// コマンド入力中 (M-x)
     Command,
     /// コマンド引数入力中
@@ -52,18 +66,137 @@ pub enum ConfirmMode {
     OpenFile(String),
     /// バッファを閉じる確認
     KillBuffer,
+    /// 巨大ファイルの読み込み確認（パス, サイズ）
+    LargeFile(PathBuf, u64),
+    /// スワップファイルからの復元確認（スワップファイルのパス）
+    RecoverSwap(PathBuf),
+    /// 他のプロセスが編集中のファイルを開いた（ファイルのパス）
+    Locked(PathBuf),
+}
+
+/// サイドパネル（開いている間はキー入力をパネルが受け取る）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    /// Undo履歴
+    UndoHistory,
+    /// データインスペクタ
+    Inspector,
+    /// ブックマーク一覧
+    Bookmarks,
+    /// ミニマップ（サイドパネルは出さず、右端の帯でブロックを選ぶ）
+    Minimap,
+    /// ファイル中の文字列の一覧
+    Strings,
+    /// テンプレートで解析した構造の木
+    Structure,
+    /// カーソル位置からの逆アセンブル
+    #[cfg(feature = "disasm")]
+    Disasm,
 }
-use crate::buffer::Document;
+
+use crate::buffer::{BackupPolicy, Document, EditKind, FileLock, SaveJob};
+use crate::checksum::Checksum;
 use crate::clipboard::{self, HexFormat};
-use crate::encoding::{self, CharEncoding};
-use crate::ui::{HexView, ViewMode};
+use crate::config::Config;
+#[cfg(feature = "disasm")]
+use crate::disasm::{self, Arch};
+use crate::encoding::{self, CharEncoding, CharTable, TableError};
+use crate::inspector::{self, Field};
+use crate::search::{self, Outcome, SearchJob};
+use crate::session::{FileState, Sessions};
+use crate::strings::{self, StringsJob};
+use crate::template::{self, Node, Template};
+use crate::transform;
+use crate::ui::{
+    self, ByteFormat, CommandPalette, HelpView, HexView, Minimap, MinimapMode, SidePanel,
+    StatusField, StatusPart, TabBar, Theme, ViewMode, BUILTIN_THEMES, GROUP_SIZES,
+};
+
+/// 保存を一度に書き込むバイト数（これ以上は分割して進捗を表示する）
+const SAVE_CHUNK: usize = 4 * 1024 * 1024;
+
+/// これより広い範囲の検索は別スレッドで行う
+const ASYNC_SEARCH_SIZE: usize = 16 * 1024 * 1024;
+
+/// 検索の一致箇所に置ける複数カーソルの最大数
+const MAX_CURSORS: usize = 10_000;
+
+/// 実行中の保存
+struct SaveTask {
+    /// 書き込みの進行状況
+    job: SaveJob,
+    /// 保存前のスワップファイル（完了時に消す）
+    swap: Option<PathBuf>,
+    /// 完了時のメッセージ
+    message: String,
+    /// 完了後に実行する確認済みのアクション
+    after: ConfirmMode,
+}
+
+/// 別スレッドで実行中の検索
+struct SearchTask {
+    job: SearchJob,
+    /// 結果をメッセージで知らせる場合の前置き（インクリメンタル検索では None）
+    report: Option<String>,
+}
+
+/// ミニマップの色（内容・大きさ・色分けが変わったら作り直す）
+struct MinimapCache {
+    revision: u64,
+    blocks: usize,
+    mode: MinimapMode,
+    theme: Theme,
+    colors: Vec<ratatui::style::Color>,
+}
+
+/// 文字列一覧の抽出（別スレッドで進め、パネル表示中に tick() で結果を受け取る）
+struct StringsTask {
+    /// 抽出を始めたときのリビジョン（編集されたらやり直す）
+    revision: u64,
+    job: StringsJob,
+    /// このオフセット以降の最初の文字列が届くまで、届くたびに選択し直す
+    select_from: Option<usize>,
+}
+
+/// テンプレートで解析した構造（パネルを開くたびに解析し直す）
+struct StructureTree {
+    /// テンプレートの名前（パネルの見出し）
+    name: String,
+    template: Template,
+    nodes: Vec<Node>,
+    /// 折りたたんだノードの番号
+    collapsed: BTreeSet<usize>,
+}
+
+/// 非アクティブなバッファの状態（アクティブなバッファの分は App のフィールドにある）
+struct BufferState {
+    document: Document,
+    cursor: usize,
+    offset: usize,
+    bytes_per_row: usize,
+    selection: Option<(usize, usize)>,
+    selection_start: Option<usize>,
+    encoding: CharEncoding,
+    swap_revision: Option<u64>,
+    lock: Option<FileLock>,
+    bookmarks: Vec<usize>,
+    base: Option<i128>,
+}
 
 /// アプリケーション状態
 pub struct App {
+    /// ユーザー設定
+    pub(super) config: Config,
     /// 編集中のドキュメント
-    document: Document,
+    pub(super) document: Document,
+    /// 読み取り専用モード（-r）
+    readonly: bool,
+    /// 開くファイルに使うエンコーディング（--encoding、前回の状態より優先）
+    forced_encoding: Option<CharEncoding>,
     /// カーソル位置
-    cursor: usize,
+    pub(super) cursor: usize,
+    /// 複数カーソル：主カーソル以外のカーソルの主カーソルからの距離（昇順）
+    cursors: Vec<isize>,
     /// 表示オフセット
     offset: usize,
     /// 1行あたりのバイト数
@@ -72,79 +205,221 @@ pub struct App {
     visible_rows: usize,
     /// HEX/ASCIIモード
     hex_mode: bool,
+    /// バイト値の表示形式（HEX / 8進 / 2進）
+    byte_format: ByteFormat,
+    /// HEX表示で空白で区切るまとまりのバイト数
+    group: usize,
+    /// アドレス表示の基数（16 or 10）
+    addr_radix: u8,
+    /// 基準アドレス（表示するアドレス = ファイルオフセット + base）
+    pub(super) base: Option<i128>,
+    /// 基準アドレスがあるときにファイルオフセットの列も表示するか
+    pub(super) absolute_column: bool,
+    /// ミニマップの色分け（None なら表示しない）
+    minimap: Option<MinimapMode>,
+    /// ミニマップの色のキャッシュ
+    minimap_cache: Option<MinimapCache>,
+    /// 文字列一覧の抽出結果
+    strings: Option<StringsTask>,
+    /// 逆アセンブルの命令セット
+    #[cfg(feature = "disasm")]
+    pub(super) disasm_arch: Arch,
+    /// 読み込んだテンプレートと解析結果
+    structure: Option<StructureTree>,
     /// 編集モード
-    edit_mode: EditMode,
+    pub(super) edit_mode: EditMode,
     /// 入力状態
     input_state: InputState,
     /// プレフィックスキー状態（C-x等）
     prefix_key: PrefixKey,
+    /// M-数字で入力中の数値引数
+    prefix_arg: Option<usize>,
+    /// 最後に計算したチェックサム（checksum write で書き込む）
+    pub(super) last_checksum: Option<Checksum>,
     /// 選択範囲
-    selection: Option<(usize, usize)>,
+    pub(super) selection: Option<(usize, usize)>,
     /// 選択開始位置
     selection_start: Option<usize>,
     /// 文字エンコーディング
-    encoding: CharEncoding,
+    pub(super) encoding: CharEncoding,
+    /// 読み込んだ文字テーブル（エンコーディング "Table" で使う、全バッファ共通）
+    char_table: Option<CharTable>,
     /// 終了フラグ
     should_quit: bool,
     /// ステータスメッセージ
-    status_message: Option<String>,
+    pub(super) status_message: Option<String>,
     /// 検索モード
     search_mode: bool,
     /// 検索クエリ（入力中の文字列）
     search_query: String,
+    /// 検索を絞り込んでいる範囲（検索開始時の選択範囲）
+    search_region: Option<(usize, usize)>,
     /// 前回の検索クエリ（検索再利用用）
     last_search_query: String,
     /// 検索開始位置（検索キャンセル時に戻る位置）
     search_start_pos: usize,
+    /// 後方へインクリメンタル検索中（C-r で開始/切り替え）
+    search_backward: bool,
+    /// 検索クエリをテキストとして扱う（HEX判定しない）
+    search_literal: bool,
+    /// テキストの検索クエリをバッファのエンコーディングで変換する（I-search 中に M-e で切り替え）
+    search_encoded: bool,
     /// 置換モード
     replace_mode: ReplaceMode,
     /// 置換先パターン
     replace_with: String,
     /// プロンプト入力モード
-    prompt_mode: PromptMode,
+    pub(super) prompt_mode: PromptMode,
     /// プロンプト入力内容
-    prompt_input: String,
+    pub(super) prompt_input: String,
     /// 確認モード
     confirm_mode: ConfirmMode,
-    /// 実行中のコマンド名（引数入力用）
-    current_command: String,
+    /// 引数を入力中のコマンド
+    pub(super) current_command: Option<&'static Command>,
+    /// 2段目の入力を待つコマンドの文字列
+    /// （insert-string で入力した文字列、paste-as で読んだクリップボードの内容）
+    pub(super) pending_string: Option<String>,
+    /// 最後に M-w / C-w でクリップボードに置いた文字列と元のバイト列
+    /// 同じ文字列をペーストしたときは元のバイト列に戻す
+    last_copy: Option<(String, Vec<u8>)>,
+    /// M-x の候補一覧で選択中の行
+    command_selected: usize,
+    /// 表示中のサイドパネル
+    panel: Option<Panel>,
+    /// パネルの選択行
+    panel_selected: usize,
+    /// ヘルプの表示位置（None なら表示しない）
+    pub(super) help: Option<usize>,
+    /// 最後に自動保存を確認した時刻
+    last_autosave: Instant,
+    /// スワップファイルに書き出した時点のリビジョン（未作成なら None）
+    swap_revision: Option<u64>,
+    /// 実行中の保存
+    saving: Option<SaveTask>,
+    /// 実行中の検索
+    searching: Option<SearchTask>,
+    /// 編集中のファイルのロック
+    lock: Option<FileLock>,
+    /// ファイルごとの状態と最近開いたファイル
+    sessions: Sessions,
+    /// ファイルを開くプロンプトで選択中の最近のファイル
+    recent_index: Option<usize>,
+    /// 非アクティブなバッファ（次に切り替わる順）
+    buffers: VecDeque<BufferState>,
+    /// タブの並びでのアクティブなバッファの位置（buffers は次のタブから順に並ぶ）
+    buffer_index: usize,
+    /// 前回描画したタブバーの位置（マウスのクリック用）
+    tab_bar_area: Option<Rect>,
+    /// 起動時にまだ開いていないファイル（パス, mmapで開くか）
+    pending_files: Vec<(PathBuf, bool)>,
+    /// 比較モードの相手（右側に表示する読み取り専用のドキュメント）
+    compare: Option<Document>,
+    /// 比較対象を左右に並べて表示する（false なら差分の強調と移動だけ）
+    compare_split: bool,
+    /// ブックマークしたオフセット（昇順）
+    bookmarks: Vec<usize>,
+    /// 配色テーマ（名前, 配色）
+    theme: (String, Theme),
+    /// HEX 列のバイト値ごとの色（テーマと byte_colors の規則から作る）
+    byte_colors: [Color; 256],
 }
 
 impl App {
     /// 新しいアプリケーションを作成
     pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    /// 設定を指定してアプリケーションを作成
+    pub fn with_config(config: Config) -> Self {
+        let mut document = Document::new();
+        document.set_backup_policy(config.backup);
+        // テーマが読めなければデフォルトの配色で起動して知らせる
+        let (theme, theme_error) = match Theme::resolve(&config.theme, &config.themes) {
+            Ok(theme) => ((config.theme.clone(), theme), None),
+            Err(e) => (("dark".to_string(), Theme::DARK), Some(e.to_string())),
+        };
+        let (byte_colors, byte_colors_error) = match theme.1.byte_colors(&config.byte_colors) {
+            Ok(colors) => (colors, None),
+            Err(e) => (theme.1.byte_colors(&[]).unwrap_or([theme.1.hex_normal; 256]), Some(e)),
+        };
+        let group = if GROUP_SIZES.contains(&config.group) { config.group } else { 1 };
         Self {
-            document: Document::new(),
+            config,
+            document,
+            readonly: false,
+            forced_encoding: None,
             cursor: 0,
+            cursors: Vec::new(),
             offset: 0,
             bytes_per_row: 16,
             visible_rows: 24,
             hex_mode: true,
+            byte_format: ByteFormat::Hex,
+            group,
+            addr_radix: 16,
+            base: None,
+            absolute_column: true,
+            minimap: None,
+            minimap_cache: None,
+            strings: None,
+            #[cfg(feature = "disasm")]
+            disasm_arch: Arch::default(),
+            structure: None,
             edit_mode: EditMode::Overwrite,
             input_state: InputState::Normal,
             prefix_key: PrefixKey::None,
+            prefix_arg: None,
+            last_checksum: None,
             selection: None,
             selection_start: None,
             encoding: CharEncoding::Utf8,
+            char_table: None,
             should_quit: false,
-            status_message: None,
+            status_message: theme_error.or(byte_colors_error.map(|e| e.to_string())),
             search_mode: false,
             search_query: String::new(),
             last_search_query: String::new(),
             search_start_pos: 0,
+            search_backward: false,
+            search_literal: false,
+            search_encoded: false,
+            search_region: None,
             replace_mode: ReplaceMode::Off,
             replace_with: String::new(),
             prompt_mode: PromptMode::Off,
             prompt_input: String::new(),
             confirm_mode: ConfirmMode::Off,
-            current_command: String::new(),
+            current_command: None,
+            pending_string: None,
+            last_copy: None,
+            command_selected: 0,
+            panel: None,
+            panel_selected: 0,
+            help: None,
+            last_autosave: Instant::now(),
+            swap_revision: None,
+            saving: None,
+            searching: None,
+            lock: None,
+            sessions: Sessions::default(),
+            recent_index: None,
+            buffers: VecDeque::new(),
+            buffer_index: 0,
+            tab_bar_area: None,
+            pending_files: Vec::new(),
+            compare: None,
+            compare_split: false,
+            bookmarks: Vec::new(),
+            theme,
+            byte_colors,
         }
     }
 
     /// 全角英数記号（U+FF01〜U+FF5E）を半角（U+0021〜U+007E）に変換
     fn normalize_fullwidth(c: char) -> char {
         let cp = c as u32;
-        if cp >= 0xFF01 && cp <= 0xFF5E {
+        if (0xFF01..=0xFF5E).contains(&cp) {
             char::from_u32(cp - 0xFF00 + 0x20).unwrap_or(c)
         } else if c == '　' {
             ' ' // 全角スペース → 半角スペース
@@ -154,20 +429,914 @@ impl App {
     }
 
     /// ファイルを開く
+    /// サイズがしきい値を超える場合は読み込まずに確認モードに入る
     pub fn open(&mut self, path: impl Into<PathBuf>) -> Result<()> {
-        self.document = Document::open(path)?;
-        self.cursor = 0;
-        self.offset = 0;
-        self.selection = None;
+        let path = path.into();
+        let size = file_size(&path)?;
+        let threshold = self.config.large_file_threshold;
+        if threshold > 0 && size > threshold {
+            self.confirm_mode = ConfirmMode::LargeFile(path, size);
+            return Ok(());
+        }
+        self.set_document(Document::open(path)?);
+        self.check_lock();
+        Ok(())
+    }
+
+    /// ファイルをメモリマップで開く（読み取り専用）
+    pub fn open_mmap(&mut self, path: impl Into<PathBuf>) -> Result<()> {
+        self.set_document(Document::open_mmap(path)?);
         Ok(())
     }
 
     /// バイト列から読み込み（標準入力用）
     pub fn load_bytes(&mut self, data: Vec<u8>) {
-        self.document = Document::from_bytes(data);
+        self.set_document(Document::from_bytes(data));
+    }
+
+    /// ドキュメントを差し替えてカーソル等を初期化
+    fn set_document(&mut self, mut document: Document) {
+        if self.readonly {
+            document.set_readonly(true);
+        }
+        document.set_backup_policy(self.config.backup);
+        // 差し替える前のドキュメントの編集は破棄されるのでスワップも消す
+        self.remove_swap();
+        self.record_session();
+        self.lock = None;
+        self.document = document;
+        self.last_autosave = Instant::now();
         self.cursor = 0;
+        self.cursors.clear();
         self.offset = 0;
         self.selection = None;
+        self.selection_start = None;
+        self.bookmarks.clear();
+        self.base = None;
+        self.minimap_cache = None;
+        self.strings = None;
+        self.restore_session();
+    }
+
+    /// ファイルごとの状態を設定（起動時に読み込んだもの）
+    pub fn set_sessions(&mut self, sessions: Sessions) {
+        self.sessions = sessions;
+    }
+
+    /// 現在のファイルの状態を記録
+    fn record_session(&mut self) {
+        let Some(path) = self.document.path() else {
+            return;
+        };
+        let state = FileState {
+            path: path.clone(),
+            cursor: self.cursor,
+            offset: self.offset,
+            bytes_per_row: self.bytes_per_row,
+            encoding: self.encoding.name().to_string(),
+            bookmarks: self.bookmarks.clone(),
+        };
+        self.sessions.update(state);
+    }
+
+    /// 終了処理: 全バッファのスワップファイルを消し、状態をセッションファイルに書き出す
+    pub fn shutdown(&mut self) -> Result<(), crate::session::SessionError> {
+        self.remove_swap();
+        self.record_session();
+        for buffer in std::mem::take(&mut self.buffers) {
+            self.put_buffer(buffer);
+            self.remove_swap();
+            self.record_session();
+        }
+        self.sessions.save()
+    }
+
+    /// 複数のファイルをそれぞれのバッファに開く（最初のファイルがアクティブになる）
+    /// 確認が必要なファイルがあれば、確認後に tick() から残りを開く
+    pub fn open_files(&mut self, paths: Vec<PathBuf>, mmap: bool) -> Result<()> {
+        // 後ろから開き、開くたびに前のバッファをリングの先頭へ送る
+        self.pending_files = paths.into_iter().map(|path| (path, mmap)).collect();
+        self.open_pending()
+    }
+
+    /// 保留中のファイルを確認待ちになるまで開く
+    fn open_pending(&mut self) -> Result<()> {
+        while self.confirm_mode == ConfirmMode::Off
+            && let Some((path, mmap)) = self.pending_files.pop()
+        {
+            // 名前のない未変更のバッファは残さない
+            if self.document.path().is_some() || self.document.is_modified() {
+                let current = self.take_buffer();
+                self.buffers.push_front(current);
+            }
+            if mmap {
+                self.open_mmap(path)?;
+            } else {
+                self.open(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 比較モードを開始する（現在のバッファと path を並べて表示）
+    pub fn open_compare(&mut self, path: impl Into<PathBuf>) -> Result<()> {
+        let path = path.into();
+        self.load_compare(&path, true)?;
+        let count = self.count_diffs();
+        self.status_message = Some(format!(
+            "Comparing with {} - {} differing bytes (M-n/M-p: next/prev diff)",
+            path.display(),
+            count
+        ));
+        Ok(())
+    }
+
+    /// path を基準に読み込み、分割表示せずに異なるバイトを強調する
+    fn open_baseline(&mut self, path: &Path) -> Result<()> {
+        self.load_compare(path, false)?;
+        let count = self.count_diffs();
+        self.status_message = Some(format!(
+            "Baseline {} - {} differing bytes (M-n/M-p: next/prev diff)",
+            path.display(),
+            count
+        ));
+        Ok(())
+    }
+
+    /// 比較対象を読み込む
+    fn load_compare(&mut self, path: &Path, split: bool) -> Result<()> {
+        let mut document = Document::open(path)?;
+        document.set_readonly(true);
+        self.compare = Some(document);
+        self.compare_split = split;
+        Ok(())
+    }
+
+    /// 比較モードを終了する
+    pub(super) fn close_compare(&mut self) {
+        if self.compare.take().is_some() {
+            self.status_message = Some("Compare off".to_string());
+        }
+    }
+
+    /// 比較対象と異なるバイト数
+    fn count_diffs(&self) -> usize {
+        let Some(ref other) = self.compare else {
+            return 0;
+        };
+        let (a, b) = (self.document.data(), other.data());
+        let common = a.len().min(b.len());
+        let differing = a[..common].iter().zip(&b[..common]).filter(|(x, y)| x != y).count();
+        differing + a.len().max(b.len()) - common
+    }
+
+    /// 次（forward = false で前）の差分の先頭へ移動
+    pub(super) fn goto_diff(&mut self, forward: bool) {
+        let Some(ref other) = self.compare else {
+            self.status_message = Some("Not comparing (M-x compare)".to_string());
+            return;
+        };
+        let (a, b) = (self.document.data(), other.data());
+        let end = a.len().max(b.len());
+        let differs = |pos: usize| a.get(pos) != b.get(pos);
+
+        let found = if forward {
+            // 現在の差分の続きを飛ばしてから次の差分を探す
+            let mut pos = self.cursor;
+            while pos < end && differs(pos) {
+                pos += 1;
+            }
+            (pos..end).find(|&pos| differs(pos))
+        } else {
+            // 手前の差分を探し、その差分の先頭まで戻る
+            let mut pos = self.cursor;
+            while pos > 0 && differs(pos - 1) {
+                pos -= 1;
+            }
+            (0..pos).rev().find(|&pos| differs(pos)).map(|mut pos| {
+                while pos > 0 && differs(pos - 1) {
+                    pos -= 1;
+                }
+                pos
+            })
+        };
+
+        match found {
+            Some(pos) => {
+                self.cursor = pos.min(self.document.len());
+                self.ensure_cursor_visible();
+                self.status_message = Some(format!("Diff at {:08X}", pos));
+            }
+            None => {
+                self.status_message = Some(if forward {
+                    "No more differences".to_string()
+                } else {
+                    "No previous differences".to_string()
+                });
+            }
+        }
+    }
+
+    /// 次（forward = false で前）の未保存の変更箇所の先頭へ移動
+    pub(super) fn goto_modified(&mut self, forward: bool) {
+        let ranges = self.document.modified_ranges();
+        if ranges.is_empty() {
+            self.status_message = Some("No unsaved changes".to_string());
+            return;
+        }
+        let found = if forward {
+            ranges.iter().find(|&&(start, _)| start > self.cursor)
+        } else {
+            // カーソルが変更箇所の中にあれば、その先頭より前を探す
+            let current = ranges
+                .iter()
+                .find(|&&(start, end)| start <= self.cursor && self.cursor < end)
+                .map_or(self.cursor, |&(start, _)| start);
+            ranges.iter().rev().find(|&&(start, _)| start < current)
+        };
+
+        match found {
+            Some(&(start, end)) => {
+                self.cursor = start.min(self.document.len());
+                self.ensure_cursor_visible();
+                self.status_message =
+                    Some(format!("Modified at {:08X} ({} bytes)", start, end - start));
+            }
+            None => {
+                self.status_message = Some(if forward {
+                    "No more changes".to_string()
+                } else {
+                    "No previous changes".to_string()
+                });
+            }
+        }
+    }
+
+    /// アクティブなバッファを取り出す（空のバッファが残る）
+    fn take_buffer(&mut self) -> BufferState {
+        self.cursors.clear();
+        let mut document = Document::new();
+        document.set_backup_policy(self.config.backup);
+        BufferState {
+            document: std::mem::replace(&mut self.document, document),
+            cursor: std::mem::take(&mut self.cursor),
+            offset: std::mem::take(&mut self.offset),
+            bytes_per_row: self.bytes_per_row,
+            selection: self.selection.take(),
+            selection_start: self.selection_start.take(),
+            encoding: self.encoding,
+            swap_revision: self.swap_revision.take(),
+            lock: self.lock.take(),
+            bookmarks: std::mem::take(&mut self.bookmarks),
+            base: self.base.take(),
+        }
+    }
+
+    /// バッファをアクティブにする
+    fn put_buffer(&mut self, buffer: BufferState) {
+        self.document = buffer.document;
+        self.cursor = buffer.cursor;
+        self.offset = buffer.offset;
+        self.bytes_per_row = buffer.bytes_per_row;
+        self.selection = buffer.selection;
+        self.selection_start = buffer.selection_start;
+        self.encoding = buffer.encoding;
+        self.swap_revision = buffer.swap_revision;
+        self.lock = buffer.lock;
+        self.bookmarks = buffer.bookmarks;
+        self.base = buffer.base;
+        self.minimap_cache = None;
+        self.strings = None;
+        self.last_autosave = Instant::now();
+        self.panel = None;
+        self.ensure_cursor_visible();
+    }
+
+    /// 次のバッファへ切り替える（forward = false で前のバッファ）
+    pub(super) fn switch_buffer(&mut self, forward: bool) {
+        let next = if forward {
+            self.buffers.pop_front()
+        } else {
+            self.buffers.pop_back()
+        };
+        let Some(next) = next else {
+            self.status_message = Some("No other buffers".to_string());
+            return;
+        };
+        let current = self.take_buffer();
+        let count = self.buffers.len() + 2;
+        if forward {
+            self.buffers.push_back(current);
+            self.buffer_index = (self.buffer_index + 1) % count;
+        } else {
+            self.buffers.push_front(current);
+            self.buffer_index = (self.buffer_index + count - 1) % count;
+        }
+        self.put_buffer(next);
+        self.status_message = Some(format!(
+            "Buffer: {}",
+            self.document.filename().unwrap_or("*scratch*")
+        ));
+    }
+
+    /// タブの並びで index 番目のバッファに切り替える
+    fn select_buffer(&mut self, index: usize) {
+        let count = self.buffers.len() + 1;
+        for _ in 0..(index + count - self.buffer_index) % count {
+            self.switch_buffer(true);
+        }
+    }
+
+    /// タブの名前（タブの並び順、変更ありの印つき）
+    fn tab_titles(&self) -> Vec<String> {
+        let title = |document: &Document| {
+            let name = document.filename().unwrap_or("*scratch*");
+            if document.is_modified() { format!("{}[+]", name) } else { name.to_string() }
+        };
+        // アクティブなバッファ, 次のタブ, ... の並びを回転させる
+        let mut titles: Vec<String> = std::iter::once(&self.document)
+            .chain(self.buffers.iter().map(|buffer| &buffer.document))
+            .map(title)
+            .collect();
+        titles.rotate_right(self.buffer_index);
+        titles
+    }
+
+    /// タブバーを表示するか
+    fn show_tab_bar(&self) -> bool {
+        self.config.tab_bar && !self.buffers.is_empty()
+    }
+
+    /// マウスモードか
+    pub fn mouse_enabled(&self) -> bool {
+        self.config.mouse
+    }
+
+    /// マウスの操作（タブのクリック、ホイールでのスクロール）
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let busy = self.search_mode
+            || self.replace_mode != ReplaceMode::Off
+            || self.prompt_mode != PromptMode::Off
+            || self.confirm_mode != ConfirmMode::Off
+            || self.help.is_some();
+        if busy {
+            return;
+        }
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(area) = self.tab_bar_area
+                    && mouse.row == area.y
+                    && let Some(index) = TabBar::new(&self.tab_titles())
+                        .selected(self.buffer_index)
+                        .tab_at(area.width, mouse.column.saturating_sub(area.x))
+                {
+                    self.select_buffer(index);
+                }
+            }
+            MouseEventKind::ScrollDown if self.panel.is_none() => {
+                for _ in 0..3 {
+                    self.cursor_down();
+                }
+            }
+            MouseEventKind::ScrollUp if self.panel.is_none() => {
+                for _ in 0..3 {
+                    self.cursor_up();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// カーソル位置のブックマークを設定（既にあれば解除）
+    pub(super) fn toggle_bookmark(&mut self) {
+        match self.bookmarks.binary_search(&self.cursor) {
+            Ok(index) => {
+                self.bookmarks.remove(index);
+                self.status_message = Some(format!("Bookmark removed at {:08X}", self.cursor));
+            }
+            Err(index) => {
+                self.bookmarks.insert(index, self.cursor);
+                self.status_message = Some(format!(
+                    "Bookmark set at {:08X} ({} total)",
+                    self.cursor,
+                    self.bookmarks.len()
+                ));
+            }
+        }
+    }
+
+    /// カーソルより後ろの次のブックマークへ移動（末尾からは先頭に戻る）
+    pub(super) fn jump_bookmark(&mut self) {
+        let Some(&first) = self.bookmarks.first() else {
+            self.status_message = Some("No bookmarks (C-x r m to set)".to_string());
+            return;
+        };
+        let next = self
+            .bookmarks
+            .iter()
+            .copied()
+            .find(|&pos| pos > self.cursor)
+            .unwrap_or(first);
+        let index = self.bookmarks.binary_search(&next).unwrap_or(0);
+        self.cursor = next.min(self.document.len());
+        self.ensure_cursor_visible();
+        self.status_message = Some(format!(
+            "Bookmark {}/{} at {:08X}",
+            index + 1,
+            self.bookmarks.len(),
+            self.cursor
+        ));
+    }
+
+    /// 前回開いたときの状態を復元
+    fn restore_session(&mut self) {
+        let Some(state) = self.document.path().and_then(|path| self.sessions.get(path)) else {
+            return;
+        };
+        let state = state.clone();
+        if state.bytes_per_row > 0 {
+            self.bytes_per_row = state.bytes_per_row;
+        }
+        // 文字テーブルは保存しないので、読み込んでいなければ前回の "Table" は使わない
+        if self.forced_encoding.is_none()
+            && let Some(encoding) = CharEncoding::from_name(&state.encoding)
+            && (encoding != CharEncoding::Custom || self.char_table.is_some())
+        {
+            self.encoding = encoding;
+        }
+        let len = self.document.len();
+        self.cursor = state.cursor.min(len);
+        self.offset = state.offset.min(len) / self.row_bytes() * self.row_bytes();
+        self.bookmarks = state.bookmarks.into_iter().filter(|&pos| pos < len).collect();
+        self.ensure_cursor_visible();
+    }
+
+    /// 選べるテーマの名前（組み込み、設定ファイルで定義したものの順）
+    pub(super) fn theme_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|name| name.to_string()).collect();
+        for name in self.config.themes.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+
+    /// 1行に表示するバイト数（2進表示は幅が広いので最大8バイトにする）
+    fn row_bytes(&self) -> usize {
+        if self.byte_format == ByteFormat::Binary {
+            self.bytes_per_row.min(8)
+        } else {
+            self.bytes_per_row
+        }
+    }
+
+    /// バイト値の表示形式を切り替える
+    pub(super) fn set_byte_format(&mut self, format: ByteFormat) {
+        self.finish_hex_input();
+        self.byte_format = format;
+        // 1行のバイト数が変わることがあるので表示位置を合わせ直す
+        self.offset = self.offset / self.row_bytes() * self.row_bytes();
+        self.ensure_cursor_visible();
+        self.status_message = Some(format!("Byte format: {}", format.name()));
+    }
+
+    /// アドレス表示の基数を切り替える（16進 <-> 10進）
+    pub(super) fn toggle_addr_radix(&mut self) {
+        self.addr_radix = if self.addr_radix == 16 { 10 } else { 16 };
+        self.status_message = Some(if self.addr_radix == 16 {
+            "Addresses: hex".to_string()
+        } else {
+            "Addresses: decimal".to_string()
+        });
+    }
+
+    /// アドレスを表示用の基数で文字列にする
+    fn format_addr(&self, addr: usize) -> String {
+        if self.addr_radix == 16 {
+            format!("{:08X}", addr)
+        } else {
+            format!("{:010}", addr)
+        }
+    }
+
+    /// 基準アドレスを設定する（カーソル位置が input のアドレスになる、空なら 0）
+    pub(super) fn set_base(&mut self, input: &str) {
+        let input = input.trim();
+        let addr = if input.is_empty() {
+            0
+        } else {
+            match Self::parse_address(input) {
+                Some(addr) => addr,
+                None => {
+                    self.status_message = Some(format!("Invalid address: {}", input));
+                    return;
+                }
+            }
+        };
+        self.base = Some(addr as i128 - self.cursor as i128);
+        self.status_message = Some(format!(
+            "Base: {:08X} is {:X} (M-x base-off to reset)",
+            self.cursor, addr
+        ));
+    }
+
+    /// カーソル位置の表示（基準アドレスがあれば「アドレス (ファイルオフセット)」）
+    fn format_cursor(&self) -> String {
+        match self.base {
+            Some(base) => {
+                let value = self.cursor as i128 + base;
+                let sign = if value < 0 { "-" } else { "" };
+                let relative = if self.addr_radix == 16 {
+                    format!("{}{:08X}", sign, value.unsigned_abs())
+                } else {
+                    format!("{}{:010}", sign, value.unsigned_abs())
+                };
+                format!("{} ({})", relative, self.format_addr(self.cursor))
+            }
+            None => self.format_addr(self.cursor),
+        }
+    }
+
+    /// グループのバイト数を設定（空なら次の大きさ）
+    pub(super) fn set_group(&mut self, arg: &str) {
+        let group = match arg.trim() {
+            "" => {
+                let index = GROUP_SIZES.iter().position(|&g| g == self.group).map_or(0, |i| i + 1);
+                GROUP_SIZES[index % GROUP_SIZES.len()]
+            }
+            arg => match arg.parse::<usize>() {
+                Ok(group) if GROUP_SIZES.contains(&group) => group,
+                _ => {
+                    self.status_message = Some(format!("Group must be 1, 2, 4 or 8: {}", arg));
+                    return;
+                }
+            },
+        };
+        self.group = group;
+        self.status_message = Some(format!("Group: {} byte{}", group, if group == 1 { "" } else { "s" }));
+    }
+
+    /// 文字エンコーディングを切り替える（空なら F2 と同じく次のエンコーディング）
+    /// 文字テーブルは読み込んであるときだけ選べる
+    pub(super) fn set_encoding(&mut self, arg: &str) {
+        let encoding = match arg.trim() {
+            "" => match self.encoding.next() {
+                CharEncoding::Custom if self.char_table.is_none() => CharEncoding::Custom.next(),
+                encoding => encoding,
+            },
+            arg => match CharEncoding::from_name(arg) {
+                Some(CharEncoding::Custom) if self.char_table.is_none() => {
+                    self.status_message =
+                        Some("No character table loaded (M-x load-table)".to_string());
+                    return;
+                }
+                Some(encoding) => encoding,
+                None => {
+                    self.status_message = Some(format!("Unknown encoding: {}", arg));
+                    return;
+                }
+            },
+        };
+        self.encoding = encoding;
+        self.status_message = Some(format!("Encoding: {}", encoding.name()));
+    }
+
+    /// 文字テーブル（.tbl）を読み込み、現在のバッファをそれで表示する
+    pub fn load_char_table(&mut self, path: &Path) -> Result<usize, TableError> {
+        let table = CharTable::load(path)?;
+        let count = table.len();
+        self.char_table = Some(table);
+        self.encoding = CharEncoding::Custom;
+        Ok(count)
+    }
+
+    /// テキストを encoding でバイト列に変換（文字テーブルは読み込んだもので、変換できなければ None）
+    pub(super) fn encode_text(&self, text: &str, encoding: CharEncoding) -> Option<Vec<u8>> {
+        match (encoding, &self.char_table) {
+            (CharEncoding::Custom, Some(table)) => table.encode(text),
+            _ => encoding::encode_string_strict(text, encoding),
+        }
+    }
+
+    /// バイト列を現在のエンコーディングで文字列に変換
+    fn decode_text(&self, bytes: &[u8]) -> String {
+        match (self.encoding, &self.char_table) {
+            (CharEncoding::Custom, Some(table)) => table.decode(bytes),
+            _ => encoding::decode_bytes(bytes, self.encoding),
+        }
+    }
+
+    /// テーマを切り替える（空なら次のテーマ）
+    pub(super) fn set_theme(&mut self, name: &str) {
+        let name = match name.trim() {
+            "" => {
+                let names = self.theme_names();
+                let index = names.iter().position(|n| *n == self.theme.0).map_or(0, |i| i + 1);
+                names[index % names.len()].clone()
+            }
+            name => name.to_string(),
+        };
+        match Theme::resolve(&name, &self.config.themes) {
+            Ok(theme) => {
+                self.status_message = Some(format!("Theme: {}", name));
+                // 規則が読めなければテーマの色だけで分類
+                self.byte_colors = theme
+                    .byte_colors(&self.config.byte_colors)
+                    .or_else(|_| theme.byte_colors(&[]))
+                    .unwrap_or([theme.hex_normal; 256]);
+                self.theme = (name, theme);
+            }
+            Err(e) => self.status_message = Some(e.to_string()),
+        }
+    }
+
+    /// 読み取り専用モードを設定
+    pub fn set_readonly(&mut self, readonly: bool) {
+        self.readonly = readonly;
+        self.document.set_readonly(readonly || self.document.is_mapped());
+    }
+
+    /// 開くファイルのエンコーディングを固定する（現在のバッファにもすぐ反映）
+    pub fn set_forced_encoding(&mut self, encoding: Option<CharEncoding>) {
+        self.forced_encoding = encoding;
+        if let Some(encoding) = encoding {
+            self.encoding = encoding;
+        }
+    }
+
+    /// ドキュメントの保存を開始する（パス指定時は別名保存）
+    /// 大きなファイルは tick() で少しずつ書き込み、完了後に after を実行する
+    pub(super) fn save_document(&mut self, path: Option<&Path>, after: ConfirmMode) {
+        let swap = self.document.swap_path();
+        let message = match path {
+            Some(path) => format!("Saved: {}", path.display()),
+            None => "Saved".to_string(),
+        };
+        // 別名保存のパスは書き込みが完了したときにドキュメントへ反映される
+        let job = match path {
+            Some(path) => self.document.begin_save_as(path.to_path_buf()),
+            None => self.document.begin_save(),
+        };
+        match job {
+            Ok(job) => {
+                self.saving = Some(SaveTask {
+                    job,
+                    swap,
+                    message,
+                    after,
+                });
+                self.step_save();
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Save failed: {}", e));
+            }
+        }
+    }
+
+    /// 保存を1チャンク進める。完了したらスワップファイルを消して後続の処理を実行
+    fn step_save(&mut self) {
+        let Some(mut task) = self.saving.take() else {
+            return;
+        };
+        match self.document.continue_save(&mut task.job, SAVE_CHUNK) {
+            Ok(false) => self.saving = Some(task),
+            Ok(true) => {
+                // 保存でファイルが置き換わったのでロックを取り直す
+                self.lock = None;
+                self.lock = FileLock::acquire(task.job.target()).ok().flatten();
+                if self.swap_revision.take().is_some()
+                    && let Some(swap) = task.swap
+                {
+                    let _ = std::fs::remove_file(swap);
+                }
+                self.status_message = Some(task.message);
+                if task.after != ConfirmMode::Off {
+                    self.confirm_mode = task.after;
+                    self.execute_confirmed_action();
+                }
+            }
+            Err(e) => {
+                // 中断された保存の一時ファイルは SaveJob の破棄時に消える
+                self.status_message = Some(format!("Save failed: {}", e));
+            }
+        }
+    }
+
+    /// 定期処理（メインループから呼ぶ）
+    /// 未保存の変更があれば一定間隔でスワップファイルに書き出す
+    pub fn tick(&mut self) {
+        if self.saving.is_some() {
+            self.step_save();
+            return;
+        }
+        self.poll_search();
+
+        // 確認が済んだら起動時に指定された残りのファイルを開く
+        if self.confirm_mode == ConfirmMode::Off
+            && !self.pending_files.is_empty()
+            && let Err(e) = self.open_pending()
+        {
+            self.status_message = Some(format!("Failed to open: {}", e));
+        }
+
+        if self.panel == Some(Panel::Strings) {
+            self.step_strings();
+        }
+
+        let interval = self.config.autosave_interval;
+        if interval == 0
+            || !self.document.is_modified()
+            || self.document.is_readonly()
+            || self.last_autosave.elapsed() < Duration::from_secs(interval)
+        {
+            return;
+        }
+        self.last_autosave = Instant::now();
+
+        let revision = self.document.revision();
+        if self.swap_revision == Some(revision) {
+            return;
+        }
+        let Some(swap) = self.document.swap_path() else {
+            return;
+        };
+        match self.document.write_swap(&swap) {
+            Ok(()) => self.swap_revision = Some(revision),
+            Err(e) => self.status_message = Some(format!("Autosave failed: {}", e)),
+        }
+    }
+
+    /// 自分で書き出したスワップファイルを消す（正常終了・保存・破棄時）
+    fn remove_swap(&mut self) {
+        if self.swap_revision.take().is_some()
+            && let Some(swap) = self.document.swap_path()
+        {
+            let _ = std::fs::remove_file(swap);
+        }
+    }
+
+    /// 開いたファイルをロックする
+    /// 他のプロセスがロックしていれば確認し、そうでなければスワップファイルを確認する
+    fn check_lock(&mut self) {
+        if self.document.is_readonly() {
+            return;
+        }
+        let Some(path) = self.document.path().cloned() else {
+            return;
+        };
+        match FileLock::acquire(&path) {
+            Ok(Some(lock)) => self.lock = Some(lock),
+            Ok(None) => {
+                self.confirm_mode = ConfirmMode::Locked(path);
+                return;
+            }
+            // ロックに対応していないファイルシステムなどは無視する
+            Err(_) => {}
+        }
+        self.check_swap();
+    }
+
+    /// ロック確認中のキー処理
+    fn handle_locked_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match code {
+            // r: 読み取り専用で開く
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                self.confirm_mode = ConfirmMode::Off;
+                self.document.set_readonly(true);
+                self.status_message = Some("Opened read-only".to_string());
+            }
+            // e: ロックせずに編集する
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                self.confirm_mode = ConfirmMode::Off;
+                self.status_message = Some("Editing without lock".to_string());
+                self.check_swap();
+            }
+            // c / Escape / C-g: 開くのをやめる
+            KeyCode::Char('c') | KeyCode::Char('C') | KeyCode::Esc => {
+                self.confirm_mode = ConfirmMode::Off;
+                self.do_kill_buffer();
+                self.status_message = Some("Cancelled".to_string());
+            }
+            KeyCode::Char('g') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.confirm_mode = ConfirmMode::Off;
+                self.do_kill_buffer();
+                self.status_message = Some("Cancelled".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    /// 開いたファイルにスワップファイルが残っていれば復元を確認する
+    fn check_swap(&mut self) {
+        if self.document.is_readonly() {
+            return;
+        }
+        if let Some(swap) = self.document.swap_path()
+            && swap.is_file()
+        {
+            self.confirm_mode = ConfirmMode::RecoverSwap(swap);
+        }
+    }
+
+    /// スワップファイル確認中のキー処理
+    fn handle_recover_swap_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let ConfirmMode::RecoverSwap(swap) = self.confirm_mode.clone() else {
+            return;
+        };
+        match code {
+            // r: 復元する
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                let Some(path) = self.document.path().cloned() else {
+                    return;
+                };
+                self.confirm_mode = ConfirmMode::Off;
+                match Document::recover(path, &swap) {
+                    Ok(document) => {
+                        self.set_document(document);
+                        // スワップは復元した内容と一致しているので保存時に消す
+                        self.swap_revision = Some(self.document.revision());
+                        self.status_message = Some("Recovered unsaved changes".to_string());
+                    }
+                    Err(e) => {
+                        self.status_message = Some(format!("Recovery failed: {}", e));
+                    }
+                }
+            }
+            // d: スワップファイルを削除
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                self.confirm_mode = ConfirmMode::Off;
+                self.status_message = Some(match std::fs::remove_file(&swap) {
+                    Ok(()) => "Swap file deleted".to_string(),
+                    Err(e) => format!("Failed to delete swap file: {}", e),
+                });
+            }
+            // i / Escape / C-g: 何もせずに開く
+            KeyCode::Char('i') | KeyCode::Char('I') | KeyCode::Esc => {
+                self.confirm_mode = ConfirmMode::Off;
+            }
+            KeyCode::Char('g') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.confirm_mode = ConfirmMode::Off;
+            }
+            _ => {}
+        }
+    }
+
+    /// 保存時のバックアップ方式を設定
+    pub(super) fn set_backup_policy(&mut self, policy: BackupPolicy) {
+        self.config.backup = policy;
+        self.document.set_backup_policy(policy);
+        self.status_message = Some(format!("Backup on save: {}", policy.name()));
+    }
+
+    /// ステータスメッセージを設定
+    pub fn set_status_message(&mut self, message: impl Into<String>) {
+        self.status_message = Some(message.into());
+    }
+
+    /// 編集可能か確認（読み取り専用ならメッセージを出して false）
+    pub(super) fn check_writable(&mut self) -> bool {
+        if self.document.is_readonly() {
+            self.status_message = Some("Buffer is read-only".to_string());
+            false
+        } else {
+            true
+        }
+    }
+
+    /// 書き換えられる選択範囲があるか確認（なければメッセージを出して false）
+    pub(super) fn check_selection(&mut self) -> bool {
+        if !self.check_writable() {
+            return false;
+        }
+        if self.selection.is_none() {
+            self.status_message = Some("No selection".to_string());
+            return false;
+        }
+        true
+    }
+
+    /// 検索クエリを設定し、先頭から最初のマッチ位置にカーソルを移動（--find 用）
+    /// literal が true ならクエリをHEXとして解釈しない
+    /// 戻り値: マッチが見つかったか
+    pub fn find_first(&mut self, query: &str, literal: bool) -> bool {
+        self.search_query = query.to_string();
+        self.last_search_query = query.to_string();
+        self.search_literal = literal;
+
+        let pattern = self.search_query_to_bytes();
+        match search::find(self.document.data(), &pattern, 0) {
+            Some(pos) => {
+                self.cursor = pos;
+                self.ensure_cursor_visible();
+                self.status_message = Some(format!("Found at {:08X}", pos));
+                true
+            }
+            None => {
+                self.status_message = Some(format!("Not found: {}", query));
+                false
+            }
+        }
     }
 
     /// 終了すべきかどうか
@@ -187,20 +1356,21 @@ impl App {
 
     /// 表示可能行数を設定
     pub fn set_visible_rows(&mut self, rows: usize) {
-        self.visible_rows = rows.saturating_sub(1); // ステータスバー分
+        // ステータスバーとタブバーの分
+        self.visible_rows = rows.saturating_sub(1 + self.show_tab_bar() as usize);
     }
 
     /// カーソルを上に移動
     fn cursor_up(&mut self) {
-        if self.cursor >= self.bytes_per_row {
-            self.cursor -= self.bytes_per_row;
+        if self.cursor >= self.row_bytes() {
+            self.cursor -= self.row_bytes();
             self.ensure_cursor_visible();
         }
     }
 
     /// カーソルを下に移動
     fn cursor_down(&mut self) {
-        let new_pos = self.cursor + self.bytes_per_row;
+        let new_pos = self.cursor + self.row_bytes();
         if new_pos < self.document.len() {
             self.cursor = new_pos;
             self.ensure_cursor_visible();
@@ -224,92 +1394,231 @@ impl App {
     }
 
     /// カーソル位置が表示範囲内になるようにスクロール
-    fn ensure_cursor_visible(&mut self) {
-        let cursor_row = self.cursor / self.bytes_per_row;
-        let offset_row = self.offset / self.bytes_per_row;
+    pub(super) fn ensure_cursor_visible(&mut self) {
+        let cursor_row = self.cursor / self.row_bytes();
+        let offset_row = self.offset / self.row_bytes();
 
         if cursor_row < offset_row {
-            self.offset = cursor_row * self.bytes_per_row;
+            self.offset = cursor_row * self.row_bytes();
         } else if cursor_row >= offset_row + self.visible_rows {
-            self.offset = (cursor_row - self.visible_rows + 1) * self.bytes_per_row;
+            self.offset = (cursor_row - self.visible_rows + 1) * self.row_bytes();
         }
     }
 
     /// ページアップ
     fn page_up(&mut self) {
-        let page_size = self.visible_rows * self.bytes_per_row;
+        let page_size = self.visible_rows * self.row_bytes();
         self.cursor = self.cursor.saturating_sub(page_size);
         self.offset = self.offset.saturating_sub(page_size);
     }
 
     /// ページダウン
     fn page_down(&mut self) {
-        let page_size = self.visible_rows * self.bytes_per_row;
+        let page_size = self.visible_rows * self.row_bytes();
         let max_pos = self.document.len(); // EOF位置まで移動可能
         self.cursor = (self.cursor + page_size).min(max_pos);
         self.offset = (self.offset + page_size).min(
-            (self.document.len() / self.bytes_per_row).saturating_sub(self.visible_rows)
-                * self.bytes_per_row,
+            (self.document.len() / self.row_bytes()).saturating_sub(self.visible_rows)
+                * self.row_bytes(),
         );
         self.ensure_cursor_visible();
     }
 
     /// 行頭に移動
     fn cursor_home(&mut self) {
-        self.cursor = (self.cursor / self.bytes_per_row) * self.bytes_per_row;
+        self.cursor = (self.cursor / self.row_bytes()) * self.row_bytes();
     }
 
     /// 行末に移動（EOF位置まで移動可能）
     fn cursor_end(&mut self) {
-        let row_start = (self.cursor / self.bytes_per_row) * self.bytes_per_row;
-        let row_end = (row_start + self.bytes_per_row).min(self.document.len());
+        let row_start = (self.cursor / self.row_bytes()) * self.row_bytes();
+        let row_end = (row_start + self.row_bytes()).min(self.document.len());
         self.cursor = row_end;
     }
 
-    /// HEX入力処理
-    fn input_hex(&mut self, ch: char) {
-        // 全角→半角、小文字→大文字の正規化
-        let normalized = Self::normalize_hex_char(ch);
-        let Some(digit) = normalized.and_then(|c| c.to_digit(16)) else {
+    /// HEX入力処理（8進・2進表示ではその基数の桁を入力）
+    /// 1桁目から即座にバイトへ反映し、入力済みの桁より下位は元の値を残す
+    fn input_hex(&mut self, ch: char) {
+        // 全角→半角、小文字→大文字の正規化
+        let normalized = Self::normalize_hex_char(ch);
+        let radix = self.byte_format.radix();
+        let Some(digit) = normalized.and_then(|c| c.to_digit(radix)) else {
+            return;
+        };
+        if self.input_state == InputState::Normal && !self.check_multi_cursor_edit() {
+            return;
+        }
+
+        let (entered, count) = match self.input_state {
+            InputState::Normal => (0, 0),
+            InputState::Digits(value, count) => (value as u32, count),
+        };
+        let entered = entered * radix + digit;
+        let count = count + 1;
+        // 入力していない下位の桁の重み
+        let scale = radix.pow((self.byte_format.digits() - count) as u32);
+        if entered * scale > 0xFF {
+            // 8進の1桁目は0〜3のみ
+            return;
+        }
+
+        if count == 1 {
+            // 最後の桁の入力までを1回のUndoで戻せるようにグループ化する
+            self.document.begin_group();
+            // 挿入モードやEOFでは新しいバイトを挿入
+            if self.edit_mode == EditMode::Insert || self.cursor >= self.document.len() {
+                let _ = self.document.insert(self.cursor, 0);
+            }
+        }
+        // 上書きモード：入力していない下位の桁は既存の値を保持
+        let old = self.document.get(self.cursor).unwrap_or(0) as u32;
+        let value = entered * scale + old % scale;
+        let _ = self.document.set(self.cursor, value as u8);
+        // 複数カーソルも同じグループで書き換える
+        for pos in self.extra_cursors() {
+            let old = self.document.get(pos).unwrap_or(0) as u32;
+            let _ = self.document.set(pos, (entered * scale + old % scale) as u8);
+        }
+
+        if scale == 1 {
+            // 最後の桁：確定して次へ
+            self.document.end_group();
+            self.cursor_right();
+            self.input_state = InputState::Normal;
+        } else {
+            self.input_state = InputState::Digits(entered as u8, count);
+        }
+    }
+
+    /// execute を通さずに処理する入力か（ペースト、クリック、検索・プロンプトなどのモード中のキー）
+    fn bypasses_execute(&self, event: &Event) -> bool {
+        match event {
+            Event::Paste(_) => true,
+            // ポインタの移動やスクロールでは入力を途切れさせない
+            Event::Mouse(mouse) => matches!(mouse.kind, MouseEventKind::Down(_)),
+            Event::Key(key) => {
+                self.search_mode
+                    || self.replace_mode != ReplaceMode::Off
+                    || self.prompt_mode != PromptMode::Off
+                    || self.confirm_mode != ConfirmMode::Off
+                    || self.help.is_some()
+                    || (self.panel.is_some() && !self.inspector_passes_key(key))
+            }
+            _ => false,
+        }
+    }
+
+    /// 入力途中の桁を確定する（1バイト分を入力し終えていない状態を終わらせる）
+    fn finish_hex_input(&mut self) {
+        if let InputState::Digits(..) = self.input_state {
+            self.input_state = InputState::Normal;
+            self.document.end_group();
+        }
+    }
+
+    /// 複数カーソルの主カーソル以外の位置（ファイル内のもののみ、昇順）
+    fn extra_cursors(&self) -> Vec<usize> {
+        let len = self.document.len();
+        self.cursors
+            .iter()
+            .filter_map(|&delta| self.cursor.checked_add_signed(delta))
+            .filter(|&pos| pos < len)
+            .collect()
+    }
+
+    /// 複数カーソルで編集できるか（挿入モードでは位置がずれるので上書きのみ）
+    fn check_multi_cursor_edit(&mut self) -> bool {
+        if self.cursors.is_empty() || self.edit_mode == EditMode::Overwrite {
+            return true;
+        }
+        self.status_message =
+            Some("Multiple cursors edit in overwrite mode only (Insert to switch)".to_string());
+        false
+    }
+
+    /// 検索パターンの一致箇所すべてにカーソルを置く
+    pub(super) fn mark_all_matches(&mut self) {
+        if self.search_query.is_empty() {
+            self.search_query = self.last_search_query.clone();
+        }
+        let pattern = self.search_query_to_bytes();
+        if pattern.is_empty() {
+            self.status_message = Some("No search pattern (C-s to search first)".to_string());
+            return;
+        }
+        let data = self.document.data();
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while let Some(pos) = search::find(data, &pattern, start) {
+            if matches.len() == MAX_CURSORS {
+                self.status_message =
+                    Some(format!("Too many matches (more than {})", MAX_CURSORS));
+                return;
+            }
+            matches.push(pos);
+            start = pos + pattern.len();
+        }
+        if matches.len() < 2 {
+            self.status_message = Some(match matches.len() {
+                0 => "Not found".to_string(),
+                _ => "Only one match".to_string(),
+            });
+            return;
+        }
+        // 主カーソルはカーソル位置以降で最初の一致箇所
+        let main = matches.iter().copied().find(|&pos| pos >= self.cursor).unwrap_or(matches[0]);
+        self.cursors = matches
+            .iter()
+            .filter(|&&pos| pos != main)
+            .map(|&pos| pos as isize - main as isize)
+            .collect();
+        self.cursor = main;
+        self.clear_selection();
+        self.ensure_cursor_visible();
+        self.status_message = Some(format!(
+            "{} cursors at matches of {} (C-g to leave)",
+            matches.len(),
+            self.search_query
+        ));
+    }
+
+    /// HEX列で1桁ずつ移動する（ASCII列では1バイトずつ）
+    fn move_digit(&mut self, forward: bool) {
+        if !self.hex_mode {
+            if forward { self.cursor_right() } else { self.cursor_left() }
+            return;
+        }
+        let digits = self.byte_format.digits();
+        let count = match self.input_state {
+            InputState::Normal => 0,
+            InputState::Digits(_, count) => count,
+        };
+        if forward && count + 1 < digits {
+            self.enter_digit(count + 1);
+        } else if forward {
+            self.finish_hex_input();
+            self.cursor_right();
+        } else if count > 1 {
+            self.enter_digit(count - 1);
+        } else if count == 1 {
+            self.finish_hex_input();
+        } else if self.cursor > 0 {
+            self.cursor_left();
+            self.enter_digit(digits - 1);
+        }
+    }
+
+    /// カーソル位置のバイトの count 桁目から入力する状態にする（上の桁は今の値のまま）
+    fn enter_digit(&mut self, count: usize) {
+        let Some(byte) = self.document.get(self.cursor) else {
             return;
         };
-        let digit = digit as u8;
-
-        match self.input_state {
-            InputState::Normal => {
-                // 1桁目：上位ニブルを即座に反映
-                match self.edit_mode {
-                    EditMode::Overwrite => {
-                        // 上書きモード：既存バイトの下位ニブルは保持
-                        let low_nibble = if self.cursor < self.document.len() {
-                            self.document.get(self.cursor).unwrap_or(0) & 0x0F
-                        } else {
-                            0
-                        };
-                        let value = (digit << 4) | low_nibble;
-                        if self.cursor < self.document.len() {
-                            let _ = self.document.set(self.cursor, value);
-                        } else {
-                            let _ = self.document.insert(self.cursor, value);
-                        }
-                    }
-                    EditMode::Insert => {
-                        // 挿入モード：新しいバイトを挿入
-                        let value = digit << 4;
-                        let _ = self.document.insert(self.cursor, value);
-                    }
-                }
-                self.input_state = InputState::HexFirstDigit(digit);
-            }
-            InputState::HexFirstDigit(first) => {
-                // 2桁目：下位ニブルを更新して次へ
-                let value = (first << 4) | digit;
-                // 1桁目で既にバイトが存在するので上書き
-                let _ = self.document.set(self.cursor, value);
-                self.cursor_right();
-                self.input_state = InputState::Normal;
-            }
+        if self.input_state == InputState::Normal {
+            // 最後の桁の入力までを1回のUndoで戻せるようにグループ化する
+            self.document.begin_group();
         }
+        let scale = self.byte_format.radix().pow((self.byte_format.digits() - count) as u32);
+        self.input_state = InputState::Digits((byte as u32 / scale) as u8, count);
     }
 
     /// HEX文字の正規化（全角→半角、小文字→大文字）
@@ -355,7 +1664,7 @@ impl App {
     /// ASCII入力処理（文字をバッファのエンコーディングに変換して入力）
     fn input_ascii(&mut self, ch: char) {
         // 文字をバッファのエンコーディングに変換
-        let bytes = match encoding::encode_char(ch, self.encoding) {
+        let bytes = match self.encode_text(&ch.to_string(), self.encoding) {
             Some(bytes) => bytes,
             None => {
                 // エンコードできない文字
@@ -368,29 +1677,18 @@ impl App {
             }
         };
 
-        if bytes.is_empty() {
+        if bytes.is_empty() || !self.check_multi_cursor_edit() {
             return;
         }
 
-        match self.edit_mode {
-            EditMode::Overwrite => {
-                // 上書きモード：各バイトを順番に上書き（EOFを超えた分は追加）
-                for (i, &byte) in bytes.iter().enumerate() {
-                    let pos = self.cursor + i;
-                    if pos < self.document.len() {
-                        let _ = self.document.set(pos, byte);
-                    } else {
-                        let _ = self.document.insert(pos, byte);
-                    }
-                }
-            }
-            EditMode::Insert => {
-                // 挿入モード：バイト列を挿入
-                for (i, &byte) in bytes.iter().enumerate() {
-                    let _ = self.document.insert(self.cursor + i, byte);
-                }
-            }
+        self.document.begin_group();
+        self.write_bytes(self.cursor, &bytes);
+        // 複数カーソルはファイルの末尾を超えない分だけ上書き
+        for pos in self.extra_cursors() {
+            let len = bytes.len().min(self.document.len() - pos);
+            let _ = self.document.set_range(pos, &bytes[..len]);
         }
+        self.document.end_group();
 
         // カーソルをバイト数分進める
         for _ in 0..bytes.len() {
@@ -398,6 +1696,24 @@ impl App {
         }
     }
 
+    /// 編集モードに応じてバイト列を書き込む
+    /// 上書きモードでは既存バイトを上書きし、EOFを超えた分は追加する
+    pub(super) fn write_bytes(&mut self, pos: usize, bytes: &[u8]) {
+        match self.edit_mode {
+            EditMode::Overwrite => {
+                let split = bytes.len().min(self.document.len().saturating_sub(pos));
+                let (head, tail) = bytes.split_at(split);
+                self.document.begin_group();
+                let _ = self.document.set_range(pos, head);
+                let _ = self.document.insert_bytes(pos + split, tail);
+                self.document.end_group();
+            }
+            EditMode::Insert => {
+                let _ = self.document.insert_bytes(pos, bytes);
+            }
+        }
+    }
+
     /// 選択開始（マークを設定）
     fn start_selection(&mut self) {
         self.selection_start = Some(self.cursor);
@@ -406,7 +1722,7 @@ impl App {
     }
 
     /// 選択解除
-    fn clear_selection(&mut self) {
+    pub(super) fn clear_selection(&mut self) {
         self.selection_start = None;
         self.selection = None;
     }
@@ -459,71 +1775,270 @@ impl App {
         self.update_selection();
     }
 
+    /// テキストを設定に従ってクリップボードにコピー（システムクリップボード / OSC 52）
+    /// 失敗したら理由をステータスに出して false
+    pub(super) fn copy_to_clipboard(&mut self, text: &str) -> bool {
+        match clipboard::copy_text_to(text, self.config.clipboard, self.config.osc52_limit) {
+            Ok(()) => true,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                false
+            }
+        }
+    }
+
+    /// M-w / C-w 用: バイト列をそのまま文字列としてクリップボードに置く
+    /// UTF-8 として読めないか制御文字を含むときはスペース区切りの HEX にする
+    /// コピーできたら (HEX にしたか) を返す
+    fn copy_raw(&mut self, data: Vec<u8>) -> Option<bool> {
+        let text = std::str::from_utf8(&data)
+            .ok()
+            .filter(|text| {
+                !text.chars().any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+            })
+            .map(str::to_string);
+        let as_hex = text.is_none();
+        let text = text.unwrap_or_else(|| clipboard::bytes_to_hex(&data, HexFormat::Spaced));
+        if !self.copy_to_clipboard(&text) {
+            return None;
+        }
+        self.last_copy = Some((text, data));
+        Some(as_hex)
+    }
+
     /// 選択範囲をコピー (M-w)
-    /// システムクリップボード + OSC 52 (ターミナルクリップボード)
     fn copy(&mut self) {
-        if let Some((start, end)) = self.selection {
-            if let Some(data) = self.document.get_range(start, end + 1) {
-                // 両方のクリップボードにコピー
-                let _ = clipboard::copy_hex_to_all(data, HexFormat::Spaced);
-                self.status_message = Some(format!("Copied {} bytes", end - start + 1));
-                self.clear_selection();
-            }
-        } else {
+        let Some((_, data)) = self.selected_bytes() else {
+            self.status_message = Some("No selection".to_string());
+            return;
+        };
+        let len = data.len();
+        if let Some(as_hex) = self.copy_raw(data) {
+            let suffix = if as_hex { " as HEX (not plain text)" } else { "" };
+            self.status_message = Some(format!("Copied {} bytes{}", len, suffix));
+            self.clear_selection();
+        }
+    }
+
+    /// HEX形式を選んでコピー (copy-hex)。選択していなければカーソル位置の1バイト
+    pub(super) fn copy_hex(&mut self, format: HexFormat) {
+        if self.selection.is_some() {
+            self.copy_as(format);
+        } else if let Some(byte) = self.document.get(self.cursor)
+            && self.copy_to_clipboard(&clipboard::bytes_to_hex(&[byte], format))
+        {
+            self.status_message = Some(format!("Copied 1 byte as {}", format.name()));
+        }
+    }
+
+    /// 選択範囲をソースコードのリテラルなどの形式でコピー (C-x w)
+    pub(super) fn copy_as(&mut self, format: HexFormat) {
+        let Some((_, data)) = self.selected_bytes() else {
             self.status_message = Some("No selection".to_string());
+            return;
+        };
+        if self.copy_to_clipboard(&clipboard::bytes_to_hex(&data, format)) {
+            self.status_message =
+                Some(format!("Copied {} bytes as {}", data.len(), format.name()));
+            self.clear_selection();
         }
     }
 
-    /// 選択範囲をHEX形式でコピー
-    fn copy_hex(&mut self) {
-        if let Some((start, end)) = self.selection {
-            if let Some(data) = self.document.get_range(start, end + 1) {
-                // 両方のクリップボードにコピー
-                let _ = clipboard::copy_hex_to_all(data, HexFormat::Spaced);
-                self.status_message = Some("Copied as HEX".to_string());
-                self.clear_selection();
-            }
-        } else if let Some(byte) = self.document.get(self.cursor) {
-            let _ = clipboard::copy_hex_to_all(&[byte], HexFormat::Spaced);
+    /// 選択範囲を現在のエンコーディングでデコードした文字列としてコピー
+    /// デコードできないバイトは置換文字（U+FFFD）になる
+    pub(super) fn copy_as_text(&mut self) {
+        let Some((_, data)) = self.selected_bytes() else {
+            self.status_message = Some("No selection".to_string());
+            return;
+        };
+        let text = self.decode_text(&data);
+        if self.copy_to_clipboard(&text) {
+            self.status_message = Some(format!(
+                "Copied {} bytes as {} text ({} chars)",
+                data.len(),
+                self.encoding.name(),
+                text.chars().count()
+            ));
+            self.clear_selection();
         }
     }
 
     /// 選択範囲をカット (C-w)
-    /// システムクリップボード + OSC 52 (ターミナルクリップボード)
+    /// コピーできなかったときは削除しない
     fn cut(&mut self) {
-        if let Some((start, end)) = self.selection {
-            if let Some(data) = self.document.get_range(start, end + 1) {
-                // 両方のクリップボードにコピー
-                let _ = clipboard::copy_hex_to_all(data, HexFormat::Spaced);
-                // 選択範囲を削除（末尾から削除）
-                for i in (start..=end).rev() {
-                    let _ = self.document.delete(i);
-                }
+        let Some((start, data)) = self.selected_bytes() else {
+            self.status_message = Some("No selection".to_string());
+            return;
+        };
+        let len = data.len();
+        if self.copy_raw(data).is_none() {
+            return;
+        }
+        // 選択範囲を削除
+        let _ = self.document.delete_range(start, start + len);
+        self.cursor = start;
+        self.status_message = Some(format!("Cut {} bytes", len));
+        self.clear_selection();
+    }
+
+    /// 選択範囲を削除（クリップボードには触れない）
+    pub(super) fn delete_region(&mut self) {
+        let Some((start, end)) = self.selection else {
+            self.status_message = Some("No selection".to_string());
+            return;
+        };
+        let end = (end + 1).min(self.document.len());
+        self.clear_selection();
+        if start < end {
+            self.delete_bytes(start, end);
+        }
+    }
+
+    /// カーソル位置から count バイト削除 (C-d / Delete)。選択中なら選択範囲を削除
+    fn delete_forward(&mut self, count: usize) {
+        if self.selection.is_some() {
+            self.delete_region();
+            return;
+        }
+        if !self.cursors.is_empty() {
+            let len = self.document.len();
+            self.delete_at_cursors(|pos| (pos, pos.saturating_add(count).min(len)));
+            return;
+        }
+        let end = self.cursor.saturating_add(count).min(self.document.len());
+        if self.cursor < end {
+            self.delete_bytes(self.cursor, end);
+        }
+    }
+
+    /// カーソルの前の count バイトを削除 (Backspace)。選択中なら選択範囲を削除
+    fn delete_backward(&mut self, count: usize) {
+        if self.selection.is_some() {
+            self.delete_region();
+            return;
+        }
+        if !self.cursors.is_empty() {
+            self.delete_at_cursors(|pos| (pos.saturating_sub(count), pos));
+            return;
+        }
+        let start = self.cursor.saturating_sub(count);
+        if start < self.cursor {
+            self.delete_bytes(start, self.cursor);
+        }
+    }
+
+    /// 複数カーソルそれぞれで range(位置) の範囲を1回のUndoで削除する
+    /// 後ろの範囲から削除し、各カーソルは削除した範囲の先頭へ（重なったカーソルはまとめる）
+    fn delete_at_cursors(&mut self, range: impl Fn(usize) -> (usize, usize)) {
+        let mut positions = self.extra_cursors();
+        positions.push(self.cursor);
+        positions.sort_unstable();
+        // 重なる範囲はまとめる
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for &pos in &positions {
+            let (start, end) = range(pos);
+            match ranges.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ if start < end => ranges.push((start, end)),
+                _ => {}
+            }
+        }
+        if ranges.is_empty() {
+            return;
+        }
+
+        self.document.begin_group();
+        let mut result = Ok(());
+        for &(start, end) in ranges.iter().rev() {
+            result = self.document.delete_range(start, end).map(|_| ());
+            if result.is_err() {
+                break;
+            }
+        }
+        self.document.end_group();
+        if let Err(e) = result {
+            self.status_message = Some(format!("Delete failed: {}", e));
+            return;
+        }
+
+        // 削除した範囲の分だけ前へずらす
+        let moved = |pos: usize| {
+            let start = range(pos).0;
+            let deleted: usize = ranges.iter().map(|&(a, b)| b.min(start).saturating_sub(a)).sum();
+            start - deleted
+        };
+        let main = moved(self.cursor);
+        let mut cursors: Vec<isize> = positions
+            .iter()
+            .map(|&pos| moved(pos))
+            .filter(|&pos| pos != main)
+            .map(|pos| pos as isize - main as isize)
+            .collect();
+        cursors.dedup();
+        self.cursor = main;
+        self.cursors = cursors;
+        self.ensure_cursor_visible();
+        let deleted: usize = ranges.iter().map(|&(a, b)| b - a).sum();
+        self.status_message = Some(format!(
+            "Deleted {} bytes at {} cursors",
+            deleted,
+            self.cursors.len() + 1
+        ));
+    }
+
+    /// start..end を削除してカーソルを start に置く
+    fn delete_bytes(&mut self, start: usize, end: usize) {
+        match self.document.delete_range(start, end) {
+            Ok(_) => {
                 self.cursor = start;
-                self.status_message = Some(format!("Cut {} bytes", end - start + 1));
-                self.clear_selection();
+                if end - start > 1 {
+                    self.status_message = Some(format!("Deleted {} bytes", end - start));
+                }
             }
-        } else {
-            self.status_message = Some("No selection".to_string());
+            Err(e) => self.status_message = Some(format!("Delete failed: {}", e)),
         }
+        self.ensure_cursor_visible();
     }
 
     /// システムクリップボードからペースト (C-y)
+    /// クリップボードを読めない（OSC 52 だけの環境など）ときは最後に M-w / C-w した内容
     fn paste(&mut self) {
         // システムクリップボードからテキストを取得
-        let content = match arboard::Clipboard::new().and_then(|mut cb| cb.get_text()) {
-            Ok(text) => text,
-            Err(_) => {
-                self.status_message = Some("Clipboard empty or unavailable".to_string());
-                return;
-            }
-        };
-        self.paste_from_terminal(&content);
+        match arboard::Clipboard::new().and_then(|mut cb| cb.get_text()) {
+            Ok(text) => self.paste_from_terminal(&text),
+            Err(_) => match self.last_copy.clone() {
+                Some((_, bytes)) if self.check_writable() => self.paste_bytes(&bytes),
+                Some(_) => {}
+                None => {
+                    self.status_message = Some("Clipboard empty or unavailable".to_string());
+                }
+            },
+        }
+    }
+
+    /// クリップボードの内容を HEX として解釈してペースト (C-x y)
+    fn paste_hex(&mut self) {
+        if self.prepare_paste_as() {
+            self.paste_as("hex");
+        }
     }
 
     /// ターミナルからのペースト（Bracketed Paste）を処理
     /// ペーストされた内容をバイト列としてカーソル位置に挿入
     fn paste_from_terminal(&mut self, content: &str) {
+        if !self.check_writable() {
+            return;
+        }
+
+        // 自分でコピーした文字列なら元のバイト列に戻す
+        if let Some((text, bytes)) = &self.last_copy
+            && text == content
+        {
+            let bytes = bytes.clone();
+            self.paste_bytes(&bytes);
+            return;
+        }
+
         // HEX文字列かどうかを判定（全角文字も正規化して判定）
         let trimmed = content.trim();
         let bytes = if Self::looks_like_hex(trimmed) {
@@ -533,40 +2048,75 @@ impl App {
             // 生のバイト列として扱う
             content.as_bytes().to_vec()
         };
+        self.paste_bytes(&bytes);
+    }
+
+    /// paste-as の準備: クリップボードの内容を読んでおく
+    /// 読めないときは最後に M-w / C-w した文字列を使う
+    pub(super) fn prepare_paste_as(&mut self) -> bool {
+        if !self.check_writable() {
+            return false;
+        }
+        let text = arboard::Clipboard::new()
+            .and_then(|mut cb| cb.get_text())
+            .ok()
+            .or_else(|| self.last_copy.as_ref().map(|(text, _)| text.clone()));
+        match text {
+            Some(text) if !text.is_empty() => {
+                self.pending_string = Some(text);
+                true
+            }
+            _ => {
+                self.status_message = Some("Clipboard empty or unavailable".to_string());
+                false
+            }
+        }
+    }
+
+    /// paste-as: クリップボードの内容を指定の形式で解釈してペースト（空なら C-y と同じ判定）
+    pub(super) fn paste_as(&mut self, format: &str) {
+        let Some(text) = self.pending_string.take() else {
+            return;
+        };
+        let result = match format.trim().to_ascii_lowercase().as_str() {
+            "" => {
+                self.paste_from_terminal(&text);
+                return;
+            }
+            "raw" | "text" => Ok(text.into_bytes()),
+            "hex" => clipboard::hex_to_bytes(&text).map_err(|e| e.to_string()),
+            "base64" | "b64" => {
+                transform::base64_decode(text.as_bytes()).map_err(|e| e.to_string())
+            }
+            "string" | "str" => clipboard::unescape_string(&text).map_err(|e| e.to_string()),
+            other => Err(format!("Unknown format: {} (raw, hex, base64 or string)", other)),
+        };
+        match result {
+            Ok(bytes) => self.paste_bytes(&bytes),
+            Err(e) => self.status_message = Some(e),
+        }
+    }
 
+    /// バイト列をカーソル位置にペースト（選択中なら選択範囲と置き換える）
+    fn paste_bytes(&mut self, bytes: &[u8]) {
         if bytes.is_empty() {
             return;
         }
 
+        // 選択範囲の削除と書き込みを1回のUndoで戻せるようにする
+        self.document.begin_group();
+
         // 選択範囲があれば削除してから挿入
         if let Some((start, end)) = self.selection {
-            for i in (start..=end).rev() {
-                let _ = self.document.delete(i);
-            }
+            let end = (end + 1).min(self.document.len());
+            let _ = self.document.delete_range(start, end);
             self.cursor = start;
             self.clear_selection();
         }
 
         // 編集モードに応じて処理
-        match self.edit_mode {
-            EditMode::Overwrite => {
-                // 上書きモード：既存バイトを上書き、EOFを超えた分は追加
-                for (i, &byte) in bytes.iter().enumerate() {
-                    let pos = self.cursor + i;
-                    if pos < self.document.len() {
-                        let _ = self.document.set(pos, byte);
-                    } else {
-                        let _ = self.document.insert(pos, byte);
-                    }
-                }
-            }
-            EditMode::Insert => {
-                // 挿入モード：カーソル位置にバイト列を挿入
-                for (i, &byte) in bytes.iter().enumerate() {
-                    let _ = self.document.insert(self.cursor + i, byte);
-                }
-            }
-        }
+        self.write_bytes(self.cursor, bytes);
+        self.document.end_group();
 
         self.cursor += bytes.len();
         self.ensure_cursor_visible();
@@ -576,93 +2126,125 @@ impl App {
     /// 検索クエリをバイト列に変換
     fn search_query_to_bytes(&self) -> Vec<u8> {
         let trimmed = self.search_query.trim();
-        if Self::looks_like_hex(trimmed) {
-            Self::normalized_hex_to_bytes(trimmed).unwrap_or_else(|| self.search_query.as_bytes().to_vec())
+        if !self.search_literal && Self::looks_like_hex(trimmed) {
+            Self::normalized_hex_to_bytes(trimmed)
+                .unwrap_or_else(|| self.search_text_to_bytes(&self.search_query))
+        } else {
+            self.search_text_to_bytes(&self.search_query)
+        }
+    }
+
+    /// 検索・置換のテキストをバイト列に変換（M-e で有効ならバッファのエンコーディングで）
+    fn search_text_to_bytes(&self, text: &str) -> Vec<u8> {
+        if self.search_encoded {
+            self.encode_text(text, self.encoding)
+                .unwrap_or_else(|| encoding::encode_string(text, self.encoding))
         } else {
-            self.search_query.as_bytes().to_vec()
+            text.as_bytes().to_vec()
         }
     }
 
     /// 前方検索（現在位置から後ろへ）
     fn find_next(&mut self) {
+        self.find_from_cursor(search::Direction::Forward, String::new());
+    }
+
+    /// カーソル位置から検索し、結果のメッセージの前に prefix を付ける
+    fn find_from_cursor(&mut self, direction: search::Direction, prefix: String) {
         let pattern = self.search_query_to_bytes();
         if pattern.is_empty() {
             return;
         }
+        let (first, last) = self.search_bounds();
+        let from = match direction {
+            search::Direction::Forward => self.cursor + 1,
+            search::Direction::Backward => self.cursor,
+        };
+        let query = search::Query {
+            pattern,
+            range: first..last,
+            from,
+            direction,
+        };
+        self.start_search(query, Some(prefix));
+    }
 
-        let data = self.document.data();
-        let start = self.cursor + 1;
-
-        // 現在位置から末尾まで検索
-        if let Some(pos) = Self::find_pattern(data, &pattern, start) {
-            self.cursor = pos;
-            self.ensure_cursor_visible();
-            self.status_message = Some(format!("Found at {:08X}", pos));
-            return;
+    /// 検索を始める。範囲が広ければ別スレッドで行い、結果は tick で受け取る
+    fn start_search(&mut self, query: search::Query, report: Option<String>) {
+        self.searching = None;
+        if query.range.len() < ASYNC_SEARCH_SIZE {
+            let outcome = search::run(&self.document, &query, |_| true);
+            self.finish_search(outcome.unwrap_or(Outcome::NotFound), report);
+        } else {
+            let job = SearchJob::spawn(self.document.snapshot(), query);
+            self.searching = Some(SearchTask { job, report });
         }
+    }
 
-        // 先頭から現在位置まで検索（ラップアラウンド）
-        if let Some(pos) = Self::find_pattern(data, &pattern, 0) {
-            if pos < start {
+    /// 検索結果の位置へカーソルを移す
+    fn finish_search(&mut self, outcome: Outcome, report: Option<String>) {
+        let message = match outcome {
+            Outcome::Found(pos) => {
                 self.cursor = pos;
                 self.ensure_cursor_visible();
-                self.status_message = Some(format!("Wrapped, found at {:08X}", pos));
-                return;
+                format!("Found at {:08X}", pos)
+            }
+            Outcome::Wrapped(pos) => {
+                self.cursor = pos;
+                self.ensure_cursor_visible();
+                format!("Wrapped, found at {:08X}", pos)
             }
+            Outcome::NotFound => "Not found".to_string(),
+        };
+        if let Some(prefix) = report {
+            self.status_message = Some(prefix + &message);
         }
-
-        self.status_message = Some("Not found".to_string());
     }
 
-    /// 後方検索（現在位置から前へ）
-    fn find_prev(&mut self) {
-        let pattern = self.search_query_to_bytes();
-        if pattern.is_empty() {
-            return;
-        }
-
-        let data = self.document.data();
-        let end = self.cursor;
-
-        // 現在位置から先頭まで検索
-        if let Some(pos) = Self::find_pattern_reverse(data, &pattern, end) {
-            self.cursor = pos;
-            self.ensure_cursor_visible();
-            self.status_message = Some(format!("Found at {:08X}", pos));
+    /// 別スレッドの検索が終わっていれば結果を反映する
+    fn poll_search(&mut self) {
+        let Some(task) = self.searching.as_mut() else {
             return;
+        };
+        if let Some(outcome) = task.job.poll()
+            && let Some(task) = self.searching.take()
+        {
+            self.finish_search(outcome, task.report);
         }
+    }
 
-        // 末尾から現在位置まで検索（ラップアラウンド）
-        if let Some(pos) = Self::find_pattern_reverse(data, &pattern, data.len()) {
-            if pos > end {
-                self.cursor = pos;
-                self.ensure_cursor_visible();
-                self.status_message = Some(format!("Wrapped, found at {:08X}", pos));
+    /// 数値（値と型）のバイト表現を前方検索
+    pub(super) fn search_value(&mut self, input: &str) {
+        let (field, bytes) = match inspector::parse_typed_value(input) {
+            Ok(value) => value,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
                 return;
             }
-        }
-
-        self.status_message = Some("Not found".to_string());
+        };
+        let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        let hex = hex.join(" ");
+        // 以降は C-s / C-r で続けて検索できる
+        self.search_query = hex.clone();
+        self.search_literal = false;
+        self.last_search_query = hex.clone();
+        let value = input.split_whitespace().next().unwrap_or_default();
+        let prefix = format!("{} {} = {}: ", field.name(), value, hex);
+        self.find_from_cursor(search::Direction::Forward, prefix);
     }
 
-    /// パターンを前方検索
-    fn find_pattern(data: &[u8], pattern: &[u8], start: usize) -> Option<usize> {
-        if pattern.is_empty() || start + pattern.len() > data.len() {
-            return None;
-        }
-        data[start..].windows(pattern.len()).position(|w| w == pattern).map(|p| p + start)
+    /// 後方検索（現在位置から前へ）
+    fn find_prev(&mut self) {
+        self.find_from_cursor(search::Direction::Backward, String::new());
     }
 
-    /// パターンを後方検索
-    fn find_pattern_reverse(data: &[u8], pattern: &[u8], end: usize) -> Option<usize> {
-        if pattern.is_empty() || end == 0 {
-            return None;
+    /// 検索する範囲 [first, last)（選択範囲に絞っていればその範囲）
+    fn search_bounds(&self) -> (usize, usize) {
+        let len = self.document.len();
+        match self.search_region {
+            Some((start, end)) => (start.min(len), (end + 1).min(len)),
+            None => (0, len),
         }
-        let search_end = end.min(data.len());
-        if search_end < pattern.len() {
-            return None;
-        }
-        data[..search_end].windows(pattern.len()).rposition(|w| w == pattern)
     }
 
     /// 文字列がHEX形式かどうかを判定（全角文字も考慮）
@@ -674,7 +2256,7 @@ impl App {
         let normalized = Self::normalize_hex_string(s);
 
         // 偶数長で全て16進数なら HEX とみなす
-        normalized.len() % 2 == 0
+        normalized.len().is_multiple_of(2)
             && normalized.len() >= 2
             && normalized.chars().all(|c| c.is_ascii_hexdigit())
     }
@@ -700,7 +2282,7 @@ impl App {
     /// 正規化されたHEX文字列をバイト列に変換
     fn normalized_hex_to_bytes(s: &str) -> Option<Vec<u8>> {
         let normalized = Self::normalize_hex_string(s);
-        if normalized.len() % 2 != 0 {
+        if !normalized.len().is_multiple_of(2) {
             return None;
         }
         let mut bytes = Vec::with_capacity(normalized.len() / 2);
@@ -716,12 +2298,38 @@ impl App {
     /// アクションを実行
     pub fn execute(&mut self, action: Action) {
         // ステータスメッセージをクリア（一部のアクションを除く）
-        if !matches!(action, Action::EnterCtrlX) {
+        if !matches!(action, Action::EnterCtrlX | Action::EnterCtrlXR | Action::EnterCtrlXW) {
             self.status_message = None;
         }
 
+        // 数値引数はプレフィックスキーの後の最初のアクションで使う
+        let count = match action {
+            Action::Argument(_)
+            | Action::EnterCtrlX
+            | Action::EnterCtrlXR
+            | Action::EnterCtrlXW => None,
+            _ => self.prefix_arg.take(),
+        };
+
+        // HEX入力の途中で他の操作をしたら1桁目の入力を確定する
+        if !matches!(action, Action::InputHex(_) | Action::DigitLeft | Action::DigitRight) {
+            self.finish_hex_input();
+        }
+
+        if action.is_edit() && !self.check_writable() {
+            return;
+        }
+
         match action {
             Action::Quit => {
+                // 未保存のバッファがあればそのバッファに切り替えて確認
+                if !self.document.is_modified()
+                    && let Some(index) = self.buffers.iter().position(|b| b.document.is_modified())
+                {
+                    for _ in 0..=index {
+                        self.switch_buffer(true);
+                    }
+                }
                 if self.document.is_modified() {
                     self.confirm_mode = ConfirmMode::Quit;
                 } else {
@@ -729,11 +2337,7 @@ impl App {
                 }
             }
             Action::Save => {
-                if let Err(e) = self.document.save() {
-                    self.status_message = Some(format!("Save failed: {}", e));
-                } else {
-                    self.status_message = Some("Saved".to_string());
-                }
+                self.save_document(None, ConfirmMode::Off);
             }
             // カーソル移動（選択開始中は選択範囲を更新）
             Action::CursorUp => {
@@ -752,6 +2356,14 @@ impl App {
                 self.cursor_right();
                 self.update_selection();
             }
+            Action::DigitLeft => {
+                self.move_digit(false);
+                self.update_selection();
+            }
+            Action::DigitRight => {
+                self.move_digit(true);
+                self.update_selection();
+            }
             Action::CursorHome => {
                 self.cursor_home();
                 self.update_selection();
@@ -787,9 +2399,23 @@ impl App {
             Action::SelectRight => self.select_right(),
             // クリップボード
             Action::Copy => self.copy(),
-            Action::CopyHex => self.copy_hex(),
+            Action::CopyHex => {
+                if let Some(command) = find_command("copy-hex") {
+                    self.start_command(command);
+                }
+            }
+            Action::CopyAs(format) => self.copy_as(format),
+            Action::CopyAsText => self.copy_as_text(),
             Action::Cut => self.cut(),
+            Action::Delete => self.delete_forward(count.unwrap_or(1)),
+            Action::Backspace => self.delete_backward(count.unwrap_or(1)),
             Action::Paste => self.paste(),
+            Action::PasteHex => self.paste_hex(),
+            Action::PasteAs => {
+                if let Some(command) = find_command("paste-as") {
+                    self.start_command(command);
+                }
+            }
             // モード切替
             Action::ToggleMode => self.hex_mode = !self.hex_mode,
             Action::ToggleEditMode => {
@@ -798,28 +2424,50 @@ impl App {
                     EditMode::Insert => EditMode::Overwrite,
                 };
             }
-            Action::ToggleEncoding => {
-                self.encoding = self.encoding.next();
-                self.status_message = Some(format!("Encoding: {}", self.encoding.name()));
-            }
+            Action::ToggleByteFormat => self.set_byte_format(self.byte_format.next()),
+            Action::ToggleAddrRadix => self.toggle_addr_radix(),
+            Action::ToggleEncoding => self.set_encoding(""),
             // 入力
             Action::InputHex(ch) => self.input_hex(ch),
             Action::InputAscii(ch) => self.input_ascii(ch),
+            Action::Increment => self.step_value(count.unwrap_or(1) as i64),
+            Action::Decrement => self.step_value(-(count.unwrap_or(1) as i64)),
             // プレフィックスキー
             Action::EnterCtrlX => {
                 self.prefix_key = PrefixKey::CtrlX;
                 self.status_message = Some("C-x-".to_string());
             }
+            Action::EnterCtrlXR => {
+                self.prefix_key = PrefixKey::CtrlXR;
+                self.status_message = Some("C-x r-".to_string());
+            }
+            Action::EnterCtrlXW => {
+                self.prefix_key = PrefixKey::CtrlXW;
+                self.status_message = Some("C-x w-".to_string());
+            }
+            Action::Argument(digit) => {
+                let arg = self.prefix_arg.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+                let arg = arg.min(i32::MAX as usize);
+                self.prefix_arg = Some(arg);
+                self.status_message = Some(format!("Arg: {}", arg));
+            }
             Action::Cancel => {
                 self.prefix_key = PrefixKey::None;
-                self.input_state = InputState::Normal;
                 self.clear_selection();
-                self.status_message = Some("Quit".to_string());
+                if self.cursors.is_empty() {
+                    self.status_message = Some("Quit".to_string());
+                } else {
+                    self.cursors.clear();
+                    self.status_message = Some("Multiple cursors cleared".to_string());
+                }
             }
             // Undo/Redo
             Action::Undo => {
                 if let Some(pos) = self.document.undo() {
-                    self.cursor = pos.min(self.document.len().saturating_sub(1));
+                    // 複数カーソルの並びを崩さないよう、そのときはカーソルを動かさない
+                    if self.cursors.is_empty() {
+                        self.cursor = pos.min(self.document.len().saturating_sub(1));
+                    }
                     self.ensure_cursor_visible();
                     self.status_message = Some("Undo".to_string());
                 } else {
@@ -828,7 +2476,10 @@ impl App {
             }
             Action::Redo => {
                 if let Some(pos) = self.document.redo() {
-                    self.cursor = pos.min(self.document.len().saturating_sub(1));
+                    // 複数カーソルの並びを崩さないよう、そのときはカーソルを動かさない
+                    if self.cursors.is_empty() {
+                        self.cursor = pos.min(self.document.len().saturating_sub(1));
+                    }
                     self.ensure_cursor_visible();
                     self.status_message = Some("Redo".to_string());
                 } else {
@@ -836,29 +2487,20 @@ impl App {
                 }
             }
             // 検索
-            Action::StartSearch => {
-                self.search_mode = true;
-                self.search_query.clear();
-                self.search_start_pos = self.cursor;
-            }
-            Action::StartSearchBack => {
+            Action::StartSearch | Action::StartSearchBack => {
                 self.search_mode = true;
+                self.search_backward = action == Action::StartSearchBack;
                 self.search_query.clear();
                 self.search_start_pos = self.cursor;
+                // 選択中なら選択範囲の中だけを検索
+                self.search_region = self.selection;
             }
-            Action::SearchNext => {
-                if !self.search_query.is_empty() {
-                    self.find_next();
-                }
-            }
-            Action::SearchPrev => {
-                if !self.search_query.is_empty() {
-                    self.find_prev();
-                }
-            }
+            Action::SearchNext if !self.search_query.is_empty() => self.find_next(),
+            Action::SearchPrev if !self.search_query.is_empty() => self.find_prev(),
             // 置換
             Action::StartReplace => {
                 self.replace_mode = ReplaceMode::EnteringSearch;
+                self.search_literal = false;
                 self.search_query.clear();
                 self.replace_with.clear();
                 self.search_start_pos = self.cursor;
@@ -872,6 +2514,7 @@ impl App {
             Action::OpenFile => {
                 self.prompt_mode = PromptMode::OpenFile;
                 self.prompt_input.clear();
+                self.recent_index = None;
             }
             // 別名保存
             Action::SaveAs => {
@@ -879,6 +2522,11 @@ impl App {
                 // 現在のファイル名をデフォルトに
                 self.prompt_input = self.document.filename().unwrap_or("").to_string();
             }
+            // ファイルを挿入
+            Action::InsertFile => {
+                self.prompt_mode = PromptMode::InsertFile;
+                self.prompt_input.clear();
+            }
             // バッファを閉じる
             Action::KillBuffer => {
                 if self.document.is_modified() {
@@ -887,11 +2535,27 @@ impl App {
                     self.do_kill_buffer();
                 }
             }
+            // バッファ切り替え
+            Action::NextBuffer => self.switch_buffer(true),
+            Action::PrevBuffer => self.switch_buffer(false),
+            // 比較モード
+            Action::NextDiff => self.goto_diff(true),
+            Action::PrevDiff => self.goto_diff(false),
+            // ブックマーク
+            Action::SetBookmark => self.toggle_bookmark(),
+            Action::JumpBookmark => self.jump_bookmark(),
+            // パネル
+            Action::UndoHistory => self.open_panel(Panel::UndoHistory),
+            Action::Inspector => self.open_panel(Panel::Inspector),
+            Action::ListBookmarks => self.open_panel(Panel::Bookmarks),
+            Action::Minimap => self.open_panel(Panel::Minimap),
+            Action::Help => self.help = Some(0),
             // コマンド実行 (M-x)
             Action::ExecuteCommand => {
                 self.prompt_mode = PromptMode::Command;
                 self.prompt_input.clear();
-                self.current_command.clear();
+                self.current_command = None;
+                self.command_selected = 0;
             }
             _ => {}
         }
@@ -899,89 +2563,156 @@ impl App {
 
     /// イベントを処理
     pub fn handle_event(&mut self) -> Result<()> {
-        if event::poll(std::time::Duration::from_millis(100))? {
-            match event::read()? {
-                // ペーストイベント（Bracketed Paste Mode）
-                Event::Paste(content) => {
-                    if self.search_mode {
-                        // 検索モード中はクエリに追加
-                        self.search_query.push_str(&content);
-                        self.do_incremental_search();
-                    } else {
-                        self.paste_from_terminal(&content);
-                    }
+        // 保存中は待たずに処理を進め、検索中・文字列の抽出中は結果をこまめに確認する
+        let timeout = if self.saving.is_some() {
+            0
+        } else if self.searching.is_some() || self.scanning_strings() {
+            20
+        } else {
+            100
+        };
+        if event::poll(Duration::from_millis(timeout))? {
+            let event = event::read()?;
+            self.process_event(event);
+        }
+        Ok(())
+    }
+
+    /// 1つの入力イベントを処理
+    fn process_event(&mut self, event: Event) {
+        // 保存中は C-g / Escape での中断のみ受け付ける
+        if self.saving.is_some() {
+            if let Event::Key(key) = event
+                && key.kind == KeyEventKind::Press
+                && (key.code == KeyCode::Esc
+                    || (key.code == KeyCode::Char('g')
+                        && key.modifiers.contains(KeyModifiers::CONTROL)))
+            {
+                self.saving = None;
+                self.status_message = Some("Save cancelled".to_string());
+            }
+            return;
+        }
+        // 検索の結果待ち（I-search 以外）は C-g / Escape での中断のみ受け付ける
+        if self.searching.is_some() && !self.search_mode {
+            if let Event::Key(key) = event
+                && key.kind == KeyEventKind::Press
+                && (key.code == KeyCode::Esc
+                    || (key.code == KeyCode::Char('g')
+                        && key.modifiers.contains(KeyModifiers::CONTROL)))
+            {
+                self.searching = None;
+                self.status_message = Some("Search cancelled".to_string());
+            }
+            return;
+        }
+        // execute を通らない入力の前に入力途中のバイトを確定し、
+        // その入力による編集が入力中のバイトのUndoグループに混ざらないようにする
+        if self.bypasses_execute(&event) {
+            self.finish_hex_input();
+        }
+        match event {
+            // ペーストイベント（Bracketed Paste Mode）
+            Event::Paste(content) => {
+                if self.search_mode {
+                    // 検索モード中はクエリに追加
+                    self.search_literal = false;
+                    self.search_query.push_str(&content);
+                    self.do_incremental_search();
+                } else {
+                    self.paste_from_terminal(&content);
+                }
+            }
+            // キーイベント
+            Event::Key(key) => {
+                if key.kind != KeyEventKind::Press {
+                    return;
                 }
-                // キーイベント
-                Event::Key(key) => {
-                    if key.kind != KeyEventKind::Press {
-                        return Ok(());
-                    }
 
-                    // 検索モード中は特別な処理
-                    if self.search_mode {
-                        self.handle_search_key(key);
-                        return Ok(());
-                    }
+                // 検索モード中は特別な処理
+                if self.search_mode {
+                    self.handle_search_key(key);
+                    return;
+                }
 
-                    // 置換モード中は特別な処理
-                    if self.replace_mode != ReplaceMode::Off {
-                        self.handle_replace_key(key);
-                        return Ok(());
-                    }
+                // 置換モード中は特別な処理
+                if self.replace_mode != ReplaceMode::Off {
+                    self.handle_replace_key(key);
+                    return;
+                }
 
-                    // プロンプトモード中は特別な処理
-                    if self.prompt_mode != PromptMode::Off {
-                        self.handle_prompt_key(key);
-                        return Ok(());
-                    }
+                // プロンプトモード中は特別な処理
+                if self.prompt_mode != PromptMode::Off {
+                    self.handle_prompt_key(key);
+                    return;
+                }
 
-                    // 確認モード中は特別な処理
-                    if self.confirm_mode != ConfirmMode::Off {
-                        self.handle_confirm_key(key);
-                        return Ok(());
-                    }
+                // 確認モード中は特別な処理
+                if self.confirm_mode != ConfirmMode::Off {
+                    self.handle_confirm_key(key);
+                    return;
+                }
 
-                    let mods = KeyMod {
-                        ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
-                        shift: key.modifiers.contains(KeyModifiers::SHIFT),
-                        alt: key.modifiers.contains(KeyModifiers::ALT),
-                    };
+                // ヘルプ表示中はスクロールと閉じる操作のみ
+                if self.help.is_some() {
+                    self.handle_help_key(key);
+                    return;
+                }
 
-                    // プレフィックスキー状態に応じて処理を分岐
-                    let action = match self.prefix_key {
-                        PrefixKey::None => Action::from_key(key.code, mods),
-                        PrefixKey::CtrlX => {
-                            self.prefix_key = PrefixKey::None; // プレフィックス状態をリセット
-                            Action::from_key_after_ctrl_x(key.code, mods)
-                        }
-                    };
+                // パネル表示中はパネルで処理
+                if self.panel.is_some() && !self.inspector_passes_key(&key) {
+                    self.handle_panel_key(key);
+                    return;
+                }
 
-                    if action != Action::None {
-                        self.execute(action);
-                    } else if let KeyCode::Char(ch) = key.code {
-                        // 修飾キーがなければ文字入力
-                        if !mods.ctrl && !mods.alt {
-                            if self.hex_mode {
-                                self.execute(Action::InputHex(ch));
-                            } else {
-                                self.execute(Action::InputAscii(ch));
-                            }
+                let mods = KeyMod {
+                    ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
+                    shift: key.modifiers.contains(KeyModifiers::SHIFT),
+                    alt: key.modifiers.contains(KeyModifiers::ALT),
+                };
+
+                // プレフィックスキー状態に応じて処理を分岐
+                let action = match self.prefix_key {
+                    PrefixKey::None => Action::from_key(key.code, mods),
+                    PrefixKey::CtrlX => {
+                        self.prefix_key = PrefixKey::None; // プレフィックス状態をリセット
+                        Action::from_key_after_ctrl_x(key.code, mods)
+                    }
+                    PrefixKey::CtrlXR => {
+                        self.prefix_key = PrefixKey::None;
+                        Action::from_key_after_ctrl_x_r(key.code, mods)
+                    }
+                    PrefixKey::CtrlXW => {
+                        self.prefix_key = PrefixKey::None;
+                        Action::from_key_after_ctrl_x_w(key.code, mods)
+                    }
+                };
+
+                if action != Action::None {
+                    self.execute(action);
+                } else if let KeyCode::Char(ch) = key.code {
+                    // 修飾キーがなければ文字入力
+                    if !mods.ctrl && !mods.alt {
+                        if self.hex_mode {
+                            self.execute(Action::InputHex(ch));
+                        } else {
+                            self.execute(Action::InputAscii(ch));
                         }
                     }
                 }
-                // フォーカスイベント
-                Event::FocusGained => {
-                    // フォーカス復帰時：将来的にファイルの外部変更チェックを行う
-                    self.status_message = Some("Focus gained".to_string());
-                }
-                Event::FocusLost => {
-                    // フォーカス喪失時：特に何もしない
-                }
-                // その他のイベントは無視
-                _ => {}
             }
+            Event::Mouse(mouse) => self.handle_mouse(mouse),
+            // フォーカスイベント
+            Event::FocusGained => {
+                // フォーカス復帰時：将来的にファイルの外部変更チェックを行う
+                self.status_message = Some("Focus gained".to_string());
+            }
+            Event::FocusLost => {
+                // フォーカス喪失時：特に何もしない
+            }
+            // その他のイベントは無視
+            _ => {}
         }
-        Ok(())
     }
 
     /// 検索モード中のキー処理
@@ -992,6 +2723,8 @@ impl App {
             // Escape / C-g: 検索キャンセル
             KeyCode::Esc | KeyCode::Char('g') if ctrl => {
                 self.search_mode = false;
+                self.search_region = None;
+                self.searching = None;
                 self.cursor = self.search_start_pos;
                 self.ensure_cursor_visible();
                 self.status_message = Some("Cancelled".to_string());
@@ -999,34 +2732,47 @@ impl App {
             // Enter: 検索確定
             KeyCode::Enter => {
                 self.search_mode = false;
+                self.search_region = None;
                 if !self.search_query.is_empty() {
                     // 検索クエリを保存
                     self.last_search_query = self.search_query.clone();
                     self.status_message = Some(format!("I-search: {}", self.search_query));
+                    // 結果待ちの検索は終わったときにメッセージで知らせる
+                    if let Some(task) = self.searching.as_mut() {
+                        task.report = Some(String::new());
+                    }
                 } else {
                     self.status_message = Some("Search cancelled".to_string());
                 }
             }
-            // C-s: 次を検索
+            // C-s: 次を検索（後方検索中なら前方へ切り替え）
             KeyCode::Char('s') if ctrl => {
+                self.search_backward = false;
                 // クエリが空なら前回の検索クエリを使用
                 if self.search_query.is_empty() && !self.last_search_query.is_empty() {
                     self.search_query = self.last_search_query.clone();
                 }
                 self.find_next();
             }
-            // C-r: 前を検索
+            // C-r: 前を検索（前方検索中なら後方へ切り替え）
             KeyCode::Char('r') if ctrl => {
+                self.search_backward = true;
                 // クエリが空なら前回の検索クエリを使用
                 if self.search_query.is_empty() && !self.last_search_query.is_empty() {
                     self.search_query = self.last_search_query.clone();
                 }
                 self.find_prev();
             }
+            // M-e: テキストをバッファのエンコーディングで検索するか切り替え
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.search_encoded = !self.search_encoded;
+                self.do_incremental_search();
+            }
             // Backspace: 1文字削除
             KeyCode::Backspace => {
                 self.search_query.pop();
                 if self.search_query.is_empty() {
+                    self.searching = None;
                     self.cursor = self.search_start_pos;
                     self.ensure_cursor_visible();
                 } else {
@@ -1035,6 +2781,7 @@ impl App {
             }
             // 文字入力
             KeyCode::Char(ch) if !ctrl => {
+                self.search_literal = false;
                 self.search_query.push(ch);
                 self.do_incremental_search();
             }
@@ -1049,16 +2796,20 @@ impl App {
             return;
         }
 
-        let data = self.document.data();
-        // 検索開始位置から検索
-        if let Some(pos) = Self::find_pattern(data, &pattern, self.search_start_pos) {
-            self.cursor = pos;
-            self.ensure_cursor_visible();
-        } else if let Some(pos) = Self::find_pattern(data, &pattern, 0) {
-            // ラップアラウンド
-            self.cursor = pos;
-            self.ensure_cursor_visible();
-        }
+        // 前方は検索開始位置以降に始まる一致、後方はそれより前に始まる一致から探す
+        let (first, last) = self.search_bounds();
+        let (from, direction) = if self.search_backward {
+            ((self.search_start_pos + pattern.len()).saturating_sub(1), search::Direction::Backward)
+        } else {
+            (self.search_start_pos, search::Direction::Forward)
+        };
+        let query = search::Query {
+            pattern,
+            range: first..last,
+            from,
+            direction,
+        };
+        self.start_search(query, None);
     }
 
     /// 置換モード中のキー処理
@@ -1166,7 +2917,7 @@ impl App {
         let data = self.document.data();
         let start = self.cursor;
 
-        if let Some(pos) = Self::find_pattern(data, &pattern, start) {
+        if let Some(pos) = search::find(data, &pattern, start) {
             self.cursor = pos;
             self.ensure_cursor_visible();
             self.status_message = Some(format!(
@@ -1190,25 +2941,24 @@ impl App {
         }
 
         // 現在位置が検索パターンとマッチするか確認
-        if let Some(data) = self.document.get_range(self.cursor, self.cursor + from_bytes.len()) {
-            if data == from_bytes {
-                // 削除（末尾から）
-                for i in (0..from_bytes.len()).rev() {
-                    let _ = self.document.delete(self.cursor + i);
-                }
-                // 挿入
-                for (i, &byte) in to_bytes.iter().enumerate() {
-                    let _ = self.document.insert(self.cursor + i, byte);
-                }
-                // カーソルを置換後の末尾に移動
-                self.cursor += to_bytes.len();
-            }
+        if let Some(data) = self.document.get_range(self.cursor, self.cursor + from_bytes.len())
+            && data == from_bytes
+        {
+            self.document.begin_group();
+            let _ = self
+                .document
+                .delete_range(self.cursor, self.cursor + from_bytes.len());
+            let _ = self.document.insert_bytes(self.cursor, &to_bytes);
+            self.document.end_group();
+            // カーソルを置換後の末尾に移動
+            self.cursor += to_bytes.len();
         }
     }
 
     /// 残り全てを置換
     fn do_replace_all_remaining(&mut self) {
         let mut count = 0;
+        self.document.begin_group();
         loop {
             let from_bytes = self.search_query_to_bytes();
             if from_bytes.is_empty() {
@@ -1218,7 +2968,7 @@ impl App {
             let data = self.document.data();
             let start = self.cursor;
 
-            if let Some(pos) = Self::find_pattern(data, &from_bytes, start) {
+            if let Some(pos) = search::find(data, &from_bytes, start) {
                 self.cursor = pos;
                 self.do_replace_current();
                 count += 1;
@@ -1226,6 +2976,7 @@ impl App {
                 break;
             }
         }
+        self.document.end_group();
 
         self.replace_mode = ReplaceMode::Off;
         self.status_message = Some(format!("Replaced {} occurrences", count));
@@ -1235,9 +2986,10 @@ impl App {
     fn replace_with_to_bytes(&self) -> Vec<u8> {
         let trimmed = self.replace_with.trim();
         if Self::looks_like_hex(trimmed) {
-            Self::normalized_hex_to_bytes(trimmed).unwrap_or_else(|| self.replace_with.as_bytes().to_vec())
+            Self::normalized_hex_to_bytes(trimmed)
+                .unwrap_or_else(|| self.search_text_to_bytes(&self.replace_with))
         } else {
-            self.replace_with.as_bytes().to_vec()
+            self.search_text_to_bytes(&self.replace_with)
         }
     }
 
@@ -1258,15 +3010,87 @@ impl App {
             // Backspace
             KeyCode::Backspace => {
                 self.prompt_input.pop();
+                self.command_selected = 0;
+            }
+            // ファイルを開く: 上下 / M-p M-n で最近のファイル、Tab で補完
+            KeyCode::Up if self.prompt_mode == PromptMode::OpenFile => self.cycle_recent(true),
+            KeyCode::Down if self.prompt_mode == PromptMode::OpenFile => self.cycle_recent(false),
+            KeyCode::Char('p')
+                if self.prompt_mode == PromptMode::OpenFile
+                    && key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                self.cycle_recent(true)
+            }
+            KeyCode::Char('n')
+                if self.prompt_mode == PromptMode::OpenFile
+                    && key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                self.cycle_recent(false)
             }
+            KeyCode::Tab if self.prompt_mode == PromptMode::OpenFile => self.complete_recent(),
+            // M-x: 上下 / C-p C-n で候補を選択、Tab で補完
+            KeyCode::Up if self.prompt_mode == PromptMode::Command => {
+                self.command_selected = self.command_selected.saturating_sub(1);
+            }
+            KeyCode::Char('p') if ctrl && self.prompt_mode == PromptMode::Command => {
+                self.command_selected = self.command_selected.saturating_sub(1);
+            }
+            KeyCode::Down if self.prompt_mode == PromptMode::Command => {
+                let count = self.command_candidates().len();
+                self.command_selected = (self.command_selected + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Char('n') if ctrl && self.prompt_mode == PromptMode::Command => {
+                let count = self.command_candidates().len();
+                self.command_selected = (self.command_selected + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Tab if self.prompt_mode == PromptMode::Command => self.complete_command(),
             // 文字入力
             KeyCode::Char(ch) if !ctrl => {
                 self.prompt_input.push(ch);
+                self.command_selected = 0;
             }
             _ => {}
         }
     }
 
+    /// 最近開いたファイルを順に入力欄へ（older = true で古い方へ）
+    fn cycle_recent(&mut self, older: bool) {
+        let count = self.sessions.recent().count();
+        if count == 0 {
+            self.status_message = Some("No recent files".to_string());
+            return;
+        }
+        let index = match (self.recent_index, older) {
+            (None, true) => 0,
+            (None, false) => return,
+            (Some(i), true) => (i + 1).min(count - 1),
+            (Some(0), false) => {
+                self.recent_index = None;
+                self.prompt_input.clear();
+                return;
+            }
+            (Some(i), false) => i - 1,
+        };
+        if let Some(path) = self.sessions.recent().nth(index) {
+            self.prompt_input = path.display().to_string();
+            self.recent_index = Some(index);
+        }
+    }
+
+    /// 入力中の文字列を含む最近のファイルで補完
+    fn complete_recent(&mut self) {
+        let input = self.prompt_input.clone();
+        let found = self
+            .sessions
+            .recent()
+            .map(|path| path.display().to_string())
+            .find(|path| *path != input && path.contains(&input));
+        match found {
+            Some(path) => self.prompt_input = path,
+            None => self.status_message = Some("No matching recent file".to_string()),
+        }
+    }
+
     /// プロンプト入力を実行
     fn execute_prompt(&mut self) {
         let input = self.prompt_input.clone();
@@ -1288,6 +3112,24 @@ impl App {
             PromptMode::SaveAs => {
                 self.save_as(&input);
             }
+            PromptMode::InsertFile => {
+                self.insert_file(&input);
+            }
+            PromptMode::InspectorValue => {
+                self.write_inspector_value(&input);
+            }
+            PromptMode::CompareFile => {
+                let path = expand_path(input.trim());
+                if let Err(e) = self.open_compare(&path) {
+                    self.status_message = Some(format!("Failed to open: {}", e));
+                }
+            }
+            PromptMode::BaselineFile => {
+                let path = expand_path(input.trim());
+                if let Err(e) = self.open_baseline(&path) {
+                    self.status_message = Some(format!("Failed to open: {}", e));
+                }
+            }
             PromptMode::Command => {
                 self.dispatch_command(&input);
             }
@@ -1298,239 +3140,785 @@ impl App {
         }
     }
 
-    /// コマンドをディスパッチ
-    fn dispatch_command(&mut self, cmd: &str) {
-        let cmd = cmd.trim().to_lowercase();
-        match cmd.as_str() {
-            // 引数不要なコマンド
-            "goto" | "g" => {
-                self.prompt_mode = PromptMode::GotoAddress;
-                self.prompt_input.clear();
-            }
-            "save" | "s" => {
-                if let Err(e) = self.document.save() {
-                    self.status_message = Some(format!("Save failed: {}", e));
-                } else {
-                    self.status_message = Some("Saved".to_string());
+    /// M-x の入力を実行（名前か別名が一致しなければ候補一覧で選択中のコマンド）
+    fn dispatch_command(&mut self, input: &str) {
+        let input = input.trim().to_lowercase();
+        if input.is_empty() {
+            return;
+        }
+        let exact = find_command(&input).filter(|_| self.command_selected == 0);
+        let Some(command) =
+            exact.or_else(|| complete_commands(&input).get(self.command_selected).copied())
+        else {
+            self.status_message = Some(format!("Unknown command: {} (try 'help')", input));
+            return;
+        };
+        self.start_command(command);
+    }
+
+    /// コマンドを実行（引数が要るなら入力を始める）
+    fn start_command(&mut self, command: &'static Command) {
+        match command.kind {
+            CommandKind::Run(run) => run(self),
+            CommandKind::Arg { ready, .. } => {
+                if ready(self) {
+                    self.current_command = Some(command);
+                    self.prompt_mode = PromptMode::CommandArg;
+                    self.prompt_input.clear();
                 }
             }
-            "quit" | "q" => {
-                self.execute(Action::Quit);
+        }
+    }
+
+    /// コマンドを引数付きで実行
+    fn execute_command_with_arg(&mut self, arg: &str) {
+        if let Some(command) = self.current_command.take()
+            && let CommandKind::Arg { run, .. } = command.kind
+        {
+            run(self, arg);
+        }
+    }
+
+    /// M-x の入力に一致するコマンドの候補
+    fn command_candidates(&self) -> Vec<&'static Command> {
+        complete_commands(&self.prompt_input.trim().to_lowercase())
+    }
+
+    /// M-x の候補一覧で Tab: 選択中のコマンド名を入力欄へ
+    fn complete_command(&mut self) {
+        match self.command_candidates().get(self.command_selected) {
+            Some(command) => {
+                self.prompt_input = command.name.to_string();
+                self.command_selected = 0;
             }
-            // 引数が必要なコマンド
-            "fill" | "f" => {
-                if self.selection.is_none() {
-                    self.status_message = Some("No selection".to_string());
+            None => self.status_message = Some("No matching command".to_string()),
+        }
+    }
+
+    /// 選択範囲の先頭位置とバイト列（EOF位置は除く）
+    pub(super) fn selected_bytes(&self) -> Option<(usize, Vec<u8>)> {
+        let (start, end) = self.selection?;
+        let end = (end + 1).min(self.document.len());
+        let bytes = self.document.get_range(start, end)?;
+        Some((start, bytes.to_vec()))
+    }
+
+    /// アドレスにジャンプ
+    fn goto_address(&mut self, input: &str) {
+        let input = input.trim();
+        if input.is_empty() {
+            self.status_message = Some("No address".to_string());
+            return;
+        }
+
+        match Self::parse_address(input).and_then(|addr| usize::try_from(addr).ok()) {
+            Some(addr) => {
+                if addr <= self.document.len() {
+                    self.cursor = addr;
+                    self.ensure_cursor_visible();
+                    self.status_message = Some(format!("Jumped to {:08X}", addr));
                 } else {
-                    self.current_command = "fill".to_string();
-                    self.prompt_mode = PromptMode::CommandArg;
-                    self.prompt_input.clear();
+                    self.status_message = Some(format!(
+                        "Address {:X} exceeds file size {:X}",
+                        addr,
+                        self.document.len()
+                    ));
                 }
             }
-            "insert" | "i" => {
-                self.current_command = "insert".to_string();
-                self.prompt_mode = PromptMode::CommandArg;
-                self.prompt_input.clear();
+            None => {
+                self.status_message = Some("Invalid address".to_string());
             }
-            "help" | "?" | "h" => {
-                self.status_message = Some(
-                    "Commands: fill(f) insert(i) goto(g) save(s) quit(q) help(?)".to_string()
-                );
+        }
+    }
+
+    /// アドレスをパース（0x 接頭辞・h 接尾辞・A-Fを含む場合は16進、それ以外は10進）
+    pub(super) fn parse_address(input: &str) -> Option<u64> {
+        if input.starts_with("0x") || input.starts_with("0X") {
+            u64::from_str_radix(&input[2..], 16).ok()
+        } else if input.ends_with('h') || input.ends_with('H') {
+            u64::from_str_radix(&input[..input.len() - 1], 16).ok()
+        } else if input.chars().all(|c| c.is_ascii_hexdigit()) && input.chars().any(|c| c.is_ascii_alphabetic()) {
+            // A-Fを含む場合は16進数として解釈
+            u64::from_str_radix(input, 16).ok()
+        } else {
+            // 10進数
+            input.parse().ok()
+        }
+    }
+
+    /// ファイルを開く
+    fn open_file(&mut self, path: &str) {
+        let path = path.trim();
+        if path.is_empty() {
+            self.status_message = Some("No file specified".to_string());
+            return;
+        }
+
+        let expanded = expand_path(path);
+
+        match self.open(&expanded) {
+            Ok(()) if self.confirm_mode != ConfirmMode::Off => {
+                // 巨大ファイル・スワップファイルの確認待ち
             }
-            "" => {
-                // 空入力は無視
+            Ok(()) => {
+                self.status_message = Some(format!("Opened: {}", expanded.display()));
             }
-            _ => {
-                self.status_message = Some(format!("Unknown command: {} (try 'help')", cmd));
+            Err(e) => {
+                self.status_message = Some(format!("Failed to open: {}", e));
             }
         }
     }
 
-    /// コマンドを引数付きで実行
-    fn execute_command_with_arg(&mut self, arg: &str) {
-        let cmd = self.current_command.clone();
-        self.current_command.clear();
-
-        match cmd.as_str() {
-            "fill" => {
-                self.cmd_fill(arg);
+    /// パネルを開く
+    pub(super) fn open_panel(&mut self, panel: Panel) {
+        self.panel = Some(panel);
+        match panel {
+            Panel::UndoHistory => {
+                self.panel_selected = self.panel_items().len().saturating_sub(1);
+                self.status_message = Some(if self.panel_items().is_empty() {
+                    "No edits".to_string()
+                } else {
+                    "Enter: jump  r: revert to selected  q: close".to_string()
+                });
             }
-            "insert" => {
-                self.cmd_insert(arg);
+            Panel::Inspector => {
+                self.panel_selected = self.panel_selected.min(inspector::FIELDS.len() - 1);
+                self.status_message =
+                    Some("Enter/digits: edit value  Left/Right: move  q: close".to_string());
             }
-            _ => {
-                self.status_message = Some(format!("Unknown command: {}", cmd));
+            Panel::Minimap => {
+                self.minimap.get_or_insert_default();
+                self.panel_selected =
+                    ui::block_of(self.document.len(), self.minimap_blocks(), self.cursor);
+                self.status_message =
+                    Some("Up/Down: select block  Enter: jump  q: close".to_string());
+            }
+            Panel::Strings => {
+                // カーソル位置以降の最初の文字列を選択（抽出が進むのに合わせて選択し直す）
+                self.step_strings();
+                if let Some(ref mut task) = self.strings {
+                    task.select_from = Some(self.cursor);
+                }
+                self.select_found_string();
+                self.status_message = Some("Enter: jump  q: close".to_string());
+            }
+            Panel::Structure => {
+                let Some(mut tree) = self.structure.take() else {
+                    self.panel = None;
+                    self.status_message = Some("No template loaded".to_string());
+                    return;
+                };
+                match tree.template.apply(self.document.data()) {
+                    Ok(nodes) => {
+                        if nodes.len() != tree.nodes.len() {
+                            tree.collapsed.clear();
+                        }
+                        tree.nodes = nodes;
+                        self.status_message = Some(
+                            "Enter: select  Left/Right: fold  Space: toggle  q: close".to_string(),
+                        );
+                    }
+                    Err(e) => {
+                        tree.nodes.clear();
+                        self.status_message = Some(format!("{}: {}", tree.name, e));
+                    }
+                }
+                self.structure = Some(tree);
+                // カーソル位置を含む最も深いフィールドを選択
+                let rows = self.structure_rows();
+                self.panel_selected = rows
+                    .iter()
+                    .rposition(|&i| self.structure_node(i).is_some_and(|n| n.offset <= self.cursor))
+                    .unwrap_or(0);
+            }
+            #[cfg(feature = "disasm")]
+            Panel::Disasm => {
+                self.panel_selected = 0;
+                self.status_message =
+                    Some("Enter: jump  a: switch architecture  q: close".to_string());
+            }
+            Panel::Bookmarks => {
+                // カーソル位置以降の最初のブックマークを選択
+                self.panel_selected = self
+                    .bookmarks
+                    .partition_point(|&pos| pos < self.cursor)
+                    .min(self.bookmarks.len().saturating_sub(1));
+                self.status_message = Some(if self.bookmarks.is_empty() {
+                    "No bookmarks (C-x r m to set)".to_string()
+                } else {
+                    "Enter: jump  d: delete  q: close".to_string()
+                });
             }
         }
     }
 
-    /// fill コマンド: 選択範囲を指定バイトで埋める
-    fn cmd_fill(&mut self, arg: &str) {
-        let arg = arg.trim();
-
-        // バイト値をパース
-        let byte = if arg.starts_with("0x") || arg.starts_with("0X") {
-            u8::from_str_radix(&arg[2..], 16).ok()
-        } else if arg.len() == 2 && arg.chars().all(|c| c.is_ascii_hexdigit()) {
-            u8::from_str_radix(arg, 16).ok()
+    /// ミニマップの表示を切り替える（別の色分けで表示中なら切り替えるだけ）
+    pub(super) fn toggle_minimap(&mut self, mode: MinimapMode) {
+        if self.minimap == Some(mode) {
+            self.minimap = None;
+            if self.panel == Some(Panel::Minimap) {
+                self.panel = None;
+            }
+            self.status_message = Some("Minimap off".to_string());
         } else {
-            arg.parse().ok()
-        };
+            self.minimap = Some(mode);
+            self.status_message = Some(match mode {
+                MinimapMode::Entropy => "Minimap: entropy (M-m: jump)".to_string(),
+                MinimapMode::ByteClass => "Minimap: byte class (M-m: jump)".to_string(),
+            });
+        }
+    }
+
+    /// ミニマップのブロック数（前回描画したときの高さ）
+    fn minimap_blocks(&self) -> usize {
+        self.minimap_cache
+            .as_ref()
+            .map_or(self.visible_rows, |cache| cache.blocks)
+            .max(1)
+    }
 
-        let Some(byte) = byte else {
-            self.status_message = Some("Invalid byte value".to_string());
+    /// 文字列の抽出の結果を受け取る（編集されていたら最初からやり直す）
+    fn step_strings(&mut self) {
+        let revision = self.document.revision();
+        if self.strings.as_ref().is_none_or(|task| task.revision != revision) {
+            self.strings = Some(StringsTask {
+                revision,
+                job: StringsJob::spawn(self.document.snapshot(), strings::MIN_LENGTH),
+                select_from: None,
+            });
+        }
+        if let Some(ref mut task) = self.strings
+            && task.job.poll()
+        {
+            self.select_found_string();
+        }
+    }
+
+    /// 文字列一覧で select_from 以降の最初の文字列を選択する
+    fn select_found_string(&mut self) {
+        let Some(ref mut task) = self.strings else {
             return;
         };
-
-        let Some((start, end)) = self.selection else {
-            self.status_message = Some("No selection".to_string());
+        let Some(pos) = task.select_from else {
             return;
         };
+        let found = task.job.found();
+        let index = found.partition_point(|s| s.offset < pos);
+        self.panel_selected = index.min(found.len().saturating_sub(1));
+        if index < found.len() || task.job.is_done() {
+            task.select_from = None;
+        }
+    }
+
+    /// 文字列一覧を表示中で、抽出が終わっていないか
+    fn scanning_strings(&self) -> bool {
+        self.panel == Some(Panel::Strings)
+            && self.strings.as_ref().is_some_and(|task| !task.job.is_done())
+    }
+
+    /// 文字列一覧のパネルの見出し（抽出中は進み具合も表示）
+    fn strings_title(&self) -> String {
+        let Some(ref task) = self.strings else {
+            return " Strings ".to_string();
+        };
+        let count = task.job.found().len();
+        if task.job.is_done() {
+            format!(" Strings ({}) ", count)
+        } else {
+            format!(" Strings ({}, {}%) ", count, task.job.percent())
+        }
+    }
+
+    /// 文字列一覧の first 番目から count 行分の表示行（巨大な一覧でも見える分だけ作る）
+    fn strings_items(&self, first: usize, count: usize) -> Vec<String> {
+        let found = self.strings.as_ref().map_or(&[][..], |task| task.job.found());
+        found
+            .iter()
+            .skip(first)
+            .take(count)
+            .map(|s| {
+                format!("{:08X} {:<5} {}", s.offset, s.kind.name(), s.text.replace('\t', " "))
+            })
+            .collect()
+    }
 
-        // 選択範囲を埋める
-        for i in start..=end {
-            if i < self.document.len() {
-                let _ = self.document.set(i, byte);
+    /// テンプレートを読み込んで構造のパネルを開く（空なら読み込み済みのものを開き直す）
+    /// ファイルが見つからなければ設定ディレクトリの templates/名前.toml を探す
+    pub(super) fn load_template(&mut self, arg: &str) {
+        let arg = arg.trim();
+        if !arg.is_empty() {
+            let path = expand_path(arg);
+            let path = if path.exists() {
+                path
+            } else {
+                template::user_template_path(arg).unwrap_or(path)
+            };
+            match Template::load(&path) {
+                Ok(template) => {
+                    let name = path.file_stem().map_or_else(
+                        || arg.to_string(),
+                        |stem| stem.to_string_lossy().into_owned(),
+                    );
+                    self.structure = Some(StructureTree {
+                        name,
+                        template,
+                        nodes: Vec::new(),
+                        collapsed: BTreeSet::new(),
+                    });
+                }
+                Err(e) => {
+                    self.status_message = Some(format!("{}: {}", path.display(), e));
+                    return;
+                }
             }
         }
+        self.open_panel(Panel::Structure);
+    }
 
-        let count = end - start + 1;
-        self.status_message = Some(format!("Filled {} bytes with {:02X}", count, byte));
-        self.clear_selection();
+    /// 構造の木の i 番目のノード
+    fn structure_node(&self, i: usize) -> Option<&Node> {
+        self.structure.as_ref().and_then(|tree| tree.nodes.get(i))
     }
 
-    /// insert コマンド: 指定サイズのバイトを挿入
-    fn cmd_insert(&mut self, arg: &str) {
-        // フォーマット: "count byte" or "count" (デフォルト 00)
-        let parts: Vec<&str> = arg.trim().split_whitespace().collect();
+    /// 構造のパネルに表示するノードの番号（折りたたんだノードの子孫は除く）
+    fn structure_rows(&self) -> Vec<usize> {
+        let Some(ref tree) = self.structure else {
+            return Vec::new();
+        };
+        let mut rows = Vec::new();
+        let mut i = 0;
+        while i < tree.nodes.len() {
+            rows.push(i);
+            i += if tree.collapsed.contains(&i) { tree.nodes[i].descendants + 1 } else { 1 };
+        }
+        rows
+    }
 
-        let (count, byte) = match parts.len() {
-            1 => {
-                let count = Self::parse_number(parts[0]);
-                (count, Some(0u8))
+    /// 構造のパネルで選択中のノードを折りたたむ / 開く（None なら切り替え）
+    fn fold_structure(&mut self, collapse: Option<bool>) {
+        let rows = self.structure_rows();
+        let Some(&index) = rows.get(self.panel_selected) else {
+            return;
+        };
+        let Some(ref mut tree) = self.structure else {
+            return;
+        };
+        let node = &tree.nodes[index];
+        let collapsed = tree.collapsed.contains(&index);
+        let collapse = collapse.unwrap_or(!collapsed);
+        if node.descendants > 0 && collapse != collapsed {
+            if collapse {
+                tree.collapsed.insert(index);
+            } else {
+                tree.collapsed.remove(&index);
             }
-            2 => {
-                let count = Self::parse_number(parts[0]);
-                let byte = Self::parse_byte(parts[1]);
-                (count, byte)
+        } else if collapse {
+            // 子がないか閉じているノードでは親へ移動
+            let depth = node.depth;
+            if let Some(parent) = rows[..self.panel_selected]
+                .iter()
+                .rposition(|&i| tree.nodes[i].depth < depth)
+            {
+                self.panel_selected = parent;
             }
-            _ => {
-                self.status_message = Some("Usage: insert <count> [byte]".to_string());
+        }
+    }
+
+    /// 構造のパネルで選択中のフィールドにカーソルを移し、範囲を選択する
+    fn select_structure_field(&mut self) {
+        let rows = self.structure_rows();
+        let Some(node) = rows.get(self.panel_selected).and_then(|&i| self.structure_node(i)) else {
+            return;
+        };
+        let (offset, size) = (node.offset, node.size);
+        let message = format!(
+            "{}: {} at {} ({} bytes)",
+            node.name,
+            node.ty,
+            self.format_addr(offset),
+            size
+        );
+        self.cursor = offset.min(self.document.len());
+        self.selection_start = None;
+        self.selection = (size > 0).then(|| (offset, offset + size - 1));
+        self.ensure_cursor_visible();
+        self.status_message = Some(message);
+    }
+
+    /// インスペクタで選択中の項目
+    fn inspector_field(&self) -> Field {
+        inspector::FIELDS[self.panel_selected.min(inspector::FIELDS.len() - 1)]
+    }
+
+    /// インスペクタの値入力を開始（initial は入力欄の初期値）
+    fn start_inspector_edit(&mut self, initial: &str) {
+        if !self.check_writable() {
+            return;
+        }
+        self.prompt_mode = PromptMode::InspectorValue;
+        self.prompt_input = initial.to_string();
+    }
+
+    /// インスペクタで入力した値をカーソル位置に書き込む（1回のUndoで戻せる）
+    fn write_inspector_value(&mut self, input: &str) {
+        let field = self.inspector_field();
+        let bytes = match field.encode(input) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
                 return;
             }
         };
 
-        let Some(count) = count else {
-            self.status_message = Some("Invalid count".to_string());
-            return;
-        };
+        // 挿入モードでも値の書き換えは上書きにする（EOFを超えた分は追加）
+        let pos = self.cursor;
+        let split = bytes.len().min(self.document.len().saturating_sub(pos));
+        self.document.begin_group();
+        let _ = self.document.set_range(pos, &bytes[..split]);
+        let _ = self.document.insert_bytes(pos + split, &bytes[split..]);
+        self.document.end_group();
+
+        self.status_message = Some(format!(
+            "{} at {:08X} = {}",
+            field.name(),
+            pos,
+            input.trim()
+        ));
+    }
 
-        let Some(byte) = byte else {
-            self.status_message = Some("Invalid byte value".to_string());
+    /// カーソル位置の値を delta だけ増減
+    /// インスペクタを開いていれば選択中の型、そうでなければ1バイトを対象にする
+    fn step_value(&mut self, delta: i64) {
+        let field = match self.panel {
+            Some(Panel::Inspector) => self.inspector_field(),
+            // FIELDS の先頭は u8
+            _ => inspector::FIELDS[0],
+        };
+        let end = (self.cursor + field.ty.size()).min(self.document.len());
+        let stepped = self
+            .document
+            .get_range(self.cursor, end)
+            .and_then(|bytes| field.step(&bytes, delta));
+        let Some(bytes) = stepped else {
+            self.status_message =
+                Some(format!("Not enough bytes for {} at cursor", field.name()));
             return;
         };
-
-        if count == 0 {
-            self.status_message = Some("Count must be > 0".to_string());
+        if let Err(e) = self.document.set_range(self.cursor, &bytes) {
+            self.status_message = Some(format!("Edit failed: {}", e));
             return;
         }
+        self.status_message = Some(format!(
+            "{} at {:08X} = {}",
+            field.name(),
+            self.cursor,
+            field.decode(&bytes).unwrap_or_default()
+        ));
+    }
 
-        // カーソル位置に挿入
-        for i in 0..count {
-            let _ = self.document.insert(self.cursor + i, byte);
+    /// パネルの表示行
+    fn panel_items(&self) -> Vec<String> {
+        match self.panel {
+            Some(Panel::Inspector) => {
+                let end = (self.cursor + 8).min(self.document.len());
+                let bytes = self.document.get_range(self.cursor, end).unwrap_or_default();
+                inspector::FIELDS
+                    .iter()
+                    .map(|field| {
+                        let value = field.decode(&bytes).unwrap_or_else(|| "-".to_string());
+                        format!(" {:<7} {}", field.name(), value)
+                    })
+                    .collect()
+            }
+            Some(Panel::Bookmarks) => self
+                .bookmarks
+                .iter()
+                .enumerate()
+                .map(|(i, &pos)| {
+                    let end = (pos + 4).min(self.document.len());
+                    let bytes = self.document.get_range(pos, end).unwrap_or_default();
+                    format!("{:>4} {:08X} {}", i + 1, pos, Self::preview_bytes(&bytes))
+                })
+                .collect(),
+            Some(Panel::UndoHistory) => self
+                .document
+                .undo_history()
+                .enumerate()
+                .map(|(i, entry)| {
+                    let detail = match entry.kind {
+                        EditKind::Set => format!(
+                            "Set {} -> {}",
+                            Self::preview_bytes(entry.old),
+                            Self::preview_bytes(entry.new)
+                        ),
+                        EditKind::Insert => format!("Ins {}", Self::preview_bytes(entry.new)),
+                        EditKind::Delete => format!("Del {}", Self::preview_bytes(entry.old)),
+                        EditKind::Group(n) => format!("Group ({} edits)", n),
+                    };
+                    format!("{:>4} {:08X} {}", i + 1, entry.offset, detail)
+                })
+                .collect(),
+            Some(Panel::Structure) => {
+                let Some(ref tree) = self.structure else {
+                    return Vec::new();
+                };
+                self.structure_rows()
+                    .into_iter()
+                    .map(|i| {
+                        let node = &tree.nodes[i];
+                        let marker = match (node.descendants, tree.collapsed.contains(&i)) {
+                            (0, _) => " ",
+                            (_, true) => "+",
+                            (_, false) => "-",
+                        };
+                        let mut line = format!(
+                            "{:08X} {}{}{} {}",
+                            node.offset,
+                            "  ".repeat(node.depth),
+                            marker,
+                            node.name,
+                            node.ty
+                        );
+                        if !node.value.is_empty() {
+                            line.push_str(" = ");
+                            line.push_str(&node.value);
+                        }
+                        if !node.label.is_empty() {
+                            line.push_str(&format!(" ({})", node.label));
+                        }
+                        line
+                    })
+                    .collect()
+            }
+            #[cfg(feature = "disasm")]
+            Some(Panel::Disasm) => self
+                .disassemble()
+                .iter()
+                .map(|inst| format!("{:08X} {}", inst.offset, inst.text))
+                .collect(),
+            Some(Panel::Minimap) | Some(Panel::Strings) | None => Vec::new(),
         }
+    }
 
-        self.status_message = Some(format!("Inserted {} bytes of {:02X}", count, byte));
+    /// カーソル位置からパネルの高さ分の命令を逆アセンブル
+    #[cfg(feature = "disasm")]
+    fn disassemble(&self) -> Vec<disasm::Instruction> {
+        // x86_64 の命令は最大15バイト
+        let count = self.visible_rows.max(1);
+        let end = self.cursor.saturating_add(count * 15).min(self.document.len());
+        let bytes = self.document.get_range(self.cursor, end).unwrap_or_default();
+        disasm::disassemble(&bytes, self.cursor, self.disasm_arch, count)
     }
 
-    /// 数値をパース（0x prefix または 10進数）
-    fn parse_number(s: &str) -> Option<usize> {
-        if s.starts_with("0x") || s.starts_with("0X") {
-            usize::from_str_radix(&s[2..], 16).ok()
+    /// 逆アセンブルの命令セットを切り替える（空なら次の命令セットへ）
+    #[cfg(feature = "disasm")]
+    pub(super) fn set_disasm_arch(&mut self, arg: &str) {
+        let arch = if arg.trim().is_empty() {
+            self.disasm_arch.next()
         } else {
-            s.parse().ok()
-        }
+            match Arch::from_name(arg) {
+                Some(arch) => arch,
+                None => {
+                    self.status_message = Some(format!("Unknown architecture: {}", arg.trim()));
+                    return;
+                }
+            }
+        };
+        self.disasm_arch = arch;
+        self.open_panel(Panel::Disasm);
+        self.status_message = Some(format!("Disassembling as {}", arch.name()));
     }
 
-    /// バイト値をパース
-    fn parse_byte(s: &str) -> Option<u8> {
-        if s.starts_with("0x") || s.starts_with("0X") {
-            u8::from_str_radix(&s[2..], 16).ok()
-        } else if s.len() <= 2 && s.chars().all(|c| c.is_ascii_hexdigit()) {
-            u8::from_str_radix(s, 16).ok()
+    /// バイト列の短い表示（長い場合は省略）
+    fn preview_bytes(bytes: &[u8]) -> String {
+        const MAX: usize = 4;
+        let hex = clipboard::bytes_to_hex(&bytes[..bytes.len().min(MAX)], HexFormat::Spaced);
+        if bytes.len() > MAX {
+            format!("{}.. ({} bytes)", hex, bytes.len())
         } else {
-            s.parse().ok()
+            hex
         }
     }
 
-    /// アドレスにジャンプ
-    fn goto_address(&mut self, input: &str) {
-        let input = input.trim();
-        if input.is_empty() {
-            self.status_message = Some("No address".to_string());
+    /// ヘルプ表示中のキー処理
+    fn handle_help_key(&mut self, key: crossterm::event::KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let Some(scroll) = self.help else {
             return;
-        }
+        };
+        // ヘルプの表示行数（画面の高さから上下の余白と枠を除く）
+        let page = (self.visible_rows + 1).saturating_sub(4).max(1);
+        let max = help_lines().len().saturating_sub(page);
+        let scroll = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc | KeyCode::F(1) => {
+                self.help = None;
+                return;
+            }
+            KeyCode::Char('g') if ctrl => {
+                self.help = None;
+                return;
+            }
+            KeyCode::Up | KeyCode::Char('k') => scroll.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => scroll + 1,
+            KeyCode::Char('n') if ctrl => scroll + 1,
+            KeyCode::PageUp => scroll.saturating_sub(page),
+            KeyCode::Char('v') if alt => scroll.saturating_sub(page),
+            KeyCode::PageDown | KeyCode::Char(' ') => scroll + page,
+            KeyCode::Char('v') if ctrl => scroll + page,
+            KeyCode::Home | KeyCode::Char('<') => 0,
+            KeyCode::End | KeyCode::Char('>') => max,
+            _ => scroll,
+        };
+        self.help = Some(scroll.min(max));
+    }
 
-        // 0x プレフィックスまたは h サフィックスで16進数
-        let addr = if input.starts_with("0x") || input.starts_with("0X") {
-            usize::from_str_radix(&input[2..], 16)
-        } else if input.ends_with('h') || input.ends_with('H') {
-            usize::from_str_radix(&input[..input.len()-1], 16)
-        } else if input.chars().all(|c| c.is_ascii_hexdigit()) && input.chars().any(|c| c.is_ascii_alphabetic()) {
-            // A-Fを含む場合は16進数として解釈
-            usize::from_str_radix(input, 16)
-        } else {
-            // 10進数
-            input.parse()
+    /// インスペクタを開いたまま使えるキー（数値引数と C-x に続くキー）
+    fn inspector_passes_key(&self, key: &crossterm::event::KeyEvent) -> bool {
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        self.panel == Some(Panel::Inspector)
+            && (self.prefix_key != PrefixKey::None
+                || (alt && matches!(key.code, KeyCode::Char('0'..='9')))
+                || (ctrl && key.code == KeyCode::Char('x')))
+    }
+
+    /// パネル表示中のキー処理
+    fn handle_panel_key(&mut self, key: crossterm::event::KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let selected = self.panel_selected;
+        let count = match self.panel {
+            Some(Panel::Minimap) => self.minimap_blocks(),
+            Some(Panel::Strings) => {
+                self.strings.as_ref().map_or(0, |task| task.job.found().len())
+            }
+            _ => self.panel_items().len(),
         };
 
-        match addr {
-            Ok(addr) => {
-                if addr <= self.document.len() {
-                    self.cursor = addr;
-                    self.ensure_cursor_visible();
-                    self.status_message = Some(format!("Jumped to {:08X}", addr));
-                } else {
-                    self.status_message = Some(format!(
-                        "Address {:X} exceeds file size {:X}",
-                        addr,
-                        self.document.len()
-                    ));
-                }
+        match key.code {
+            // q / Escape / C-g: 閉じる
+            KeyCode::Char('q') | KeyCode::Esc => self.panel = None,
+            KeyCode::Char('g') if ctrl => self.panel = None,
+            // 上下移動
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.panel_selected = self.panel_selected.saturating_sub(1);
             }
-            Err(_) => {
-                self.status_message = Some("Invalid address".to_string());
+            KeyCode::Char('p') if ctrl => {
+                self.panel_selected = self.panel_selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.panel_selected = (self.panel_selected + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Char('n') if ctrl => {
+                self.panel_selected = (self.panel_selected + 1).min(count.saturating_sub(1));
+            }
+            // インスペクタ: 左右でカーソル移動、数字で値の入力を開始
+            KeyCode::Left if self.panel == Some(Panel::Inspector) => self.cursor_left(),
+            KeyCode::Right if self.panel == Some(Panel::Inspector) => self.cursor_right(),
+            KeyCode::Char('b') if ctrl && self.panel == Some(Panel::Inspector) => {
+                self.cursor_left()
+            }
+            KeyCode::Char('f') if ctrl && self.panel == Some(Panel::Inspector) => {
+                self.cursor_right()
+            }
+            KeyCode::Enter if self.panel == Some(Panel::Inspector) => {
+                self.start_inspector_edit("")
+            }
+            KeyCode::Char(c @ ('0'..='9' | '-' | '.'))
+                if self.panel == Some(Panel::Inspector) =>
+            {
+                self.start_inspector_edit(&c.to_string())
             }
+            // 構造: 左右で折りたたみ、スペースで切り替え
+            KeyCode::Left if self.panel == Some(Panel::Structure) => {
+                self.fold_structure(Some(true))
+            }
+            KeyCode::Right if self.panel == Some(Panel::Structure) => {
+                self.fold_structure(Some(false))
+            }
+            KeyCode::Char(' ') if self.panel == Some(Panel::Structure) => {
+                self.fold_structure(None)
+            }
+            // 逆アセンブル: a で命令セットを切り替え
+            #[cfg(feature = "disasm")]
+            KeyCode::Char('a') if self.panel == Some(Panel::Disasm) => self.set_disasm_arch(""),
+            // Enter: 選択項目の位置へジャンプ
+            KeyCode::Enter => self.panel_jump(),
+            // r: 選択項目の時点まで戻す
+            KeyCode::Char('r') if self.panel == Some(Panel::UndoHistory) => self.panel_revert(),
+            // d: 選択したブックマークを削除
+            KeyCode::Char('d')
+                if self.panel == Some(Panel::Bookmarks)
+                    && self.panel_selected < self.bookmarks.len() =>
+            {
+                let pos = self.bookmarks.remove(self.panel_selected);
+                self.panel_selected =
+                    self.panel_selected.min(self.bookmarks.len().saturating_sub(1));
+                self.status_message = Some(format!("Bookmark removed at {:08X}", pos));
+            }
+            _ => {}
+        }
+
+        // 文字列一覧で選択を動かしたら、届いた結果に合わせて選択し直すのをやめる
+        if self.panel_selected != selected
+            && let Some(ref mut task) = self.strings
+        {
+            task.select_from = None;
+        }
+
+        // 構造のパネルでは選択に合わせてフィールドの範囲を選択する
+        if self.panel == Some(Panel::Structure) && self.panel_selected != selected {
+            self.select_structure_field();
         }
     }
 
-    /// ファイルを開く
-    fn open_file(&mut self, path: &str) {
-        let path = path.trim();
-        if path.is_empty() {
-            self.status_message = Some("No file specified".to_string());
+    /// パネルの選択項目の位置へジャンプ
+    fn panel_jump(&mut self) {
+        if self.panel == Some(Panel::Structure) {
+            self.select_structure_field();
             return;
         }
-
-        // チルダ展開
-        let expanded = if path.starts_with("~/") {
-            if let Some(home) = std::env::var_os("HOME") {
-                PathBuf::from(home).join(&path[2..])
-            } else {
-                PathBuf::from(path)
+        let offset = match self.panel {
+            Some(Panel::UndoHistory) => self
+                .document
+                .undo_entry(self.panel_selected)
+                .map(|entry| entry.offset),
+            Some(Panel::Bookmarks) => self.bookmarks.get(self.panel_selected).copied(),
+            Some(Panel::Minimap) => Some(
+                ui::block_range(self.document.len(), self.minimap_blocks(), self.panel_selected).0,
+            ),
+            Some(Panel::Strings) => self.strings.as_ref().and_then(|task| {
+                task.job.found().get(self.panel_selected).map(|s| s.offset)
+            }),
+            // 逆アセンブルは新しいカーソル位置から表示し直すので先頭を選択
+            #[cfg(feature = "disasm")]
+            Some(Panel::Disasm) => {
+                let offset = self.disassemble().get(self.panel_selected).map(|i| i.offset);
+                self.panel_selected = 0;
+                offset
             }
-        } else {
-            PathBuf::from(path)
+            Some(Panel::Inspector) | Some(Panel::Structure) | None => None,
         };
+        if let Some(offset) = offset {
+            self.cursor = offset.min(self.document.len());
+            self.ensure_cursor_visible();
+            self.status_message = Some(format!("Jumped to {:08X}", self.cursor));
+        }
+    }
 
-        match self.open(&expanded) {
-            Ok(()) => {
-                self.status_message = Some(format!("Opened: {}", expanded.display()));
-            }
-            Err(e) => {
-                self.status_message = Some(format!("Failed to open: {}", e));
-            }
+    /// 選択した編集の直後の状態まで戻す
+    fn panel_revert(&mut self) {
+        if !self.check_writable() {
+            return;
         }
+        let depth = self.panel_selected + 1;
+        let undone = self.document.undo_depth().saturating_sub(depth);
+        if let Some(pos) = self.document.undo_to(depth) {
+            self.cursor = pos.min(self.document.len());
+            self.ensure_cursor_visible();
+        }
+        self.status_message = Some(format!("Reverted {} edits (redo with C-/)", undone));
     }
 
     /// 確認モード中のキー処理
@@ -1539,21 +3927,34 @@ impl App {
             KeyCode::Char(c) => KeyCode::Char(Self::normalize_fullwidth(c)),
             other => other,
         };
+        if let ConfirmMode::LargeFile(..) = self.confirm_mode {
+            self.handle_large_file_key(normalized, key.modifiers);
+            return;
+        }
+        if let ConfirmMode::RecoverSwap(..) = self.confirm_mode {
+            self.handle_recover_swap_key(normalized, key.modifiers);
+            return;
+        }
+        if let ConfirmMode::Locked(..) = self.confirm_mode {
+            self.handle_locked_key(normalized, key.modifiers);
+            return;
+        }
         match normalized {
             // y: 保存して実行
             KeyCode::Char('y') | KeyCode::Char('Y') => {
-                // まず保存
-                if let Err(e) = self.document.save() {
-                    self.status_message = Some(format!("Save failed: {}", e));
-                    self.confirm_mode = ConfirmMode::Off;
-                    return;
-                }
-                // 保存成功したらアクション実行
-                self.execute_confirmed_action();
+                // 保存が完了したらアクション実行
+                let after = std::mem::take(&mut self.confirm_mode);
+                self.save_document(None, after);
             }
             // n: 保存せずに実行
             KeyCode::Char('n') | KeyCode::Char('N') => {
-                self.execute_confirmed_action();
+                if self.confirm_mode == ConfirmMode::Quit {
+                    // 他のバッファの変更も破棄して終了
+                    self.confirm_mode = ConfirmMode::Off;
+                    self.should_quit = true;
+                } else {
+                    self.execute_confirmed_action();
+                }
             }
             // c / Escape / C-g: キャンセル
             KeyCode::Char('c') | KeyCode::Char('C') | KeyCode::Esc => {
@@ -1568,12 +3969,57 @@ impl App {
         }
     }
 
+    /// 巨大ファイル確認中のキー処理
+    fn handle_large_file_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let ConfirmMode::LargeFile(path, _) = self.confirm_mode.clone() else {
+            return;
+        };
+        let result = match code {
+            // l: そのまま全体を読み込む
+            KeyCode::Char('l') | KeyCode::Char('L') => Document::open(&path),
+            // m / r: メモリマップで読み取り専用として開く
+            KeyCode::Char('m') | KeyCode::Char('M') | KeyCode::Char('r') | KeyCode::Char('R') => {
+                Document::open_mmap(&path)
+            }
+            // c / Escape / C-g: キャンセル
+            KeyCode::Char('c') | KeyCode::Char('C') | KeyCode::Esc => {
+                self.confirm_mode = ConfirmMode::Off;
+                self.status_message = Some("Cancelled".to_string());
+                return;
+            }
+            KeyCode::Char('g') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.confirm_mode = ConfirmMode::Off;
+                self.status_message = Some("Cancelled".to_string());
+                return;
+            }
+            _ => return,
+        };
+
+        self.confirm_mode = ConfirmMode::Off;
+        match result {
+            Ok(document) => {
+                let mapped = document.is_mapped();
+                self.set_document(document);
+                self.check_lock();
+                self.status_message = Some(if mapped {
+                    format!("Opened read-only (mmap): {}", path.display())
+                } else {
+                    format!("Opened: {}", path.display())
+                });
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Failed to open: {}", e));
+            }
+        }
+    }
+
     /// 確認後のアクションを実行
     fn execute_confirmed_action(&mut self) {
         let mode = std::mem::take(&mut self.confirm_mode);
         match mode {
             ConfirmMode::Quit => {
-                self.should_quit = true;
+                // 保存後、他に未保存のバッファがあれば続けて確認
+                self.execute(Action::Quit);
             }
             ConfirmMode::OpenFile(path) => {
                 self.open_file(&path);
@@ -1581,46 +4027,106 @@ impl App {
             ConfirmMode::KillBuffer => {
                 self.do_kill_buffer();
             }
-            ConfirmMode::Off => {}
+            ConfirmMode::LargeFile(..)
+            | ConfirmMode::RecoverSwap(..)
+            | ConfirmMode::Locked(..)
+            | ConfirmMode::Off => {}
         }
     }
 
     /// バッファを閉じる（空のバッファにする）
     fn do_kill_buffer(&mut self) {
-        self.document = Document::new();
-        self.cursor = 0;
-        self.offset = 0;
-        self.selection = None;
-        self.selection_start = None;
+        match self.buffers.pop_front() {
+            Some(next) => {
+                self.remove_swap();
+                self.record_session();
+                // 閉じたタブの次のタブが同じ位置に来る（末尾なら先頭へ）
+                self.buffer_index %= self.buffers.len() + 1;
+                self.put_buffer(next);
+            }
+            None => self.set_document(Document::new()),
+        }
         self.status_message = Some("Buffer killed".to_string());
     }
 
-    /// 別名保存
-    fn save_as(&mut self, path: &str) {
+    /// ファイルの内容をカーソル位置に書き込む（1回のUndoで戻せる）
+    fn insert_file(&mut self, path: &str) {
         let path = path.trim();
         if path.is_empty() {
             self.status_message = Some("No file specified".to_string());
             return;
         }
 
-        // チルダ展開
-        let expanded = if path.starts_with("~/") {
-            if let Some(home) = std::env::var_os("HOME") {
-                PathBuf::from(home).join(&path[2..])
-            } else {
-                PathBuf::from(path)
+        let expanded = expand_path(path);
+        let bytes = match std::fs::read(&expanded) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to read: {}", e));
+                return;
             }
-        } else {
-            PathBuf::from(path)
         };
 
-        match self.document.save_as(&expanded) {
-            Ok(()) => {
-                self.status_message = Some(format!("Saved: {}", expanded.display()));
+        self.write_bytes(self.cursor, &bytes);
+        self.clear_selection();
+        self.status_message = Some(format!(
+            "Inserted {} bytes from {}",
+            bytes.len(),
+            expanded.display()
+        ));
+    }
+
+    /// 別名保存
+    fn save_as(&mut self, path: &str) {
+        let path = path.trim();
+        if path.is_empty() {
+            self.status_message = Some("No file specified".to_string());
+            return;
+        }
+
+        let expanded = expand_path(path);
+
+        self.save_document(Some(&expanded), ConfirmMode::Off);
+    }
+
+    /// ステータスバーの書式の項目を文字列にする
+    fn status_field(&self, field: StatusField) -> String {
+        match field {
+            StatusField::File => self.document.filename().unwrap_or("[New]").to_string(),
+            StatusField::Modified => {
+                if self.document.is_modified() {
+                    "[+]".to_string()
+                } else if self.document.is_readonly() {
+                    "[RO]".to_string()
+                } else {
+                    String::new()
+                }
             }
-            Err(e) => {
-                self.status_message = Some(format!("Failed to save: {}", e));
+            StatusField::Offset => self.format_cursor(),
+            StatusField::Length => self.format_addr(self.document.len()),
+            StatusField::Percent => {
+                let percent = self.cursor as u128 * 100 / self.document.len().max(1) as u128;
+                percent.min(100).to_string()
             }
+            StatusField::Mode => {
+                if self.hex_mode { self.byte_format.name() } else { "ASC" }.to_string()
+            }
+            StatusField::Edit => match self.edit_mode {
+                EditMode::Overwrite => "OVR".to_string(),
+                EditMode::Insert => "INS".to_string(),
+            },
+            StatusField::Encoding => self.encoding.name().to_string(),
+            StatusField::Compare => match self.compare {
+                Some(ref other) => format!(" | vs {}", other.filename().unwrap_or("?")),
+                None => String::new(),
+            },
+            StatusField::Value => match self.document.get(self.cursor) {
+                Some(byte) => format!("0x{:02X} ({})", byte, byte),
+                None => "--".to_string(),
+            },
+            StatusField::Selection => match self.selection {
+                Some((start, end)) => format!("{} bytes", end - start + 1),
+                None => String::new(),
+            },
         }
     }
 
@@ -1698,44 +4204,263 @@ impl App {
         format!(" {}", parts.join(" | "))
     }
 
+    /// ミニマップを描画（色は内容が変わったときだけ計算し直す）
+    fn draw_minimap(&mut self, frame: &mut Frame, area: ratatui::layout::Rect, mode: MinimapMode) {
+        let blocks = area.height as usize;
+        let revision = self.document.revision();
+        let theme = self.theme.1;
+        let stale = self.minimap_cache.as_ref().is_none_or(|cache| {
+            cache.revision != revision
+                || cache.blocks != blocks
+                || cache.mode != mode
+                || cache.theme != theme
+        });
+        if stale {
+            let document = &self.document;
+            let colors = ui::block_colors(
+                document.len(),
+                |start, end| document.get_range(start, end).unwrap_or_default(),
+                blocks,
+                mode,
+                &theme,
+            );
+            self.minimap_cache = Some(MinimapCache { revision, blocks, mode, theme, colors });
+        }
+        let Some(ref cache) = self.minimap_cache else {
+            return;
+        };
+
+        let len = self.document.len();
+        let view_end = (self.offset + self.visible_rows * self.row_bytes()).min(len);
+        let selected = (self.panel == Some(Panel::Minimap)).then_some(self.panel_selected);
+        let minimap = Minimap::new(&cache.colors)
+            .view(
+                ui::block_of(len, blocks, self.offset),
+                ui::block_of(len, blocks, view_end.saturating_sub(1)),
+            )
+            .selected(selected)
+            .theme(theme);
+        frame.render_widget(minimap, area);
+    }
+
+    /// 表示位置のスクロールバーを描画（行単位）
+    fn draw_scrollbar(&self, frame: &mut Frame, area: ratatui::layout::Rect) {
+        let row_bytes = self.row_bytes();
+        let rows = self.document.len() / row_bytes + 1;
+        let mut state = ScrollbarState::new(rows.saturating_sub(self.visible_rows) + 1)
+            .position(self.offset / row_bytes)
+            .viewport_content_length(self.visible_rows);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_symbol(Some(ratatui::symbols::line::VERTICAL))
+            .track_style(Style::default().fg(self.theme.1.hex_zero))
+            .thumb_style(Style::default().fg(self.theme.1.addr));
+        frame.render_stateful_widget(scrollbar, area, &mut state);
+    }
+
     /// UIを描画
     pub fn draw(&mut self, frame: &mut Frame) {
         let size = frame.area();
         self.set_visible_rows(size.height as usize);
 
+        // タブバー（バッファが複数あるとき、一番上の行）
+        self.tab_bar_area = self.show_tab_bar().then_some(Rect { height: 1, ..size });
+        let screen = match self.tab_bar_area {
+            Some(area) => {
+                let titles = self.tab_titles();
+                let tabs = TabBar::new(&titles).selected(self.buffer_index).theme(self.theme.1);
+                frame.render_widget(tabs, area);
+                Rect { y: size.y + 1, height: size.height.saturating_sub(1), ..size }
+            }
+            None => size,
+        };
+
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Min(1),    // メイン
                 Constraint::Length(1), // ステータス
             ])
-            .split(size);
+            .split(screen);
+
+        // サイドパネル
+        let main_area = if self.panel.is_some() && self.panel != Some(Panel::Minimap) {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(1), Constraint::Length(44)])
+                .split(layout[0]);
+            let (items, selected) = if self.panel == Some(Panel::Strings) {
+                // 選択行が見える範囲だけ表示行を作る
+                let height = columns[1].height as usize;
+                let first = self.panel_selected.saturating_sub(height.saturating_sub(1));
+                (self.strings_items(first, height), self.panel_selected - first)
+            } else {
+                (self.panel_items(), self.panel_selected)
+            };
+            let title = match self.panel {
+                Some(Panel::UndoHistory) => " Undo history ".to_string(),
+                Some(Panel::Inspector) => " Inspector ".to_string(),
+                Some(Panel::Bookmarks) => " Bookmarks ".to_string(),
+                Some(Panel::Strings) => self.strings_title(),
+                Some(Panel::Structure) => match self.structure {
+                    Some(ref tree) => format!(" Structure: {} ", tree.name),
+                    None => " Structure ".to_string(),
+                },
+                #[cfg(feature = "disasm")]
+                Some(Panel::Disasm) => format!(" Disasm: {} ", self.disasm_arch.name()),
+                Some(Panel::Minimap) | None => String::new(),
+            };
+            let panel = SidePanel::new(&title, &items)
+                .selected(selected)
+                .theme(self.theme.1);
+            frame.render_widget(panel, columns[1]);
+            columns[0]
+        } else {
+            layout[0]
+        };
+
+        // ミニマップは右端に表示
+        let main_area = match self.minimap {
+            Some(mode) => {
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Min(1), Constraint::Length(3)])
+                    .split(main_area);
+                self.draw_minimap(frame, columns[1], mode);
+                columns[0]
+            }
+            None => main_area,
+        };
+
+        // スクロールバーは右端の1列（ヘッダー行の下）
+        let main_area = {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(1), Constraint::Length(1)])
+                .split(main_area);
+            let mut bar_area = columns[1];
+            bar_area.y += 1;
+            bar_area.height = bar_area.height.saturating_sub(1);
+            self.draw_scrollbar(frame, bar_area);
+            columns[0]
+        };
+
+        // 比較モードでは左右に分割
+        let (main_area, compare_area) = if self.compare.is_some() && self.compare_split {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(main_area);
+            (columns[0], Some(columns[1]))
+        } else {
+            (main_area, None)
+        };
+        let mode = if self.hex_mode {
+            ViewMode::Hex
+        } else {
+            ViewMode::Ascii
+        };
+        // 表示する行の分だけ読む（行をまたぐ文字のために前後 4 バイト多めに）
+        let window_start = self.offset.saturating_sub(4);
+        let window_end = self.offset + self.visible_rows * self.row_bytes() + 4;
+        fn read_window(doc: &Document, start: usize, end: usize) -> Cow<'_, [u8]> {
+            let end = end.min(doc.len());
+            doc.get_range(start.min(end), end).unwrap_or_default()
+        }
+        let data = read_window(&self.document, window_start, window_end);
+        let compare_data =
+            self.compare.as_ref().map(|other| read_window(other, window_start, window_end));
+        let modified = self.document.modified_ranges();
 
         // HEXビュー
-        let hex_view = HexView::new(self.document.data())
+        let cursors = self.extra_cursors();
+        let hex_view = HexView::new(&data)
+            .window(window_start, self.document.len())
             .offset(self.offset)
             .cursor(self.cursor)
+            .cursors(&cursors)
+            .digit(match self.input_state {
+                InputState::Normal => 0,
+                InputState::Digits(_, count) => count,
+            })
             .selection(self.selection)
-            .bytes_per_row(self.bytes_per_row)
+            .bytes_per_row(self.row_bytes())
             .encoding(self.encoding)
-            .mode(if self.hex_mode {
-                ViewMode::Hex
-            } else {
-                ViewMode::Ascii
-            });
-        frame.render_widget(hex_view, layout[0]);
+            .char_table(self.char_table.as_ref())
+            .control_chars(self.config.control_chars)
+            .compare(compare_data.as_deref())
+            .bookmarks(&self.bookmarks)
+            .modified(&modified)
+            .theme(self.theme.1)
+            .byte_colors(&self.byte_colors)
+            .format(self.byte_format)
+            .group(self.group)
+            .addr_radix(self.addr_radix)
+            .base(self.base, self.absolute_column)
+            .mode(mode);
+        frame.render_widget(hex_view, main_area);
+
+        if let (Some(area), Some(other), Some(other_doc)) =
+            (compare_area, compare_data.as_deref(), self.compare.as_ref())
+        {
+            let compare_view = HexView::new(other)
+                .window(window_start, other_doc.len())
+                .offset(self.offset)
+                .cursor(self.cursor)
+                .bytes_per_row(self.row_bytes())
+                .encoding(self.encoding)
+                .char_table(self.char_table.as_ref())
+                .control_chars(self.config.control_chars)
+                .compare(Some(&data))
+                .theme(self.theme.1)
+                .byte_colors(&self.byte_colors)
+                .format(self.byte_format)
+                .group(self.group)
+                .addr_radix(self.addr_radix)
+                .base(self.base, self.absolute_column)
+                .mode(mode);
+            frame.render_widget(compare_view, area);
+        }
 
         // ステータスバー（ファイル名 + 情報を統合）
         let filename = self.document.filename().unwrap_or("[New]");
-        let modified = if self.document.is_modified() { "[+]" } else { "" };
-        let mode_str = if self.hex_mode { "HEX" } else { "ASC" };
-        let edit_str = match self.edit_mode {
-            EditMode::Overwrite => "OVR",
-            EditMode::Insert => "INS",
+        let modified = if self.document.is_modified() {
+            "[+]"
+        } else if self.document.is_readonly() {
+            "[RO]"
+        } else {
+            ""
         };
+        let status_parts = ui::parse_status_format(&self.config.status_format);
 
-        let status = if self.search_mode {
-            format!("I-search: {}_", self.search_query)
+        let status = if let Some(ref task) = self.saving {
+            let total = task.job.total().max(1);
+            format!(
+                "Saving {} {}% ({} / {}) - C-g to cancel",
+                task.job.target().display(),
+                task.job.written() * 100 / total,
+                format_size(task.job.written()),
+                format_size(task.job.total())
+            )
+        } else if self.search_mode {
+            let region = if self.search_region.is_some() { " (in region)" } else { "" };
+            let direction = if self.search_backward { " backward" } else { "" };
+            let encoding = if self.search_encoded {
+                format!(" [{}]", self.encoding.name())
+            } else {
+                String::new()
+            };
+            let progress = match &self.searching {
+                Some(task) => format!("  (searching {}%)", task.job.percent()),
+                None => String::new(),
+            };
+            format!(
+                "I-search{}{}{}: {}_{}",
+                direction, region, encoding, self.search_query, progress
+            )
+        } else if let Some(task) = &self.searching {
+            format!("Searching {}% - C-g to cancel", task.job.percent())
         } else if self.replace_mode == ReplaceMode::EnteringSearch {
             format!("Query replace: {}_", self.search_query)
         } else if self.replace_mode == ReplaceMode::EnteringReplace {
@@ -1743,40 +4468,94 @@ impl App {
         } else if self.prompt_mode == PromptMode::GotoAddress {
             format!("Goto address: {}_", self.prompt_input)
         } else if self.prompt_mode == PromptMode::OpenFile {
-            format!("Open file: {}_", self.prompt_input)
+            format!("Open file (Up/Down: recent, Tab: complete): {}_", self.prompt_input)
+        } else if self.prompt_mode == PromptMode::InspectorValue {
+            format!(
+                "{} at {:08X}: {}_",
+                self.inspector_field().name(),
+                self.cursor,
+                self.prompt_input
+            )
+        } else if self.prompt_mode == PromptMode::CompareFile {
+            format!("Compare with: {}_", self.prompt_input)
+        } else if self.prompt_mode == PromptMode::BaselineFile {
+            format!("Baseline file: {}_", self.prompt_input)
+        } else if self.prompt_mode == PromptMode::InsertFile {
+            format!("Insert file: {}_", self.prompt_input)
         } else if self.prompt_mode == PromptMode::SaveAs {
             format!("Save as: {}_", self.prompt_input)
         } else if self.prompt_mode == PromptMode::Command {
             format!("M-x {}_", self.prompt_input)
         } else if self.prompt_mode == PromptMode::CommandArg {
-            let prompt = match self.current_command.as_str() {
-                "fill" => "Fill with byte (hex):",
-                "insert" => "Insert (count [byte]):",
-                _ => "Arg:",
+            let prompt = match self.current_command.map(|command| command.kind) {
+                Some(CommandKind::Arg { prompt, .. }) => prompt(self),
+                _ => "Arg:".to_string(),
             };
             format!("{} {}_", prompt, self.prompt_input)
+        } else if let ConfirmMode::LargeFile(ref path, size) = self.confirm_mode {
+            format!(
+                "{} is {} - (l)oad anyway, (m)map read-only, (c)ancel",
+                path.display(),
+                format_size(size)
+            )
+        } else if let ConfirmMode::Locked(ref path) = self.confirm_mode {
+            format!(
+                "{} is locked by another process - open (r)ead-only, (e)dit anyway, (c)ancel",
+                path.display()
+            )
+        } else if let ConfirmMode::RecoverSwap(ref swap) = self.confirm_mode {
+            format!(
+                "Swap file {} found - (r)ecover, (d)elete swap, (i)gnore",
+                swap.display()
+            )
         } else if self.confirm_mode != ConfirmMode::Off {
             "Save changes? (y)es (n)o (c)ancel".to_string()
         } else if let Some(ref msg) = self.status_message {
             format!(" {}{} | {}", filename, modified, msg)
-        } else if let Some((start, end)) = self.selection {
+        } else if let Some((start, end)) = self.selection
+            && !status_parts.contains(&StatusPart::Field(StatusField::Selection))
+        {
+            // 書式に %sel がなければ選択中は選択範囲の解釈を表示
             format!(" {}{} | {}", filename, modified, self.format_selection_info(start, end))
         } else {
-            format!(
-                " {}{} | {:08X}/{:08X} | {} {} | {}",
-                filename,
-                modified,
-                self.cursor,
-                self.document.len(),
-                mode_str,
-                edit_str,
-                self.encoding.name(),
-            )
+            status_parts
+                .iter()
+                .map(|part| match part {
+                    StatusPart::Text(text) => text.clone(),
+                    StatusPart::Field(field) => self.status_field(*field),
+                })
+                .collect()
         };
 
         let status_widget = Paragraph::new(status)
-            .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+            .style(Style::default().bg(self.theme.1.status_bg).fg(self.theme.1.status));
         frame.render_widget(status_widget, layout[1]);
+
+        // M-x の候補一覧はステータスバーの直上
+        if self.prompt_mode == PromptMode::Command {
+            let items: Vec<_> = self
+                .command_candidates()
+                .into_iter()
+                .map(|command| (command.label(), command.help))
+                .collect();
+            let palette = CommandPalette::new(&items)
+                .selected(self.command_selected)
+                .theme(self.theme.1);
+            let height = palette.height().min(layout[0].height);
+            let area = Rect {
+                y: layout[1].y - height,
+                height,
+                ..layout[1]
+            };
+            frame.render_widget(palette, area);
+        }
+
+        // ヘルプは全体に重ねて表示
+        if let Some(scroll) = self.help {
+            let lines = help_lines();
+            let help = HelpView::new(&lines).scroll(scroll).theme(self.theme.1);
+            frame.render_widget(help, size);
+        }
     }
 }
 
@@ -1785,3 +4564,231 @@ impl Default for App {
         Self::new()
     }
 }
+
+/// 入力されたパスのチルダを展開
+pub(super) fn expand_path(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// ファイルサイズを取得（ブロックデバイスにも対応するため末尾までシーク）
+fn file_size(path: &std::path::Path) -> std::io::Result<u64> {
+    use std::io::{Seek, SeekFrom};
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::End(0))
+}
+
+/// バイト数を人間向けの表記に変換
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for u in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = u;
+    }
+    format!("{:.1} {}", value, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::app_with;
+    use crossterm::event::KeyEvent;
+
+    /// 修飾キーなしのキー入力
+    fn press(app: &mut App, code: KeyCode) {
+        app.process_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn test_hex_input_group_closed_before_paste() {
+        let mut app = app_with(b"abcd");
+        press(&mut app, KeyCode::Char('4'));
+        assert_eq!(app.document.get(0), Some(0x41));
+
+        // 入力途中のペーストは別の操作として取り消される
+        app.process_event(Event::Paste("zz".to_string()));
+        assert_eq!(app.document.data(), b"zzcd");
+        app.execute(Action::Undo);
+        assert_eq!(app.document.data(), b"Abcd");
+        app.execute(Action::Undo);
+        assert_eq!(app.document.data(), b"abcd");
+    }
+
+    #[test]
+    fn test_undo_groups_hex_input_at_cursors() {
+        let mut app = app_with(b"aaaa");
+        app.cursors = vec![2];
+        press(&mut app, KeyCode::Char('4'));
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Char('4'));
+        press(&mut app, KeyCode::Char('3'));
+        assert_eq!(app.document.data(), b"BCBC");
+
+        // 1バイト分の入力ごとに、全カーソルの書き換えをまとめて取り消す
+        app.execute(Action::Undo);
+        assert_eq!(app.document.data(), b"BaBa");
+        app.execute(Action::Undo);
+        assert_eq!(app.document.data(), b"aaaa");
+        app.execute(Action::Redo);
+        assert_eq!(app.document.data(), b"BaBa");
+    }
+
+    #[test]
+    fn test_recover_swap_on_open() {
+        let path = std::env::temp_dir().join(format!("ehx-app-swap-{}.bin", std::process::id()));
+        std::fs::write(&path, b"abcd").unwrap();
+
+        // 編集中に異常終了してスワップファイルだけが残った状態
+        let mut app = App::new();
+        app.open(&path).unwrap();
+        press(&mut app, KeyCode::Char('4'));
+        press(&mut app, KeyCode::Char('1'));
+        let swap = app.document.swap_path().unwrap();
+        app.document.write_swap(&swap).unwrap();
+        drop(app);
+
+        let mut app = App::new();
+        app.open(&path).unwrap();
+        assert_eq!(app.confirm_mode, ConfirmMode::RecoverSwap(swap.clone()));
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.document.data(), b"Abcd");
+        assert!(app.document.is_modified());
+        assert_eq!(app.status_message.as_deref(), Some("Recovered unsaved changes"));
+        drop(app);
+
+        // 復元せずにスワップファイルを消す
+        let mut app = App::new();
+        app.open(&path).unwrap();
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.document.data(), b"abcd");
+        assert!(!swap.exists());
+        drop(app);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_delete_at_multiple_cursors() {
+        let mut app = app_with(b"xxABxxABxxAB");
+        app.cursor = 2;
+        app.cursors = vec![4, 8];
+        app.prefix_arg = Some(2);
+        app.execute(Action::Delete);
+        assert_eq!(app.document.data(), b"xxxxxx");
+        assert_eq!((app.cursor, app.extra_cursors()), (2, vec![4]));
+        // 末尾のカーソルはファイルの外になるので編集の対象から外れる
+        assert_eq!(app.cursors, vec![2, 4]);
+        app.execute(Action::Undo);
+        assert_eq!(app.document.data(), b"xxABxxABxxAB");
+
+        // 重なる範囲はまとめて削除し、カーソルも1つになる
+        let mut app = app_with(b"abcdef");
+        app.cursor = 1;
+        app.cursors = vec![1];
+        app.prefix_arg = Some(2);
+        app.execute(Action::Backspace);
+        assert_eq!(app.document.data(), b"cdef");
+        assert_eq!((app.cursor, app.cursors.len()), (0, 0));
+    }
+
+    #[test]
+    fn test_encoding_per_file() {
+        let path = std::env::temp_dir().join(format!("ehx-app-enc-{}.bin", std::process::id()));
+        std::fs::write(&path, b"\x82\xa0").unwrap();
+
+        // 前回使ったエンコーディングで開き直す
+        let mut app = App::new();
+        app.open(&path).unwrap();
+        app.set_encoding("sjis");
+        app.load_bytes(Vec::new());
+        assert_eq!(app.encoding, CharEncoding::ShiftJis);
+        app.set_encoding("utf8");
+        app.open(&path).unwrap();
+        assert_eq!(app.encoding, CharEncoding::ShiftJis);
+
+        // --encoding は前回の状態より優先
+        app.load_bytes(Vec::new());
+        app.set_forced_encoding(Some(CharEncoding::EucJp));
+        app.open(&path).unwrap();
+        assert_eq!(app.encoding, CharEncoding::EucJp);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_char_table_encoding() {
+        let mut app = app_with(b"\x00\x00");
+        app.set_encoding("table");
+        assert_eq!(app.encoding, CharEncoding::Utf8);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("No character table loaded (M-x load-table)")
+        );
+
+        let path = std::env::temp_dir().join(format!("ehx-table-{}.tbl", std::process::id()));
+        std::fs::write(&path, "00=A\n01=B\n8140=あ\n").unwrap();
+        assert_eq!(app.load_char_table(&path).unwrap(), 3);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(app.encoding, CharEncoding::Custom);
+
+        // ASCII 列での入力はテーブルで変換する
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Char('B'));
+        assert_eq!(app.document.data(), b"\x01\x00");
+        press(&mut app, KeyCode::Char('C'));
+        assert_eq!(app.document.data(), b"\x01\x00");
+        assert_eq!(app.decode_text(b"\x81\x40\x00"), "あA");
+    }
+
+    #[test]
+    fn test_argument_accumulates_and_is_capped() {
+        let mut app = app_with(b"\x10");
+        app.execute(Action::Argument(1));
+        app.execute(Action::Argument(2));
+        assert_eq!(app.prefix_arg, Some(12));
+        for _ in 0..20 {
+            app.execute(Action::Argument(9));
+        }
+        assert_eq!(app.prefix_arg, Some(i32::MAX as usize));
+    }
+
+    #[test]
+    fn test_argument_applies_after_ctrl_x() {
+        let mut app = app_with(b"\x10");
+        // M-3 C-x + で3増やし、引数は使ったら消える
+        app.execute(Action::Argument(3));
+        app.execute(Action::EnterCtrlX);
+        assert_eq!(app.prefix_arg, Some(3));
+        press(&mut app, KeyCode::Char('+'));
+        assert_eq!(app.document.get(0), Some(0x13));
+        assert_eq!(app.prefix_arg, None);
+
+        // C-x の後の未定義のキーはキャンセルになり、引数も消える
+        app.execute(Action::Argument(3));
+        app.execute(Action::EnterCtrlX);
+        app.process_event(Event::Key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL)));
+        assert_eq!(app.prefix_arg, None);
+        app.execute(Action::EnterCtrlX);
+        press(&mut app, KeyCode::Char('+'));
+        assert_eq!(app.document.get(0), Some(0x14));
+    }
+
+    #[test]
+    fn test_step_value_reports_failure() {
+        let mut app = app_with(b"\x10");
+        app.document.set_readonly(true);
+        app.step_value(1);
+        assert_eq!(app.document.get(0), Some(0x10));
+        assert!(app.status_message.as_deref().is_some_and(|m| m.starts_with("Edit failed")));
+    }
+}
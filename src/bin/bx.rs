@@ -2,26 +2,57 @@
 //!
 //! Unix-style binary manipulation tool.
 
-use std::io::{self, Read, Write};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, IsTerminal, Read, Seek, Write};
+use std::ops::ControlFlow;
 
 use anyhow::{bail, Result};
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+use ehx::checksum::{self, Algorithm, Crc, CrcParams};
+use ehx::encoding::{self, CharEncoding};
+use ehx::executable::{Executable, Section};
+use ehx::hexfile::{self, HexFormat};
+use ehx::patch::{self, PatchError, PatchFormat};
+use ehx::search::{self, MaskedPattern, MultiFinder, MultiMatch};
+use ehx::template::{self, Node, Template};
+use ehx::transform::{self, BaseEncoding};
+use ehx::ui::{counts_entropy, entropy};
+use serde_json::{json, Number, Value};
 
 /// Binary hex tool for pipes
 #[derive(Parser, Debug)]
 #[command(name = "bx")]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    /// Print reports (find, grep, info, entropy, gaps, blocks, dedupe, bits, crc, sections,
+    /// template) as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
     #[command(subcommand)]
     command: Command,
 }
 
 #[derive(Subcommand, Debug)]
 enum Command {
-    /// Find hex pattern in input, output matching offsets
+    /// Find hex patterns in input, output matching offsets (exit status 1 if none, 2 on error)
     Find {
-        /// Hex pattern to search (e.g., "DEADBEEF" or "DE AD BE EF")
-        pattern: String,
+        /// Hex pattern to search (e.g., "DEADBEEF", "DE AD BE EF", "E8 ?? ?? ?? ?? 4?")
+        #[arg(required_unless_present = "patterns")]
+        pattern: Option<String>,
+
+        /// More patterns, all searched in one pass (each match shows which one it is)
+        #[arg(short = 'e', long = "pattern", value_name = "PATTERN")]
+        patterns: Vec<String>,
+
+        /// Patterns are text, searched as encoded with --encoding
+        #[arg(short, long)]
+        text: bool,
+
+        /// Encoding for --text (e.g., "utf8" (default), "sjis", "utf16le")
+        #[arg(short = 'E', long, requires = "text", value_parser = parse_encoding)]
+        encoding: Option<CharEncoding>,
 
         /// Input file (default: stdin)
         #[arg(short, long)]
@@ -30,6 +61,52 @@ enum Command {
         /// Output format: "hex" (default), "dec", "both"
         #[arg(short, long, default_value = "hex")]
         format: String,
+
+        /// Print nothing; only the exit status tells whether anything matched
+        #[arg(short, long)]
+        quiet: bool,
+
+        /// Bytes of hex context to show after each match
+        #[arg(short = 'A', long, value_name = "N")]
+        after: Option<usize>,
+
+        /// Bytes of hex context to show before each match
+        #[arg(short = 'B', long, value_name = "N")]
+        before: Option<usize>,
+
+        /// Bytes of hex context to show before and after each match
+        #[arg(short = 'C', long, value_name = "N")]
+        context: Option<usize>,
+    },
+
+    /// Search for a regular expression in binary data
+    Grep {
+        /// Regex (e.g., "MZ.{58}PE\\x00\\x00", "(?i)password", "[\\x20-\\x7E]{8,}")
+        pattern: String,
+
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Bytes of context to show before and after each match
+        #[arg(short = 'C', long, default_value = "0")]
+        context: usize,
+
+        /// Print only the offsets of matches
+        #[arg(short = 'b', long)]
+        offsets: bool,
+
+        /// Print only the number of matches
+        #[arg(short, long, conflicts_with = "offsets")]
+        count: bool,
+
+        /// Match against text decoded with --encoding instead of raw bytes
+        #[arg(short, long)]
+        text: bool,
+
+        /// Encoding for --text (e.g., "utf8" (default), "sjis", "utf16le")
+        #[arg(short = 'E', long, requires = "text", value_parser = parse_encoding)]
+        encoding: Option<CharEncoding>,
     },
 
     /// Extract byte range from input
@@ -45,16 +122,123 @@ enum Command {
         /// Output as hex dump instead of raw bytes
         #[arg(short = 'x', long)]
         hex: bool,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Output the first bytes of the input
+    Head {
+        /// Number of bytes (hex with 0x prefix, or decimal; K, M and G suffixes allowed)
+        #[arg(short = 'c', long = "bytes", default_value = "256")]
+        count: String,
+
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Output as hex dump instead of raw bytes
+        #[arg(short = 'x', long)]
+        hex: bool,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Output the last bytes of the input, or everything from an offset on
+    Tail {
+        /// Number of bytes, or "+OFFSET" to start at that (0-based) offset
+        /// (hex with 0x prefix, or decimal; K, M and G suffixes allowed)
+        #[arg(short = 'c', long = "bytes", default_value = "256")]
+        count: String,
+
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Output as hex dump instead of raw bytes
+        #[arg(short = 'x', long)]
+        hex: bool,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Hex dump in xxd format (offset, grouped hex, ASCII column)
+    Dump {
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Bytes per line (default: 16, or 30 with --plain)
+        #[arg(short, long)]
+        cols: Option<usize>,
+
+        /// Bytes per group in the hex column (0: no grouping)
+        #[arg(short, long, default_value = "2")]
+        group: usize,
+
+        /// Upper-case hex digits
+        #[arg(short, long)]
+        upper: bool,
+
+        /// Start offset (hex with 0x prefix, or decimal)
+        #[arg(short, long)]
+        skip: Option<String>,
+
+        /// Number of bytes to dump (hex with 0x prefix, or decimal)
+        #[arg(short, long)]
+        len: Option<String>,
+
+        /// Plain hex without offsets or ASCII column
+        #[arg(short, long)]
+        plain: bool,
+
+        /// Colorize bytes: "auto" (default, when stdout is a terminal), "always", "never"
+        #[arg(long, default_value = "auto")]
+        color: String,
+    },
+
+    /// Reverse a hex dump (xxd, hexdump -C, bx dump or plain hex) back to binary
+    Undump {
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Treat every hex digit as data (no offsets or ASCII columns, like xxd -r -p)
+        #[arg(short, long)]
+        plain: bool,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
     },
 
     /// Replace hex pattern in input
     Replace {
-        /// Pattern to find (hex)
+        /// Pattern to find (hex, or text with --text)
         from: String,
 
-        /// Pattern to replace with (hex)
+        /// Pattern to replace with (hex, or text with --text); may differ in length
         to: String,
 
+        /// Patterns are text encoded with --encoding; \n, \r, \t, \0 and \\ are
+        /// characters and \xHH is a raw byte (e.g., 'v1.2\x00')
+        #[arg(short, long)]
+        text: bool,
+
+        /// Encoding for --text (e.g., "utf8" (default), "sjis", "utf16le")
+        #[arg(short = 'E', long, requires = "text", value_parser = parse_encoding)]
+        encoding: Option<CharEncoding>,
+
+        /// Pad a shorter replacement with 00 bytes to the pattern's length, so offsets
+        /// after it stay put (a longer one is an error)
+        #[arg(short, long)]
+        pad: bool,
+
         /// Input file (default: stdin)
         #[arg(short, long)]
         input: Option<String>,
@@ -62,291 +246,3589 @@ enum Command {
         /// Replace all occurrences (default: first only)
         #[arg(short, long)]
         all: bool,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
     },
 
     /// Patch bytes at specific offsets
     Patch {
-        /// Patches in format "offset=hexvalue" (e.g., "0x100=FF" "0x200=DEAD")
+        /// Patches in format "offset=hexvalue" (e.g., "0x100=FF" "0x200=DEAD"), or
+        /// "offset=old:new" to check the original bytes first (e.g., "0x100=7403:EB03")
         patches: Vec<String>,
 
+        /// Bytes that must be at an offset before anything is patched, "offset=hexvalue"
+        /// (repeatable; aborts with a diff on mismatch)
+        #[arg(short, long, value_name = "OFFSET=HEX")]
+        expect: Vec<String>,
+
         /// Input file (default: stdin)
         #[arg(short, long)]
         input: Option<String>,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
     },
 
-    /// Show file info (size, entropy, etc.)
-    Info {
-        /// Input file (default: stdin)
+    /// Apply an IPS, BPS or VCDIFF (xdelta3) patch file
+    Patchfile {
+        /// Patch file
+        patch: String,
+
+        /// Input file to patch (default: stdin)
         #[arg(short, long)]
         input: Option<String>,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Patch format: ips, bps or vcdiff (default: detect from the header)
+        #[arg(short, long, value_parser = parse_patch_format)]
+        format: Option<PatchFormat>,
+
+        /// Skip the source and result checksums stored in BPS and VCDIFF patches
+        #[arg(long)]
+        no_verify: bool,
     },
 
-    /// Convert between hex and binary
-    Conv {
-        /// Direction: "hex2bin" or "bin2hex"
-        direction: String,
+    /// Generate a patch that turns ORIGINAL into MODIFIED
+    Genpatch {
+        /// Original file
+        original: String,
 
+        /// Modified file
+        modified: String,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Patch format: ips, bps or list ("offset=hex" lines for bx patch)
+        /// (default: from the output file extension, otherwise list)
+        #[arg(short, long)]
+        format: Option<String>,
+    },
+
+    /// Emit the input as a source-code byte array (C, Rust, Python, Go or assembly)
+    Cformat {
         /// Input file (default: stdin)
         #[arg(short, long)]
         input: Option<String>,
 
-        /// For bin2hex: bytes per line (default: 16)
-        #[arg(short, long, default_value = "16")]
-        width: usize,
-    },
-}
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+        /// Language: c (default), rust, python, go or asm
+        #[arg(short, long, default_value = "c", value_parser = parse_language)]
+        lang: Language,
 
-    match args.command {
-        Command::Find { pattern, input, format } => cmd_find(&pattern, input.as_deref(), &format),
-        Command::Slice { range, input, hex } => cmd_slice(&range, input.as_deref(), hex),
-        Command::Replace { from, to, input, all } => cmd_replace(&from, &to, input.as_deref(), all),
-        Command::Patch { patches, input } => cmd_patch(&patches, input.as_deref()),
-        Command::Info { input } => cmd_info(input.as_deref()),
-        Command::Conv { direction, input, width } => cmd_conv(&direction, input.as_deref(), width),
-    }
-}
+        /// Identifier name (default: from the input file name, like xxd -i, or "data")
+        #[arg(short, long)]
+        name: Option<String>,
 
-/// Read input from file or stdin
-fn read_input(path: Option<&str>) -> Result<Vec<u8>> {
-    match path {
-        Some(p) => Ok(std::fs::read(p)?),
-        None => {
-            let mut buf = Vec::new();
-            io::stdin().read_to_end(&mut buf)?;
-            Ok(buf)
-        }
-    }
-}
+        /// Bytes per line
+        #[arg(short, long, default_value = "12")]
+        cols: usize,
 
-/// Parse hex string to bytes
-fn parse_hex(s: &str) -> Result<Vec<u8>> {
-    let cleaned: String = s
-        .chars()
-        .filter(|c| c.is_ascii_hexdigit())
-        .collect();
+        /// Declare the array read-only (C: const, Rust: const instead of static;
+        /// Python, Go and asm have no such qualifier)
+        #[arg(long = "const")]
+        constant: bool,
+    },
 
-    if cleaned.len() % 2 != 0 {
-        bail!("Hex string must have even length");
-    }
+    /// Parse input with a binary template and print its fields
+    Template {
+        /// Template file (TOML), or the name of one in the config directory's templates/
+        template: String,
 
-    (0..cleaned.len())
-        .step_by(2)
-        .map(|i| {
-            u8::from_str_radix(&cleaned[i..i + 2], 16)
-                .map_err(|e| anyhow::anyhow!("Invalid hex: {}", e))
-        })
-        .collect()
-}
+        /// Input file (default: stdin)
+        input: Option<String>,
+    },
 
-/// Parse range string "start:end"
-fn parse_range(s: &str, max_len: usize) -> Result<(usize, usize)> {
-    let parts: Vec<&str> = s.split(':').collect();
-    if parts.len() != 2 {
-        bail!("Range must be in format 'start:end'");
-    }
+    /// List the segments and sections of an ELF, PE or Mach-O executable
+    Sections {
+        /// Input file (default: stdin)
+        input: Option<String>,
 
-    let start = if parts[0].is_empty() {
-        0
-    } else {
-        parse_offset(parts[0])?
-    };
+        /// Write the contents of the named section (or segment) instead of the list
+        #[arg(short = 'x', long, value_name = "NAME")]
+        extract: Option<String>,
 
-    let end = if parts[1].is_empty() {
-        max_len
-    } else {
-        parse_offset(parts[1])?
-    };
+        /// Output file for --extract (default: stdout)
+        #[arg(short, long, requires = "extract")]
+        output: Option<String>,
+    },
 
-    Ok((start, end.min(max_len)))
-}
+    /// Swap byte order in fixed-size groups (endianness conversion)
+    Swap {
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
 
-/// Parse offset (hex with 0x prefix or decimal)
-fn parse_offset(s: &str) -> Result<usize> {
-    if s.starts_with("0x") || s.starts_with("0X") {
-        usize::from_str_radix(&s[2..], 16).map_err(|e| anyhow::anyhow!("Invalid hex offset: {}", e))
-    } else {
-        s.parse().map_err(|e| anyhow::anyhow!("Invalid offset: {}", e))
-    }
-}
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
 
-/// Find pattern in data
-fn find_pattern(data: &[u8], pattern: &[u8]) -> Vec<usize> {
-    let mut results = Vec::new();
-    if pattern.is_empty() || pattern.len() > data.len() {
-        return results;
-    }
+        /// Group size in bytes
+        #[arg(short, long, default_value = "2", value_parser = ["2", "4", "8"])]
+        width: String,
 
-    for i in 0..=data.len() - pattern.len() {
-        if &data[i..i + pattern.len()] == pattern {
-            results.push(i);
-        }
-    }
-    results
-}
+        /// Pad the input to a multiple of the width with this byte (hex) instead of failing
+        #[arg(short, long, value_name = "HEX")]
+        pad: Option<String>,
+    },
 
-// === Commands ===
+    /// Generate random bytes or a test pattern
+    Rand {
+        /// Number of bytes (hex with 0x prefix, or decimal)
+        length: String,
 
-fn cmd_find(pattern: &str, input: Option<&str>, format: &str) -> Result<()> {
-    let data = read_input(input)?;
-    let pattern_bytes = parse_hex(pattern)?;
-    let matches = find_pattern(&data, &pattern_bytes);
+        /// Pattern: random (default), inc (00 01 .. FF 00 ..), cyclic (De Bruijn
+        /// "aaaabaaac..." whose 4-byte windows are unique, for finding offsets) or fill
+        #[arg(short, long, default_value = "random")]
+        pattern: String,
 
-    for offset in matches {
-        match format {
-            "dec" => println!("{}", offset),
-            "both" => println!("0x{:08X} ({})", offset, offset),
-            _ => println!("0x{:08X}", offset),
-        }
-    }
+        /// Bytes repeated by the fill pattern (hex)
+        #[arg(short, long, default_value = "00", value_name = "HEX")]
+        fill: String,
 
-    Ok(())
-}
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 
-fn cmd_slice(range: &str, input: Option<&str>, hex_output: bool) -> Result<()> {
-    let data = read_input(input)?;
-    let (start, end) = parse_range(range, data.len())?;
+    /// Extract every Nth byte (or group of bytes), or interleave inputs with --join
+    Stride {
+        /// Input file (default: stdin)
+        #[arg(short, long, conflicts_with = "join")]
+        input: Option<String>,
 
-    if start >= data.len() {
-        bail!("Start offset {} exceeds file size {}", start, data.len());
-    }
+        /// The lanes to interleave with --join ("-" for stdin)
+        #[arg(requires = "join")]
+        files: Vec<String>,
 
-    let slice = &data[start..end];
+        /// Take one group out of every N
+        #[arg(short = 'n', long, default_value = "2", conflicts_with = "join")]
+        step: usize,
 
-    if hex_output {
-        // Hex dump format
-        for (i, chunk) in slice.chunks(16).enumerate() {
-            let offset = start + i * 16;
-            print!("{:08X}  ", offset);
-            for (j, byte) in chunk.iter().enumerate() {
-                print!("{:02X} ", byte);
-                if j == 7 {
-                    print!(" ");
-                }
-            }
-            println!();
-        }
-    } else {
-        // Raw binary output
-        io::stdout().write_all(slice)?;
-    }
+        /// Index of the group to take, from 0 to N-1
+        #[arg(short, long, default_value = "0", conflicts_with = "join")]
+        phase: usize,
 
-    Ok(())
-}
+        /// Bytes per group (e.g. 2 for 16-bit interleaved ROMs)
+        #[arg(short, long, default_value = "1")]
+        width: usize,
 
-fn cmd_replace(from: &str, to: &str, input: Option<&str>, all: bool) -> Result<()> {
-    let mut data = read_input(input)?;
-    let from_bytes = parse_hex(from)?;
-    let to_bytes = parse_hex(to)?;
+        /// Interleave the inputs group by group instead (the inverse of extracting each phase)
+        #[arg(short, long)]
+        join: bool,
 
-    let matches = find_pattern(&data, &from_bytes);
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 
-    if matches.is_empty() {
-        // No matches, output unchanged
-        io::stdout().write_all(&data)?;
-        return Ok(());
-    }
+    /// Show file info (size, entropy, etc.)
+    Info {
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+    },
 
-    // Replace (from end to avoid offset shifts when replacing multiple)
-    let indices: Vec<usize> = if all {
-        matches.into_iter().rev().collect()
-    } else {
-        vec![matches[0]]
-    };
+    /// Concatenate inputs, optionally aligning each one and inserting separators
+    Cat {
+        /// Input files ("-" for stdin)
+        #[arg(required = true)]
+        files: Vec<String>,
 
-    for offset in indices.iter().rev() {
-        let end = offset + from_bytes.len();
-        data.splice(*offset..end, to_bytes.iter().cloned());
-    }
+        /// Pad each input to a multiple of N bytes (hex with 0x prefix, or decimal)
+        #[arg(short, long, value_name = "N")]
+        align: Option<String>,
 
-    io::stdout().write_all(&data)?;
-    Ok(())
-}
+        /// Byte used for padding (hex)
+        #[arg(short, long, default_value = "00")]
+        pad: String,
 
-fn cmd_patch(patches: &[String], input: Option<&str>) -> Result<()> {
-    let mut data = read_input(input)?;
+        /// Hex bytes inserted between inputs
+        #[arg(short, long, value_name = "HEX")]
+        separator: Option<String>,
 
-    for patch in patches {
-        let parts: Vec<&str> = patch.split('=').collect();
-        if parts.len() != 2 {
-            bail!("Patch must be in format 'offset=hexvalue': {}", patch);
-        }
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 
-        let offset = parse_offset(parts[0])?;
-        let value = parse_hex(parts[1])?;
+    /// Per-block entropy with a bar chart, flagging high-entropy (compressed/encrypted) regions
+    Entropy {
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
 
-        if offset + value.len() > data.len() {
-            bail!("Patch at {} with {} bytes exceeds file size {}",
-                  offset, value.len(), data.len());
-        }
+        /// Block size in bytes (hex with 0x prefix, or decimal)
+        #[arg(short, long, default_value = "4096")]
+        block: String,
 
-        data[offset..offset + value.len()].copy_from_slice(&value);
-    }
+        /// Entropy (bits/byte, 0-8) at or above which a block is flagged
+        #[arg(short, long, default_value = "7.2")]
+        threshold: f64,
 
-    io::stdout().write_all(&data)?;
-    Ok(())
-}
+        /// Width of the bar chart in characters
+        #[arg(short, long, default_value = "50")]
+        width: usize,
+    },
 
-fn cmd_info(input: Option<&str>) -> Result<()> {
-    let data = read_input(input)?;
+    /// Convert between binary and hex, base64, base32, Intel HEX or S-record text
+    Conv {
+        /// Direction: "bin2hex" (b2h), "hex2bin" (h2b), "bin2base64", "base642bin",
+        /// and likewise for base64url, base32, base32hex, ihex and srec
+        direction: String,
 
-    println!("Size: {} bytes (0x{:X})", data.len(), data.len());
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
 
-    if !data.is_empty() {
-        // Entropy calculation
-        let mut freq = [0u64; 256];
-        for &byte in &data {
-            freq[byte as usize] += 1;
-        }
-        let len = data.len() as f64;
-        let entropy: f64 = freq.iter()
-            .filter(|&&f| f > 0)
-            .map(|&f| {
-                let p = f as f64 / len;
-                -p * p.log2()
-            })
-            .sum();
-        println!("Entropy: {:.4} bits/byte", entropy);
+        /// For bin2hex: bytes per line (default: 16)
+        #[arg(short, long, default_value = "16")]
+        width: usize,
 
-        // Null byte percentage
-        let nulls = freq[0];
-        println!("Null bytes: {} ({:.1}%)", nulls, nulls as f64 / len * 100.0);
+        /// For base64/base32 output: omit the trailing '=' padding
+        #[arg(long)]
+        no_pad: bool,
 
-        // Printable ASCII percentage
-        let printable: u64 = (0x20u8..=0x7E).map(|b| freq[b as usize]).sum();
-        println!("Printable ASCII: {} ({:.1}%)", printable, printable as f64 / len * 100.0);
-    }
+        /// For ihex/srec: load address of the binary (default: 0), or when converting
+        /// back, the address that becomes offset 0 (default: the lowest address)
+        #[arg(short, long, value_name = "ADDR")]
+        address: Option<String>,
 
-    Ok(())
-}
+        /// For bin2ihex/bin2srec: data bytes per record
+        #[arg(short, long, default_value = "16", value_name = "N")]
+        record_len: usize,
 
-fn cmd_conv(direction: &str, input: Option<&str>, width: usize) -> Result<()> {
-    match direction {
-        "bin2hex" | "b2h" => {
-            let data = read_input(input)?;
-            for chunk in data.chunks(width) {
-                for byte in chunk {
-                    print!("{:02X} ", byte);
-                }
-                println!();
+        /// For ihex2bin/srec2bin: byte that fills gaps between records (hex)
+        #[arg(long, default_value = "FF")]
+        fill: String,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Write a checksum manifest (hash, size, name per line) or check files against one
+    Verify {
+        /// Files to list in the manifest
+        #[arg(required_unless_present = "check")]
+        files: Vec<String>,
+
+        /// Check the files listed in this manifest (exit status 1 if any fails, 2 on error)
+        #[arg(short, long, value_name = "MANIFEST", conflicts_with = "files")]
+        check: Option<String>,
+
+        /// Hash for a new manifest (e.g., "sha256" (default), "sha1", "md5", "crc32")
+        #[arg(short, long, default_value = "sha256", value_parser = parse_algorithm)]
+        algorithm: Algorithm,
+
+        /// Output file for the manifest (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Find runs of a repeated byte, e.g. 00/FF free space in firmware images
+    Gaps {
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Byte value to look for (hex, repeatable; default: 00 and FF)
+        #[arg(short, long = "byte", value_name = "HEX")]
+        bytes: Vec<String>,
+
+        /// Shortest run reported (hex with 0x prefix, or decimal)
+        #[arg(short, long, default_value = "16")]
+        min: String,
+    },
+
+    /// Extract bit fields (e.g. bits 3..7 of the byte at 0x10) in binary, decimal and hex
+    Bits {
+        /// Offset of the first byte (hex with 0x prefix, or decimal)
+        offset: String,
+
+        /// Bit ranges, comma separated: "3..7" (inclusive), "5", "0..3,4..15".
+        /// Bit 0 is the least significant bit of the value read at OFFSET
+        bitspec: String,
+
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Bytes read as one value (default: enough for the highest bit, up to 8)
+        #[arg(short, long)]
+        width: Option<usize>,
+
+        /// Read the value big-endian (default: little-endian)
+        #[arg(short, long)]
+        big_endian: bool,
+
+        /// Number bits from the most significant end (bit 0 = MSB), as in protocol diagrams
+        #[arg(long)]
+        msb0: bool,
+    },
+
+    /// Per-block statistics, classifying each block as zero, text, code-like,
+    /// compressed/encrypted, etc.
+    Blocks {
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Block size in bytes (hex with 0x prefix, or decimal)
+        #[arg(short, long, default_value = "4096")]
+        block: String,
+    },
+
+    /// CRC with a named preset (CRC-16/MODBUS, CRC-32/JAMCRC, ...) or custom parameters
+    Crc {
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Preset, e.g. "CRC-16/CCITT-FALSE", "modbus", "jamcrc" (default: CRC-32)
+        #[arg(short, long, conflicts_with = "poly")]
+        preset: Option<String>,
+
+        /// List the presets with their parameters
+        #[arg(short, long, conflicts_with_all = ["input", "preset", "poly"])]
+        list: bool,
+
+        /// Width in bits (1-64) of a custom CRC
+        #[arg(short, long, requires = "poly")]
+        width: Option<u32>,
+
+        /// Polynomial of a custom CRC (hex, without the top bit, e.g. 1021)
+        #[arg(long, requires = "width")]
+        poly: Option<String>,
+
+        /// Initial register value of a custom CRC (hex, default: 0)
+        #[arg(long, requires = "poly")]
+        init: Option<String>,
+
+        /// Value XORed into the result of a custom CRC (hex, default: 0)
+        #[arg(long, requires = "poly")]
+        xorout: Option<String>,
+
+        /// Process bits LSB first (reflect input bytes and the result)
+        #[arg(long, requires = "poly")]
+        reflect: bool,
+    },
+
+    /// Find fixed-size blocks that occur more than once (repeated sectors, padding,
+    /// wear-leveled copies)
+    Dedupe {
+        /// Input file (default: stdin)
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Block size (hex with 0x prefix or decimal, K/M suffix allowed)
+        #[arg(short, long, default_value = "4096")]
+        block: String,
+
+        /// Report only blocks occurring at least this many times
+        #[arg(short, long, default_value = "2")]
+        min: usize,
+    },
+
+    /// Re-run a bx command whenever its input changes (e.g., "bx watch -- info -i fw.bin")
+    Watch {
+        /// File to watch (repeatable; default: the command's input file, -i or positional)
+        #[arg(short, long = "file", value_name = "FILE")]
+        files: Vec<String>,
+
+        /// Clear the screen before each run
+        #[arg(short, long)]
+        clear: bool,
+
+        /// Milliseconds without further changes to wait for before re-running
+        #[arg(short, long, default_value = "200")]
+        delay: u64,
+
+        /// The bx command and its arguments, after "--"
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let json = args.json;
+
+    match args.command {
+        Command::Find {
+            pattern,
+            patterns,
+            text,
+            encoding,
+            input,
+            format,
+            quiet,
+            after,
+            before,
+            context,
+        } => {
+            let patterns: Vec<String> = pattern.into_iter().chain(patterns).collect();
+            let encoding = text.then(|| encoding.unwrap_or(CharEncoding::Utf8));
+            let context = (
+                before.or(context).unwrap_or(0),
+                after.or(context).unwrap_or(0),
+            );
+            // Like grep: exit status 1 when nothing matched, 2 on errors
+            let input = input.as_deref();
+            let found = cmd_find(&patterns, encoding, input, &format, context, quiet, json);
+            if !exit_on_error(found) {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Grep { pattern, input, context, offsets, count, text, encoding } => {
+            let encoding = text.then(|| encoding.unwrap_or(CharEncoding::Utf8));
+            let output = match (offsets, count) {
+                (true, _) => GrepOutput::Offsets,
+                (_, true) => GrepOutput::Count,
+                _ => GrepOutput::Lines,
+            };
+            cmd_grep(&pattern, input.as_deref(), context, output, encoding, json)
+        }
+        Command::Slice { range, input, hex, output } => {
+            cmd_slice(&range, input.as_deref(), hex, output.as_deref())
+        }
+        Command::Head { count, input, hex, output } => {
+            cmd_head(&count, input.as_deref(), hex, output.as_deref())
+        }
+        Command::Tail { count, input, hex, output } => {
+            cmd_tail(&count, input.as_deref(), hex, output.as_deref())
+        }
+        Command::Dump { input, cols, group, upper, skip, len, plain, color } => {
+            let options = DumpOptions {
+                cols: cols.unwrap_or(if plain { 30 } else { 16 }),
+                group,
+                upper,
+                color: use_color(&color)?,
+            };
+            cmd_dump(input.as_deref(), skip.as_deref(), len.as_deref(), plain, &options)
+        }
+        Command::Undump { input, plain, output } => {
+            cmd_undump(input.as_deref(), plain, output.as_deref())
+        }
+        Command::Replace { from, to, text, encoding, pad, input, all, output } => {
+            let encoding = text.then(|| encoding.unwrap_or(CharEncoding::Utf8));
+            let (from, mut to) = match encoding {
+                Some(encoding) => (parse_text(&from, encoding)?, parse_text(&to, encoding)?),
+                None => (parse_hex(&from)?, parse_hex(&to)?),
+            };
+            if pad {
+                if to.len() > from.len() {
+                    bail!(
+                        "Replacement is {} bytes, longer than the {}-byte pattern (drop --pad)",
+                        to.len(),
+                        from.len()
+                    );
+                }
+                to.resize(from.len(), 0);
+            }
+            cmd_replace(&from, &to, input.as_deref(), all, output.as_deref())
+        }
+        Command::Patch { patches, expect, input, output } => {
+            cmd_patch(&patches, &expect, input.as_deref(), output.as_deref())
+        }
+        Command::Patchfile { patch, input, output, format, no_verify } => {
+            cmd_patchfile(&patch, input.as_deref(), output.as_deref(), format, !no_verify)
+        }
+        Command::Genpatch { original, modified, output, format } => {
+            cmd_genpatch(&original, &modified, output.as_deref(), format.as_deref())
+        }
+        Command::Cformat { input, output, lang, name, cols, constant } => {
+            let name = name.unwrap_or_else(|| identifier(input.as_deref().unwrap_or("data")));
+            let options = CformatOptions { lang, name, cols, constant };
+            cmd_cformat(input.as_deref(), output.as_deref(), &options)
+        }
+        Command::Template { template, input } => cmd_template(&template, input.as_deref(), json),
+        Command::Sections { input, extract, output } => {
+            cmd_sections(input.as_deref(), extract.as_deref(), output.as_deref(), json)
+        }
+        Command::Swap { input, output, width, pad } => {
+            cmd_swap(input.as_deref(), output.as_deref(), width.parse()?, pad.as_deref())
+        }
+        Command::Rand { length, pattern, fill, output } => {
+            cmd_rand(&length, &pattern, &fill, output.as_deref())
+        }
+        Command::Stride { input, files, step, phase, width, join, output } => {
+            cmd_stride(input.as_deref(), &files, step, phase, width, join, output.as_deref())
+        }
+        Command::Info { input } => cmd_info(input.as_deref(), json),
+        Command::Cat { files, align, pad, separator, output } => {
+            cmd_cat(&files, align.as_deref(), &pad, separator.as_deref(), output.as_deref())
+        }
+        Command::Entropy { input, block, threshold, width } => {
+            cmd_entropy(input.as_deref(), &block, threshold, width, json)
+        }
+        Command::Conv { direction, input, width, no_pad, address, record_len, fill, output } => {
+            let options = ConvOptions {
+                width,
+                pad: !no_pad,
+                address: address.as_deref().map(parse_offset).transpose()?.map(|a| a as u64),
+                record_len,
+                fill: match parse_hex(&fill)?.as_slice() {
+                    &[byte] => byte,
+                    _ => bail!("Fill must be a single byte"),
+                },
+            };
+            cmd_conv(&direction, input.as_deref(), output.as_deref(), &options)
+        }
+        Command::Verify { files, check, algorithm, output } => match check {
+            Some(manifest) => {
+                if !exit_on_error(cmd_verify_check(&manifest)) {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            None => cmd_verify_create(&files, algorithm, output.as_deref()),
+        },
+        Command::Gaps { input, bytes, min } => cmd_gaps(input.as_deref(), &bytes, &min, json),
+        Command::Blocks { input, block } => cmd_blocks(input.as_deref(), &block, json),
+        Command::Bits { offset, bitspec, input, width, big_endian, msb0 } => {
+            let order = BitOrder { big_endian, msb0 };
+            cmd_bits(&offset, &bitspec, input.as_deref(), width, order, json)
+        }
+        Command::Crc { input, preset, list, width, poly, init, xorout, reflect } => {
+            if list {
+                return cmd_crc_list(json);
+            }
+            let params = match (width, poly) {
+                (Some(width), Some(poly)) => CrcParams {
+                    name: "custom",
+                    width,
+                    poly: parse_crc_value(&poly)?,
+                    init: init.as_deref().map_or(Ok(0), parse_crc_value)?,
+                    refin: reflect,
+                    refout: reflect,
+                    xorout: xorout.as_deref().map_or(Ok(0), parse_crc_value)?,
+                    check: 0,
+                },
+                _ => {
+                    let name = preset.as_deref().unwrap_or("CRC-32");
+                    CrcParams::preset(name).ok_or_else(|| {
+                        anyhow::anyhow!("Unknown CRC preset '{}' (see bx crc --list)", name)
+                    })?
+                }
+            };
+            cmd_crc(input.as_deref(), &params, json)
+        }
+        Command::Dedupe { input, block, min } => cmd_dedupe(input.as_deref(), &block, min, json),
+        Command::Watch { files, clear, delay, command } => {
+            cmd_watch(&files, clear, std::time::Duration::from_millis(delay), &command, json)
+        }
+    }
+}
+
+/// Read input from file or stdin
+fn read_input(path: Option<&str>) -> Result<Vec<u8>> {
+    match path {
+        Some(p) => Ok(std::fs::read(p)?),
+        None => {
+            let mut buf = Vec::new();
+            io::stdin().read_to_end(&mut buf)?;
+            Ok(buf)
+        }
+    }
+}
+
+/// Bytes read at a time by the commands that stream their input
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// Open input for streaming (file or stdin)
+fn open_input(path: Option<&str>) -> Result<Box<dyn Read>> {
+    Ok(match path {
+        Some(p) => Box::new(std::fs::File::open(p)?),
+        None => Box::new(io::stdin().lock()),
+    })
+}
+
+/// Open input positioned at `start` (files seek there, pipes are read through up to it)
+fn open_input_at(path: Option<&str>, start: usize) -> Result<Box<dyn Read>> {
+    Ok(match path {
+        Some(path) => {
+            let mut file = std::fs::File::open(path)?;
+            let size = file.metadata()?.len();
+            if start as u64 >= size {
+                bail!("Start offset {} exceeds file size {}", start, size);
+            }
+            file.seek(io::SeekFrom::Start(start as u64))?;
+            Box::new(file)
+        }
+        None => {
+            let mut stdin = io::stdin().lock();
+            let skipped = io::copy(&mut stdin.by_ref().take(start as u64), &mut io::sink())?;
+            if skipped < start as u64 {
+                bail!("Start offset {} exceeds input size {}", start, skipped);
+            }
+            Box::new(stdin)
+        }
+    })
+}
+
+/// Commands whose exit status 1 means "not found" or "mismatch" report errors with status 2,
+/// as grep does, so that scripts can tell the two apart
+fn exit_on_error<T>(result: Result<T>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(2);
+    })
+}
+
+/// Append up to `len` bytes to `buf`; false once the input is exhausted
+fn read_chunk(reader: &mut dyn Read, buf: &mut Vec<u8>, len: usize) -> io::Result<bool> {
+    let read = reader.take(len as u64).read_to_end(buf)?;
+    Ok(read == len)
+}
+
+/// Open output (file or stdout); binary output is never written to a terminal
+fn open_output(output: Option<&str>, binary: bool) -> Result<Box<dyn Write>> {
+    Ok(match output {
+        Some(path) => Box::new(io::BufWriter::new(std::fs::File::create(path)?)),
+        None => {
+            let stdout = io::stdout().lock();
+            if binary && stdout.is_terminal() {
+                bail!("Refusing to write binary data to a terminal (use -o FILE or redirect stdout)");
+            }
+            Box::new(io::BufWriter::new(stdout))
+        }
+    })
+}
+
+/// Streaming commands write while they read, so the output must not truncate the input
+fn check_distinct(input: Option<&str>, output: Option<&str>) -> Result<()> {
+    if let (Some(input), Some(output)) = (input, output) {
+        let same = std::fs::canonicalize(input)
+            .ok()
+            .zip(std::fs::canonicalize(output).ok())
+            .is_some_and(|(a, b)| a == b);
+        if same {
+            bail!("Output file must differ from the input file");
+        }
+    }
+    Ok(())
+}
+
+/// Write binary output to a file, or to stdout unless it is a terminal
+fn write_output(output: Option<&str>, data: &[u8]) -> Result<()> {
+    let mut out = open_output(output, true)?;
+    out.write_all(data)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Write text output to a file or stdout
+fn write_text_output(output: Option<&str>, text: &str) -> Result<()> {
+    let mut out = open_output(output, false)?;
+    out.write_all(text.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Parse hex string to bytes
+fn parse_hex(s: &str) -> Result<Vec<u8>> {
+    let cleaned: String = s
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .collect();
+
+    if !cleaned.len().is_multiple_of(2) {
+        bail!("Hex string must have even length");
+    }
+
+    (0..cleaned.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&cleaned[i..i + 2], 16)
+                .map_err(|e| anyhow::anyhow!("Invalid hex: {}", e))
+        })
+        .collect()
+}
+
+/// Float rounded to a fixed number of decimals (null if not finite)
+fn json_float(value: f64, decimals: i32) -> Value {
+    let scale = 10f64.powi(decimals);
+    json!((value * scale).round() / scale)
+}
+
+/// Print a JSON value on its own line
+fn print_json(value: &Value) -> io::Result<()> {
+    writeln!(io::stdout().lock(), "{}", value)
+}
+
+/// Hex bytes without separators
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Hex dump layout
+struct DumpOptions {
+    /// Bytes per line
+    cols: usize,
+    /// Bytes per group (0: the whole line is one group)
+    group: usize,
+    /// Upper-case hex digits
+    upper: bool,
+    /// ANSI colors by byte class
+    color: bool,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        Self { cols: 16, group: 2, upper: false, color: false }
+    }
+}
+
+/// Resolve a --color value
+fn use_color(when: &str) -> Result<bool> {
+    match when {
+        "auto" => Ok(io::stdout().is_terminal()),
+        "always" => Ok(true),
+        "never" => Ok(false),
+        _ => bail!("Color must be 'auto', 'always' or 'never'"),
+    }
+}
+
+/// ANSI color of a byte (same classes as xxd -R)
+fn byte_color(byte: u8) -> &'static str {
+    match byte {
+        0x00 => "\x1b[37m",
+        0xFF => "\x1b[34m",
+        b'\t' | b'\n' | b'\r' | b' ' => "\x1b[33m",
+        0x21..=0x7E => "\x1b[32m",
+        _ => "\x1b[31m",
+    }
+}
+
+/// Character shown for a byte in an ASCII column
+fn ascii_char(byte: u8) -> char {
+    if (0x20..=0x7E).contains(&byte) { byte as char } else { '.' }
+}
+
+/// Write one byte's hex digits (colored if requested)
+fn write_hex_byte(out: &mut impl Write, byte: u8, options: &DumpOptions) -> io::Result<()> {
+    let hex = if options.upper { format!("{:02X}", byte) } else { format!("{:02x}", byte) };
+    if options.color {
+        write!(out, "{}{}\x1b[0m", byte_color(byte), hex)
+    } else {
+        out.write_all(hex.as_bytes())
+    }
+}
+
+/// Write an xxd-style dump; offsets start at `start`
+fn write_dump(
+    out: &mut impl Write,
+    data: &[u8],
+    start: usize,
+    options: &DumpOptions,
+) -> io::Result<()> {
+    for (i, chunk) in data.chunks(options.cols).enumerate() {
+        write!(out, "{:08x}: ", start + i * options.cols)?;
+        for j in 0..options.cols {
+            match chunk.get(j) {
+                Some(&byte) => write_hex_byte(out, byte, options)?,
+                None => out.write_all(b"  ")?,
+            }
+            if (options.group > 0 && (j + 1) % options.group == 0) || j + 1 == options.cols {
+                out.write_all(b" ")?;
+            }
+        }
+        out.write_all(b" ")?;
+        for &byte in chunk {
+            if options.color {
+                write!(out, "{}{}\x1b[0m", byte_color(byte), ascii_char(byte))?;
+            } else {
+                write!(out, "{}", ascii_char(byte))?;
+            }
+        }
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Write plain hex (xxd -p)
+fn write_plain(out: &mut impl Write, data: &[u8], options: &DumpOptions) -> io::Result<()> {
+    for chunk in data.chunks(options.cols) {
+        for &byte in chunk {
+            write_hex_byte(out, byte, options)?;
+        }
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Blank or comment line ('#', ';' or '//') in a dump
+fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#') || line.starts_with(';') || line.starts_with("//")
+}
+
+/// Parse a hex dump back to bytes
+///
+/// Each line may start with an offset ("00000010:" like xxd, or "00000010" followed by two
+/// spaces like hexdump -C) and end with an ASCII column ("|...|", or as many characters as
+/// the line has bytes). Offsets are relative to the first one; gaps are filled with zeros,
+/// or with the previous line after a hexdump "*" line.
+fn undump(text: &str) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut base = None;
+    let mut repeat: Option<Vec<u8>> = None;
+    let mut previous = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        if is_comment(line) {
+            continue;
+        }
+        let line = line.trim_end_matches('\r').trim_start();
+        if line.trim_end() == "*" {
+            repeat = Some(previous.clone());
+            continue;
+        }
+        let (offset, rest) = split_offset(line, base.is_some());
+        let offset = match offset {
+            Some(offset) => {
+                let offset = usize::from_str_radix(offset, 16)
+                    .map_err(|_| anyhow::anyhow!("Invalid offset at line {}", index + 1))?;
+                let base = *base.get_or_insert(offset);
+                Some(offset.checked_sub(base).ok_or_else(|| {
+                    anyhow::anyhow!("Offset goes backwards at line {}", index + 1)
+                })?)
+            }
+            None => None,
+        };
+        let bytes = parse_dump_line(rest)
+            .ok_or_else(|| anyhow::anyhow!("Invalid hex at line {}", index + 1))?;
+
+        let pos = offset.unwrap_or(data.len());
+        if pos > data.len() {
+            match repeat.take() {
+                Some(fill) if !fill.is_empty() => {
+                    while data.len() < pos {
+                        let len = fill.len().min(pos - data.len());
+                        data.extend_from_slice(&fill[..len]);
+                    }
+                }
+                _ => data.resize(pos, 0),
+            }
+        }
+        repeat = None;
+        let end = pos + bytes.len();
+        if data.len() < end {
+            data.resize(end, 0);
+        }
+        data[pos..end].copy_from_slice(&bytes);
+        previous = bytes;
+    }
+    Ok(data)
+}
+
+/// Split the offset column from a dump line
+///
+/// A line holding only an offset (the last line of hexdump -C) counts only once earlier
+/// lines had offsets, so plain hex lines are never taken for one.
+fn split_offset(line: &str, has_offsets: bool) -> (Option<&str>, &str) {
+    let end = line.find(char::is_whitespace).unwrap_or(line.len());
+    let (first, rest) = line.split_at(end);
+    if let Some(offset) = first.strip_suffix(':') {
+        return (Some(offset), rest);
+    }
+    // hexdump -C: an offset of 6+ digits followed by two spaces
+    let hex = first.len() >= 6 && first.chars().all(|c| c.is_ascii_hexdigit());
+    let gap = rest.starts_with("  ") || rest.starts_with('\t');
+    if hex && (gap || (has_offsets && rest.trim().is_empty())) {
+        (Some(first), rest)
+    } else {
+        (None, line)
+    }
+}
+
+/// Parse the hex part of a dump line, dropping its ASCII column (None if not hex)
+fn parse_dump_line(line: &str) -> Option<Vec<u8>> {
+    // Split on runs of two or more spaces (or tabs). The ASCII column is what follows
+    // the hex: as many characters as there are bytes (xxd), or "|...|" (hexdump -C)
+    let mut bytes = Vec::new();
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        if !bytes.is_empty() && rest.chars().count() == bytes.len() {
+            break;
+        }
+        let end = [rest.find("  "), rest.find('\t')].into_iter().flatten().min();
+        let segment = &rest[..end.unwrap_or(rest.len())];
+        let hex = segment
+            .split_whitespace()
+            .map(parse_hex_token)
+            .collect::<Option<Vec<_>>>();
+        match hex {
+            Some(hex) => bytes.extend(hex.concat()),
+            // Anything else after the hex is an ASCII column, even if trailing spaces
+            // were stripped by an editor
+            None => return (!bytes.is_empty()).then_some(bytes),
+        }
+        rest = rest[segment.len()..].trim_start();
+    }
+    Some(bytes)
+}
+
+/// Parse a whitespace-free run of hex digits (None if odd-length or not hex)
+fn parse_hex_token(token: &str) -> Option<Vec<u8>> {
+    if !token.len().is_multiple_of(2) || !token.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..token.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&token[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Encode text with escapes: \n, \r, \t, \0 and \\ are characters (encoded like the
+/// rest), \xHH is a raw byte
+fn parse_text(s: &str, encoding: CharEncoding) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut text = String::new();
+    let flush = |text: &mut String, bytes: &mut Vec<u8>| -> Result<()> {
+        match encoding::encode_string_strict(text, encoding) {
+            Some(encoded) => bytes.extend(encoded),
+            None => bail!("'{}' cannot be encoded as {}", text, encoding.name()),
+        }
+        text.clear();
+        Ok(())
+    };
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('r') => text.push('\r'),
+            Some('t') => text.push('\t'),
+            Some('0') => text.push('\0'),
+            Some('\\') => text.push('\\'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let valid = digits.len() == 2 && digits.chars().all(|c| c.is_ascii_hexdigit());
+                let byte = valid
+                    .then(|| u8::from_str_radix(&digits, 16).ok())
+                    .flatten()
+                    .ok_or_else(|| anyhow::anyhow!("Invalid escape '\\x{}' in {}", digits, s))?;
+                flush(&mut text, &mut bytes)?;
+                bytes.push(byte);
+            }
+            Some(other) => bail!("Unknown escape '\\{}' in {}", other, s),
+            None => bail!("Trailing backslash in {}", s),
+        }
+    }
+    flush(&mut text, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Parse encoding name (character tables are not supported here)
+fn parse_encoding(s: &str) -> Result<CharEncoding, String> {
+    match CharEncoding::from_name(s) {
+        Some(CharEncoding::Custom) | None => Err(format!("unknown encoding '{}'", s)),
+        Some(encoding) => Ok(encoding),
+    }
+}
+
+/// Parse a checksum algorithm name for clap
+fn parse_algorithm(s: &str) -> Result<Algorithm, String> {
+    Algorithm::parse(s).map_err(|e| e.to_string())
+}
+
+/// Parse a patch format name for clap
+fn parse_patch_format(s: &str) -> Result<PatchFormat, String> {
+    PatchFormat::from_name(s).ok_or_else(|| format!("unknown patch format '{}'", s))
+}
+
+/// Parse a source language name for clap
+fn parse_language(s: &str) -> Result<Language, String> {
+    Language::from_name(s).ok_or_else(|| format!("unknown language '{}'", s))
+}
+
+/// Parse range string "start:end" (no end means to the end of the input)
+fn parse_range(s: &str) -> Result<(usize, Option<usize>)> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() != 2 {
+        bail!("Range must be in format 'start:end'");
+    }
+
+    let start = if parts[0].is_empty() {
+        0
+    } else {
+        parse_offset(parts[0])?
+    };
+
+    let end = if parts[1].is_empty() {
+        None
+    } else {
+        Some(parse_offset(parts[1])?)
+    };
+
+    Ok((start, end))
+}
+
+/// Parse a byte count: hex with 0x prefix or decimal, with an optional K, M or G suffix
+/// (powers of 1024)
+fn parse_size(s: &str) -> Result<usize> {
+    let shift = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => 10,
+        Some('M') => 20,
+        Some('G') => 30,
+        _ => return parse_offset(s),
+    };
+    parse_offset(&s[..s.len() - 1])?
+        .checked_mul(1 << shift)
+        .ok_or_else(|| anyhow::anyhow!("Size too large: {}", s))
+}
+
+/// Parse offset (hex with 0x prefix or decimal)
+fn parse_offset(s: &str) -> Result<usize> {
+    if s.starts_with("0x") || s.starts_with("0X") {
+        usize::from_str_radix(&s[2..], 16).map_err(|e| anyhow::anyhow!("Invalid hex offset: {}", e))
+    } else {
+        s.parse().map_err(|e| anyhow::anyhow!("Invalid offset: {}", e))
+    }
+}
+
+// === Commands ===
+
+fn cmd_find(
+    patterns: &[String],
+    encoding: Option<CharEncoding>,
+    input: Option<&str>,
+    format: &str,
+    (before, after): (usize, usize),
+    quiet: bool,
+    json: bool,
+) -> Result<bool> {
+    let masked = patterns
+        .iter()
+        .map(|pattern| match encoding {
+            None => Ok(MaskedPattern::parse(pattern)?),
+            Some(encoding) => match encoding::encode_string_strict(pattern, encoding) {
+                Some(bytes) if !bytes.is_empty() => Ok(MaskedPattern::exact(&bytes)),
+                Some(_) => bail!("Empty pattern"),
+                None => bail!("'{}' cannot be encoded as {}", pattern, encoding.name()),
+            },
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let masked_len: Vec<usize> = masked.iter().map(MaskedPattern::len).collect();
+    let overlap = masked_len.iter().max().unwrap_or(&1) - 1;
+    // A single exact pattern goes through memmem
+    let exact = (masked.len() == 1 && masked[0].is_exact()).then(|| masked[0].bytes.clone());
+    let finder = MultiFinder::new(masked);
+    let find = |data: &[u8]| match &exact {
+        Some(bytes) => search::find_all(data, bytes)
+            .into_iter()
+            .map(|offset| MultiMatch { offset, pattern: 0 })
+            .collect(),
+        None => finder.find_all(data),
+    };
+
+    let mut records = Vec::new();
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    let mut count = 0;
+    // Context after a match counts from its end, so reach past the longest pattern
+    let context = (before, overlap + 1 + after);
+    let mut input = open_input(input)?;
+    stream_matches(&mut *input, overlap, context, find, |found, at, bytes| {
+        count += 1;
+        // With -q the first match settles the exit status; the rest of the input is not read
+        if quiet {
+            return Ok(ControlFlow::Break(()));
+        }
+        // Context around this match: `before` bytes, the match itself and `after` bytes
+        let len = masked_len[found.pattern];
+        let shown = &bytes[..bytes.len().min(found.offset - at + len + after)];
+        if json {
+            let mut record = json!({
+                "offset": found.offset,
+                "pattern": patterns[found.pattern],
+            });
+            if before > 0 || after > 0 {
+                record["context_offset"] = json!(at);
+                record["context"] = json!(hex_string(shown));
+            }
+            records.push(record);
+            return Ok(ControlFlow::Continue(()));
+        }
+        if (before > 0 || after > 0) && count > 1 {
+            writeln!(out, "--")?;
+        }
+        let offset = found.offset;
+        match format {
+            "dec" => write!(out, "{}", offset)?,
+            "both" => write!(out, "0x{:08X} ({})", offset, offset)?,
+            _ => write!(out, "0x{:08X}", offset)?,
+        }
+        if patterns.len() > 1 {
+            write!(out, "  {}", patterns[found.pattern])?;
+        }
+        writeln!(out)?;
+        if before > 0 || after > 0 {
+            write_dump(&mut out, shown, at, &DumpOptions::default())?;
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+    out.flush()?;
+    drop(out);
+
+    if json && !quiet {
+        print_json(&Value::Array(records))?;
+    }
+    Ok(count > 0)
+}
+
+/// Search the input a chunk at a time, passing matches to `report` in order of offset
+/// together with the input offset and bytes of their context: up to `before` bytes before
+/// the match and `after` bytes from its start. Each window keeps the tail of the previous
+/// one, so matches that straddle a chunk boundary are still found (and reported once).
+/// Reading stops as soon as `report` breaks.
+fn stream_matches(
+    reader: &mut dyn Read,
+    overlap: usize,
+    (before, after): (usize, usize),
+    find: impl Fn(&[u8]) -> Vec<MultiMatch>,
+    mut report: impl FnMut(MultiMatch, usize, &[u8]) -> Result<ControlFlow<()>>,
+) -> Result<()> {
+    let mut window = Vec::new();
+    // Input offset of window[0]
+    let mut base = 0;
+    // window[..start] was searched already and is only kept as context
+    let mut start = 0;
+    let reach = overlap.max(after);
+    loop {
+        let more = read_chunk(reader, &mut window, CHUNK_SIZE)?;
+        // A match starting in the tail may run into the next chunk; leave it for the next window
+        let limit = if more { window.len() - reach.min(window.len()) } else { window.len() };
+        for found in find(&window) {
+            if found.offset < start {
+                continue;
+            }
+            if found.offset >= limit {
+                break;
+            }
+            let from = found.offset.saturating_sub(before);
+            let to = (found.offset + after).min(window.len());
+            let flow = report(
+                MultiMatch { offset: base + found.offset, ..found },
+                base + from,
+                &window[from..to],
+            )?;
+            if flow.is_break() {
+                return Ok(());
+            }
+        }
+        if !more {
+            return Ok(());
+        }
+        let drained = limit.saturating_sub(before);
+        window.drain(..drained);
+        base += drained;
+        start = limit - drained;
+    }
+}
+
+/// What bx grep prints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GrepOutput {
+    Lines,
+    Offsets,
+    Count,
+}
+
+/// Longest match shown in full by bx grep
+const GREP_SHOW_BYTES: usize = 64;
+
+fn cmd_grep(
+    pattern: &str,
+    input: Option<&str>,
+    context: usize,
+    output: GrepOutput,
+    encoding: Option<CharEncoding>,
+    json: bool,
+) -> Result<()> {
+    let data = read_input(input)?;
+    // Byte ranges of the matches, and the matched text for --text
+    let matches: Vec<(usize, usize, Option<String>)> = match encoding {
+        // Without Unicode, `.` and `\xFF` stand for single bytes rather than UTF-8 sequences
+        None => regex::bytes::RegexBuilder::new(pattern)
+            .unicode(false)
+            .build()?
+            .find_iter(&data)
+            .map(|found| (found.start(), found.end(), None))
+            .collect(),
+        Some(encoding) => {
+            let regex = regex::Regex::new(pattern)?;
+            let decoded = encoding::decode_chars(&data, encoding);
+            let text: String = decoded.iter().map(|&(_, c)| c).collect();
+            // Input offset of each character, by its byte offset in `text`
+            let positions: Vec<(usize, usize)> = text
+                .char_indices()
+                .zip(&decoded)
+                .map(|((index, _), &(pos, _))| (index, pos))
+                .collect();
+            let offset = |index: usize| match positions.binary_search_by_key(&index, |p| p.0) {
+                Ok(i) => positions[i].1,
+                Err(_) => data.len(),
+            };
+            regex
+                .find_iter(&text)
+                .map(|found| {
+                    let start = offset(found.start());
+                    (start, offset(found.end()), Some(found.as_str().to_string()))
+                })
+                .collect()
+        }
+    };
+
+    if json {
+        let value = match output {
+            GrepOutput::Count => json!({ "count": matches.len() }),
+            GrepOutput::Offsets => matches.iter().map(|(start, _, _)| json!(start)).collect(),
+            GrepOutput::Lines => matches
+                .iter()
+                .map(|(start, end, text)| {
+                    let mut fields = json!({
+                        "offset": start,
+                        "length": end - start,
+                        "hex": hex_string(&data[*start..*end]),
+                    });
+                    if let Some(text) = text {
+                        fields["text"] = json!(text);
+                    }
+                    fields
+                })
+                .collect(),
+        };
+        print_json(&value)?;
+        return Ok(());
+    }
+
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    match output {
+        GrepOutput::Count => writeln!(out, "{}", matches.len())?,
+        GrepOutput::Offsets => {
+            for (start, _, _) in &matches {
+                writeln!(out, "0x{:08X}", start)?;
+            }
+        }
+        GrepOutput::Lines => {
+            for (start, end, text) in &matches {
+                let shown = (end - start).min(GREP_SHOW_BYTES);
+                let before = &data[start.saturating_sub(context)..*start];
+                let matched = &data[*start..start + shown];
+                let after = &data[start + shown..(start + shown + context).min(data.len())];
+                let hex = |bytes: &[u8]| {
+                    bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+                };
+                let ascii = |bytes: &[u8]| -> String {
+                    bytes.iter().map(|&b| ascii_char(b)).collect()
+                };
+                let more = if shown < end - start { " ..." } else { "" };
+                let (hex_column, text_column) = if context > 0 {
+                    (
+                        format!("{} [{}{}] {}", hex(before), hex(matched), more, hex(after)),
+                        format!("{}[{}]{}", ascii(before), ascii(matched), ascii(after)),
+                    )
+                } else {
+                    (format!("{}{}", hex(matched), more), ascii(matched))
+                };
+                let text_column = match text {
+                    Some(text) => format!("\"{}\"", text.escape_debug()),
+                    None => text_column,
+                };
+                writeln!(out, "0x{:08X}  {}  {}", start, hex_column.trim(), text_column)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn cmd_slice(
+    range: &str,
+    input: Option<&str>,
+    hex_output: bool,
+    output: Option<&str>,
+) -> Result<()> {
+    let (start, end) = parse_range(range)?;
+    check_distinct(input, output)?;
+
+    let mut reader = open_input_at(input, start)?;
+    let len = end.map_or(u64::MAX, |end| end.saturating_sub(start) as u64);
+    write_bytes(&mut reader.by_ref().take(len), start, hex_output, output)
+}
+
+/// Write the bytes of `reader`, which start at offset `start` of the input,
+/// raw or as a hex dump
+fn write_bytes(
+    reader: &mut dyn Read,
+    start: usize,
+    hex_output: bool,
+    output: Option<&str>,
+) -> Result<()> {
+    let mut out = open_output(output, !hex_output)?;
+
+    if hex_output {
+        // Hex dump format (colored only on a terminal)
+        let color = output.is_none() && use_color("auto")?;
+        let options = DumpOptions { color, ..DumpOptions::default() };
+        let mut offset = start;
+        let mut chunk = Vec::new();
+        loop {
+            chunk.clear();
+            let more = read_chunk(reader, &mut chunk, CHUNK_SIZE)?;
+            write_dump(&mut out, &chunk, offset, &options)?;
+            offset += chunk.len();
+            if !more {
+                break;
+            }
+        }
+    } else {
+        // Raw binary output
+        io::copy(reader, &mut out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn cmd_head(count: &str, input: Option<&str>, hex_output: bool, output: Option<&str>) -> Result<()> {
+    let count = parse_size(count)?;
+    check_distinct(input, output)?;
+    let mut reader = open_input(input)?;
+    write_bytes(&mut reader.by_ref().take(count as u64), 0, hex_output, output)
+}
+
+fn cmd_tail(count: &str, input: Option<&str>, hex_output: bool, output: Option<&str>) -> Result<()> {
+    check_distinct(input, output)?;
+    // "+OFFSET": everything from the offset on
+    if let Some(offset) = count.strip_prefix('+') {
+        let start = parse_size(offset)?;
+        return write_bytes(&mut open_input_at(input, start)?, start, hex_output, output);
+    }
+
+    let count = parse_size(count)?;
+    match input {
+        Some(path) => {
+            let size = std::fs::metadata(path)?.len() as usize;
+            let start = size.saturating_sub(count);
+            let mut file = std::fs::File::open(path)?;
+            file.seek(io::SeekFrom::Start(start as u64))?;
+            write_bytes(&mut file, start, hex_output, output)
+        }
+        // A pipe is read to the end, keeping only the last `count` bytes
+        None => {
+            let mut stdin = io::stdin().lock();
+            let mut last = Vec::new();
+            let mut total = 0;
+            loop {
+                let read = last.len();
+                let more = read_chunk(&mut stdin, &mut last, CHUNK_SIZE)?;
+                total += last.len() - read;
+                if last.len() > count {
+                    last.drain(..last.len() - count);
+                }
+                if !more {
+                    break;
+                }
+            }
+            write_bytes(&mut last.as_slice(), total - last.len(), hex_output, output)
+        }
+    }
+}
+
+fn cmd_dump(
+    input: Option<&str>,
+    skip: Option<&str>,
+    len: Option<&str>,
+    plain: bool,
+    options: &DumpOptions,
+) -> Result<()> {
+    if options.cols == 0 {
+        bail!("Columns must be at least 1");
+    }
+    let data = read_input(input)?;
+    let start = skip.map(parse_offset).transpose()?.unwrap_or(0).min(data.len());
+    let end = match len {
+        Some(len) => start.saturating_add(parse_offset(len)?).min(data.len()),
+        None => data.len(),
+    };
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    if plain {
+        write_plain(&mut out, &data[start..end], options)?;
+    } else {
+        write_dump(&mut out, &data[start..end], start, options)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn cmd_undump(input: Option<&str>, plain: bool, output: Option<&str>) -> Result<()> {
+    let data = read_input(input)?;
+    let text = String::from_utf8_lossy(&data);
+    let bytes = if plain {
+        let hex: String = text.lines().filter(|line| !is_comment(line)).collect();
+        parse_hex(&hex)?
+    } else {
+        undump(&text)?
+    };
+    write_output(output, &bytes)
+}
+
+fn cmd_replace(
+    from: &[u8],
+    to: &[u8],
+    input: Option<&str>,
+    all: bool,
+    output: Option<&str>,
+) -> Result<()> {
+    if from.is_empty() {
+        bail!("Empty pattern");
+    }
+    check_distinct(input, output)?;
+    let mut out = open_output(output, true)?;
+    replace_stream(&mut *open_input(input)?, &mut out, from, to, all)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Replace `from` with `to` while copying the input a chunk at a time
+fn replace_stream(
+    reader: &mut dyn Read,
+    out: &mut impl Write,
+    from: &[u8],
+    to: &[u8],
+    all: bool,
+) -> Result<()> {
+    // Bytes read but not yet written; the last from.len() - 1 may begin a match
+    // that continues in the next chunk
+    let mut window = Vec::new();
+    let mut replaced = false;
+    loop {
+        let more = read_chunk(reader, &mut window, CHUNK_SIZE)?;
+        let limit = if more {
+            window.len() - (from.len() - 1).min(window.len())
+        } else {
+            window.len()
+        };
+        // Replace left to right, skipping matches that overlap one already replaced
+        let mut pos = 0;
+        if all || !replaced {
+            for offset in search::find_all(&window, from) {
+                if offset >= limit {
+                    break;
+                }
+                if offset < pos {
+                    continue;
+                }
+                out.write_all(&window[pos..offset])?;
+                out.write_all(to)?;
+                pos = offset + from.len();
+                replaced = true;
+                if !all {
+                    break;
+                }
+            }
+        }
+        let done = pos.max(limit);
+        out.write_all(&window[pos..done])?;
+        window.drain(..done);
+        if !more {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn cmd_patch(
+    patches: &[String],
+    expect: &[String],
+    input: Option<&str>,
+    output: Option<&str>,
+) -> Result<()> {
+    let mut data = read_input(input)?;
+
+    // (offset, expected original bytes, new bytes)
+    let mut edits = Vec::new();
+    for patch in patches {
+        let Some((offset, value)) = patch.split_once('=') else {
+            bail!("Patch must be in format 'offset=hexvalue' or 'offset=old:new': {}", patch);
+        };
+        let offset = parse_offset(offset)?;
+        let (old, new) = match value.split_once(':') {
+            Some((old, new)) => (Some(parse_hex(old)?), parse_hex(new)?),
+            None => (None, parse_hex(value)?),
+        };
+        if offset.checked_add(new.len()).is_none_or(|end| end > data.len()) {
+            bail!("Patch at {} with {} bytes exceeds file size {}",
+                  offset, new.len(), data.len());
+        }
+        edits.push((offset, old, new));
+    }
+    let mut expected = Vec::new();
+    for check in expect {
+        let Some((offset, value)) = check.split_once('=') else {
+            bail!("Expected bytes must be in format 'offset=hexvalue': {}", check);
+        };
+        expected.push((parse_offset(offset)?, parse_hex(value)?));
+    }
+    expected.extend(edits.iter().filter_map(|(offset, old, _)| Some((*offset, old.clone()?))));
+    expected.sort_by_key(|&(offset, _)| offset);
+
+    // Check everything against the original before changing a byte
+    let mismatches: Vec<String> = expected
+        .iter()
+        .filter_map(|(offset, bytes)| expect_mismatch(&data, *offset, bytes))
+        .collect();
+    if !mismatches.is_empty() {
+        bail!(
+            "Original bytes differ, nothing was patched (wrong file or version?)\n{}",
+            mismatches.join("\n")
+        );
+    }
+
+    for (offset, _, new) in edits {
+        data[offset..offset + new.len()].copy_from_slice(&new);
+    }
+
+    write_output(output, &data)
+}
+
+/// Expected vs. found bytes at offset, with the differing bytes marked, or None if they match
+fn expect_mismatch(data: &[u8], offset: usize, expected: &[u8]) -> Option<String> {
+    let found = data.get(offset..).unwrap_or_default();
+    let found = &found[..found.len().min(expected.len())];
+    if found == expected {
+        return None;
+    }
+    let hex = |bytes: &[u8]| {
+        bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+    };
+    let marks: String = expected
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if found.get(i) == Some(&b) { "   " } else { "^^ " })
+        .collect();
+    let end = if found.len() < expected.len() { " (end of file)" } else { "" };
+    Some(format!(
+        "  0x{:08X}  expected: {}\n              found:    {}{}\n                        {}",
+        offset,
+        hex(expected),
+        hex(found),
+        end,
+        marks.trim_end()
+    ))
+}
+
+fn cmd_patchfile(
+    patch: &str,
+    input: Option<&str>,
+    output: Option<&str>,
+    format: Option<PatchFormat>,
+    verify: bool,
+) -> Result<()> {
+    let patch = std::fs::read(patch)?;
+    let format = format
+        .or_else(|| PatchFormat::detect(&patch))
+        .ok_or(PatchError::UnknownFormat)?;
+    let source = read_input(input)?;
+    let data = patch::apply(&patch, &source, format, verify)
+        .map_err(|e| anyhow::anyhow!("{} patch: {}", format.name(), e))?;
+
+    write_output(output, &data)
+}
+
+fn cmd_genpatch(
+    original: &str,
+    modified: &str,
+    output: Option<&str>,
+    format: Option<&str>,
+) -> Result<()> {
+    let creatable = |format| matches!(format, PatchFormat::Ips | PatchFormat::Bps);
+    let format = match format {
+        Some(name) if name.eq_ignore_ascii_case("list") => None,
+        Some(name) => match PatchFormat::from_name(name).filter(|&f| creatable(f)) {
+            Some(format) => Some(format),
+            None => bail!("Unknown patch format '{}' (ips, bps or list)", name),
+        },
+        None => output
+            .and_then(|path| path.rsplit_once('.'))
+            .and_then(|(_, ext)| PatchFormat::from_name(ext))
+            .filter(|&f| creatable(f)),
+    };
+    let source = std::fs::read(original)?;
+    let target = std::fs::read(modified)?;
+
+    let data = match format {
+        Some(format) => patch::create(&source, &target, format)?,
+        None => {
+            if source.len() != target.len() {
+                bail!(
+                    "Files differ in size ({} vs {} bytes); use --format ips or bps",
+                    source.len(),
+                    target.len()
+                );
+            }
+            let mut list = String::new();
+            for range in patch::changed_ranges(&source, &target, 1) {
+                let hex = hex_string(&target[range.clone()]);
+                list.push_str(&format!("0x{:X}={}\n", range.start, hex));
+            }
+            // The list is text, so it may go to a terminal
+            return write_text_output(output, &list);
+        }
+    };
+
+    write_output(output, &data)
+}
+
+/// Source language for bx cformat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    C,
+    Rust,
+    Python,
+    Go,
+    Asm,
+}
+
+impl Language {
+    /// Language from its name (case-insensitive)
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "c" | "cpp" | "c++" => Some(Self::C),
+            "rust" | "rs" => Some(Self::Rust),
+            "python" | "py" => Some(Self::Python),
+            "go" | "golang" => Some(Self::Go),
+            "asm" | "nasm" | "db" => Some(Self::Asm),
+            _ => None,
+        }
+    }
+}
+
+/// bx cformat layout
+struct CformatOptions {
+    lang: Language,
+    /// Identifier of the array
+    name: String,
+    /// Bytes per line
+    cols: usize,
+    /// Read-only declaration
+    constant: bool,
+}
+
+/// Identifier from a file name, as xxd -i makes it ("fw-1.bin" -> "fw_1_bin")
+fn identifier(path: &str) -> String {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let mut ident: String =
+        name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+/// Format bytes as a source-code array declaration
+fn cformat(data: &[u8], options: &CformatOptions) -> String {
+    let name = &options.name;
+    let len = data.len();
+    // Go source is indented with tabs (gofmt)
+    let indent = match options.lang {
+        Language::Asm => "    db ",
+        Language::Go => "\t",
+        _ => "    ",
+    };
+    let lines: Vec<String> = data
+        .chunks(options.cols.max(1))
+        .map(|chunk| {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("0x{:02X}", b)).collect();
+            format!("{}{}", indent, bytes.join(", "))
+        })
+        .collect();
+    // Languages with list syntax take a trailing comma on every line
+    let body = match options.lang {
+        Language::Asm => lines.join("\n"),
+        Language::C => lines.join(",\n"),
+        _ => lines.iter().map(|line| format!("{},\n", line)).collect::<String>(),
+    };
+    let body = body.trim_end_matches('\n');
+    let body = if body.is_empty() { String::new() } else { format!("{}\n", body) };
+
+    match options.lang {
+        Language::C => {
+            let qualifier = if options.constant { "const " } else { "" };
+            // An empty initializer is not valid C before C23, so an empty input gets one zero byte
+            let array = if data.is_empty() {
+                format!("{name}[1] = {{0}};")
+            } else {
+                format!("{name}[] = {{\n{body}}};")
+            };
+            format!("{q}unsigned char {array}\n{q}unsigned int {name}_len = {len};\n", q = qualifier)
+        }
+        Language::Rust => {
+            let keyword = if options.constant { "const" } else { "static" };
+            let name = name.to_ascii_uppercase();
+            format!("pub {keyword} {name}: [u8; {len}] = [\n{body}];\n")
+        }
+        Language::Python => format!("{name} = bytes([\n{body}])\n"),
+        Language::Go => format!("var {name} = []byte{{\n{body}}}\n"),
+        Language::Asm => format!("{name}:\n{body}{name}_len equ $ - {name}\n"),
+    }
+}
+
+fn cmd_cformat(input: Option<&str>, output: Option<&str>, options: &CformatOptions) -> Result<()> {
+    if options.cols == 0 {
+        bail!("Columns must be at least 1");
+    }
+    let data = read_input(input)?;
+    let text = cformat(&data, options);
+    write_text_output(output, &text)
+}
+
+fn cmd_template(path: &str, input: Option<&str>, json: bool) -> Result<()> {
+    let file = std::path::Path::new(path);
+    let file = match template::user_template_path(path) {
+        Some(user) if !file.exists() => user,
+        _ => file.to_path_buf(),
+    };
+    let template = Template::load(&file).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+    let data = read_input(input)?;
+    let nodes = template.apply(&data)?;
+
+    if json {
+        print_json(&template_json(&nodes))?;
+        return Ok(());
+    }
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    write_tree(&mut out, &nodes)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Print template fields as an indented tree: offset, name, type and value (or size)
+fn write_tree(out: &mut impl Write, nodes: &[Node]) -> io::Result<()> {
+    let width = nodes.iter().map(|n| n.depth * 2 + n.name.len()).max().unwrap_or(0);
+    let ty_width = nodes.iter().map(|n| n.ty.len()).max().unwrap_or(0);
+    for node in nodes {
+        let name = format!("{}{}", "  ".repeat(node.depth), node.name);
+        let value = if node.descendants > 0 || node.value.is_empty() {
+            format!("({} bytes)", node.size)
+        } else if !node.label.is_empty() {
+            format!("{} ({})", node.value, node.label)
+        } else {
+            node.value.clone()
+        };
+        let ty = &node.ty;
+        writeln!(out, "0x{:08X}  {:<width$}  {:<ty_width$}  {}", node.offset, name, ty, value)?;
+    }
+    Ok(())
+}
+
+/// The tree starting at nodes[0] as a JSON object
+/// (structs and arrays carry "fields", other fields a "value" and maybe an "enum" name)
+fn template_json(nodes: &[Node]) -> Value {
+    let Some(node) = nodes.first() else {
+        return Value::Null;
+    };
+    let mut fields = json!({
+        "name": node.name,
+        "type": node.ty,
+        "offset": node.offset,
+        "size": node.size,
+    });
+    if node.descendants > 0 || node.value.is_empty() {
+        let mut children = Vec::new();
+        let mut i = 1;
+        while i <= node.descendants {
+            children.push(template_json(&nodes[i..]));
+            i += nodes[i].descendants + 1;
+        }
+        fields["fields"] = Value::Array(children);
+    } else {
+        // Numbers (u8, i32, f64, ...) stay numbers; strings and byte previews are quoted
+        let number = matches!(node.ty.as_bytes().first(), Some(b'u' | b'i' | b'f'))
+            .then(|| node.value.parse::<Number>().ok())
+            .flatten();
+        fields["value"] = number.map_or_else(|| json!(node.value), Value::Number);
+        if !node.label.is_empty() {
+            fields["enum"] = json!(node.label);
+        }
+    }
+    fields
+}
+
+fn cmd_sections(
+    input: Option<&str>,
+    extract: Option<&str>,
+    output: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let data = read_input(input)?;
+    let exe = Executable::parse(&data)?;
+
+    if let Some(name) = extract {
+        let Some(section) = exe.find(name) else {
+            bail!("No section or segment named '{}'", name);
+        };
+        let bytes = usize::try_from(section.offset)
+            .ok()
+            .zip(usize::try_from(section.size).ok())
+            .and_then(|(offset, size)| data.get(offset..offset.checked_add(size)?))
+            .ok_or_else(|| anyhow::anyhow!("Section '{}' lies outside the file", name))?;
+        return write_output(output, bytes);
+    }
+
+    let endian = if exe.big_endian { "big" } else { "little" };
+    if json {
+        let list = |sections: &[Section]| {
+            sections
+                .iter()
+                .map(|s| {
+                    json!({
+                        "name": s.name,
+                        "offset": s.offset,
+                        "size": s.size,
+                        "address": s.addr,
+                        "mem_size": s.mem_size,
+                        "flags": s.flags,
+                    })
+                })
+                .collect::<Value>()
+        };
+        print_json(&json!({
+            "format": exe.format.name(),
+            "class": exe.class,
+            "arch": exe.arch,
+            "endian": endian,
+            "segments": list(&exe.segments),
+            "sections": list(&exe.sections),
+        }))?;
+        return Ok(());
+    }
+
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    writeln!(out, "{} {} ({} endian)", exe.class, exe.arch, endian)?;
+    if !exe.segments.is_empty() {
+        writeln!(out, "\nSegments:")?;
+        write_sections(&mut out, &exe.segments)?;
+    }
+    writeln!(out, "\nSections:")?;
+    write_sections(&mut out, &exe.sections)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Table of segments or sections: name, file offset and size, address, memory size, flags
+fn write_sections(out: &mut impl Write, sections: &[Section]) -> io::Result<()> {
+    let width = sections.iter().map(|s| s.name.len()).max().unwrap_or(0).max(4);
+    writeln!(
+        out,
+        "  {:<width$}  {:<10}  {:<10}  {:<18}  {:<10}  Flags",
+        "Name", "Offset", "Size", "Address", "MemSize"
+    )?;
+    for s in sections {
+        writeln!(
+            out,
+            "  {:<width$}  0x{:08X}  0x{:08X}  0x{:016X}  0x{:08X}  {}",
+            s.name, s.offset, s.size, s.addr, s.mem_size, s.flags
+        )?;
+    }
+    Ok(())
+}
+
+fn cmd_swap(
+    input: Option<&str>,
+    output: Option<&str>,
+    width: usize,
+    pad: Option<&str>,
+) -> Result<()> {
+    let mut data = read_input(input)?;
+    if let Some(pad) = pad {
+        let pad = match parse_hex(pad)?.as_slice() {
+            &[byte] => byte,
+            _ => bail!("Padding must be a single byte"),
+        };
+        data.resize(data.len().next_multiple_of(width), pad);
+    }
+    if !data.len().is_multiple_of(width) {
+        bail!("Input length {} is not a multiple of {} (use --pad)", data.len(), width);
+    }
+    transform::swap_bytes(&mut data, width)?;
+
+    write_output(output, &data)
+}
+
+/// Alphabet of the cyclic pattern (as pwntools cyclic)
+const CYCLIC_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/// Length of the cyclic pattern's unique windows
+const CYCLIC_WINDOW: usize = 4;
+
+fn cmd_rand(length: &str, pattern: &str, fill: &str, output: Option<&str>) -> Result<()> {
+    let len = parse_offset(length)?;
+    let data = match pattern {
+        "random" => transform::random_bytes(len)?,
+        "inc" => (0..len).map(|i| i as u8).collect(),
+        "cyclic" => {
+            let data = transform::de_bruijn(CYCLIC_ALPHABET, CYCLIC_WINDOW, len);
+            if data.len() < len {
+                bail!("Cyclic pattern is at most {} bytes", data.len());
+            }
+            data
+        }
+        "fill" => {
+            let fill = parse_hex(fill)?;
+            if fill.is_empty() {
+                bail!("Fill must be at least one byte");
+            }
+            fill.iter().copied().cycle().take(len).collect()
+        }
+        _ => bail!("Pattern must be 'random', 'inc', 'cyclic' or 'fill'"),
+    };
+
+    write_output(output, &data)
+}
+
+fn cmd_stride(
+    input: Option<&str>,
+    files: &[String],
+    step: usize,
+    phase: usize,
+    width: usize,
+    join: bool,
+    output: Option<&str>,
+) -> Result<()> {
+    if step == 0 || width == 0 {
+        bail!("Step and width must be at least 1");
+    }
+    let data = if join {
+        if files.len() < 2 {
+            bail!("--join needs at least two inputs");
+        }
+        let lanes = files
+            .iter()
+            .map(|file| read_input(Some(file.as_str()).filter(|f| *f != "-")))
+            .collect::<Result<Vec<_>>>()?;
+        interleave(&lanes, width)
+    } else {
+        if phase >= step {
+            bail!("Phase must be less than the step ({})", step);
+        }
+        stride(&read_input(input)?, step, phase, width)
+    };
+
+    write_output(output, &data)
+}
+
+/// Groups of `width` bytes whose index is `phase` modulo `step`
+fn stride(data: &[u8], step: usize, phase: usize, width: usize) -> Vec<u8> {
+    data.chunks(width).skip(phase).step_by(step).flatten().copied().collect()
+}
+
+/// Take `width` bytes from each lane in turn; lanes that run out are skipped
+fn interleave(lanes: &[Vec<u8>], width: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(lanes.iter().map(Vec::len).sum());
+    let mut chunks: Vec<_> = lanes.iter().map(|lane| lane.chunks(width)).collect();
+    loop {
+        let before = data.len();
+        for chunk in chunks.iter_mut().filter_map(Iterator::next) {
+            data.extend_from_slice(chunk);
+        }
+        if data.len() == before {
+            return data;
+        }
+    }
+}
+
+fn cmd_info(input: Option<&str>, json: bool) -> Result<()> {
+    // Everything reported comes from the byte frequencies, so the input is only counted
+    let mut reader = open_input(input)?;
+    let mut freq = [0u64; 256];
+    let mut chunk = Vec::new();
+    loop {
+        chunk.clear();
+        let more = read_chunk(&mut *reader, &mut chunk, CHUNK_SIZE)?;
+        for &byte in &chunk {
+            freq[byte as usize] += 1;
+        }
+        if !more {
+            break;
+        }
+    }
+    let size: u64 = freq.iter().sum();
+    let nulls = freq[0];
+    let printable: u64 = (0x20u8..=0x7E).map(|b| freq[b as usize]).sum();
+
+    if json {
+        let entropy = if size == 0 { Value::Null } else { json_float(counts_entropy(&freq), 4) };
+        print_json(&json!({
+            "size": size,
+            "entropy": entropy,
+            "null_bytes": nulls,
+            "printable": printable,
+        }))?;
+        return Ok(());
+    }
+
+    println!("Size: {} bytes (0x{:X})", size, size);
+
+    if size > 0 {
+        println!("Entropy: {:.4} bits/byte", counts_entropy(&freq));
+        let len = size as f64;
+
+        // Null byte percentage
+        println!("Null bytes: {} ({:.1}%)", nulls, nulls as f64 / len * 100.0);
+
+        // Printable ASCII percentage
+        println!("Printable ASCII: {} ({:.1}%)", printable, printable as f64 / len * 100.0);
+    }
+
+    Ok(())
+}
+
+fn cmd_cat(
+    files: &[String],
+    align: Option<&str>,
+    pad: &str,
+    separator: Option<&str>,
+    output: Option<&str>,
+) -> Result<()> {
+    let align = align.map(parse_offset).transpose()?.unwrap_or(1);
+    if align == 0 {
+        bail!("Alignment must be at least 1");
+    }
+    let pad = match parse_hex(pad)?.as_slice() {
+        &[byte] => byte,
+        _ => bail!("Padding must be a single byte"),
+    };
+    let separator = separator.map(parse_hex).transpose()?.unwrap_or_default();
+
+    let mut data = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        if i > 0 {
+            data.extend_from_slice(&separator);
+        }
+        let start = data.len();
+        data.extend(read_input(Some(file).filter(|f| *f != "-").map(String::as_str))?);
+        let len = data.len() - start;
+        data.resize(start + len.next_multiple_of(align), pad);
+    }
+
+    write_output(output, &data)
+}
+
+fn cmd_entropy(
+    input: Option<&str>,
+    block: &str,
+    threshold: f64,
+    width: usize,
+    json: bool,
+) -> Result<()> {
+    let block = parse_offset(block)?;
+    if block == 0 {
+        bail!("Block size must be at least 1");
+    }
+    let data = read_input(input)?;
+    let values: Vec<f64> = data.chunks(block).map(entropy).collect();
+    let regions = high_entropy_regions(&values, threshold);
+
+    if json {
+        let blocks: Value = values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| {
+                json!({
+                    "offset": i * block,
+                    "entropy": json_float(value, 4),
+                    "high": value >= threshold,
+                })
+            })
+            .collect();
+        let regions: Value = regions
+            .iter()
+            .map(|&(first, last)| {
+                let end = ((last + 1) * block).min(data.len());
+                let average = values[first..=last].iter().sum::<f64>() / (last - first + 1) as f64;
+                json!({
+                    "start": first * block,
+                    "end": end,
+                    "average": json_float(average, 4),
+                })
+            })
+            .collect();
+        print_json(&json!({
+            "block_size": block,
+            "threshold": threshold,
+            "blocks": blocks,
+            "regions": regions,
+        }))?;
+        return Ok(());
+    }
+
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    for (i, &value) in values.iter().enumerate() {
+        let bar = "#".repeat((value / 8.0 * width as f64).round() as usize);
+        let flag = if value >= threshold { "  high" } else { "" };
+        writeln!(out, "0x{:08X}  {:.4}  |{:<width$}|{}", i * block, value, bar, flag)?;
+    }
+
+    if regions.is_empty() {
+        writeln!(out, "No blocks at or above {:.2} bits/byte", threshold)?;
+    } else {
+        writeln!(out, "High-entropy regions (>= {:.2} bits/byte):", threshold)?;
+        for (first, last) in regions {
+            let start = first * block;
+            let end = ((last + 1) * block).min(data.len());
+            let average = values[first..=last].iter().sum::<f64>() / (last - first + 1) as f64;
+            writeln!(
+                out,
+                "  0x{:08X}-0x{:08X}  {} bytes, average {:.4}",
+                start,
+                end,
+                end - start,
+                average
+            )?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Runs of consecutive blocks at or above the threshold, as (first, last) block indices
+fn high_entropy_regions(values: &[f64], threshold: f64) -> Vec<(usize, usize)> {
+    let mut regions: Vec<(usize, usize)> = Vec::new();
+    for (i, &value) in values.iter().enumerate() {
+        if value < threshold {
+            continue;
+        }
+        match regions.last_mut() {
+            Some((_, last)) if *last + 1 == i => *last = i,
+            _ => regions.push((i, i)),
+        }
+    }
+    regions
+}
+
+/// What bx conv converts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Conversion {
+    ToHex,
+    FromHex,
+    Encode(BaseEncoding),
+    Decode(BaseEncoding),
+    ToRecords(HexFormat),
+    FromRecords(HexFormat),
+}
+
+impl Conversion {
+    fn parse(direction: &str) -> Option<Self> {
+        match direction {
+            "bin2hex" | "b2h" => return Some(Self::ToHex),
+            "hex2bin" | "h2b" => return Some(Self::FromHex),
+            _ => {}
+        }
+        if let Some(name) = direction.strip_prefix("bin2") {
+            return BaseEncoding::from_name(name)
+                .map(Self::Encode)
+                .or_else(|| HexFormat::from_name(name).map(Self::ToRecords));
+        }
+        let name = direction.strip_suffix("2bin")?;
+        BaseEncoding::from_name(name)
+            .map(Self::Decode)
+            .or_else(|| HexFormat::from_name(name).map(Self::FromRecords))
+    }
+
+    /// All directions, for the error message
+    fn names() -> String {
+        let mut names = vec!["bin2hex (b2h)".to_string(), "hex2bin (h2b)".to_string()];
+        let formats = [HexFormat::IntelHex, HexFormat::SRecord];
+        let text_forms = BaseEncoding::ALL
+            .iter()
+            .map(|e| e.name())
+            .chain(formats.iter().map(|f| f.name()));
+        for name in text_forms {
+            names.push(format!("bin2{}", name));
+            names.push(format!("{}2bin", name));
+        }
+        names.join(", ")
+    }
+}
+
+/// Options of bx conv that only some directions use
+struct ConvOptions {
+    /// bin2hex: bytes per line
+    width: usize,
+    /// base64/base32 output: with '=' padding
+    pad: bool,
+    /// ihex/srec: load address
+    address: Option<u64>,
+    /// bin2ihex/bin2srec: data bytes per record
+    record_len: usize,
+    /// ihex2bin/srec2bin: byte for gaps between records
+    fill: u8,
+}
+
+fn cmd_conv(
+    direction: &str,
+    input: Option<&str>,
+    output: Option<&str>,
+    options: &ConvOptions,
+) -> Result<()> {
+    let Some(conversion) = Conversion::parse(direction) else {
+        bail!("Unknown direction '{}' (try {})", direction, Conversion::names());
+    };
+    if options.width == 0 {
+        bail!("Width must be at least 1");
+    }
+    check_distinct(input, output)?;
+    let binary = matches!(
+        conversion,
+        Conversion::FromHex | Conversion::Decode(_) | Conversion::FromRecords(_)
+    );
+    let mut reader = open_input(input)?;
+    let mut out = open_output(output, binary)?;
+
+    match conversion {
+        Conversion::ToHex => conv_to_hex(&mut *reader, &mut out, options.width)?,
+        Conversion::FromHex => conv_from_hex(&mut *reader, &mut out)?,
+        Conversion::Encode(encoding) => {
+            conv_encode(&mut *reader, &mut out, encoding, options.pad)?
+        }
+        Conversion::Decode(encoding) => conv_decode(&mut *reader, &mut out, encoding)?,
+        // Converted in memory (records may come in any address order)
+        Conversion::ToRecords(format) => {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            let address = options.address.unwrap_or(0);
+            out.write_all(format.encode(&data, address, options.record_len)?.as_bytes())?;
+        }
+        Conversion::FromRecords(format) => {
+            let mut text = String::new();
+            reader.read_to_string(&mut text)?;
+            let records = format.decode(&text)?;
+            out.write_all(&hexfile::to_binary(&records, options.address, options.fill)?)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn conv_to_hex(reader: &mut dyn Read, out: &mut impl Write, width: usize) -> Result<()> {
+    // Read whole lines at a time
+    let chunk_size = CHUNK_SIZE.div_ceil(width) * width;
+    let mut chunk = Vec::new();
+    loop {
+        chunk.clear();
+        let more = read_chunk(reader, &mut chunk, chunk_size)?;
+        for line in chunk.chunks(width) {
+            for byte in line {
+                write!(out, "{:02X} ", byte)?;
+            }
+            writeln!(out)?;
+        }
+        if !more {
+            return Ok(());
+        }
+    }
+}
+
+fn conv_from_hex(reader: &mut dyn Read, out: &mut impl Write) -> Result<()> {
+    // Non-hex characters are skipped; a digit pair may be split across chunks
+    let mut high: Option<u8> = None;
+    let mut chunk = Vec::new();
+    let mut bytes = Vec::new();
+    loop {
+        chunk.clear();
+        let more = read_chunk(reader, &mut chunk, CHUNK_SIZE)?;
+        bytes.clear();
+        for digit in chunk.iter().filter_map(|&c| (c as char).to_digit(16)) {
+            match high.take() {
+                Some(high) => bytes.push(high << 4 | digit as u8),
+                None => high = Some(digit as u8),
             }
         }
-        "hex2bin" | "h2b" => {
-            let mut text = String::new();
-            match input {
-                Some(p) => {
-                    text = std::fs::read_to_string(p)?;
+        out.write_all(&bytes)?;
+        if !more {
+            break;
+        }
+    }
+    if high.is_some() {
+        bail!("Hex string must have even length");
+    }
+    Ok(())
+}
+
+/// Encode as one line; chunks are whole groups, so only the last one can need padding
+fn conv_encode(
+    reader: &mut dyn Read,
+    out: &mut impl Write,
+    encoding: BaseEncoding,
+    pad: bool,
+) -> Result<()> {
+    let chunk_size = CHUNK_SIZE / encoding.block_bytes() * encoding.block_bytes();
+    let mut chunk = Vec::new();
+    loop {
+        chunk.clear();
+        let more = read_chunk(reader, &mut chunk, chunk_size)?;
+        out.write_all(encoding.encode(&chunk, pad).as_bytes())?;
+        if !more {
+            break;
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Decode whole groups of characters as they arrive (whitespace and line breaks are skipped)
+fn conv_decode(reader: &mut dyn Read, out: &mut impl Write, encoding: BaseEncoding) -> Result<()> {
+    let mut chunk = Vec::new();
+    let mut text = Vec::new();
+    loop {
+        chunk.clear();
+        let more = read_chunk(reader, &mut chunk, CHUNK_SIZE)?;
+        text.extend(chunk.iter().filter(|b| !b.is_ascii_whitespace()));
+        let end = if more {
+            text.len() / encoding.block_chars() * encoding.block_chars()
+        } else {
+            text.len()
+        };
+        out.write_all(&encoding.decode(&text[..end])?)?;
+        text.drain(..end);
+        if !more {
+            return Ok(());
+        }
+    }
+}
+
+/// One line of a bx verify manifest: "sha256:HASH  SIZE  NAME"
+#[derive(Debug, PartialEq)]
+struct ManifestEntry {
+    algorithm: Algorithm,
+    hash: String,
+    size: u64,
+    name: String,
+}
+
+impl ManifestEntry {
+    /// Hash `reader` a chunk at a time, so large images are never loaded whole
+    fn read(name: &str, reader: &mut dyn Read, algorithm: Algorithm) -> io::Result<Self> {
+        let mut hasher = algorithm.hasher();
+        let mut size = 0;
+        let mut chunk = Vec::new();
+        loop {
+            chunk.clear();
+            let more = read_chunk(reader, &mut chunk, CHUNK_SIZE)?;
+            hasher.update(&chunk);
+            size += chunk.len() as u64;
+            if !more {
+                break;
+            }
+        }
+        Ok(Self {
+            algorithm,
+            hash: hasher.finish().hex(),
+            size,
+            name: name.to_string(),
+        })
+    }
+
+    /// Hash the file the entry is named after
+    fn read_file(name: &str, algorithm: Algorithm) -> io::Result<Self> {
+        Self::read(name, &mut std::fs::File::open(name)?, algorithm)
+    }
+
+    /// The name comes last so that it may contain spaces
+    fn parse(line: &str) -> Result<Self> {
+        let split = |s: &str| {
+            s.trim_start()
+                .split_once(char::is_whitespace)
+                .map(|(field, rest)| (field.to_string(), rest.trim_start().to_string()))
+        };
+        let Some((hash, rest)) = split(line) else {
+            bail!("Invalid manifest line: {}", line);
+        };
+        let Some((size, name)) = split(&rest) else {
+            bail!("Invalid manifest line: {}", line);
+        };
+        let Some((algorithm, hash)) = hash.split_once(':') else {
+            bail!("Missing algorithm in manifest line: {}", line);
+        };
+        Ok(Self {
+            algorithm: Algorithm::parse(algorithm)?,
+            hash: hash.to_ascii_uppercase(),
+            size: size.parse().map_err(|e| anyhow::anyhow!("Invalid size '{}': {}", size, e))?,
+            name: name.trim_end().to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ManifestEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}  {}  {}", self.algorithm.key(), self.hash, self.size, self.name)
+    }
+}
+
+fn cmd_verify_create(files: &[String], algorithm: Algorithm, output: Option<&str>) -> Result<()> {
+    let mut manifest = String::new();
+    for file in files {
+        let entry = ManifestEntry::read_file(file, algorithm)
+            .map_err(|e| anyhow::anyhow!("{}: {}", file, e))?;
+        manifest.push_str(&format!("{}\n", entry));
+    }
+    write_text_output(output, &manifest)
+}
+
+/// Check every file in the manifest; false if any is missing or differs
+fn cmd_verify_check(manifest: &str) -> Result<bool> {
+    let text = std::fs::read_to_string(manifest)?;
+    let entries = text
+        .lines()
+        .filter(|line| !is_comment(line))
+        .map(ManifestEntry::parse)
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    let mut failed = 0;
+    for entry in &entries {
+        // A size mismatch is reported without hashing the file
+        let actual = std::fs::metadata(&entry.name).and_then(|metadata| {
+            if metadata.len() != entry.size {
+                return Ok(Err(metadata.len()));
+            }
+            ManifestEntry::read_file(&entry.name, entry.algorithm).map(Ok)
+        });
+        let problem = match actual {
+            Err(e) => Some(format!("MISSING  {} ({})", entry.name, e)),
+            Ok(Err(size)) => Some(format!(
+                "FAILED   {} (size {}, expected {})",
+                entry.name, size, entry.size
+            )),
+            Ok(Ok(actual)) if actual != *entry => {
+                Some(format!("FAILED   {} ({} mismatch)", entry.name, entry.algorithm.name()))
+            }
+            Ok(Ok(_)) => None,
+        };
+        match problem {
+            Some(line) => {
+                failed += 1;
+                writeln!(out, "{}", line)?;
+            }
+            None => writeln!(out, "OK       {}", entry.name)?,
+        }
+    }
+    out.flush()?;
+
+    if failed > 0 {
+        eprintln!("{} of {} files failed", failed, entries.len());
+    }
+    Ok(failed == 0)
+}
+
+/// A run of one repeated byte
+#[derive(Debug, PartialEq)]
+struct Run {
+    offset: usize,
+    len: usize,
+    byte: u8,
+}
+
+/// Report runs of any of `targets` that are at least `min` long, reading a chunk at a time
+fn find_runs(
+    reader: &mut dyn Read,
+    targets: &[u8],
+    min: usize,
+    mut report: impl FnMut(Run) -> Result<()>,
+) -> Result<()> {
+    let mut current: Option<Run> = None;
+    let mut offset = 0;
+    let mut chunk = Vec::new();
+    loop {
+        chunk.clear();
+        let more = read_chunk(reader, &mut chunk, CHUNK_SIZE)?;
+        for &byte in &chunk {
+            match &mut current {
+                Some(run) if run.byte == byte => run.len += 1,
+                _ => {
+                    if let Some(run) = current.take().filter(|run| run.len >= min) {
+                        report(run)?;
+                    }
+                    current = targets.contains(&byte).then_some(Run { offset, len: 1, byte });
+                }
+            }
+            offset += 1;
+        }
+        if !more {
+            break;
+        }
+    }
+    if let Some(run) = current.filter(|run| run.len >= min) {
+        report(run)?;
+    }
+    Ok(())
+}
+
+fn cmd_gaps(input: Option<&str>, bytes: &[String], min: &str, json: bool) -> Result<()> {
+    let min = parse_offset(min)?.max(1);
+    let targets = if bytes.is_empty() {
+        vec![0x00, 0xFF]
+    } else {
+        bytes
+            .iter()
+            .map(|byte| match parse_hex(byte)?.as_slice() {
+                &[byte] => Ok(byte),
+                _ => bail!("'{}' is not a single byte", byte),
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut records = Vec::new();
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    let (mut count, mut total) = (0, 0);
+    find_runs(&mut *open_input(input)?, &targets, min, |run| {
+        count += 1;
+        total += run.len;
+        if json {
+            records.push(json!({
+                "offset": run.offset,
+                "length": run.len,
+                "byte": format!("{:02X}", run.byte),
+            }));
+        } else {
+            writeln!(
+                out,
+                "0x{:08X}-0x{:08X}  {:>10} bytes  {:02X}",
+                run.offset,
+                run.offset + run.len,
+                run.len,
+                run.byte
+            )?;
+        }
+        Ok(())
+    })?;
+
+    if json {
+        drop(out);
+        print_json(&Value::Array(records))?;
+        return Ok(());
+    }
+    writeln!(out, "{} runs, {} bytes", count, total)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Share of text characters at or above which a block is text
+const TEXT_RATIO: f64 = 0.9;
+/// Entropy (bits/byte) at or above which a block looks compressed or encrypted
+const RANDOM_ENTROPY: f64 = 7.2;
+/// Entropy at or above which a block looks like machine code rather than plain data
+const CODE_ENTROPY: f64 = 5.0;
+
+/// Rough kind of content, judged from byte statistics alone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockClass {
+    Zero,
+    /// One byte value throughout other than 00 (e.g. FF padding)
+    Fill,
+    Text,
+    Data,
+    Code,
+    Random,
+}
+
+impl BlockClass {
+    const ALL: [Self; 6] =
+        [Self::Zero, Self::Fill, Self::Text, Self::Data, Self::Code, Self::Random];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Zero => "zero",
+            Self::Fill => "fill",
+            Self::Text => "text",
+            Self::Data => "data",
+            Self::Code => "code-like",
+            Self::Random => "compressed/encrypted",
+        }
+    }
+}
+
+/// Statistics of one block
+#[derive(Debug)]
+struct BlockStats {
+    offset: usize,
+    len: usize,
+    entropy: f64,
+    /// Most frequent byte (the lowest one on a tie) and its count
+    top: (u8, u64),
+    /// Printable ASCII, tab, CR and LF
+    text: u64,
+}
+
+impl BlockStats {
+    fn new(offset: usize, data: &[u8]) -> Self {
+        let mut freq = [0u64; 256];
+        for &byte in data {
+            freq[byte as usize] += 1;
+        }
+        let top = (0..=255u8)
+            .map(|byte| (byte, freq[byte as usize]))
+            .max_by_key(|&(byte, count)| (count, std::cmp::Reverse(byte)))
+            .unwrap_or((0, 0));
+        let text = (0x20u8..=0x7E)
+            .chain([b'\t', b'\n', b'\r'])
+            .map(|byte| freq[byte as usize])
+            .sum();
+        Self {
+            offset,
+            len: data.len(),
+            entropy: counts_entropy(&freq),
+            top,
+            text,
+        }
+    }
+
+    fn ratio(&self, count: u64) -> f64 {
+        count as f64 / self.len.max(1) as f64
+    }
+
+    fn class(&self) -> BlockClass {
+        if self.top.1 == self.len as u64 {
+            if self.top.0 == 0 { BlockClass::Zero } else { BlockClass::Fill }
+        } else if self.ratio(self.text) >= TEXT_RATIO {
+            BlockClass::Text
+        } else if self.entropy >= RANDOM_ENTROPY {
+            BlockClass::Random
+        } else if self.entropy >= CODE_ENTROPY {
+            BlockClass::Code
+        } else {
+            BlockClass::Data
+        }
+    }
+}
+
+fn cmd_blocks(input: Option<&str>, block: &str, json: bool) -> Result<()> {
+    let block = parse_offset(block)?;
+    if block == 0 {
+        bail!("Block size must be at least 1");
+    }
+
+    let mut reader = open_input(input)?;
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    let mut records = Vec::new();
+    // Blocks and bytes per class
+    let mut totals = [(0usize, 0usize); BlockClass::ALL.len()];
+    if !json {
+        writeln!(out, "Offset      Entropy  Top byte    Text  Class")?;
+    }
+    let mut chunk = Vec::new();
+    let mut offset = 0;
+    loop {
+        chunk.clear();
+        let more = read_chunk(&mut *reader, &mut chunk, block)?;
+        if chunk.is_empty() {
+            break;
+        }
+        let stats = BlockStats::new(offset, &chunk);
+        let class = stats.class();
+        let total = &mut totals[class as usize];
+        total.0 += 1;
+        total.1 += stats.len;
+        if json {
+            records.push(json!({
+                "offset": stats.offset,
+                "length": stats.len,
+                "entropy": json_float(stats.entropy, 4),
+                "top_byte": format!("{:02X}", stats.top.0),
+                "top_ratio": json_float(stats.ratio(stats.top.1), 4),
+                "text_ratio": json_float(stats.ratio(stats.text), 4),
+                "class": class.name(),
+            }));
+        } else {
+            writeln!(
+                out,
+                "0x{:08X}  {:.4}   {:02X} {:>5.1}%  {:>5.1}%  {}",
+                stats.offset,
+                stats.entropy,
+                stats.top.0,
+                stats.ratio(stats.top.1) * 100.0,
+                stats.ratio(stats.text) * 100.0,
+                class.name()
+            )?;
+        }
+        offset += chunk.len();
+        if !more {
+            break;
+        }
+    }
+
+    if json {
+        drop(out);
+        print_json(&Value::Array(records))?;
+        return Ok(());
+    }
+    writeln!(out)?;
+    for (class, (blocks, bytes)) in BlockClass::ALL.iter().zip(totals) {
+        if blocks > 0 {
+            writeln!(out, "{:<21} {:>6} blocks  {:>12} bytes", class.name(), blocks, bytes)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Blocks with the same content
+#[derive(Debug)]
+struct DuplicateGroup {
+    /// Offsets of the copies, ascending
+    offsets: Vec<usize>,
+    /// SHA-256 of the block
+    hash: Vec<u8>,
+    /// First bytes of the block
+    head: Vec<u8>,
+}
+
+/// Hash every full block of the input and group the ones seen more than once,
+/// most copies first
+fn find_duplicates(reader: &mut dyn Read, block: usize) -> Result<Vec<DuplicateGroup>> {
+    // Hash -> (first offset, later offsets, first bytes once a copy turns up); nothing is
+    // allocated for blocks seen only once
+    let mut seen: HashMap<[u8; 32], (usize, Vec<usize>, Vec<u8>)> = HashMap::new();
+    let mut chunk = Vec::new();
+    let mut offset = 0;
+    loop {
+        chunk.clear();
+        read_chunk(reader, &mut chunk, block)?;
+        // A short last block cannot equal a full one
+        if chunk.len() < block {
+            break;
+        }
+        let hash = Algorithm::Sha256.compute(&chunk).bytes.try_into().unwrap_or([0; 32]);
+        match seen.get_mut(&hash) {
+            Some((_, copies, head)) => {
+                if copies.is_empty() {
+                    head.extend_from_slice(&chunk[..block.min(8)]);
+                }
+                copies.push(offset);
+            }
+            None => {
+                seen.insert(hash, (offset, Vec::new(), Vec::new()));
+            }
+        }
+        offset += block;
+    }
+
+    let mut groups: Vec<DuplicateGroup> = seen
+        .into_iter()
+        .filter(|(_, (_, copies, _))| !copies.is_empty())
+        .map(|(hash, (first, copies, head))| DuplicateGroup {
+            offsets: [first].into_iter().chain(copies).collect(),
+            hash: hash.to_vec(),
+            head,
+        })
+        .collect();
+    groups.sort_by_key(|group| (std::cmp::Reverse(group.offsets.len()), group.offsets[0]));
+    Ok(groups)
+}
+
+fn cmd_dedupe(input: Option<&str>, block: &str, min: usize, json: bool) -> Result<()> {
+    let block = parse_size(block)?;
+    if block == 0 {
+        bail!("Block size must be at least 1");
+    }
+    let groups: Vec<DuplicateGroup> = find_duplicates(&mut *open_input(input)?, block)?
+        .into_iter()
+        .filter(|group| group.offsets.len() >= min.max(2))
+        .collect();
+
+    if json {
+        let records = groups
+            .iter()
+            .map(|group| {
+                json!({
+                    "count": group.offsets.len(),
+                    "size": block,
+                    "sha256": hex_string(&group.hash),
+                    "head": hex_string(&group.head),
+                    "offsets": group.offsets,
+                })
+            })
+            .collect();
+        print_json(&records)?;
+        return Ok(());
+    }
+
+    // Offsets listed per group before the rest are only counted
+    const SHOWN: usize = 4;
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    let mut redundant = 0;
+    for group in &groups {
+        let count = group.offsets.len();
+        redundant += count - 1;
+        let offsets: Vec<String> =
+            group.offsets.iter().take(SHOWN).map(|o| format!("0x{:08X}", o)).collect();
+        let more = match count.checked_sub(SHOWN) {
+            Some(rest) if rest > 0 => format!(" (+{} more)", rest),
+            _ => String::new(),
+        };
+        writeln!(
+            out,
+            "{:>6} x  {}..  {:<16}  {}{}",
+            count,
+            &hex_string(&group.hash)[..16],
+            hex_string(&group.head),
+            offsets.join(" "),
+            more
+        )?;
+    }
+    writeln!(
+        out,
+        "{} groups of {}-byte blocks, {} redundant blocks ({} bytes)",
+        groups.len(),
+        block,
+        redundant,
+        redundant * block
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Input files named in a bx command line: the -i/--input option, or the positional inputs of
+/// commands such as template, sections and cat
+fn command_inputs(args: &[String]) -> Vec<String> {
+    let command = Args::command();
+    let Some(command) = args.first().and_then(|name| command.find_subcommand(name)) else {
+        return Vec::new();
+    };
+    let mut inputs = Vec::new();
+    let mut positionals = Vec::new();
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            positionals.extend(args.by_ref().cloned());
+        } else if let Some(option) = arg.strip_prefix("--") {
+            let (long, value) = match option.split_once('=') {
+                Some((long, value)) => (long, Some(value.to_string())),
+                None => (option, None),
+            };
+            let Some(option) = command.get_arguments().find(|a| a.get_long() == Some(long)) else {
+                continue;
+            };
+            if option.get_action().takes_values() {
+                let value = value.or_else(|| args.next().cloned());
+                if option.get_id() == "input" {
+                    inputs.extend(value);
                 }
-                None => {
-                    io::stdin().read_to_string(&mut text)?;
+            }
+        } else if let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) {
+            // Short flags may be grouped, and the last one may carry its value (-iFILE)
+            for (i, flag) in flags.char_indices() {
+                let Some(option) = command.get_arguments().find(|a| a.get_short() == Some(flag))
+                else {
+                    continue;
+                };
+                if option.get_action().takes_values() {
+                    let rest = &flags[i + flag.len_utf8()..];
+                    let value =
+                        if rest.is_empty() { args.next().cloned() } else { Some(rest.to_string()) };
+                    if option.get_id() == "input" {
+                        inputs.extend(value);
+                    }
+                    break;
                 }
             }
-            let bytes = parse_hex(&text)?;
-            io::stdout().write_all(&bytes)?;
+        } else {
+            positionals.push(arg.clone());
+        }
+    }
+    for (index, arg) in command.get_positionals().enumerate() {
+        if !matches!(arg.get_id().as_str(), "input" | "files") {
+            continue;
+        }
+        let count = if matches!(arg.get_action(), ArgAction::Append) { usize::MAX } else { 1 };
+        inputs.extend(positionals.iter().skip(index).take(count).filter(|p| *p != "-").cloned());
+    }
+    inputs
+}
+
+fn cmd_watch(
+    files: &[String],
+    clear: bool,
+    delay: std::time::Duration,
+    command: &[String],
+    json: bool,
+) -> Result<()> {
+    use notify::event::{AccessKind, AccessMode, ModifyKind};
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    if command.first().is_some_and(|name| name == "watch") {
+        bail!("bx watch cannot run itself");
+    }
+    let files = if files.is_empty() { command_inputs(command) } else { files.to_vec() };
+    if files.is_empty() {
+        bail!("Nothing to watch: give the command an input file, or name files with --file");
+    }
+
+    // Watch the directories rather than the files: tools that write a new file and rename it
+    // over the old one would otherwise end the watch
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mut paths = Vec::new();
+    for file in &files {
+        let path = std::path::Path::new(file);
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => std::path::Path::new("."),
+        };
+        let Some(name) = path.file_name() else {
+            bail!("'{}' is not a file", file);
+        };
+        let dir = dir.canonicalize()?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        paths.push(dir.join(name));
+    }
+
+    let exe = std::env::current_exe()?;
+    let args: Vec<&str> =
+        json.then_some("--json").into_iter().chain(command.iter().map(String::as_str)).collect();
+    let run = |count: usize| -> Result<()> {
+        if clear {
+            print!("\x1b[2J\x1b[H");
+            io::stdout().flush()?;
+        }
+        eprintln!("bx watch: bx {} (run {})", args.join(" "), count);
+        let status = std::process::Command::new(&exe).args(&args).status()?;
+        if let Some(code) = status.code().filter(|&code| code != 0) {
+            eprintln!("bx watch: exit status {}", code);
+        }
+        Ok(())
+    };
+
+    let mut count = 1;
+    run(count)?;
+    for event in &rx {
+        let event = event?;
+        // Reads (including the command's own) and metadata changes are not changes
+        let changed = match event.kind {
+            EventKind::Create(_) => true,
+            EventKind::Modify(kind) => !matches!(kind, ModifyKind::Metadata(_)),
+            EventKind::Access(kind) => kind == AccessKind::Close(AccessMode::Write),
+            _ => false,
+        };
+        if !changed || !event.paths.iter().any(|p| paths.contains(p)) {
+            continue;
+        }
+        // Let the writer finish before reading the file
+        while rx.recv_timeout(delay).is_ok() {}
+        count += 1;
+        run(count)?;
+    }
+    Ok(())
+}
+
+/// Parse "3..7,9" into inclusive (low, high) bit ranges
+/// (checked against the 64-bit limit here, before any width is derived from them)
+fn parse_bit_ranges(spec: &str) -> Result<Vec<(u32, u32)>> {
+    let bit = |s: &str| {
+        let bit = s
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| anyhow::anyhow!("Invalid bit number '{}'", s))?;
+        if bit >= 64 {
+            bail!("Bit {} is out of range (0-63)", bit);
+        }
+        Ok(bit)
+    };
+    spec.split(',')
+        .map(|range| {
+            let (low, high) = match range.split_once("..") {
+                Some((low, high)) => (bit(low)?, bit(high)?),
+                None => (bit(range)?, bit(range)?),
+            };
+            Ok((low.min(high), low.max(high)))
+        })
+        .collect()
+}
+
+/// How the bytes at the offset become a value and how its bits are numbered
+#[derive(Debug, Clone, Copy, Default)]
+struct BitOrder {
+    big_endian: bool,
+    /// Bit 0 is the most significant bit
+    msb0: bool,
+}
+
+/// Value of the inclusive bit range (low, high) in `bytes`
+fn extract_bits(bytes: &[u8], (low, high): (u32, u32), order: BitOrder) -> u64 {
+    let mut word = [0u8; 8];
+    let value = if order.big_endian {
+        word[8 - bytes.len()..].copy_from_slice(bytes);
+        u64::from_be_bytes(word)
+    } else {
+        word[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(word)
+    };
+    let top = bytes.len() as u32 * 8 - 1;
+    let shift = if order.msb0 { top - high } else { low };
+    let len = high - low + 1;
+    let mask = if len >= 64 { u64::MAX } else { (1 << len) - 1 };
+    (value >> shift) & mask
+}
+
+fn cmd_bits(
+    offset: &str,
+    spec: &str,
+    input: Option<&str>,
+    width: Option<usize>,
+    order: BitOrder,
+    json: bool,
+) -> Result<()> {
+    let offset = parse_offset(offset)?;
+    let ranges = parse_bit_ranges(spec)?;
+    let highest = ranges.iter().map(|&(_, high)| high).max().unwrap_or(0);
+    let width = width.unwrap_or(highest as usize / 8 + 1);
+    if !(1..=8).contains(&width) {
+        bail!("Width must be 1-8 bytes");
+    }
+    if highest as usize >= width * 8 {
+        bail!("Bit {} is outside the {}-byte value", highest, width);
+    }
+
+    let mut bytes = vec![0u8; width];
+    open_input_at(input, offset)?
+        .read_exact(&mut bytes)
+        .map_err(|_| anyhow::anyhow!("Input ends before 0x{:X}", offset + width))?;
+
+    let fields: Vec<_> = ranges
+        .iter()
+        .map(|&(low, high)| {
+            let label = if low == high { low.to_string() } else { format!("{}..{}", low, high) };
+            let bits = (high - low + 1) as usize;
+            let value = extract_bits(&bytes, (low, high), order);
+            (label, format!("{:0bits$b}", value), value)
+        })
+        .collect();
+
+    if json {
+        let records = fields
+            .into_iter()
+            .map(|(label, binary, value)| {
+                json!({
+                    "bits": label,
+                    "binary": binary,
+                    "value": value,
+                })
+            })
+            .collect();
+        print_json(&records)?;
+        return Ok(());
+    }
+
+    println!("0x{:08X}: {}", offset, hex_string(&bytes));
+    let label_width = fields.iter().map(|(label, ..)| label.len()).max().unwrap_or(0);
+    let binary_width = fields.iter().map(|(_, binary, _)| binary.len()).max().unwrap_or(0);
+    let value_width = fields.iter().map(|(.., value)| value.to_string().len()).max().unwrap_or(0);
+    for (label, binary, value) in fields {
+        println!(
+            "  bits {:<label_width$}  0b{:<binary_width$}  {:>value_width$}  0x{:X}",
+            label, binary, value, value
+        );
+    }
+    Ok(())
+}
+
+/// CRC parameter value (hex, with or without 0x)
+fn parse_crc_value(s: &str) -> Result<u64> {
+    let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    u64::from_str_radix(digits, 16).map_err(|_| anyhow::anyhow!("Invalid hex value: {}", s))
+}
+
+fn cmd_crc(input: Option<&str>, params: &CrcParams, json: bool) -> Result<()> {
+    if !params.is_valid() {
+        bail!(
+            "Invalid CRC parameters: width must be 1-64 and poly, init and xorout fit in it"
+        );
+    }
+    let mut crc = Crc::new(*params);
+    let mut reader = open_input(input)?;
+    let mut buf = Vec::with_capacity(CHUNK_SIZE);
+    loop {
+        buf.clear();
+        let more = read_chunk(&mut *reader, &mut buf, CHUNK_SIZE)?;
+        crc.update(&buf);
+        if !more {
+            break;
         }
-        _ => bail!("Direction must be 'bin2hex' (b2h) or 'hex2bin' (h2b)"),
     }
+    if json {
+        print_json(&crc_params_json(params, ("value", crc.finish())))?;
+        return Ok(());
+    }
+    println!("{:0width$X}", crc.finish(), width = params.hex_digits());
+    Ok(())
+}
+
+fn cmd_crc_list(json: bool) -> Result<()> {
+    if json {
+        let presets = checksum::PRESETS.iter().map(|p| crc_params_json(p, ("check", p.check)));
+        print_json(&presets.collect())?;
+        return Ok(());
+    }
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    writeln!(
+        out,
+        "{:<20} {:>5}  {:<16}  {:<16}  {:<7}  {:<16}  check",
+        "name", "width", "poly", "init", "reflect", "xorout"
+    )?;
+    for params in checksum::PRESETS {
+        let digits = params.hex_digits();
+        writeln!(
+            out,
+            "{:<20} {:>5}  {:<16}  {:<16}  {:<7}  {:<16}  {:0digits$X}",
+            params.name,
+            params.width,
+            format!("{:0digits$X}", params.poly),
+            format!("{:0digits$X}", params.init),
+            if params.refin { "yes" } else { "no" },
+            format!("{:0digits$X}", params.xorout),
+            params.check,
+        )?;
+    }
+    out.flush()?;
     Ok(())
 }
+
+/// Parameters and one CRC value (the result, or the check value of a preset)
+fn crc_params_json(params: &CrcParams, (key, value): (&'static str, u64)) -> Value {
+    let hex = |value: u64| format!("{:0width$X}", value, width = params.hex_digits());
+    let mut fields = json!({
+        "name": params.name,
+        "width": params.width,
+        "poly": hex(params.poly),
+        "init": hex(params.init),
+        "refin": params.refin,
+        "refout": params.refout,
+        "xorout": hex(params.xorout),
+    });
+    fields[key] = json!(hex(value));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_dump() {
+        let dump = |data: &[u8], start, options: &DumpOptions| {
+            let mut out = Vec::new();
+            write_dump(&mut out, data, start, options).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(
+            dump(b"Hello\n\x00\xFF", 0x10, &DumpOptions::default()),
+            "00000010: 4865 6c6c 6f0a 00ff                      Hello...\n"
+        );
+        let options = DumpOptions { cols: 5, upper: true, ..DumpOptions::default() };
+        assert_eq!(
+            dump(b"Hello world", 0, &options),
+            concat!(
+                "00000000: 4865 6C6C 6F  Hello\n",
+                "00000005: 2077 6F72 6C   worl\n",
+                "0000000a: 64            d\n",
+            )
+        );
+
+        let mut out = Vec::new();
+        write_plain(&mut out, b"Hello", &DumpOptions { cols: 4, ..DumpOptions::default() })
+            .unwrap();
+        assert_eq!(out, b"48656c6c\n6f\n");
+    }
+
+    #[test]
+    fn test_undump() {
+        let data: Vec<u8> = (0u8..=255).cycle().skip(40).take(300).collect();
+        for options in [
+            DumpOptions::default(),
+            DumpOptions { cols: 7, group: 0, upper: true, color: false },
+        ] {
+            let mut dump = Vec::new();
+            write_dump(&mut dump, &data, 0x100, &options).unwrap();
+            assert_eq!(undump(&String::from_utf8(dump).unwrap()).unwrap(), data);
+        }
+
+        // hexdump -C, with a repeated line and the trailing offset
+        let text = concat!(
+            "# comment\n",
+            "00000000  41 42 43 44 45 46 47 48  00 00 00 00 00 00 00 00  |ABCDEFGH........|\n",
+            "*\n",
+            "00000030  7c 20 20 7c                                       ||  ||\n",
+            "00000034\n",
+        );
+        let expected = [&b"ABCDEFGH\0\0\0\0\0\0\0\0"[..]; 3].concat();
+        let expected = [&expected[..], b"|  |"].concat();
+        assert_eq!(undump(text).unwrap(), expected);
+
+        // Old slice -x layout, ASCII column shortened by an editor, plain hex
+        assert_eq!(undump("00000000  41 42 43 44 45 46 47 48  49\n").unwrap(), b"ABCDEFGHI");
+        assert_eq!(undump("00000000: 412e 2020  A.").unwrap(), b"A.  ");
+        assert_eq!(undump("414243\n44\n").unwrap(), b"ABCD");
+        assert!(undump("00000000: 41zz 4243  A.BC").is_err());
+    }
+
+    #[test]
+    fn test_cformat() {
+        let format = |lang, constant| {
+            let options = CformatOptions { lang, name: "fw_bin".to_string(), cols: 2, constant };
+            cformat(b"ABC", &options)
+        };
+        assert_eq!(
+            format(Language::C, false),
+            concat!(
+                "unsigned char fw_bin[] = {\n",
+                "    0x41, 0x42,\n",
+                "    0x43\n",
+                "};\n",
+                "unsigned int fw_bin_len = 3;\n",
+            )
+        );
+        assert!(format(Language::C, true).starts_with("const unsigned char fw_bin[] = {"));
+        assert_eq!(
+            format(Language::Rust, true),
+            "pub const FW_BIN: [u8; 3] = [\n    0x41, 0x42,\n    0x43,\n];\n"
+        );
+        assert_eq!(
+            format(Language::Python, false),
+            "fw_bin = bytes([\n    0x41, 0x42,\n    0x43,\n])\n"
+        );
+        assert_eq!(
+            format(Language::Go, false),
+            "var fw_bin = []byte{\n\t0x41, 0x42,\n\t0x43,\n}\n"
+        );
+        assert_eq!(
+            format(Language::Asm, false),
+            "fw_bin:\n    db 0x41, 0x42\n    db 0x43\nfw_bin_len equ $ - fw_bin\n"
+        );
+        let empty =
+            CformatOptions { lang: Language::C, name: "e".to_string(), cols: 12, constant: false };
+        assert_eq!(cformat(b"", &empty), "unsigned char e[1] = {0};\nunsigned int e_len = 0;\n");
+
+        assert_eq!(identifier("dir/fw-1.bin"), "fw_1_bin");
+        assert_eq!(identifier("1.bin"), "_1_bin");
+    }
+
+    #[test]
+    fn test_template_output() {
+        let template = Template::parse(
+            r#"
+            [structs.main]
+            fields = [
+              { name = "magic", type = "char", count = 2 },
+              { name = "pair", type = "u8", count = 2, enum = "number" },
+              { name = "tail", type = "bytes", count = 1 },
+            ]
+
+            [enums.number]
+            1 = "one"
+            "#,
+        )
+        .unwrap();
+        let nodes = template.apply(b"A\"\x01\x02\x12").unwrap();
+
+        let mut tree = Vec::new();
+        write_tree(&mut tree, &nodes).unwrap();
+        assert_eq!(
+            String::from_utf8(tree).unwrap(),
+            concat!(
+                "0x00000000  main     main      (5 bytes)\n",
+                "0x00000000    magic  char[2]   \"A\"\"\n",
+                "0x00000002    pair   u8[2]     (2 bytes)\n",
+                "0x00000002      [0]  u8        1 (one)\n",
+                "0x00000003      [1]  u8        2\n",
+                "0x00000004    tail   bytes[1]  12\n",
+            )
+        );
+
+        assert_eq!(
+            template_json(&nodes).to_string(),
+            concat!(
+                r#"{"name":"main","type":"main","offset":0,"size":5,"fields":["#,
+                r#"{"name":"magic","type":"char[2]","offset":0,"size":2,"value":"\"A\"\""},"#,
+                r#"{"name":"pair","type":"u8[2]","offset":2,"size":2,"fields":["#,
+                r#"{"name":"[0]","type":"u8","offset":2,"size":1,"value":1,"enum":"one"},"#,
+                r#"{"name":"[1]","type":"u8","offset":3,"size":1,"value":2}]},"#,
+                r#"{"name":"tail","type":"bytes[1]","offset":4,"size":1,"value":"12"}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_stride_and_interleave() {
+        let data = b"0123456789";
+        assert_eq!(stride(data, 2, 0, 1), b"02468");
+        assert_eq!(stride(data, 2, 1, 1), b"13579");
+        assert_eq!(stride(data, 3, 1, 2), b"2389");
+
+        for (step, width) in [(2, 1), (3, 2), (4, 4)] {
+            let lanes: Vec<Vec<u8>> =
+                (0..step).map(|phase| stride(data, step, phase, width)).collect();
+            assert_eq!(interleave(&lanes, width), data);
+        }
+        assert_eq!(interleave(&[b"ace".to_vec(), b"b".to_vec()], 1), b"abce");
+    }
+
+    #[test]
+    fn test_json_float() {
+        assert_eq!(json_float(7.99996, 4), json!(8.0));
+        assert_eq!(json_float(0.123449, 4), json!(0.1234));
+        assert_eq!(json_float(f64::NAN, 4), Value::Null);
+    }
+
+    #[test]
+    fn test_high_entropy_regions() {
+        let values = [7.9, 7.5, 1.0, 8.0, 3.0, 7.2];
+        assert_eq!(high_entropy_regions(&values, 7.2), vec![(0, 1), (3, 3), (5, 5)]);
+        assert!(high_entropy_regions(&values, 8.5).is_empty());
+    }
+
+    #[test]
+    fn test_conversion_parse() {
+        assert_eq!(Conversion::parse("b2h"), Some(Conversion::ToHex));
+        assert_eq!(
+            Conversion::parse("bin2base64url"),
+            Some(Conversion::Encode(BaseEncoding::Base64Url))
+        );
+        assert_eq!(
+            Conversion::parse("base32hex2bin"),
+            Some(Conversion::Decode(BaseEncoding::Base32Hex))
+        );
+        assert_eq!(
+            Conversion::parse("srec2bin"),
+            Some(Conversion::FromRecords(HexFormat::SRecord))
+        );
+        assert_eq!(Conversion::parse("bin2base16"), None);
+        assert_eq!(Conversion::parse("bin2bin"), None);
+
+        let mut out = Vec::new();
+        conv_decode(&mut &b"Zm9v\nYmFy\n"[..], &mut out, BaseEncoding::Base64).unwrap();
+        assert_eq!(out, b"foobar");
+    }
+
+    #[test]
+    fn test_find_runs() {
+        let mut data = vec![0xFFu8; 5];
+        data.extend([1, 0, 0, 2]);
+        data.extend(vec![0u8; CHUNK_SIZE + 3]);
+        let collect = |min| {
+            let mut runs = Vec::new();
+            find_runs(&mut data.as_slice(), &[0x00, 0xFF], min, |run| {
+                runs.push((run.offset, run.len, run.byte));
+                Ok(())
+            })
+            .unwrap();
+            runs
+        };
+        assert_eq!(collect(3), vec![(0, 5, 0xFF), (9, CHUNK_SIZE + 3, 0x00)]);
+        assert_eq!(collect(1)[1], (6, 2, 0x00));
+    }
+
+    #[test]
+    fn test_block_class() {
+        let class = |data: &[u8]| BlockStats::new(0, data).class();
+        assert_eq!(class(&[0; 64]), BlockClass::Zero);
+        assert_eq!(class(&[0xFF; 64]), BlockClass::Fill);
+        assert_eq!(class(b"Hello, world!\r\n\tThis is text.\n"), BlockClass::Text);
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(class(&all), BlockClass::Random);
+        let sparse: Vec<u8> = (0..256).map(|i| if i % 4 == 0 { i as u8 } else { 0 }).collect();
+        assert_eq!(class(&sparse), BlockClass::Data);
+        let mixed: Vec<u8> = (0..256u32).map(|i| (i * 7 % 48) as u8).collect();
+        assert_eq!(class(&mixed), BlockClass::Code);
+
+        let stats = BlockStats::new(0, b"aab\x00");
+        assert_eq!(stats.top, (b'a', 2));
+        assert_eq!(stats.text, 3);
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let mut data = Vec::new();
+        for block in [b"AAAA", b"BBBB", b"AAAA", b"CCCC", b"BBBB", b"AAAA"] {
+            data.extend_from_slice(block);
+        }
+        // A short tail equal to the start of a block is not a copy
+        data.extend_from_slice(b"AA");
+        let groups = find_duplicates(&mut data.as_slice(), 4).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].offsets, [0, 8, 20]);
+        assert_eq!(groups[0].head, b"AAAA");
+        assert_eq!(groups[1].offsets, [4, 16]);
+        assert_eq!(groups[1].hash, Algorithm::Sha256.compute(b"BBBB").bytes);
+        assert!(find_duplicates(&mut &b"AAAABBBB"[..], 4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_command_inputs() {
+        let args = |line: &str| line.split(' ').map(String::from).collect::<Vec<_>>();
+        assert_eq!(command_inputs(&args("info -i fw.bin")), ["fw.bin"]);
+        assert_eq!(
+            command_inputs(&args("slice 0:10 --input=b.bin -xic.bin --input d.bin")),
+            ["b.bin", "c.bin", "d.bin"]
+        );
+        assert_eq!(command_inputs(&args("cat a.bin - -a 16 b.bin")), ["a.bin", "b.bin"]);
+        assert!(command_inputs(&args("find DEADBEEF -- -i")).is_empty());
+        assert!(command_inputs(&args("find -i")).is_empty());
+        assert_eq!(command_inputs(&args("template elf fw.bin")), ["fw.bin"]);
+        assert_eq!(command_inputs(&args("sections -x .text -o out.bin fw.bin")), ["fw.bin"]);
+        assert!(command_inputs(&args("sections -x .text")).is_empty());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("0x200").unwrap(), 0x200);
+        assert_eq!(parse_size("4K").unwrap(), 4096);
+        assert_eq!(parse_size("0x10k").unwrap(), 0x4000);
+        assert_eq!(parse_size("2M").unwrap(), 2 << 20);
+        assert!(parse_size("K").is_err());
+        assert!(parse_size("1T").is_err());
+    }
+
+    #[test]
+    fn test_parse_text() {
+        assert_eq!(parse_text("v1.2\\x00", CharEncoding::Utf8).unwrap(), b"v1.2\0");
+        assert_eq!(parse_text("a\\tb\\\\\\n", CharEncoding::Utf8).unwrap(), b"a\tb\\\n");
+        // Escaped characters are encoded, \x bytes are not
+        assert_eq!(
+            parse_text("A\\n\\xFF", CharEncoding::Utf16Le).unwrap(),
+            [0x41, 0x00, 0x0A, 0x00, 0xFF]
+        );
+        assert!(parse_text("\\x4", CharEncoding::Utf8).is_err());
+        assert!(parse_text("\\x+F", CharEncoding::Utf8).is_err());
+        assert!(parse_text("\\q", CharEncoding::Utf8).is_err());
+        assert!(parse_text("end\\", CharEncoding::Utf8).is_err());
+        assert!(parse_text("é", CharEncoding::Ascii).is_err());
+    }
+
+    #[test]
+    fn test_expect_mismatch() {
+        let data = [0x74, 0x03, 0x90, 0x90];
+        assert_eq!(expect_mismatch(&data, 0, &[0x74, 0x03]), None);
+        let diff = expect_mismatch(&data, 1, &[0x03, 0x91, 0x90]).unwrap();
+        let lines: Vec<&str> = diff.lines().collect();
+        assert_eq!(lines[0], "  0x00000001  expected: 03 91 90");
+        assert_eq!(lines[1], "              found:    03 90 90");
+        assert_eq!(lines[2], "                           ^^");
+        let past_end = expect_mismatch(&data, 3, &[0x90, 0x90]).unwrap();
+        assert!(past_end.contains("found:    90 (end of file)"));
+        assert!(past_end.ends_with("   ^^"));
+        assert!(expect_mismatch(&data, 8, &[0x00]).is_some());
+    }
+
+    #[test]
+    fn test_parse_crc_value() {
+        assert_eq!(parse_crc_value("1021").unwrap(), 0x1021);
+        assert_eq!(parse_crc_value("0xFFFFFFFF").unwrap(), 0xFFFF_FFFF);
+        assert_eq!(parse_crc_value("ffffffffffffffff").unwrap(), u64::MAX);
+        assert!(parse_crc_value("0x").is_err());
+        assert!(parse_crc_value("12G").is_err());
+    }
+
+    #[test]
+    fn test_extract_bits() {
+        assert_eq!(parse_bit_ranges("3..7, 9,12..10").unwrap(), vec![(3, 7), (9, 9), (10, 12)]);
+        assert!(parse_bit_ranges("3..x").is_err());
+        let error = parse_bit_ranges("64").unwrap_err().to_string();
+        assert_eq!(error, "Bit 64 is out of range (0-63)");
+        assert!(parse_bit_ranges("0..63").is_ok());
+
+        let le = BitOrder::default();
+        assert_eq!(extract_bits(&[0b1011_0110], (3, 7), le), 0b10110);
+        assert_eq!(extract_bits(&[0b1011_0110], (0, 0), le), 0);
+        // 0x1234 little-endian is 34 12; big-endian reads 34 12 as 0x3412
+        assert_eq!(extract_bits(&[0x34, 0x12], (4, 11), le), 0x23);
+        let be = BitOrder { big_endian: true, msb0: false };
+        assert_eq!(extract_bits(&[0x34, 0x12], (4, 11), be), 0x41);
+        // MSB-0 numbering: bits 0..3 are the top nibble
+        let msb0 = BitOrder { big_endian: true, msb0: true };
+        assert_eq!(extract_bits(&[0x45, 0x00], (0, 3), msb0), 4);
+        assert_eq!(extract_bits(&[0x45, 0x00], (4, 7), msb0), 5);
+        assert_eq!(extract_bits(&[0xFF; 8], (0, 63), le), u64::MAX);
+    }
+
+    #[test]
+    fn test_manifest_entry() {
+        let entry = ManifestEntry::read("fw v2.bin", &mut &b"abc"[..], Algorithm::Md5).unwrap();
+        let line = entry.to_string();
+        assert_eq!(line, "md5:900150983CD24FB0D6963F7D28E17F72  3  fw v2.bin");
+        assert_eq!(ManifestEntry::parse(&line).unwrap(), entry);
+        assert_eq!(
+            ManifestEntry::parse("crc32:cbf43926 9 check.txt").unwrap(),
+            ManifestEntry::read("check.txt", &mut &b"123456789"[..], Algorithm::Crc32).unwrap()
+        );
+        assert!(ManifestEntry::parse("CBF43926  9  check.txt").is_err());
+        assert!(ManifestEntry::parse("crc32:CBF43926  nine  check.txt").is_err());
+        assert!(ManifestEntry::parse("crc32:CBF43926  9").is_err());
+    }
+
+    #[test]
+    fn test_streaming_across_chunks() {
+        // Matches straddling the chunk boundary are found once, in order
+        let mut data = vec![0u8; CHUNK_SIZE * 2 + 10];
+        for offset in [CHUNK_SIZE - 2, CHUNK_SIZE - 1, CHUNK_SIZE * 2 + 6] {
+            data[offset..offset + 3].copy_from_slice(b"ABC");
+        }
+        data[CHUNK_SIZE - 3] = b'C';
+        let patterns = vec![MaskedPattern::exact(b"ABC"), MaskedPattern::exact(b"C")];
+        let finder = MultiFinder::new(patterns);
+        let mut found = Vec::new();
+        let mut contexts = Vec::new();
+        stream_matches(&mut data.as_slice(), 2, (2, 5), |d| finder.find_all(d), |m, at, bytes| {
+            found.push((m.offset, m.pattern));
+            contexts.push((at, bytes.to_vec()));
+            Ok(ControlFlow::Continue(()))
+        })
+        .unwrap();
+        assert_eq!(
+            found,
+            vec![
+                (CHUNK_SIZE - 3, 1),
+                (CHUNK_SIZE - 1, 0),
+                (CHUNK_SIZE + 1, 1),
+                (CHUNK_SIZE * 2 + 6, 0),
+                (CHUNK_SIZE * 2 + 8, 1),
+            ]
+        );
+        // Context comes from the input around each match, even across the boundary
+        for (&(offset, _), (at, bytes)) in found.iter().zip(&contexts) {
+            assert_eq!(*at, offset - 2);
+            assert_eq!(bytes, &data[*at..(offset + 5).min(data.len())]);
+        }
+
+        // Replacements skip overlapping matches and keep offsets when lengths differ
+        let replace = |data: &[u8], all| {
+            let mut out = Vec::new();
+            replace_stream(&mut &data[..], &mut out, b"AA", b"xyz", all).unwrap();
+            out
+        };
+        assert_eq!(replace(b"AAAAA-AA", true), b"xyzxyzA-xyz");
+        assert_eq!(replace(b"AAAAA-AA", false), b"xyzAAA-AA");
+        let mut data = vec![0u8; CHUNK_SIZE + 1];
+        data[CHUNK_SIZE - 1..].copy_from_slice(b"AA");
+        let out = replace(&data, true);
+        assert_eq!(out.len(), CHUNK_SIZE + 2);
+        assert_eq!(&out[CHUNK_SIZE - 1..], b"xyz");
+    }
+}
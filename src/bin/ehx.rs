@@ -1,9 +1,13 @@
 use std::io::{self, IsTerminal, Read, Write as _};
+use std::path::PathBuf;
 
 use anyhow::Result;
 use clap::Parser;
 use crossterm::{
-    event::{DisableBracketedPaste, EnableBracketedPaste, EnableFocusChange, DisableFocusChange},
+    event::{
+        DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture,
+    },
     execute, queue,
     terminal::{
         disable_raw_mode, enable_raw_mode, BeginSynchronizedUpdate, EndSynchronizedUpdate,
@@ -13,28 +17,75 @@ use crossterm::{
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 use ehx::app::App;
+use ehx::clipboard::{bytes_to_hex, hex_to_bytes, HexFormat};
+use ehx::config::Config;
+use ehx::encoding::CharEncoding;
+use ehx::session::Sessions;
+use ehx::ui::GROUP_SIZES;
 
 /// Terminal hex editor inspired by Stirling
 #[derive(Parser, Debug)]
 #[command(name = "hx")]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// File to open
+    /// Files to open (each in its own buffer; C-x Right / C-x Left to switch)
     #[arg(value_name = "FILE")]
-    file: Option<String>,
+    files: Vec<PathBuf>,
 
     /// Bytes per row (default: 16)
     #[arg(short, long, default_value = "16")]
     bytes_per_row: usize,
 
+    /// Group N bytes together in the hex column like xxd -g (1, 2, 4 or 8)
+    #[arg(short, long, value_name = "N", value_parser = parse_group)]
+    group: Option<usize>,
+
     /// Read-only mode
     #[arg(short, long)]
     readonly: bool,
+
+    /// Open the file read-only via mmap without loading it into memory
+    #[arg(long)]
+    mmap: bool,
+
+    /// Open with the cursor at the first occurrence of a hex pattern (e.g., "DEADBEEF")
+    #[arg(long, value_name = "PATTERN", conflicts_with = "find_text")]
+    find: Option<String>,
+
+    /// Open with the cursor at the first occurrence of a text string
+    #[arg(long, value_name = "TEXT")]
+    find_text: Option<String>,
+
+    /// Character encoding of the text column (e.g., utf8, sjis, eucjp, utf16le, cp437)
+    #[arg(short, long, value_name = "NAME", value_parser = parse_encoding)]
+    encoding: Option<CharEncoding>,
+
+    /// Load a character table (.tbl) and show the text column with it
+    #[arg(long, value_name = "FILE", conflicts_with = "encoding")]
+    table: Option<PathBuf>,
+
+    /// Compare two files side by side (M-n / M-p: next / previous difference)
+    #[arg(long, num_args = 2, value_names = ["A", "B"], conflicts_with_all = ["files", "mmap"])]
+    diff: Option<Vec<PathBuf>>,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    // 検索パターンはターミナル初期化前に検証する
+    let find = match (&args.find, &args.find_text) {
+        (Some(hex), _) => {
+            let bytes = hex_to_bytes(hex)?;
+            if bytes.is_empty() {
+                anyhow::bail!("Empty search pattern");
+            }
+            Some((bytes_to_hex(&bytes, HexFormat::Spaced), false))
+        }
+        (None, Some(text)) if !text.is_empty() => Some((text.clone(), true)),
+        (None, Some(_)) => anyhow::bail!("Empty search pattern"),
+        (None, None) => None,
+    };
+
     // 標準入力からデータを読み込む（パイプされている場合）
     let stdin_data = if !io::stdin().is_terminal() {
         let mut data = Vec::new();
@@ -45,7 +96,7 @@ fn main() -> Result<()> {
     };
 
     // ターミナルの初期化
-    // マウスモードは無効（ターミナルでのテキスト選択・コピーを優先）
+    // マウスモードは設定で有効にしたときだけ（既定はターミナルでのテキスト選択・コピーを優先）
     // Alternate Screenでトラックパッドスクロールによるバッファ移動を防止
     // Bracketed Pasteでペースト内容を一括取り込み
     // Focus Eventsでフォーカス変更を検出
@@ -61,12 +112,13 @@ fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // アプリケーションの実行
-    let result = run_app(&mut terminal, args, stdin_data);
+    let result = run_app(&mut terminal, args, stdin_data, find);
 
     // ターミナルの後処理
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
+        DisableMouseCapture,
         DisableFocusChange,
         DisableBracketedPaste,
         LeaveAlternateScreen
@@ -81,16 +133,55 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, args: Args, stdin_data: Option<Vec<u8>>) -> Result<()> {
-    let mut app = App::new();
+fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    args: Args,
+    stdin_data: Option<Vec<u8>>,
+    find: Option<(String, bool)>,
+) -> Result<()> {
+    // 設定ファイルの読み込み（エラーはステータスバーで通知してデフォルトで続行）
+    let (mut config, config_error) = match Config::load() {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(e)),
+    };
+    if let Some(group) = args.group {
+        config.group = group;
+    }
+    let mut app = App::with_config(config);
+    app.set_readonly(args.readonly);
+    app.set_forced_encoding(args.encoding);
+    // ファイルごとの状態（読めなければ空で続行）
+    app.set_sessions(Sessions::load().unwrap_or_default());
 
     // データを読み込む（優先順位: ファイル > 標準入力）
-    if let Some(ref path) = args.file {
-        app.open(path)?;
+    if let Some(paths) = args.diff {
+        app.open(&paths[0])?;
+        app.open_compare(&paths[1])?;
+    } else if !args.files.is_empty() {
+        app.open_files(args.files, args.mmap)?;
     } else if let Some(data) = stdin_data {
         app.load_bytes(data);
     }
 
+    // --table: 開いたバッファを文字テーブルで表示
+    if let Some(path) = &args.table {
+        app.load_char_table(path)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    }
+
+    // --find / --find-text: 最初のマッチ位置へ
+    if let Some((query, literal)) = find {
+        app.find_first(&query, literal);
+    }
+
+    if let Some(e) = config_error {
+        app.set_status_message(e.to_string());
+    }
+
+    if app.mouse_enabled() {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    }
+
     // ウィンドウタイトルを設定
     update_title(terminal.backend_mut(), &app)?;
 
@@ -103,15 +194,37 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, args: Args, st
         terminal.backend_mut().flush()?;
 
         app.handle_event()?;
+        app.tick();
 
         if app.should_quit() {
             break;
         }
     }
 
+    // 正常終了時はスワップファイルを残さない
+    // 状態の保存に失敗しても終了は妨げない
+    let _ = app.shutdown();
+
     Ok(())
 }
 
+/// --group の値を検証
+fn parse_group(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(group) if GROUP_SIZES.contains(&group) => Ok(group),
+        _ => Err("must be 1, 2, 4 or 8".to_string()),
+    }
+}
+
+/// --encoding の値を検証（文字テーブルは --table で指定する）
+fn parse_encoding(s: &str) -> Result<CharEncoding, String> {
+    match CharEncoding::from_name(s) {
+        Some(CharEncoding::Custom) => Err("use --table FILE for a character table".to_string()),
+        Some(encoding) => Ok(encoding),
+        None => Err(format!("unknown encoding '{}'", s)),
+    }
+}
+
 /// ウィンドウタイトルを更新
 fn update_title(backend: &mut CrosstermBackend<io::Stdout>, app: &App) -> Result<()> {
     let title = format!(
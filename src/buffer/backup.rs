@@ -0,0 +1,105 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// 保存時のバックアップ方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupPolicy {
+    /// バックアップしない
+    #[default]
+    Off,
+    /// file~ （Emacs形式）
+    Simple,
+    /// file.bak
+    Bak,
+    /// file.~1~, file.~2~, ... （番号付き、既存のものは残す）
+    Numbered,
+}
+
+impl BackupPolicy {
+    /// 表示名
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Simple => "simple",
+            Self::Bak => "bak",
+            Self::Numbered => "numbered",
+        }
+    }
+
+    /// 次の方式（トグル用）
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Simple,
+            Self::Simple => Self::Bak,
+            Self::Bak => Self::Numbered,
+            Self::Numbered => Self::Off,
+        }
+    }
+
+    /// バックアップファイルのパスを決める（Off では None）
+    pub fn backup_path(self, path: &Path) -> Option<PathBuf> {
+        match self {
+            Self::Off => None,
+            Self::Simple => Some(with_suffix(path, "~")),
+            Self::Bak => Some(with_suffix(path, ".bak")),
+            Self::Numbered => {
+                // 既存の番号付きバックアップの最大値 + 1
+                let next = numbered_backups(path).max().unwrap_or(0) + 1;
+                Some(with_suffix(path, &format!(".~{}~", next)))
+            }
+        }
+    }
+}
+
+/// パスの末尾に文字列を付け足す
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// 既存の番号付きバックアップ（file.~N~）の番号一覧
+fn numbered_backups(path: &Path) -> impl Iterator<Item = u32> {
+    let prefix = path
+        .file_name()
+        .map(|name| format!("{}.~", name.to_string_lossy()))
+        .unwrap_or_default();
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(move |entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.strip_prefix(&prefix)?.strip_suffix('~')?.parse().ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_path() {
+        let dir = std::env::temp_dir().join(format!("ehx-backup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.bin");
+
+        assert_eq!(BackupPolicy::Off.backup_path(&path), None);
+        assert_eq!(BackupPolicy::Simple.backup_path(&path), Some(dir.join("a.bin~")));
+        assert_eq!(BackupPolicy::Bak.backup_path(&path), Some(dir.join("a.bin.bak")));
+        assert_eq!(BackupPolicy::Numbered.backup_path(&path), Some(dir.join("a.bin.~1~")));
+
+        std::fs::write(dir.join("a.bin.~1~"), b"").unwrap();
+        std::fs::write(dir.join("a.bin.~3~"), b"").unwrap();
+        assert_eq!(BackupPolicy::Numbered.backup_path(&path), Some(dir.join("a.bin.~4~")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,8 +1,200 @@
+use std::borrow::Cow;
+use std::cell::OnceCell;
 use std::fs::File;
-use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use super::BufferError;
+use memmap2::{Mmap, MmapOptions};
+
+use super::{BackupPolicy, BufferError};
+
+/// 元データの実体
+enum Storage {
+    /// メモリ上に読み込んだデータ
+    Owned(Vec<u8>),
+    /// メモリマップ（読み取り専用で開いた巨大ファイル用）
+    Mapped(Mmap),
+}
+
+impl Storage {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Owned(data) => data,
+            Self::Mapped(map) => map,
+        }
+    }
+}
+
+/// ドキュメント内容の読み取り専用スナップショット
+///
+/// 元データと追加バッファを共有してピースの列だけを複製するので、全体を連結せずに作れ、
+/// 別スレッドへ渡せる。作成後にドキュメントを編集してもスナップショットの内容は変わらない。
+#[derive(Clone)]
+pub struct Snapshot {
+    original: Arc<Storage>,
+    added: Arc<Vec<u8>>,
+    pieces: Vec<Piece>,
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl Snapshot {
+    /// データ長を取得
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// データが空かどうか
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 指定範囲のバイト列を取得
+    /// 1つのピースに収まる範囲はコピーせずに返し、ピースをまたぐ範囲だけ連結する
+    pub fn get_range(&self, start: usize, end: usize) -> Option<Cow<'_, [u8]>> {
+        if start > end || end > self.len {
+            return None;
+        }
+        if start == end {
+            return Some(Cow::Borrowed(&[]));
+        }
+        let index = self.starts.partition_point(|&piece_start| piece_start <= start) - 1;
+        let piece_start = self.starts[index];
+        let slice = self.pieces[index].slice(&self.original, &self.added);
+        if end <= piece_start + slice.len() {
+            return Some(Cow::Borrowed(&slice[start - piece_start..end - piece_start]));
+        }
+        let mut out = Vec::with_capacity(end - start);
+        for (piece, &piece_start) in self.pieces[index..].iter().zip(&self.starts[index..]) {
+            if piece_start >= end {
+                break;
+            }
+            let from = start.max(piece_start) - piece_start;
+            let to = end.min(piece_start + piece.len) - piece_start;
+            out.extend_from_slice(&piece.slice(&self.original, &self.added)[from..to]);
+        }
+        Some(Cow::Owned(out))
+    }
+}
+
+/// 分割保存の進行状況（Document::begin_save で作成）
+pub struct SaveJob {
+    /// 書き込み先
+    file: File,
+    /// 一時ファイル（完了時に保存先へ置き換える、直接書き込む場合は None）
+    tmp: Option<PathBuf>,
+    /// 保存先
+    target: PathBuf,
+    /// 書き込み中のピース
+    piece: usize,
+    /// ピース内の書き込み済みバイト数
+    piece_offset: usize,
+    /// 書き込み済みバイト数
+    written: u64,
+    /// 全体のバイト数
+    total: u64,
+    /// 保存先のバックアップが済んでいるか（完了時にドキュメントへ反映する）
+    backed_up: bool,
+}
+
+impl SaveJob {
+    /// 書き込み済みバイト数
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// 全体のバイト数
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// 保存先
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+}
+
+impl Drop for SaveJob {
+    /// 完了せずに破棄された（中断・エラー）場合は一時ファイルを消す
+    fn drop(&mut self) {
+        if let Some(tmp) = self.tmp.take() {
+            let _ = std::fs::remove_file(tmp);
+        }
+    }
+}
+
+/// スワップファイルの先頭マジック
+const SWAP_MAGIC: &[u8] = b"HXSWP\x02";
+
+/// スワップの種類: 元ファイルの範囲を参照するピースを含む
+const SWAP_RELATIVE: u8 = 0;
+/// スワップの種類: 全内容を含む（保存後など、元データがディスク上のファイルと異なる場合）
+const SWAP_FULL: u8 = 1;
+
+/// 更新日時を (秒, ナノ秒) にする（スワップのヘッダー用）
+fn mtime_parts(mtime: SystemTime) -> (u64, u32) {
+    let since = mtime.duration_since(UNIX_EPOCH).unwrap_or_default();
+    (since.as_secs(), since.subsec_nanos())
+}
+
+/// スワップファイルの読み取り位置
+struct SwapReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SwapReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BufferError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| BufferError::Swap("truncated".to_string()))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, BufferError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+    }
+
+    fn u32(&mut self) -> Result<u32, BufferError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap_or_default()))
+    }
+}
+
+/// ピースの参照先バッファ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// 元データ（読み込んだファイル）
+    Original,
+    /// 追加バッファ（編集で挿入されたバイト）
+    Added,
+}
+
+/// ピーステーブルの1要素（参照先バッファの連続領域）
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    source: Source,
+    start: usize,
+    len: usize,
+}
+
+impl Piece {
+    /// 参照先のバイト列
+    fn slice<'a>(&self, original: &'a Storage, added: &'a [u8]) -> &'a [u8] {
+        let buf = match self.source {
+            Source::Original => original.as_slice(),
+            Source::Added => added,
+        };
+        &buf[self.start..self.start + self.len]
+    }
+}
 
 /// Undo/Redo用の操作記録
 #[derive(Debug, Clone)]
@@ -13,48 +205,162 @@ enum UndoOp {
     Insert(usize, u8),
     /// バイトの削除 (位置, 値)
     Delete(usize, u8),
+    /// 範囲の上書き (位置, 旧バイト列, 新バイト列)
+    SetRange(usize, Vec<u8>, Vec<u8>),
+    /// バイト列の挿入 (位置, バイト列)
+    InsertRange(usize, Vec<u8>),
+    /// 範囲の削除 (位置, 削除したバイト列)
+    DeleteRange(usize, Vec<u8>),
+    /// まとめて取り消す操作のグループ（記録順）
+    Group(Vec<UndoOp>),
+}
+
+/// 編集の種類（Undo履歴の表示用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    /// 上書き
+    Set,
+    /// 挿入
+    Insert,
+    /// 削除
+    Delete,
+    /// グループ化された編集（含まれる操作数）
+    Group(usize),
+}
+
+/// Undo履歴の1項目（バイト列は履歴から借用する）
+#[derive(Debug, Clone, Copy)]
+pub struct UndoEntry<'a> {
+    /// 編集の種類
+    pub kind: EditKind,
+    /// 編集位置
+    pub offset: usize,
+    /// 編集前のバイト列（挿入では空）
+    pub old: &'a [u8],
+    /// 編集後のバイト列（削除では空）
+    pub new: &'a [u8],
+}
+
+impl UndoOp {
+    /// 表示用の情報に変換
+    fn entry(&self) -> UndoEntry<'_> {
+        use std::slice::from_ref;
+        let (kind, offset, old, new): (_, _, &[u8], &[u8]) = match self {
+            UndoOp::Set(pos, old, new) => (EditKind::Set, *pos, from_ref(old), from_ref(new)),
+            UndoOp::Insert(pos, value) => (EditKind::Insert, *pos, &[], from_ref(value)),
+            UndoOp::Delete(pos, value) => (EditKind::Delete, *pos, from_ref(value), &[]),
+            UndoOp::SetRange(pos, old, new) => (EditKind::Set, *pos, old, new),
+            UndoOp::InsertRange(pos, bytes) => (EditKind::Insert, *pos, &[], bytes),
+            UndoOp::DeleteRange(pos, bytes) => (EditKind::Delete, *pos, bytes, &[]),
+            UndoOp::Group(ops) => {
+                let offset = ops.first().map(|op| op.entry().offset).unwrap_or(0);
+                (EditKind::Group(ops.len()), offset, &[], &[])
+            }
+        };
+        UndoEntry {
+            kind,
+            offset,
+            old,
+            new,
+        }
+    }
 }
 
 /// バイナリドキュメントを表す構造体
+///
+/// データはピーステーブルで管理する。元データは変更せず、挿入されたバイトは
+/// 追加バッファに積み、ドキュメントはその2つを参照するピースの列で表す。
+/// 任意位置の挿入・削除はピース数に比例するコストで済む。
+///
+/// 描画などの部分的な読み取りは get / get_range でピースから直接読む。
+/// data() は全体を連結するので、全体を走査する処理（検索など）だけで使う。
 #[allow(dead_code)]
 pub struct Document {
     /// ファイルパス
     path: Option<PathBuf>,
-    /// バッファデータ
-    data: Vec<u8>,
+    /// 元データ
+    original: Arc<Storage>,
+    /// 元データを読み込んだファイルの更新日時（元データがディスク上の内容と一致する間だけ）
+    original_mtime: Option<SystemTime>,
+    /// 元データの CRC-32（スワップのヘッダー用、初回に計算）
+    original_crc: OnceCell<u32>,
+    /// 追加バッファ（追記のみ、スナップショットと共有中に書き込むと複製する）
+    added: Arc<Vec<u8>>,
+    /// 最後に保存した時点の追加バッファの長さ（これより後ろを未保存の変更として扱う）
+    saved_added: usize,
+    /// ピースの列
+    pieces: Vec<Piece>,
+    /// 各ピースの開始位置（pieces と同じ長さ、位置からピースを二分探索する）
+    starts: Vec<usize>,
+    /// 全体の長さ
+    len: usize,
+    /// 連続データのキャッシュ（data() 用、編集で破棄）
+    cache: OnceCell<Arc<Vec<u8>>>,
+    /// 内容が変わるたびに増える番号（自動保存の要否判定用）
+    revision: u64,
     /// 変更フラグ
     modified: bool,
     /// 読み取り専用フラグ
     readonly: bool,
+    /// 保存時のバックアップ方式
+    backup: BackupPolicy,
+    /// このパスのバックアップを作成済みか（最初の保存時のみ作成する）
+    backed_up: bool,
     /// Undo履歴
     undo_stack: Vec<UndoOp>,
     /// Redo履歴
     redo_stack: Vec<UndoOp>,
+    /// 記録中のUndoグループ
+    group: Vec<UndoOp>,
+    /// グループのネスト深さ（0 = グループ外）
+    group_depth: usize,
 }
 
 #[allow(dead_code)]
 impl Document {
     /// 空のドキュメントを作成
     pub fn new() -> Self {
-        Self {
-            path: None,
-            data: Vec::new(),
-            modified: false,
-            readonly: false,
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
-        }
+        Self::with_storage(None, Storage::Owned(Vec::new()))
     }
 
     /// バイト列から作成
     pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self::with_storage(None, Storage::Owned(data))
+    }
+
+    /// 元データからドキュメントを作成
+    fn with_storage(path: Option<PathBuf>, original: Storage) -> Self {
+        let len = original.as_slice().len();
+        let pieces = if len > 0 {
+            vec![Piece {
+                source: Source::Original,
+                start: 0,
+                len,
+            }]
+        } else {
+            Vec::new()
+        };
+        let starts = if len > 0 { vec![0] } else { Vec::new() };
         Self {
-            path: None,
-            data,
+            path,
+            original: Arc::new(original),
+            original_mtime: None,
+            original_crc: OnceCell::new(),
+            added: Arc::default(),
+            saved_added: 0,
+            pieces,
+            starts,
+            len,
+            cache: OnceCell::new(),
+            revision: 0,
             modified: false,
             readonly: false,
+            backup: BackupPolicy::Off,
+            backed_up: false,
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            group: Vec::new(),
+            group_depth: 0,
         }
     }
 
@@ -62,87 +368,379 @@ impl Document {
     pub fn open(path: impl Into<PathBuf>) -> Result<Self, BufferError> {
         let path = path.into();
         let mut file = File::open(&path)?;
+        let mtime = file.metadata()?.modified().ok();
         let mut data = Vec::new();
         file.read_to_end(&mut data)?;
 
-        Ok(Self {
-            path: Some(path),
-            data,
-            modified: false,
-            readonly: false,
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
-        })
+        let mut doc = Self::with_storage(Some(path), Storage::Owned(data));
+        doc.original_mtime = mtime;
+        Ok(doc)
+    }
+
+    /// ファイルをメモリマップで開く（読み取り専用）
+    /// 全体をメモリに読み込まないため巨大ファイルでも即座に開ける
+    pub fn open_mmap(path: impl Into<PathBuf>) -> Result<Self, BufferError> {
+        let path = path.into();
+        let mut file = File::open(&path)?;
+        // ブロックデバイスはメタデータのサイズが0なのでシークで求める
+        let len = file.seek(SeekFrom::End(0))?;
+        // 空ファイルはマップできないので通常の読み込みにする
+        let original = if len == 0 {
+            Storage::Owned(Vec::new())
+        } else {
+            let len = usize::try_from(len).map_err(|_| BufferError::OutOfBounds(usize::MAX))?;
+            // SAFETY: 読み取り専用マップ。外部からファイルが切り詰められた場合の
+            // 挙動は保証されないが、ビューア用途として許容する
+            Storage::Mapped(unsafe { MmapOptions::new().len(len).map(&file)? })
+        };
+
+        let mut doc = Self::with_storage(Some(path), original);
+        doc.readonly = true;
+        Ok(doc)
+    }
+
+    /// スワップファイルのパス（file → .file.hxswp、パス未設定なら None）
+    pub fn swap_path(&self) -> Option<PathBuf> {
+        let path = self.path.as_ref()?;
+        let name = path.file_name()?.to_string_lossy();
+        Some(path.with_file_name(format!(".{}.hxswp", name)))
+    }
+
+    /// 未保存の編集をスワップファイルに書き出す
+    ///
+    /// 元ファイルを参照するピースは範囲だけを、追加されたバイトは実体を書く。
+    /// 元ファイルが書き出し時と同じか確かめられるよう、長さ・更新日時・CRC-32 も書く。
+    /// 保存後は元データがディスク上の内容と異なるので、全内容を書く。
+    /// 形式: マジック, 種類, [元ファイル長, 更新日時(秒, ナノ秒), CRC-32,] ピース数, ピース列
+    /// （リトルエンディアン）
+    pub fn write_swap(&self, swap: &Path) -> Result<(), BufferError> {
+        let mut out = Vec::new();
+        out.extend_from_slice(SWAP_MAGIC);
+        let relative = self.original_mtime.filter(|_| self.path.is_some());
+        match relative {
+            Some(mtime) => {
+                let original = self.original.as_slice();
+                let crc = *self.original_crc.get_or_init(|| crc32fast::hash(original));
+                let (secs, nanos) = mtime_parts(mtime);
+                out.push(SWAP_RELATIVE);
+                out.extend_from_slice(&(original.len() as u64).to_le_bytes());
+                out.extend_from_slice(&secs.to_le_bytes());
+                out.extend_from_slice(&nanos.to_le_bytes());
+                out.extend_from_slice(&crc.to_le_bytes());
+            }
+            None => out.push(SWAP_FULL),
+        }
+        out.extend_from_slice(&(self.pieces.len() as u64).to_le_bytes());
+        for piece in &self.pieces {
+            match piece.source {
+                Source::Original if relative.is_some() => {
+                    out.push(0);
+                    out.extend_from_slice(&(piece.start as u64).to_le_bytes());
+                    out.extend_from_slice(&(piece.len as u64).to_le_bytes());
+                }
+                _ => {
+                    out.push(1);
+                    out.extend_from_slice(&(piece.len as u64).to_le_bytes());
+                    out.extend_from_slice(self.piece_slice(piece));
+                }
+            }
+        }
+
+        // 書きかけのスワップを残さないよう一時ファイル経由で置き換える
+        let tmp = swap.with_extension("hxswp.tmp");
+        std::fs::write(&tmp, &out)?;
+        std::fs::rename(&tmp, swap)?;
+        Ok(())
+    }
+
+    /// スワップファイルから未保存の編集を復元する
+    /// 元ファイルの長さ・更新日時・内容が書き出し時と異なる場合はエラー
+    pub fn recover(path: impl Into<PathBuf>, swap: &Path) -> Result<Self, BufferError> {
+        let mut doc = Self::open(path)?;
+        let data = std::fs::read(swap)?;
+        let mut reader = SwapReader { data: &data, pos: 0 };
+
+        if reader.take(SWAP_MAGIC.len())? != SWAP_MAGIC {
+            return Err(BufferError::Swap("bad magic".to_string()));
+        }
+        let original_len = doc.original.as_slice().len();
+        match reader.take(1)?[0] {
+            SWAP_RELATIVE => {
+                let len = reader.u64()?;
+                let mtime = (reader.u64()?, reader.u32()?);
+                let crc = reader.u32()?;
+                let unchanged = len == original_len as u64
+                    && doc.original_mtime.map(mtime_parts) == Some(mtime)
+                    && crc32fast::hash(doc.original.as_slice()) == crc;
+                if !unchanged {
+                    return Err(BufferError::Swap(
+                        "file changed since the swap was written".to_string(),
+                    ));
+                }
+            }
+            SWAP_FULL => {}
+            kind => return Err(BufferError::Swap(format!("unknown swap type {}", kind))),
+        }
+
+        let count = reader.u64()?;
+        let mut pieces = Vec::new();
+        let mut added = Vec::new();
+        let mut len = 0;
+        for _ in 0..count {
+            let piece = match reader.take(1)?[0] {
+                0 => {
+                    let start = reader.u64()? as usize;
+                    let piece_len = reader.u64()? as usize;
+                    if start.checked_add(piece_len).is_none_or(|end| end > original_len) {
+                        return Err(BufferError::Swap("piece out of range".to_string()));
+                    }
+                    Piece {
+                        source: Source::Original,
+                        start,
+                        len: piece_len,
+                    }
+                }
+                1 => {
+                    let piece_len = reader.u64()? as usize;
+                    let start = added.len();
+                    added.extend_from_slice(reader.take(piece_len)?);
+                    Piece {
+                        source: Source::Added,
+                        start,
+                        len: piece_len,
+                    }
+                }
+                tag => return Err(BufferError::Swap(format!("unknown piece type {}", tag))),
+            };
+            // 長さ0のピースは位置の探索を乱すので捨てる
+            if piece.len > 0 {
+                len += piece.len;
+                pieces.push(piece);
+            }
+        }
+
+        doc.added = Arc::new(added);
+        doc.pieces = pieces;
+        doc.len = len;
+        doc.reindex(0);
+        doc.invalidate();
+        doc.modified = true;
+        Ok(doc)
+    }
+
+    /// 内容のリビジョン（編集・Undoのたびに変わる）
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// メモリマップで開いているかどうか
+    pub fn is_mapped(&self) -> bool {
+        matches!(*self.original, Storage::Mapped(_))
     }
 
     /// ファイルに保存
     pub fn save(&mut self) -> Result<(), BufferError> {
-        if let Some(ref path) = self.path {
-            let mut file = File::create(path)?;
-            file.write_all(&self.data)?;
-            self.modified = false;
-            Ok(())
-        } else {
-            Err(BufferError::Io(std::io::Error::new(
+        let mut job = self.begin_save()?;
+        while !self.continue_save(&mut job, usize::MAX)? {}
+        Ok(())
+    }
+
+    /// 別名で保存（保存が完了するまでドキュメントのパスは変えない）
+    pub fn save_as(&mut self, path: impl Into<PathBuf>) -> Result<(), BufferError> {
+        let mut job = self.begin_save_as(path.into())?;
+        while !self.continue_save(&mut job, usize::MAX)? {}
+        Ok(())
+    }
+
+    /// 分割保存を開始する
+    ///
+    /// 通常のファイルには一時ファイルに書いてから置き換えるため、
+    /// 途中で中断しても元のファイルは壊れない。
+    /// 保存中はドキュメントを編集しないこと。
+    pub fn begin_save(&mut self) -> Result<SaveJob, BufferError> {
+        let Some(path) = self.path.clone() else {
+            return Err(BufferError::Io(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
                 "No file path set",
-            )))
+            )));
+        };
+        self.begin_save_as(path)
+    }
+
+    /// 別名での分割保存を開始する
+    ///
+    /// ドキュメントのパスは continue_save が完了を返したときに保存先へ変わるため、
+    /// 書き込みに失敗しても次の保存は元のパスに行われる。
+    pub fn begin_save_as(&mut self, path: PathBuf) -> Result<SaveJob, BufferError> {
+        if self.readonly {
+            return Err(BufferError::ReadOnly);
+        }
+
+        // 上書き前の内容をバックアップ（保存先ごとにセッション中の最初の保存のみ）
+        let mut backed_up = self.backed_up && self.path.as_ref() == Some(&path);
+        if !backed_up
+            && path.is_file()
+            && let Some(backup) = self.backup.backup_path(&path)
+        {
+            std::fs::copy(&path, &backup)?;
+            backed_up = true;
+        }
+
+        // デバイスファイルなどは置き換えられないので直接書き込む
+        let in_place = path.exists() && !path.is_file();
+        let (file, tmp) = if in_place {
+            // 保存先をマップしたまま書き換えると読めなくなるため、先にメモリへ移す
+            if let Storage::Mapped(map) = &*self.original {
+                self.original = Arc::new(Storage::Owned(map.to_vec()));
+            }
+            (File::create(&path)?, None)
+        } else {
+            let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+            let tmp = path.with_file_name(format!(".{}.hxtmp", name));
+            let file = File::create(&tmp)?;
+            // 元のファイルのパーミッションを引き継ぐ
+            if let Ok(metadata) = std::fs::metadata(&path) {
+                let _ = file.set_permissions(metadata.permissions());
+            }
+            (file, Some(tmp))
+        };
+
+        Ok(SaveJob {
+            file,
+            tmp,
+            target: path,
+            piece: 0,
+            piece_offset: 0,
+            written: 0,
+            total: self.len as u64,
+            backed_up,
+        })
+    }
+
+    /// 分割保存を最大 budget バイト進める。完了したら true を返す
+    pub fn continue_save(&mut self, job: &mut SaveJob, budget: usize) -> Result<bool, BufferError> {
+        let mut remaining = budget;
+        while remaining > 0 && job.piece < self.pieces.len() {
+            let slice = &self.piece_slice(&self.pieces[job.piece])[job.piece_offset..];
+            let n = slice.len().min(remaining);
+            job.file.write_all(&slice[..n])?;
+            job.written += n as u64;
+            remaining -= n;
+            if n == slice.len() {
+                job.piece += 1;
+                job.piece_offset = 0;
+            } else {
+                job.piece_offset += n;
+            }
+        }
+        if job.piece < self.pieces.len() {
+            return Ok(false);
         }
+
+        job.file.flush()?;
+        if let Some(tmp) = job.tmp.take() {
+            std::fs::rename(&tmp, &job.target)?;
+        }
+        // 別名保存はここで初めて保存先をドキュメントのパスにする
+        if self.path.as_ref() != Some(&job.target) {
+            self.path = Some(job.target.clone());
+        }
+        self.backed_up = job.backed_up;
+        // ファイルの内容が元データと異なるものになった
+        self.original_mtime = None;
+        self.modified = false;
+        self.saved_added = self.added.len();
+        Ok(true)
     }
 
-    /// 別名で保存
-    pub fn save_as(&mut self, path: impl Into<PathBuf>) -> Result<(), BufferError> {
-        self.path = Some(path.into());
-        self.save()
+    /// 最後の保存以降に変更されたバイトの範囲 [start, end)（昇順、隣接する範囲は結合済み）
+    pub fn modified_ranges(&self) -> Vec<(usize, usize)> {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        let mut pos = 0;
+        for piece in &self.pieces {
+            // 保存前からあるピースを伸ばした場合は、保存後に追加された部分だけが変更
+            if piece.source == Source::Added && piece.start + piece.len > self.saved_added {
+                let start = pos + self.saved_added.saturating_sub(piece.start);
+                let end = pos + piece.len;
+                match ranges.last_mut() {
+                    Some(last) if last.1 == start => last.1 = end,
+                    _ => ranges.push((start, end)),
+                }
+            }
+            pos += piece.len;
+        }
+        ranges
+    }
+
+    /// 保存時のバックアップ方式を設定
+    pub fn set_backup_policy(&mut self, policy: BackupPolicy) {
+        self.backup = policy;
+    }
+
+    /// 保存時のバックアップ方式
+    pub fn backup_policy(&self) -> BackupPolicy {
+        self.backup
     }
 
     /// データの長さを取得
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.len
     }
 
     /// データが空かどうか
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.len == 0
     }
 
     /// 指定位置のバイトを取得
     pub fn get(&self, pos: usize) -> Option<u8> {
-        self.data.get(pos).copied()
+        if let Some(data) = self.cache.get() {
+            return data.get(pos).copied();
+        }
+        let (index, offset) = self.locate(pos)?;
+        let piece = &self.pieces[index];
+        Some(self.piece_slice(piece)[offset])
     }
 
     /// 指定範囲のバイト列を取得
-    pub fn get_range(&self, start: usize, end: usize) -> Option<&[u8]> {
-        if start <= end && end <= self.data.len() {
-            Some(&self.data[start..end])
-        } else {
-            None
+    /// 1つのピースに収まる範囲はコピーせずに返し、ピースをまたぐ範囲だけ連結する
+    pub fn get_range(&self, start: usize, end: usize) -> Option<Cow<'_, [u8]>> {
+        if start > end || end > self.len {
+            return None;
+        }
+        if let Some(data) = self.cache.get() {
+            return Some(Cow::Borrowed(&data[start..end]));
+        }
+        if start == end {
+            return Some(Cow::Borrowed(&[]));
+        }
+        let (index, offset) = self.locate(start)?;
+        let slice = self.piece_slice(&self.pieces[index]);
+        match slice.get(offset..offset + (end - start)) {
+            Some(bytes) => Some(Cow::Borrowed(bytes)),
+            None => self.read_raw(start, end).map(Cow::Owned),
         }
     }
 
     /// 指定位置のバイトを設定
     pub fn set(&mut self, pos: usize, value: u8) -> Result<(), BufferError> {
-        if pos < self.data.len() {
-            let old_value = self.data[pos];
-            if old_value != value {
-                self.data[pos] = value;
-                self.modified = true;
-                self.undo_stack.push(UndoOp::Set(pos, old_value, value));
-                self.redo_stack.clear();
-            }
-            Ok(())
-        } else {
-            Err(BufferError::OutOfBounds(pos))
+        self.check_writable()?;
+        let old_value = self.get(pos).ok_or(BufferError::OutOfBounds(pos))?;
+        if old_value != value {
+            self.overwrite_raw(pos, &[value]);
+            self.modified = true;
+            self.record(UndoOp::Set(pos, old_value, value));
         }
+        Ok(())
     }
 
     /// 指定位置にバイトを挿入
     pub fn insert(&mut self, pos: usize, value: u8) -> Result<(), BufferError> {
-        if pos <= self.data.len() {
-            self.data.insert(pos, value);
+        self.check_writable()?;
+        if pos <= self.len {
+            self.insert_raw(pos, &[value]);
             self.modified = true;
-            self.undo_stack.push(UndoOp::Insert(pos, value));
-            self.redo_stack.clear();
+            self.record(UndoOp::Insert(pos, value));
             Ok(())
         } else {
             Err(BufferError::OutOfBounds(pos))
@@ -151,38 +749,130 @@ impl Document {
 
     /// 指定位置のバイトを削除
     pub fn delete(&mut self, pos: usize) -> Result<u8, BufferError> {
-        if pos < self.data.len() {
-            let value = self.data.remove(pos);
+        self.check_writable()?;
+        let value = self.get(pos).ok_or(BufferError::OutOfBounds(pos))?;
+        self.delete_raw(pos, 1);
+        self.modified = true;
+        self.record(UndoOp::Delete(pos, value));
+        Ok(value)
+    }
+
+    /// 指定位置からバイト列を上書き（範囲は既存データ内であること）
+    /// Undo履歴には1操作として記録する
+    pub fn set_range(&mut self, pos: usize, bytes: &[u8]) -> Result<(), BufferError> {
+        self.check_writable()?;
+        let end = pos.checked_add(bytes.len()).ok_or(BufferError::OutOfBounds(usize::MAX))?;
+        let old = self.read_raw(pos, end).ok_or(BufferError::OutOfBounds(end))?;
+        if old != bytes {
+            self.overwrite_raw(pos, bytes);
             self.modified = true;
-            self.undo_stack.push(UndoOp::Delete(pos, value));
-            self.redo_stack.clear();
-            Ok(value)
-        } else {
-            Err(BufferError::OutOfBounds(pos))
+            self.record(UndoOp::SetRange(pos, old, bytes.to_vec()));
+        }
+        Ok(())
+    }
+
+    /// 指定位置にバイト列を挿入
+    /// Undo履歴には1操作として記録する
+    pub fn insert_bytes(&mut self, pos: usize, bytes: &[u8]) -> Result<(), BufferError> {
+        self.check_writable()?;
+        if pos > self.len {
+            return Err(BufferError::OutOfBounds(pos));
+        }
+        if !bytes.is_empty() {
+            self.insert_raw(pos, bytes);
+            self.modified = true;
+            self.record(UndoOp::InsertRange(pos, bytes.to_vec()));
+        }
+        Ok(())
+    }
+
+    /// 範囲 [start, end) を削除し、削除したバイト列を返す
+    /// Undo履歴には1操作として記録する
+    pub fn delete_range(&mut self, start: usize, end: usize) -> Result<Vec<u8>, BufferError> {
+        self.check_writable()?;
+        let removed = self.read_raw(start, end).ok_or(BufferError::OutOfBounds(end))?;
+        if !removed.is_empty() {
+            self.delete_raw(start, removed.len());
+            self.modified = true;
+            self.record(UndoOp::DeleteRange(start, removed.clone()));
+        }
+        Ok(removed)
+    }
+
+    /// 指定位置に fill を count バイト挿入する
+    /// メモリを確保できなければエラー（巨大な値で異常終了しないように）
+    /// Undo履歴には1操作として記録する
+    pub fn insert_fill(&mut self, pos: usize, count: usize, fill: u8) -> Result<(), BufferError> {
+        self.check_writable()?;
+        if pos > self.len {
+            return Err(BufferError::OutOfBounds(pos));
+        }
+        if count == 0 {
+            return Ok(());
+        }
+        // 追加バッファと Undo 履歴の両方に同じ量が要る
+        let mut bytes = Vec::new();
+        bytes
+            .try_reserve_exact(count)
+            .and_then(|()| Arc::make_mut(&mut self.added).try_reserve(count))
+            .map_err(|_| BufferError::OutOfMemory(count))?;
+        bytes.resize(count, fill);
+        self.insert_raw(pos, &bytes);
+        self.modified = true;
+        self.record(UndoOp::InsertRange(pos, bytes));
+        Ok(())
+    }
+
+    /// 長さを変更する（縮める場合は末尾を削除、伸ばす場合は fill で埋める）
+    pub fn resize(&mut self, new_len: usize, fill: u8) -> Result<(), BufferError> {
+        match new_len.cmp(&self.len) {
+            std::cmp::Ordering::Less => {
+                self.delete_range(new_len, self.len)?;
+            }
+            std::cmp::Ordering::Greater => {
+                self.insert_fill(self.len, new_len - self.len, fill)?;
+            }
+            std::cmp::Ordering::Equal => {}
+        }
+        Ok(())
+    }
+
+    /// Undoグループを開始する
+    /// end_group までの編集は1回のUndo/Redoでまとめて取り消される（ネスト可）
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+    }
+
+    /// Undoグループを終了する
+    pub fn end_group(&mut self) {
+        if self.group_depth == 0 {
+            return;
+        }
+        self.group_depth -= 1;
+        if self.group_depth > 0 {
+            return;
+        }
+        let mut ops = std::mem::take(&mut self.group);
+        match ops.len() {
+            0 => {}
+            1 => self.undo_stack.extend(ops.pop()),
+            _ => self.undo_stack.push(UndoOp::Group(ops)),
         }
     }
 
     /// Undo: 直前の操作を取り消す
     /// 戻り値: (成功したか, 影響を受けた位置)
     pub fn undo(&mut self) -> Option<usize> {
+        if self.readonly {
+            return None;
+        }
+        // 記録中のグループがあれば先に確定させる
+        while self.group_depth > 0 {
+            self.end_group();
+        }
         let op = self.undo_stack.pop()?;
-        let pos = match op {
-            UndoOp::Set(pos, old_value, new_value) => {
-                self.data[pos] = old_value;
-                self.redo_stack.push(UndoOp::Set(pos, old_value, new_value));
-                pos
-            }
-            UndoOp::Insert(pos, value) => {
-                self.data.remove(pos);
-                self.redo_stack.push(UndoOp::Insert(pos, value));
-                pos.saturating_sub(1).min(self.data.len().saturating_sub(1))
-            }
-            UndoOp::Delete(pos, value) => {
-                self.data.insert(pos, value);
-                self.redo_stack.push(UndoOp::Delete(pos, value));
-                pos
-            }
-        };
+        let pos = self.apply_undo(&op);
+        self.redo_stack.push(op);
         self.modified = !self.undo_stack.is_empty();
         Some(pos)
     }
@@ -190,28 +880,42 @@ impl Document {
     /// Redo: 取り消した操作をやり直す
     /// 戻り値: (成功したか, 影響を受けた位置)
     pub fn redo(&mut self) -> Option<usize> {
+        if self.readonly {
+            return None;
+        }
         let op = self.redo_stack.pop()?;
-        let pos = match op {
-            UndoOp::Set(pos, old_value, new_value) => {
-                self.data[pos] = new_value;
-                self.undo_stack.push(UndoOp::Set(pos, old_value, new_value));
-                pos
-            }
-            UndoOp::Insert(pos, value) => {
-                self.data.insert(pos, value);
-                self.undo_stack.push(UndoOp::Insert(pos, value));
-                pos
-            }
-            UndoOp::Delete(pos, value) => {
-                self.data.remove(pos);
-                self.undo_stack.push(UndoOp::Delete(pos, value));
-                pos.min(self.data.len().saturating_sub(1))
-            }
-        };
+        let pos = self.apply_redo(&op);
+        self.undo_stack.push(op);
         self.modified = true;
         Some(pos)
     }
 
+    /// Undo履歴を取得（古い順、バイト列はコピーしない）
+    pub fn undo_history(&self) -> impl ExactSizeIterator<Item = UndoEntry<'_>> {
+        self.undo_stack.iter().map(UndoOp::entry)
+    }
+
+    /// 古い方から index 番目のUndo履歴
+    pub fn undo_entry(&self, index: usize) -> Option<UndoEntry<'_>> {
+        self.undo_stack.get(index).map(UndoOp::entry)
+    }
+
+    /// Undo履歴の件数
+    pub fn undo_depth(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// 履歴が depth 件になるまでUndoする（その時点の状態に戻す）
+    /// 取り消した操作はRedoでやり直せる
+    /// 戻り値: 最後に取り消した操作の位置
+    pub fn undo_to(&mut self, depth: usize) -> Option<usize> {
+        let mut pos = None;
+        while self.undo_stack.len() > depth {
+            pos = Some(self.undo()?);
+        }
+        pos
+    }
+
     /// 変更されているかどうか
     pub fn is_modified(&self) -> bool {
         self.modified
@@ -238,8 +942,268 @@ impl Document {
     }
 
     /// 生データへの参照を取得
+    /// 編集後の初回呼び出しでピースを連結したキャッシュを作る（全体のコピーになるので、
+    /// 描画など一部だけ読めばよい処理では get_range を使う）
     pub fn data(&self) -> &[u8] {
-        &self.data
+        match self.pieces.as_slice() {
+            [] => &[],
+            [piece] => self.piece_slice(piece),
+            pieces => self.cache.get_or_init(|| {
+                let mut data = Vec::with_capacity(self.len);
+                for piece in pieces {
+                    data.extend_from_slice(self.piece_slice(piece));
+                }
+                Arc::new(data)
+            }),
+        }
+    }
+
+    /// 現在の内容のスナップショット（バックグラウンド処理用）
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            original: self.original.clone(),
+            added: self.added.clone(),
+            pieces: self.pieces.clone(),
+            starts: self.starts.clone(),
+            len: self.len,
+        }
+    }
+
+    // =========================================================================
+    // ピーステーブル操作（Undo履歴を記録しない）
+    // =========================================================================
+
+    /// 操作をUndo履歴に記録（グループ中ならグループに追加）
+    fn record(&mut self, op: UndoOp) {
+        if self.group_depth > 0 {
+            self.group.push(op);
+        } else {
+            self.undo_stack.push(op);
+        }
+        self.redo_stack.clear();
+    }
+
+    /// 操作を取り消す
+    /// 戻り値: カーソルを置く位置
+    fn apply_undo(&mut self, op: &UndoOp) -> usize {
+        match *op {
+            UndoOp::Set(pos, old_value, _) => {
+                self.overwrite_raw(pos, &[old_value]);
+                pos
+            }
+            UndoOp::Insert(pos, _) => {
+                self.delete_raw(pos, 1);
+                pos.saturating_sub(1).min(self.len.saturating_sub(1))
+            }
+            UndoOp::Delete(pos, value) => {
+                self.insert_raw(pos, &[value]);
+                pos
+            }
+            UndoOp::SetRange(pos, ref old, _) => {
+                self.overwrite_raw(pos, old);
+                pos
+            }
+            UndoOp::InsertRange(pos, ref bytes) => {
+                self.delete_raw(pos, bytes.len());
+                pos.min(self.len.saturating_sub(1))
+            }
+            UndoOp::DeleteRange(pos, ref bytes) => {
+                self.insert_raw(pos, bytes);
+                pos
+            }
+            UndoOp::Group(ref ops) => ops
+                .iter()
+                .rev()
+                .fold(0, |_, op| self.apply_undo(op)),
+        }
+    }
+
+    /// 操作をやり直す
+    /// 戻り値: カーソルを置く位置
+    fn apply_redo(&mut self, op: &UndoOp) -> usize {
+        match *op {
+            UndoOp::Set(pos, _, new_value) => {
+                self.overwrite_raw(pos, &[new_value]);
+                pos
+            }
+            UndoOp::Insert(pos, value) => {
+                self.insert_raw(pos, &[value]);
+                pos
+            }
+            UndoOp::Delete(pos, _) => {
+                self.delete_raw(pos, 1);
+                pos.min(self.len.saturating_sub(1))
+            }
+            UndoOp::SetRange(pos, _, ref new) => {
+                self.overwrite_raw(pos, new);
+                pos
+            }
+            UndoOp::InsertRange(pos, ref bytes) => {
+                self.insert_raw(pos, bytes);
+                pos
+            }
+            UndoOp::DeleteRange(pos, ref bytes) => {
+                self.delete_raw(pos, bytes.len());
+                pos.min(self.len.saturating_sub(1))
+            }
+            UndoOp::Group(ref ops) => ops.iter().fold(0, |_, op| self.apply_redo(op)),
+        }
+    }
+
+    /// 書き込み可能か確認
+    fn check_writable(&self) -> Result<(), BufferError> {
+        if self.readonly {
+            Err(BufferError::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// ピースが参照するバイト列
+    fn piece_slice(&self, piece: &Piece) -> &[u8] {
+        piece.slice(&self.original, &self.added)
+    }
+
+    /// 範囲 [start, end) をピースから直接コピーする（キャッシュを作らない）
+    fn read_raw(&self, start: usize, end: usize) -> Option<Vec<u8>> {
+        if start > end || end > self.len {
+            return None;
+        }
+        if let Some(data) = self.cache.get() {
+            return Some(data[start..end].to_vec());
+        }
+        let mut out = Vec::with_capacity(end - start);
+        let Some((first, _)) = self.locate(start) else {
+            return Some(out);
+        };
+        for (piece, &piece_start) in self.pieces[first..].iter().zip(&self.starts[first..]) {
+            if piece_start >= end {
+                break;
+            }
+            let from = start.max(piece_start) - piece_start;
+            let to = end.min(piece_start + piece.len) - piece_start;
+            out.extend_from_slice(&self.piece_slice(piece)[from..to]);
+        }
+        Some(out)
+    }
+
+    /// 位置を含むピースを探す
+    /// 戻り値: (ピースのインデックス, ピース内オフセット)
+    fn locate(&self, pos: usize) -> Option<(usize, usize)> {
+        if pos >= self.len {
+            return None;
+        }
+        let index = self.starts.partition_point(|&start| start <= pos) - 1;
+        Some((index, pos - self.starts[index]))
+    }
+
+    /// index 以降のピースの開始位置を計算し直す
+    fn reindex(&mut self, index: usize) {
+        self.starts.truncate(index);
+        let mut pos = match index {
+            0 => 0,
+            i => self.starts[i - 1] + self.pieces[i - 1].len,
+        };
+        for piece in &self.pieces[index..] {
+            self.starts.push(pos);
+            pos += piece.len;
+        }
+    }
+
+    /// pos がピース境界になるように分割し、pos から始まるピースのインデックスを返す
+    /// pos が末尾なら pieces.len() を返す
+    fn split_at(&mut self, pos: usize) -> usize {
+        let Some((index, offset)) = self.locate(pos) else {
+            return self.pieces.len();
+        };
+        if offset == 0 {
+            return index;
+        }
+        let piece = self.pieces[index];
+        self.pieces[index].len = offset;
+        self.pieces.insert(
+            index + 1,
+            Piece {
+                source: piece.source,
+                start: piece.start + offset,
+                len: piece.len - offset,
+            },
+        );
+        self.starts.insert(index + 1, pos);
+        index + 1
+    }
+
+    /// 内容の変更を記録（キャッシュを破棄）
+    fn invalidate(&mut self) {
+        self.cache.take();
+        self.revision += 1;
+    }
+
+    /// バイト列を挿入
+    fn insert_raw(&mut self, pos: usize, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        self.invalidate();
+        let start = self.added.len();
+        Arc::make_mut(&mut self.added).extend_from_slice(bytes);
+
+        let index = self.split_at(pos);
+        self.len += bytes.len();
+        // 直前のピースが追加バッファの末尾を指していれば伸ばす（連続入力の断片化を防ぐ）
+        if index > 0 {
+            let prev = &mut self.pieces[index - 1];
+            if prev.source == Source::Added && prev.start + prev.len == start {
+                prev.len += bytes.len();
+                self.reindex(index);
+                return;
+            }
+        }
+        self.pieces.insert(
+            index,
+            Piece {
+                source: Source::Added,
+                start,
+                len: bytes.len(),
+            },
+        );
+        self.reindex(index);
+    }
+
+    /// 範囲を削除
+    fn delete_raw(&mut self, pos: usize, len: usize) {
+        let len = len.min(self.len.saturating_sub(pos));
+        if len == 0 {
+            return;
+        }
+        self.invalidate();
+        let first = self.split_at(pos);
+        let last = self.split_at(pos + len);
+        self.pieces.drain(first..last);
+        self.len -= len;
+        self.reindex(first);
+    }
+
+    /// 範囲を上書き（範囲は既存データ内であること）
+    fn overwrite_raw(&mut self, pos: usize, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        // 保存後に追加したバイトの範囲内に収まっていればその場で書き換える
+        if let Some((index, offset)) = self.locate(pos) {
+            let piece = self.pieces[index];
+            if piece.source == Source::Added
+                && piece.start + offset >= self.saved_added
+                && offset + bytes.len() <= piece.len
+            {
+                self.invalidate();
+                let start = piece.start + offset;
+                Arc::make_mut(&mut self.added)[start..start + bytes.len()].copy_from_slice(bytes);
+                return;
+            }
+        }
+        self.delete_raw(pos, bytes.len());
+        self.insert_raw(pos, bytes);
     }
 }
 
@@ -248,3 +1212,290 @@ impl Default for Document {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_is_unaffected_by_edits() {
+        let mut doc = Document::from_bytes(b"Hello".to_vec());
+        let original = doc.snapshot();
+        doc.insert(5, b'!').unwrap();
+        let edited = doc.snapshot();
+        doc.delete(0).unwrap();
+        assert_eq!(original.get_range(0, 5).as_deref(), Some(&b"Hello"[..]));
+        assert_eq!(edited.get_range(3, 6).as_deref(), Some(&b"lo!"[..]));
+        assert_eq!(edited.get_range(3, 7), None);
+        assert_eq!(doc.snapshot().get_range(0, 5).as_deref(), Some(&b"ello!"[..]));
+    }
+
+    #[test]
+    fn test_insert_delete_across_pieces() {
+        let mut doc = Document::from_bytes(b"Hello".to_vec());
+        doc.insert(5, b'!').unwrap();
+        doc.insert(0, b'>').unwrap();
+        doc.insert(3, b'-').unwrap();
+        assert_eq!(doc.data(), b">He-llo!");
+        assert_eq!(doc.len(), 8);
+
+        assert_eq!(doc.delete(3).unwrap(), b'-');
+        assert_eq!(doc.delete(0).unwrap(), b'>');
+        assert_eq!(doc.data(), b"Hello!");
+        assert_eq!(doc.get(5), Some(b'!'));
+        assert_eq!(doc.get(6), None);
+    }
+
+    #[test]
+    fn test_get_range_reads_pieces_without_cache() {
+        let mut doc = Document::from_bytes((0..=99).collect());
+        for pos in (0..100).step_by(10).rev() {
+            doc.insert(pos, 0xFF).unwrap();
+        }
+        assert_eq!(doc.len(), 110);
+        assert_eq!(doc.get(0), Some(0xFF));
+        assert_eq!(doc.get(12), Some(10));
+        assert_eq!(doc.get(109), Some(99));
+        assert_eq!(doc.get(110), None);
+
+        // 1つのピース内なら借用、またぐ範囲は連結したコピー
+        assert!(matches!(doc.get_range(2, 5), Some(Cow::Borrowed([1, 2, 3]))));
+        assert_eq!(doc.get_range(9, 13).as_deref(), Some(&[8, 9, 0xFF, 10][..]));
+        assert_eq!(doc.get_range(110, 110).as_deref(), Some(&[][..]));
+        assert!(doc.get_range(100, 111).is_none());
+        assert!(doc.cache.get().is_none());
+
+        assert!(doc.set_range(usize::MAX, b"ab").is_err());
+    }
+
+    #[test]
+    fn test_set_and_undo_redo() {
+        let mut doc = Document::from_bytes(b"abcd".to_vec());
+        doc.set(1, b'X').unwrap();
+        doc.insert(4, b'e').unwrap();
+        doc.set(4, b'E').unwrap();
+        assert_eq!(doc.data(), b"aXcdE");
+
+        while doc.undo().is_some() {}
+        assert_eq!(doc.data(), b"abcd");
+        assert!(!doc.is_modified());
+
+        while doc.redo().is_some() {}
+        assert_eq!(doc.data(), b"aXcdE");
+    }
+
+    #[test]
+    fn test_range_ops_single_undo() {
+        let mut doc = Document::from_bytes(b"0123456789".to_vec());
+        doc.set_range(2, b"ab").unwrap();
+        doc.insert_bytes(10, b"XYZ").unwrap();
+        assert_eq!(doc.delete_range(0, 2).unwrap(), b"01");
+        assert_eq!(doc.data(), b"ab456789XYZ");
+        assert!(doc.set_range(10, b"??").is_err());
+
+        doc.undo();
+        assert_eq!(doc.data(), b"01ab456789XYZ");
+        doc.undo();
+        assert_eq!(doc.data(), b"01ab456789");
+        doc.undo();
+        assert_eq!(doc.data(), b"0123456789");
+        assert!(doc.undo().is_none());
+    }
+
+    #[test]
+    fn test_undo_history_entries() {
+        let mut doc = Document::from_bytes(b"abcdef".to_vec());
+        doc.set(0, b'A').unwrap();
+        doc.insert_bytes(6, b"gh").unwrap();
+        doc.begin_group();
+        doc.delete_range(1, 3).unwrap();
+        doc.set(0, b'Z').unwrap();
+        doc.end_group();
+
+        let entries: Vec<_> = doc.undo_history().collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].kind, EditKind::Set);
+        assert_eq!((entries[0].old, entries[0].new), (&b"a"[..], &b"A"[..]));
+        assert_eq!((entries[1].kind, entries[1].offset), (EditKind::Insert, 6));
+        assert_eq!((entries[1].old, entries[1].new), (&[][..], &b"gh"[..]));
+        assert_eq!((entries[2].kind, entries[2].offset), (EditKind::Group(2), 1));
+        assert_eq!(doc.undo_entry(1).map(|e| e.offset), Some(6));
+        assert!(doc.undo_entry(3).is_none());
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut doc = Document::from_bytes(b"abcdef".to_vec());
+        doc.resize(3, 0).unwrap();
+        assert_eq!(doc.data(), b"abc");
+        doc.resize(5, 0xFF).unwrap();
+        assert_eq!(doc.data(), b"abc\xFF\xFF");
+
+        doc.undo();
+        assert_eq!(doc.data(), b"abc");
+        doc.undo();
+        assert_eq!(doc.data(), b"abcdef");
+
+        // 確保できない長さはエラーにして内容を変えない
+        assert!(matches!(doc.resize(usize::MAX, 0), Err(BufferError::OutOfMemory(_))));
+        assert!(matches!(doc.resize(1 << 62, 0), Err(BufferError::OutOfMemory(_))));
+        assert_eq!(doc.data(), b"abcdef");
+        assert!(!doc.is_modified());
+    }
+
+    #[test]
+    fn test_grouped_undo() {
+        let mut doc = Document::from_bytes(b"abc".to_vec());
+        doc.begin_group();
+        doc.set(0, b'A').unwrap();
+        doc.begin_group();
+        doc.insert_bytes(3, b"de").unwrap();
+        doc.end_group();
+        doc.delete(1).unwrap();
+        doc.end_group();
+        doc.set(0, b'Z').unwrap();
+        assert_eq!(doc.data(), b"Zcde");
+
+        assert_eq!(doc.undo(), Some(0));
+        assert_eq!(doc.data(), b"Acde");
+        assert_eq!(doc.undo(), Some(0));
+        assert_eq!(doc.data(), b"abc");
+        assert!(doc.undo().is_none());
+
+        doc.redo();
+        assert_eq!(doc.data(), b"Acde");
+    }
+
+    #[test]
+    fn test_swap_roundtrip() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("ehx-swap-{}.bin", std::process::id()));
+        std::fs::write(&path, b"Hello, world").unwrap();
+
+        let mut doc = Document::open(&path).unwrap();
+        doc.insert_bytes(5, b"!!").unwrap();
+        doc.delete_range(0, 1).unwrap();
+        doc.set(0, b'J').unwrap();
+        let swap = doc.swap_path().unwrap();
+        assert_eq!(swap.file_name().unwrap().to_string_lossy().chars().next(), Some('.'));
+        doc.write_swap(&swap).unwrap();
+
+        let recovered = Document::recover(&path, &swap).unwrap();
+        assert_eq!(recovered.data(), doc.data());
+        assert!(recovered.is_modified());
+
+        // 元ファイルが変わっていれば復元しない（同じ長さでも、更新日時だけでも）
+        std::fs::write(&path, b"Jello, world").unwrap();
+        assert!(Document::recover(&path, &swap).is_err());
+        std::fs::write(&path, b"Hello, world").unwrap();
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(60)).unwrap();
+        assert!(Document::recover(&path, &swap).is_err());
+        std::fs::write(&path, b"changed").unwrap();
+        assert!(Document::recover(&path, &swap).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&swap).unwrap();
+    }
+
+    #[test]
+    fn test_swap_after_save_holds_full_content() {
+        let path = std::env::temp_dir().join(format!("ehx-swap-saved-{}.bin", std::process::id()));
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        // 保存後の元データはディスク上の内容と違うので、スワップに全内容を書く
+        let mut doc = Document::open(&path).unwrap();
+        doc.delete_range(0, 2).unwrap();
+        doc.save().unwrap();
+        doc.insert_bytes(0, b"ab").unwrap();
+        let swap = doc.swap_path().unwrap();
+        doc.write_swap(&swap).unwrap();
+
+        let recovered = Document::recover(&path, &swap).unwrap();
+        assert_eq!(recovered.data(), b"ab23456789");
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&swap).unwrap();
+    }
+
+    #[test]
+    fn test_chunked_save_and_cancel() {
+        let path = std::env::temp_dir().join(format!("ehx-save-{}.bin", std::process::id()));
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        let mut doc = Document::open(&path).unwrap();
+        doc.insert_bytes(5, b"abc").unwrap();
+
+        // 途中で破棄しても元のファイルは変わらない
+        let mut job = doc.begin_save().unwrap();
+        assert!(!doc.continue_save(&mut job, 4).unwrap());
+        drop(job);
+        assert_eq!(std::fs::read(&path).unwrap(), b"0123456789");
+        assert!(doc.is_modified());
+
+        let mut job = doc.begin_save().unwrap();
+        while !doc.continue_save(&mut job, 3).unwrap() {}
+        assert_eq!(job.written(), 13);
+        assert_eq!(std::fs::read(&path).unwrap(), b"01234abc56789");
+        assert!(!doc.is_modified());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_save_as_keeps_path() {
+        let path = std::env::temp_dir().join(format!("ehx-save-as-{}.bin", std::process::id()));
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        // 存在しないディレクトリへの別名保存に失敗しても元のパスのまま
+        let mut doc = Document::open(&path).unwrap();
+        doc.insert_bytes(0, b"ab").unwrap();
+        let missing = std::env::temp_dir().join("ehx-missing-dir").join("out.bin");
+        assert!(doc.save_as(&missing).is_err());
+        assert_eq!(doc.path(), Some(&path));
+        doc.save().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"ab0123456789");
+
+        // 完了したらパスが変わる
+        let copy = path.with_extension("copy");
+        let mut job = doc.begin_save_as(copy.clone()).unwrap();
+        assert_eq!(doc.path(), Some(&path));
+        while !doc.continue_save(&mut job, 4).unwrap() {}
+        assert_eq!(doc.path(), Some(&copy));
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&copy).unwrap();
+    }
+
+    #[test]
+    fn test_modified_ranges() {
+        let path = std::env::temp_dir().join(format!("ehx-modified-{}.bin", std::process::id()));
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        let mut doc = Document::open(&path).unwrap();
+        assert!(doc.modified_ranges().is_empty());
+        doc.set(1, b'a').unwrap();
+        doc.set(2, b'b').unwrap();
+        doc.insert_bytes(6, b"xy").unwrap();
+        assert_eq!(doc.modified_ranges(), [(1, 3), (6, 8)]);
+
+        // 保存すると消え、その後の編集だけが変更になる
+        doc.save().unwrap();
+        assert!(doc.modified_ranges().is_empty());
+        doc.set(1, b'c').unwrap();
+        doc.insert(8, b'z').unwrap();
+        assert_eq!(doc.modified_ranges(), [(1, 2), (8, 9)]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_readonly_rejects_edits() {
+        let mut doc = Document::from_bytes(vec![0; 4]);
+        doc.set_readonly(true);
+        assert!(matches!(doc.set(0, 1), Err(BufferError::ReadOnly)));
+        assert!(matches!(doc.insert(0, 1), Err(BufferError::ReadOnly)));
+        assert!(matches!(doc.delete(0), Err(BufferError::ReadOnly)));
+        assert_eq!(doc.data(), &[0; 4]);
+    }
+}
@@ -0,0 +1,41 @@
+use std::fs::{File, TryLockError};
+use std::path::Path;
+
+/// 編集中のファイルに対するアドバイザリロック（flock / LockFileEx）
+/// 破棄すると解放される
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// 排他ロックを取得する
+    /// 他のプロセスがロックしている場合は None を返す
+    pub fn acquire(path: &Path) -> std::io::Result<Option<Self>> {
+        let file = File::open(path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_conflict() {
+        let path = std::env::temp_dir().join(format!("ehx-lock-{}.bin", std::process::id()));
+        std::fs::write(&path, b"x").unwrap();
+
+        let lock = FileLock::acquire(&path).unwrap();
+        assert!(lock.is_some());
+        assert!(FileLock::acquire(&path).unwrap().is_none());
+        drop(lock);
+        assert!(FileLock::acquire(&path).unwrap().is_some());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
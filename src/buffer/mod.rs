@@ -1,6 +1,10 @@
+mod backup;
 mod document;
+mod lock;
 
-pub use document::Document;
+pub use backup::BackupPolicy;
+pub use document::{Document, EditKind, SaveJob, Snapshot, UndoEntry};
+pub use lock::FileLock;
 
 use thiserror::Error;
 
@@ -10,4 +14,10 @@ pub enum BufferError {
     Io(#[from] std::io::Error),
     #[error("Position out of bounds: {0}")]
     OutOfBounds(usize),
+    #[error("Buffer is read-only")]
+    ReadOnly,
+    #[error("Invalid swap file: {0}")]
+    Swap(String),
+    #[error("Not enough memory for {0} bytes")]
+    OutOfMemory(usize),
 }
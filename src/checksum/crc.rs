@@ -0,0 +1,333 @@
+//! パラメータを指定できる CRC（幅・多項式・初期値・反転・最終 XOR）
+//!
+//! Rocksoft モデルのパラメータで、よく使われるものはプリセットとして名前で選べる。
+//! 1〜64 ビットのどの幅でも、256 項目の表を作って1バイトずつ処理する。
+
+/// CRC のパラメータ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrcParams {
+    pub name: &'static str,
+    /// ビット数（1〜64）
+    pub width: u32,
+    /// 多項式（最上位の項を除く、反転しない形）
+    pub poly: u64,
+    pub init: u64,
+    /// 入力の各バイトをビット反転するか
+    pub refin: bool,
+    /// 最終 XOR の前に結果をビット反転するか
+    pub refout: bool,
+    pub xorout: u64,
+    /// "123456789" の CRC（確認用）
+    pub check: u64,
+}
+
+/// checksum の Algorithm が使うプリセット
+pub const CRC_16_ARC: CrcParams = preset("CRC-16/ARC", 16, 0x8005, 0x0000, true, 0x0000, 0xBB3D);
+pub const CRC_16_CCITT_FALSE: CrcParams =
+    preset("CRC-16/CCITT-FALSE", 16, 0x1021, 0xFFFF, false, 0x0000, 0x29B1);
+pub const CRC_16_XMODEM: CrcParams =
+    preset("CRC-16/XMODEM", 16, 0x1021, 0x0000, false, 0x0000, 0x31C3);
+pub const CRC_16_KERMIT: CrcParams =
+    preset("CRC-16/KERMIT", 16, 0x1021, 0x0000, true, 0x0000, 0x2189);
+pub const CRC_16_MODBUS: CrcParams =
+    preset("CRC-16/MODBUS", 16, 0x8005, 0xFFFF, true, 0x0000, 0x4B37);
+pub const CRC_32: CrcParams =
+    preset("CRC-32", 32, 0x04C11DB7, 0xFFFFFFFF, true, 0xFFFFFFFF, 0xCBF43926);
+
+/// 名前で選べるパラメータ（名前は reveng の CRC カタログに合わせる）
+pub const PRESETS: &[CrcParams] = &[
+    preset("CRC-8", 8, 0x07, 0x00, false, 0x00, 0xF4),
+    preset("CRC-8/MAXIM-DOW", 8, 0x31, 0x00, true, 0x00, 0xA1),
+    CRC_16_ARC,
+    CRC_16_CCITT_FALSE,
+    CRC_16_XMODEM,
+    CRC_16_KERMIT,
+    CRC_16_MODBUS,
+    preset("CRC-16/X-25", 16, 0x1021, 0xFFFF, true, 0xFFFF, 0x906E),
+    preset("CRC-16/USB", 16, 0x8005, 0xFFFF, true, 0xFFFF, 0xB4C8),
+    CRC_32,
+    preset(
+        "CRC-32/JAMCRC",
+        32,
+        0x04C11DB7,
+        0xFFFFFFFF,
+        true,
+        0x00000000,
+        0x340BC6D9,
+    ),
+    preset(
+        "CRC-32/BZIP2",
+        32,
+        0x04C11DB7,
+        0xFFFFFFFF,
+        false,
+        0xFFFFFFFF,
+        0xFC891918,
+    ),
+    preset(
+        "CRC-32/MPEG-2",
+        32,
+        0x04C11DB7,
+        0xFFFFFFFF,
+        false,
+        0x00000000,
+        0x0376E6E7,
+    ),
+    preset(
+        "CRC-32/CKSUM",
+        32,
+        0x04C11DB7,
+        0x00000000,
+        false,
+        0xFFFFFFFF,
+        0x765E7680,
+    ),
+    preset(
+        "CRC-32C", 32, 0x1EDC6F41, 0xFFFFFFFF, true, 0xFFFFFFFF, 0xE3069283,
+    ),
+    preset(
+        "CRC-64/XZ",
+        64,
+        0x42F0E1EBA9EA3693,
+        u64::MAX,
+        true,
+        u64::MAX,
+        0x995DC9BBDF1939FA,
+    ),
+    preset(
+        "CRC-64/ECMA-182",
+        64,
+        0x42F0E1EBA9EA3693,
+        0,
+        false,
+        0,
+        0x6C40DF5F0B497347,
+    ),
+];
+
+/// 入力と出力の反転が同じプリセット
+const fn preset(
+    name: &'static str,
+    width: u32,
+    poly: u64,
+    init: u64,
+    reflect: bool,
+    xorout: u64,
+    check: u64,
+) -> CrcParams {
+    CrcParams {
+        name,
+        width,
+        poly,
+        init,
+        refin: reflect,
+        refout: reflect,
+        xorout,
+        check,
+    }
+}
+
+impl CrcParams {
+    /// プリセットを名前で探す（大文字小文字・"-" "/" "_" は無視、"CRC-" も省略可）
+    pub fn preset(name: &str) -> Option<Self> {
+        let normalize = |s: &str| -> String {
+            s.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+                .to_ascii_lowercase()
+        };
+        let key = match normalize(name).as_str() {
+            // Algorithm::parse と同じ別名
+            "crc16" | "crc16ccitt" | "ccitt" => "crc16ccittfalse".to_string(),
+            "crc16ibm" => "crc16arc".to_string(),
+            "crc32isohdlc" | "crc32ieee" => "crc32".to_string(),
+            "crc32iscsi" | "crc32castagnoli" => "crc32c".to_string(),
+            key => key.to_string(),
+        };
+        PRESETS.iter().copied().find(|p| {
+            let full = normalize(p.name);
+            // "CRC-16/MODBUS" は "modbus" でも、"CRC-32C" は "crc32c" でも
+            let short = p
+                .name
+                .split_once('/')
+                .map(|(_, variant)| normalize(variant));
+            key == full || short.as_deref() == Some(key.as_str())
+        })
+    }
+
+    /// width ビットがすべて立った値
+    pub fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.width)
+    }
+
+    /// 結果を表示するときの16進の桁数
+    pub fn hex_digits(&self) -> usize {
+        self.width.div_ceil(4) as usize
+    }
+
+    /// 幅・多項式などが使える値か
+    pub fn is_valid(&self) -> bool {
+        (1..=64).contains(&self.width)
+            && self.poly & !self.mask() == 0
+            && self.init & !self.mask() == 0
+            && self.xorout & !self.mask() == 0
+    }
+
+    /// data の CRC
+    pub fn checksum(&self, data: &[u8]) -> u64 {
+        let mut crc = Crc::new(*self);
+        crc.update(data);
+        crc.finish()
+    }
+}
+
+/// 少しずつデータを渡して計算する CRC
+pub struct Crc {
+    params: CrcParams,
+    table: [u64; 256],
+    /// 反転しない場合は上位に詰め、反転する場合は下位に詰めたレジスタ
+    register: u64,
+}
+
+impl Crc {
+    pub fn new(params: CrcParams) -> Self {
+        let shift = 64 - params.width;
+        let mut table = [0u64; 256];
+        if params.refin {
+            let poly = reflect(params.poly, params.width);
+            for (i, entry) in table.iter_mut().enumerate() {
+                let mut crc = i as u64;
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 {
+                        (crc >> 1) ^ poly
+                    } else {
+                        crc >> 1
+                    };
+                }
+                *entry = crc;
+            }
+        } else {
+            let poly = params.poly << shift;
+            for (i, entry) in table.iter_mut().enumerate() {
+                let mut crc = (i as u64) << 56;
+                for _ in 0..8 {
+                    crc = if crc >> 63 != 0 {
+                        (crc << 1) ^ poly
+                    } else {
+                        crc << 1
+                    };
+                }
+                *entry = crc;
+            }
+        }
+        let register = if params.refin {
+            reflect(params.init, params.width)
+        } else {
+            params.init << shift
+        };
+        Self {
+            params,
+            table,
+            register,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let table = &self.table;
+        let mut crc = self.register;
+        if self.params.refin {
+            for &b in data {
+                crc = (crc >> 8) ^ table[((crc ^ u64::from(b)) & 0xFF) as usize];
+            }
+        } else {
+            for &b in data {
+                crc = (crc << 8) ^ table[((crc >> 56) ^ u64::from(b)) as usize];
+            }
+        }
+        self.register = crc;
+    }
+
+    /// 渡したデータ全体の CRC
+    pub fn finish(&self) -> u64 {
+        let params = &self.params;
+        let mut crc = if params.refin {
+            self.register
+        } else {
+            self.register >> (64 - params.width)
+        };
+        if params.refin != params.refout {
+            crc = reflect(crc, params.width);
+        }
+        (crc ^ params.xorout) & params.mask()
+    }
+}
+
+/// 下位 width ビットを反転
+fn reflect(value: u64, width: u32) -> u64 {
+    value.reverse_bits() >> (64 - width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_match_check_values() {
+        for params in PRESETS {
+            assert!(params.is_valid(), "{}", params.name);
+            assert_eq!(
+                params.checksum(b"123456789"),
+                params.check,
+                "{}",
+                params.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_custom_params_and_streaming() {
+        // CRC-12/UMTS: 入力は反転せず出力だけ反転する
+        let umts = CrcParams {
+            name: "CRC-12/UMTS",
+            width: 12,
+            poly: 0x80F,
+            init: 0,
+            refin: false,
+            refout: true,
+            xorout: 0,
+            check: 0xDAF,
+        };
+        assert_eq!(umts.checksum(b"123456789"), 0xDAF);
+        assert_eq!(umts.hex_digits(), 3);
+        // CRC-5/USB
+        let usb = preset("CRC-5/USB", 5, 0x05, 0x1F, true, 0x1F, 0x19);
+        assert_eq!(usb.checksum(b"123456789"), 0x19);
+
+        let params = CrcParams::preset("crc32").unwrap();
+        let mut crc = Crc::new(params);
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xCBF43926);
+        // 多項式が幅に収まらない、幅が 0
+        assert!(
+            !CrcParams {
+                width: 16,
+                ..params
+            }
+            .is_valid()
+        );
+        assert!(!CrcParams { width: 0, ..params }.is_valid());
+    }
+
+    #[test]
+    fn test_preset_names() {
+        let name = |s| CrcParams::preset(s).map(|p| p.name);
+        assert_eq!(name("crc-16/modbus"), Some("CRC-16/MODBUS"));
+        assert_eq!(name("MODBUS"), Some("CRC-16/MODBUS"));
+        assert_eq!(name("jamcrc"), Some("CRC-32/JAMCRC"));
+        assert_eq!(name("crc32c"), Some("CRC-32C"));
+        assert_eq!(name("CRC-32"), Some("CRC-32"));
+        assert_eq!(name("CRC-16/CCITT"), Some("CRC-16/CCITT-FALSE"));
+        assert_eq!(name("crc-99"), None);
+    }
+}
@@ -0,0 +1,323 @@
+//! チェックサム・ハッシュの計算（CRC / Adler-32 / MD5 / SHA-1 / SHA-256）
+//!
+//! パッチ後にファイル内のチェックサム欄を直すための計算。
+//! CRC は crc モジュールのプリセット、ハッシュは RustCrypto のクレートで計算する。
+
+mod crc;
+
+pub use crc::{Crc, CrcParams, PRESETS};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ChecksumError {
+    #[error("Unknown algorithm: {0} (try {names})", names = Algorithm::names().join(" "))]
+    UnknownAlgorithm(String),
+}
+
+/// チェックサムのアルゴリズム
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Crc32,
+    /// CRC-16/CCITT-FALSE
+    Crc16Ccitt,
+    /// CRC-16/XMODEM
+    Crc16Xmodem,
+    /// CRC-16/MODBUS
+    Crc16Modbus,
+    /// CRC-16/ARC（IBM）
+    Crc16Arc,
+    /// CRC-16/KERMIT
+    Crc16Kermit,
+    Adler32,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl Algorithm {
+    pub const ALL: [Self; 10] = [
+        Self::Crc32,
+        Self::Crc16Ccitt,
+        Self::Crc16Xmodem,
+        Self::Crc16Modbus,
+        Self::Crc16Arc,
+        Self::Crc16Kermit,
+        Self::Adler32,
+        Self::Md5,
+        Self::Sha1,
+        Self::Sha256,
+    ];
+
+    /// 入力に使う名前
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Crc32 => "crc32",
+            Self::Crc16Ccitt => "crc16",
+            Self::Crc16Xmodem => "xmodem",
+            Self::Crc16Modbus => "modbus",
+            Self::Crc16Arc => "arc",
+            Self::Crc16Kermit => "kermit",
+            Self::Adler32 => "adler32",
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+        }
+    }
+
+    /// 表示名
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Crc32 => "CRC-32",
+            Self::Crc16Ccitt => "CRC-16/CCITT-FALSE",
+            Self::Crc16Xmodem => "CRC-16/XMODEM",
+            Self::Crc16Modbus => "CRC-16/MODBUS",
+            Self::Crc16Arc => "CRC-16/ARC",
+            Self::Crc16Kermit => "CRC-16/KERMIT",
+            Self::Adler32 => "Adler-32",
+            Self::Md5 => "MD5",
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+        }
+    }
+
+    /// 入力に使う名前の一覧
+    pub fn names() -> Vec<&'static str> {
+        Self::ALL.iter().map(|a| a.key()).collect()
+    }
+
+    /// 名前をパース（大文字小文字・ハイフンは無視）
+    pub fn parse(input: &str) -> Result<Self, ChecksumError> {
+        let key: String = input
+            .trim()
+            .chars()
+            .filter(|&c| c != '-' && c != '_')
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let key = match key.as_str() {
+            "crc16ccitt" | "ccitt" => "crc16",
+            "crc16xmodem" => "xmodem",
+            "crc16modbus" => "modbus",
+            "crc16arc" | "crc16ibm" => "arc",
+            "crc16kermit" => "kermit",
+            "adler" => "adler32",
+            key => key,
+        };
+        Self::ALL
+            .into_iter()
+            .find(|a| a.key() == key)
+            .ok_or_else(|| ChecksumError::UnknownAlgorithm(input.trim().to_string()))
+    }
+
+    /// 整数値のチェックサムか（書き込み時にエンディアンを選べる）
+    /// ハッシュはバイト列なので常にそのままの順で書く
+    pub fn is_integer(self) -> bool {
+        !matches!(self, Self::Md5 | Self::Sha1 | Self::Sha256)
+    }
+
+    /// CRC のアルゴリズムならそのパラメータ
+    pub fn crc_params(self) -> Option<CrcParams> {
+        match self {
+            Self::Crc32 => Some(crc::CRC_32),
+            Self::Crc16Ccitt => Some(crc::CRC_16_CCITT_FALSE),
+            Self::Crc16Xmodem => Some(crc::CRC_16_XMODEM),
+            Self::Crc16Modbus => Some(crc::CRC_16_MODBUS),
+            Self::Crc16Arc => Some(crc::CRC_16_ARC),
+            Self::Crc16Kermit => Some(crc::CRC_16_KERMIT),
+            Self::Adler32 | Self::Md5 | Self::Sha1 | Self::Sha256 => None,
+        }
+    }
+
+    /// data のチェックサムを計算
+    pub fn compute(self, data: &[u8]) -> Checksum {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finish()
+    }
+
+    /// 少しずつデータを渡して計算するための状態を作る
+    pub fn hasher(self) -> Hasher {
+        let state = match self {
+            Self::Adler32 => HasherState::Adler32(1, 0),
+            Self::Md5 => HasherState::Md5(Md5::new()),
+            Self::Sha1 => HasherState::Sha1(Sha1::new()),
+            Self::Sha256 => HasherState::Sha256(Sha256::new()),
+            _ => {
+                let params = self.crc_params().expect("CRC algorithm");
+                HasherState::Crc(Box::new(Crc::new(params)), params.width)
+            }
+        };
+        Hasher {
+            algorithm: self,
+            state,
+        }
+    }
+}
+
+/// 少しずつデータを渡して計算するチェックサム（Algorithm::hasher で作る）
+pub struct Hasher {
+    algorithm: Algorithm,
+    state: HasherState,
+}
+
+enum HasherState {
+    /// CRC とそのビット数（表が大きいのでヒープに置く）
+    Crc(Box<Crc>, u32),
+    /// Adler-32 の (a, b)
+    Adler32(u32, u32),
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            HasherState::Crc(crc, _) => crc.update(data),
+            HasherState::Adler32(a, b) => (*a, *b) = adler32_update((*a, *b), data),
+            HasherState::Md5(hasher) => hasher.update(data),
+            HasherState::Sha1(hasher) => hasher.update(data),
+            HasherState::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    /// 渡したデータ全体のチェックサム
+    pub fn finish(self) -> Checksum {
+        let bytes = match self.state {
+            HasherState::Crc(crc, width) => {
+                crc.finish().to_be_bytes()[8 - width as usize / 8..].to_vec()
+            }
+            HasherState::Adler32(a, b) => ((b << 16) | a).to_be_bytes().to_vec(),
+            HasherState::Md5(hasher) => hasher.finalize().to_vec(),
+            HasherState::Sha1(hasher) => hasher.finalize().to_vec(),
+            HasherState::Sha256(hasher) => hasher.finalize().to_vec(),
+        };
+        Checksum {
+            algorithm: self.algorithm,
+            bytes,
+        }
+    }
+}
+
+/// 計算結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: Algorithm,
+    /// 整数値はビッグエンディアン、ハッシュは通常のバイト順
+    pub bytes: Vec<u8>,
+}
+
+impl Checksum {
+    /// 16進文字列（大文字、区切りなし）
+    pub fn hex(&self) -> String {
+        self.bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// ファイルに書き込むバイト列（整数値のみ big_endian に従う）
+    pub fn to_bytes(&self, big_endian: bool) -> Vec<u8> {
+        let mut bytes = self.bytes.clone();
+        if self.algorithm.is_integer() && !big_endian {
+            bytes.reverse();
+        }
+        bytes
+    }
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = adler32_update((1, 0), data);
+    (b << 16) | a
+}
+
+/// Adler-32 の (a, b) に data を加える
+fn adler32_update((mut a, mut b): (u32, u32), data: &[u8]) -> (u32, u32) {
+    const MOD: u32 = 65521;
+    // 5552 バイトまでは u32 があふれないので、まとめて剰余を取る
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(algorithm: Algorithm, data: &[u8]) -> String {
+        algorithm.compute(data).hex()
+    }
+
+    #[test]
+    fn test_check_values() {
+        // 各アルゴリズムの標準チェック値（入力 "123456789"）
+        let check = b"123456789";
+        assert_eq!(hex(Algorithm::Crc32, check), "CBF43926");
+        assert_eq!(hex(Algorithm::Crc16Ccitt, check), "29B1");
+        assert_eq!(hex(Algorithm::Crc16Xmodem, check), "31C3");
+        assert_eq!(hex(Algorithm::Crc16Modbus, check), "4B37");
+        assert_eq!(hex(Algorithm::Crc16Arc, check), "BB3D");
+        assert_eq!(hex(Algorithm::Crc16Kermit, check), "2189");
+        assert_eq!(hex(Algorithm::Adler32, check), "091E01DE");
+    }
+
+    #[test]
+    fn test_hash_vectors() {
+        assert_eq!(hex(Algorithm::Md5, b""), "D41D8CD98F00B204E9800998ECF8427E");
+        assert_eq!(
+            hex(Algorithm::Md5, b"abc"),
+            "900150983CD24FB0D6963F7D28E17F72"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha1, b"abc"),
+            "A9993E364706816ABA3E25717850C26C9CD0D89D"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha256, b"abc"),
+            "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD"
+        );
+        // パディングが 2 ブロックになる長さ
+        let long = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        assert_eq!(
+            hex(Algorithm::Sha256, long),
+            "248D6A61D20638B8E5C026930C3E6039A33CE45964FF2167F6ECEDD419DB06C1"
+        );
+        assert_eq!(
+            hex(Algorithm::Sha1, long),
+            "84983E441C3BD26EBAAE4AA1F95129E5E54670F1"
+        );
+        assert_eq!(
+            hex(Algorithm::Md5, &[b'a'; 1000]),
+            "CABE45DCC9AE5B66BA86600CCA6B8BA8"
+        );
+    }
+
+    #[test]
+    fn test_hasher_matches_compute() {
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i * 7) as u8).collect();
+        for algorithm in Algorithm::ALL {
+            let mut hasher = algorithm.hasher();
+            for chunk in data.chunks(777) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finish(), algorithm.compute(&data), "{}", algorithm.name());
+        }
+    }
+
+    #[test]
+    fn test_parse_and_endianness() {
+        assert_eq!(Algorithm::parse("CRC-32"), Ok(Algorithm::Crc32));
+        assert_eq!(Algorithm::parse("crc16-modbus"), Ok(Algorithm::Crc16Modbus));
+        assert!(Algorithm::parse("crc64").is_err());
+        let crc = Algorithm::Crc32.compute(b"123456789");
+        assert_eq!(crc.to_bytes(false), [0x26, 0x39, 0xF4, 0xCB]);
+        assert_eq!(crc.to_bytes(true), [0xCB, 0xF4, 0x39, 0x26]);
+        let md5 = Algorithm::Md5.compute(b"");
+        assert_eq!(md5.to_bytes(false), md5.bytes);
+    }
+}
@@ -2,6 +2,7 @@
 
 use arboard::Clipboard;
 use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
 use std::io::{self, Write};
 use thiserror::Error;
 
@@ -11,8 +12,25 @@ pub enum ClipboardError {
     Arboard(#[from] arboard::Error),
     #[error("Invalid hex string: {0}")]
     InvalidHex(String),
+    #[error("Invalid escape sequence: {0}")]
+    InvalidEscape(String),
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
+    #[error("Too large for OSC 52 ({0} bytes, limit {1})")]
+    TooLarge(usize, usize),
+}
+
+/// コピー先のクリップボード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardTarget {
+    /// システムクリップボードと OSC 52 の両方
+    #[default]
+    Both,
+    /// システムクリップボードのみ（OSC 52 を送らない）
+    System,
+    /// OSC 52 のみ（SSH 越しなどシステムクリップボードがない環境向け）
+    Osc52,
 }
 
 /// HEXコピーのフォーマット
@@ -23,8 +41,76 @@ pub enum HexFormat {
     Spaced,
     /// 連続: "48656C6C6F"
     Continuous,
+    /// 0x 付きスペース区切り: "0x48 0x65 0x6C 0x6C 0x6F"
+    Prefixed,
     /// C言語配列: "0x48, 0x65, 0x6C, 0x6C, 0x6F"
     CArray,
+    /// Rust配列: "[0x48, 0x65, 0x6C, 0x6C, 0x6F]"
+    RustArray,
+    /// Python bytes リテラル: b"Hello\x00"
+    PythonBytes,
+    /// エスケープした C 文字列: "Hello\x00"
+    CString,
+    /// Base64: "SGVsbG8="
+    Base64,
+}
+
+impl HexFormat {
+    pub const ALL: [Self; 8] = [
+        Self::Spaced,
+        Self::Continuous,
+        Self::Prefixed,
+        Self::CArray,
+        Self::RustArray,
+        Self::PythonBytes,
+        Self::CString,
+        Self::Base64,
+    ];
+
+    /// copy-hex で選べる HEX 形式
+    pub const HEX: [Self; 4] = [Self::Spaced, Self::Continuous, Self::Prefixed, Self::CArray];
+
+    /// 入力に使う名前
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Spaced => "hex",
+            Self::Continuous => "compact",
+            Self::Prefixed => "0x",
+            Self::CArray => "c",
+            Self::RustArray => "rust",
+            Self::PythonBytes => "python",
+            Self::CString => "string",
+            Self::Base64 => "base64",
+        }
+    }
+
+    /// 表示名
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Spaced => "HEX",
+            Self::Continuous => "compact HEX",
+            Self::Prefixed => "0x-prefixed HEX",
+            Self::CArray => "C array",
+            Self::RustArray => "Rust array",
+            Self::PythonBytes => "Python bytes",
+            Self::CString => "C string",
+            Self::Base64 => "base64",
+        }
+    }
+
+    /// 名前から取得
+    pub fn from_key(key: &str) -> Option<Self> {
+        let key = key.trim().to_ascii_lowercase();
+        let key = match key.as_str() {
+            "py" => "python",
+            "rs" => "rust",
+            "str" => "string",
+            "packed" => "compact",
+            "spaced" => "hex",
+            key => key,
+        };
+        Self::ALL.into_iter().find(|format| format.key() == key)
+    }
 }
 
 /// バイト列をHEX文字列に変換
@@ -36,6 +122,11 @@ pub fn bytes_to_hex(bytes: &[u8], format: HexFormat) -> String {
             .collect::<Vec<_>>()
             .join(" "),
         HexFormat::Continuous => bytes.iter().map(|b| format!("{:02X}", b)).collect(),
+        HexFormat::Prefixed => bytes
+            .iter()
+            .map(|b| format!("0x{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" "),
         HexFormat::CArray => {
             let inner = bytes
                 .iter()
@@ -44,9 +135,102 @@ pub fn bytes_to_hex(bytes: &[u8], format: HexFormat) -> String {
                 .join(", ");
             format!("{{ {} }}", inner)
         }
+        HexFormat::RustArray => {
+            let inner: Vec<String> = bytes.iter().map(|b| format!("0x{:02X}", b)).collect();
+            format!("[{}]", inner.join(", "))
+        }
+        HexFormat::PythonBytes => format!("b\"{}\"", escape_bytes(bytes, false)),
+        HexFormat::CString => format!("\"{}\"", escape_bytes(bytes, true)),
+        HexFormat::Base64 => STANDARD.encode(bytes),
     }
 }
 
+/// 文字列リテラルの中身としてエスケープ（表示可能な ASCII はそのまま）
+/// c_style: C では \x の後に続く16進数字も桁として読まれるので、それもエスケープする
+fn escape_bytes(bytes: &[u8], c_style: bool) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    let mut after_hex_escape = false;
+    for &b in bytes {
+        let escape = match b {
+            b'\\' => Some(r"\\"),
+            b'"' => Some(r#"\""#),
+            b'\n' => Some(r"\n"),
+            b'\r' => Some(r"\r"),
+            b'\t' => Some(r"\t"),
+            _ => None,
+        };
+        let printable = (b' '..=b'~').contains(&b)
+            && !(c_style && after_hex_escape && b.is_ascii_hexdigit());
+        after_hex_escape = false;
+        if let Some(escape) = escape {
+            out.push_str(escape);
+        } else if printable {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("\\x{:02x}", b));
+            after_hex_escape = true;
+        }
+    }
+    out
+}
+
+/// エスケープされた文字列（C / Python の文字列リテラル）をバイト列に変換
+/// 前後の引用符と Python の b 接頭辞は省略できる。エスケープ以外の文字は UTF-8 のまま
+pub fn unescape_string(text: &str) -> Result<Vec<u8>, ClipboardError> {
+    let text = text.trim();
+    let body = text.strip_prefix('b').unwrap_or(text);
+    let text = ['"', '\'']
+        .into_iter()
+        .find_map(|q| body.strip_prefix(q)?.strip_suffix(q))
+        .unwrap_or(text);
+    let mut out = Vec::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let invalid = |s: &str| ClipboardError::InvalidEscape(format!("\\{}", s));
+        let Some(e) = chars.next() else {
+            return Err(invalid(""));
+        };
+        let byte = match e {
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0C,
+            'v' => 0x0B,
+            '\\' | '"' | '\'' | '?' => e as u8,
+            'x' => {
+                // \xHH（1〜2桁）
+                let mut digits = String::new();
+                while digits.len() < 2
+                    && let Some(d) = chars.next_if(char::is_ascii_hexdigit)
+                {
+                    digits.push(d);
+                }
+                u8::from_str_radix(&digits, 16).map_err(|_| invalid("x"))?
+            }
+            '0'..='7' => {
+                // \ooo（1〜3桁の8進数）
+                let mut digits = e.to_string();
+                while digits.len() < 3
+                    && let Some(d) = chars.next_if(|c| ('0'..='7').contains(c))
+                {
+                    digits.push(d);
+                }
+                u8::from_str_radix(&digits, 8).map_err(|_| invalid(&digits))?
+            }
+            other => return Err(invalid(&other.to_string())),
+        };
+        out.push(byte);
+    }
+    Ok(out)
+}
+
 /// HEX文字列をバイト列に変換
 pub fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, ClipboardError> {
     // スペース、カンマ、0x プレフィックスを除去
@@ -61,7 +245,7 @@ pub fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, ClipboardError> {
         .filter(|c| c.is_ascii_hexdigit())
         .collect();
 
-    if cleaned.len() % 2 != 0 {
+    if !cleaned.len().is_multiple_of(2) {
         return Err(ClipboardError::InvalidHex(
             "Hex string must have even length".to_string(),
         ));
@@ -219,6 +403,31 @@ pub fn copy_text_to_all(text: &str) -> Result<(), ClipboardError> {
     result
 }
 
+/// target に従ってテキストをコピー
+/// osc52_limit: OSC 52 で送るテキストの上限（バイト数、0で無制限）。超えたら OSC 52 は送らない
+/// 両方に送る場合はどちらかに届けば成功
+pub fn copy_text_to(
+    text: &str,
+    target: ClipboardTarget,
+    osc52_limit: usize,
+) -> Result<(), ClipboardError> {
+    let system = matches!(target, ClipboardTarget::System | ClipboardTarget::Both)
+        .then(|| copy_text(text));
+    let osc52 = matches!(target, ClipboardTarget::Osc52 | ClipboardTarget::Both).then(|| {
+        if osc52_limit > 0 && text.len() > osc52_limit {
+            Err(ClipboardError::TooLarge(text.len(), osc52_limit))
+        } else {
+            copy_text_to_terminal(text)
+        }
+    });
+    match (system, osc52) {
+        (Some(Ok(())), _) | (_, Some(Ok(()))) => Ok(()),
+        // 両方失敗したら、理由がわかりやすい OSC 52 側を返す
+        (_, Some(Err(e))) | (Some(Err(e)), None) => Err(e),
+        (None, None) => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -228,10 +437,31 @@ mod tests {
         let bytes = b"Hello";
         assert_eq!(bytes_to_hex(bytes, HexFormat::Spaced), "48 65 6C 6C 6F");
         assert_eq!(bytes_to_hex(bytes, HexFormat::Continuous), "48656C6C6F");
+        assert_eq!(bytes_to_hex(bytes, HexFormat::Prefixed), "0x48 0x65 0x6C 0x6C 0x6F");
         assert_eq!(
             bytes_to_hex(bytes, HexFormat::CArray),
             "{ 0x48, 0x65, 0x6C, 0x6C, 0x6F }"
         );
+        assert_eq!(bytes_to_hex(bytes, HexFormat::RustArray), "[0x48, 0x65, 0x6C, 0x6C, 0x6F]");
+        let bytes = b"A\x00\"B\\\n\xFF";
+        assert_eq!(bytes_to_hex(bytes, HexFormat::PythonBytes), r#"b"A\x00\"B\\\n\xff""#);
+        // C では \x00B が1文字として読まれないよう B もエスケープする
+        let bytes = b"\x00Bz";
+        assert_eq!(bytes_to_hex(bytes, HexFormat::CString), r#""\x00\x42z""#);
+        assert_eq!(bytes_to_hex(b"Hello", HexFormat::Base64), "SGVsbG8=");
+        assert_eq!(HexFormat::from_key("PY"), Some(HexFormat::PythonBytes));
+        assert_eq!(HexFormat::from_key("packed"), Some(HexFormat::Continuous));
+    }
+
+    #[test]
+    fn test_unescape_string() {
+        assert_eq!(unescape_string(r#""A\x00\"B\\\n\xff""#).unwrap(), b"A\x00\"B\\\n\xFF");
+        assert_eq!(unescape_string(r"b'\x4d\x5a\0'").unwrap(), b"MZ\x00");
+        assert_eq!(unescape_string(r"\101\7z").unwrap(), b"A\x07z");
+        assert_eq!(unescape_string("日").unwrap(), "日".as_bytes());
+        assert!(unescape_string(r"\q").is_err());
+        assert!(unescape_string(r"\xZZ").is_err());
+        assert!(unescape_string("\\").is_err());
     }
 
     #[test]
@@ -0,0 +1,192 @@
+//! 設定ファイル (~/.config/hx/config.toml)
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::buffer::BackupPolicy;
+use crate::clipboard::ClipboardTarget;
+use crate::encoding::ControlChars;
+use crate::ui::{ByteColorRule, Palette, DEFAULT_STATUS_FORMAT};
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Config IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid config: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// ユーザー設定
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// これを超えるサイズのファイルは読み込み前に確認する（バイト数、0で無効）
+    pub large_file_threshold: u64,
+    /// 保存時のバックアップ方式 ("off" / "simple" / "bak" / "numbered")
+    pub backup: BackupPolicy,
+    /// 未保存の変更をスワップファイルに書き出す間隔（秒、0で無効）
+    pub autosave_interval: u64,
+    /// HEX表示で空白で区切るまとまりのバイト数 (1 / 2 / 4 / 8)
+    pub group: usize,
+    /// 配色テーマ名（組み込み: "dark" / "light" / "solarized"、または themes で定義した名前）
+    pub theme: String,
+    /// ユーザー定義のテーマ（[themes.名前]）
+    pub themes: BTreeMap<String, Palette>,
+    /// ステータスバーの書式（%f %o %len などの項目を含む文字列）
+    pub status_format: String,
+    /// HEX の色をバイトの分類や値で変える規則（[[byte_colors]]、先に書いたものが優先）
+    pub byte_colors: Vec<ByteColorRule>,
+    /// バッファが複数あるとき画面の上にタブを表示する
+    pub tab_bar: bool,
+    /// マウスを使う（タブのクリック、ホイールでのスクロール）
+    pub mouse: bool,
+    /// コピー先 ("both" / "system" / "osc52")
+    pub clipboard: ClipboardTarget,
+    /// OSC 52 で送るテキストの上限（バイト数、0で無制限）
+    pub osc52_limit: usize,
+    /// 文字の列での制御文字の表示 ("dot" / "caret" / "picture")
+    pub control_chars: ControlChars,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            large_file_threshold: 256 * 1024 * 1024,
+            backup: BackupPolicy::Off,
+            autosave_interval: 30,
+            group: 1,
+            theme: "dark".to_string(),
+            themes: BTreeMap::new(),
+            status_format: DEFAULT_STATUS_FORMAT.to_string(),
+            byte_colors: Vec::new(),
+            tab_bar: true,
+            mouse: false,
+            clipboard: ClipboardTarget::Both,
+            osc52_limit: 0,
+            control_chars: ControlChars::Dot,
+        }
+    }
+}
+
+impl Config {
+    /// 設定ディレクトリ ($XDG_CONFIG_HOME/hx または ~/.config/hx)
+    pub fn dir() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+            return Some(PathBuf::from(dir).join("hx"));
+        }
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("hx"))
+    }
+
+    /// 設定ファイルのパス
+    pub fn path() -> Option<PathBuf> {
+        Self::dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// 設定ファイルを読み込む（ファイルがなければデフォルト）
+    pub fn load() -> Result<Self, ConfigError> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// TOML文字列から読み込む
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(text)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_defaults() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.large_file_threshold, 256 * 1024 * 1024);
+        assert_eq!(config.backup, BackupPolicy::Off);
+        assert_eq!(config.autosave_interval, 30);
+        assert_eq!(config.group, 1);
+        assert_eq!(config.theme, "dark");
+        assert_eq!(config.status_format, DEFAULT_STATUS_FORMAT);
+        assert!(config.byte_colors.is_empty());
+        assert!(config.tab_bar);
+        assert!(!config.mouse);
+        assert_eq!(config.clipboard, ClipboardTarget::Both);
+        assert_eq!(config.osc52_limit, 0);
+        assert_eq!(config.control_chars, ControlChars::Dot);
+    }
+
+    #[test]
+    fn test_parse_large_file_threshold() {
+        let config = Config::parse("large_file_threshold = 1024").unwrap();
+        assert_eq!(config.large_file_threshold, 1024);
+        assert!(Config::parse("large_file_threshold = \"big\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_backup() {
+        let config = Config::parse("backup = \"numbered\"").unwrap();
+        assert_eq!(config.backup, BackupPolicy::Numbered);
+        assert!(Config::parse("backup = \"always\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        let text = "theme = \"mine\"\n[themes.mine]\nbase = \"light\"\naddr = \"red\"";
+        let config = Config::parse(text).unwrap();
+        assert_eq!(config.theme, "mine");
+        assert_eq!(config.themes["mine"].addr.as_deref(), Some("red"));
+        // 知らない項目名は打ち間違いとしてエラー
+        assert!(Config::parse("[themes.mine]\nadress = \"red\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_status_format() {
+        let config = Config::parse("status_format = \"%f %o\"").unwrap();
+        assert_eq!(config.status_format, "%f %o");
+    }
+
+    #[test]
+    fn test_parse_byte_colors() {
+        let text = "[[byte_colors]]\nclass = \"control\"\ncolor = \"yellow\"";
+        let config = Config::parse(text).unwrap();
+        assert_eq!(config.byte_colors[0].class.as_deref(), Some("control"));
+    }
+
+    #[test]
+    fn test_parse_clipboard() {
+        for (value, target) in [
+            ("both", ClipboardTarget::Both),
+            ("system", ClipboardTarget::System),
+            ("osc52", ClipboardTarget::Osc52),
+        ] {
+            let config = Config::parse(&format!("clipboard = \"{}\"", value)).unwrap();
+            assert_eq!(config.clipboard, target);
+        }
+        assert!(Config::parse("clipboard = \"x11\"").is_err());
+        assert!(Config::parse("clipboard = 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_osc52_limit() {
+        let config = Config::parse("osc52_limit = 100000").unwrap();
+        assert_eq!(config.osc52_limit, 100000);
+        assert!(Config::parse("osc52_limit = -1").is_err());
+        assert!(Config::parse("osc52_limit = \"100k\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_control_chars() {
+        let config = Config::parse("control_chars = \"picture\"").unwrap();
+        assert_eq!(config.control_chars, ControlChars::Picture);
+        assert!(Config::parse("control_chars = \"hex\"").is_err());
+    }
+}
@@ -0,0 +1,297 @@
+//! 逆アセンブル（カーソル位置からの命令を表示する、`disasm` フィーチャー）
+//!
+//! 命令の解釈は capstone に任せる。解釈できないバイトは `.byte` / `.inst` などの
+//! 生の値として表示して先へ進む。
+
+use capstone::{Capstone, ExtraMode, Mode};
+
+/// 命令セット
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Arch {
+    #[default]
+    X86_64,
+    Arm64,
+    /// 32ビットの ARM（A32）
+    Arm,
+    /// RV64GC（圧縮命令を含む）
+    RiscV64,
+    /// RV32GC（圧縮命令を含む）
+    RiscV32,
+}
+
+impl Arch {
+    /// 選べる命令セット
+    pub const ALL: [Self; 5] = [
+        Self::X86_64,
+        Self::Arm64,
+        Self::Arm,
+        Self::RiscV64,
+        Self::RiscV32,
+    ];
+
+    /// 表示名
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::X86_64 => "x86_64",
+            Self::Arm64 => "arm64",
+            Self::Arm => "arm",
+            Self::RiscV64 => "riscv64",
+            Self::RiscV32 => "riscv32",
+        }
+    }
+
+    /// 名前から（別名も受け付ける）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "x86_64" | "x86-64" | "x64" | "amd64" => Some(Self::X86_64),
+            "arm64" | "aarch64" => Some(Self::Arm64),
+            "arm" | "arm32" | "a32" => Some(Self::Arm),
+            "riscv64" | "riscv" | "rv64" => Some(Self::RiscV64),
+            "riscv32" | "rv32" => Some(Self::RiscV32),
+            _ => None,
+        }
+    }
+
+    /// 次の命令セット（パネルで切り替える順）
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&arch| arch == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// 命令セットに合わせた capstone
+    fn capstone(self) -> Option<Capstone> {
+        let (arch, mode, extra): (_, _, &[ExtraMode]) = match self {
+            Self::X86_64 => (capstone::Arch::X86, Mode::Mode64, &[]),
+            Self::Arm64 => (capstone::Arch::ARM64, Mode::Arm, &[]),
+            Self::Arm => (capstone::Arch::ARM, Mode::Arm, &[]),
+            Self::RiscV64 => (capstone::Arch::RISCV, Mode::RiscV64, &[ExtraMode::RiscVC]),
+            Self::RiscV32 => (capstone::Arch::RISCV, Mode::RiscV32, &[ExtraMode::RiscVC]),
+        };
+        Capstone::new_raw(arch, mode, extra.iter().copied(), None).ok()
+    }
+}
+
+/// 逆アセンブルした1命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// 先頭のオフセット
+    pub offset: usize,
+    /// バイト数
+    pub len: usize,
+    /// ニーモニックとオペランド
+    pub text: String,
+}
+
+/// data（ファイルの offset から始まる部分）を先頭から最大 count 命令逆アセンブルする
+/// 分岐先はファイルのオフセットで表示する。解釈できない命令は生の値で表し、x86 では
+/// 途中で切れた命令も1バイトずつ .byte になる。ARM / RISC-V で命令の長さに足りない末尾は含めない
+pub fn disassemble(data: &[u8], offset: usize, arch: Arch, count: usize) -> Vec<Instruction> {
+    let Some(cs) = arch.capstone() else {
+        return Vec::new();
+    };
+    let mut result = Vec::new();
+    let mut pos = 0;
+    while result.len() < count && pos < data.len() {
+        // capstone は解釈できない命令の手前で止まる
+        let decoded = cs
+            .disasm_count(&data[pos..], (offset + pos) as u64, count - result.len())
+            .ok();
+        for insn in decoded.iter().flat_map(|insns| insns.iter()) {
+            let text = match insn.op_str().filter(|ops| !ops.is_empty()) {
+                Some(ops) => format!("{} {}", insn.mnemonic().unwrap_or_default(), ops),
+                None => insn.mnemonic().unwrap_or_default().to_string(),
+            };
+            result.push(Instruction {
+                offset: offset + pos,
+                len: insn.bytes().len(),
+                text,
+            });
+            pos += insn.bytes().len();
+        }
+        if result.len() >= count || pos >= data.len() {
+            break;
+        }
+        let Some((len, text)) = raw(&data[pos..], arch) else {
+            break;
+        };
+        result.push(Instruction {
+            offset: offset + pos,
+            len,
+            text,
+        });
+        pos += len;
+    }
+    result
+}
+
+/// 解釈できなかった命令を生の値として表示（命令の長さ分のバイトがなければ None）
+fn raw(data: &[u8], arch: Arch) -> Option<(usize, String)> {
+    match arch {
+        Arch::X86_64 => Some((1, format!(".byte 0x{:02x}", data[0]))),
+        Arch::Arm64 | Arch::Arm => {
+            let word = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+            Some((4, format!(".inst 0x{:08x}", word)))
+        }
+        // 下位2ビットが 11 でなければ16ビットの圧縮命令
+        Arch::RiscV64 | Arch::RiscV32 if data[0] & 0b11 != 0b11 => {
+            let half = u16::from_le_bytes(data.get(..2)?.try_into().ok()?);
+            Some((2, format!(".inst 0x{:04x}", half)))
+        }
+        Arch::RiscV64 | Arch::RiscV32 => {
+            let word = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+            Some((4, format!(".inst 0x{:08x}", word)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(data: &[u8], arch: Arch) -> Vec<String> {
+        disassemble(data, 0x1000, arch, usize::MAX)
+            .into_iter()
+            .map(|i| i.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_x86_64() {
+        let code = [
+            0xF3, 0x0F, 0x1E, 0xFA, // endbr64
+            0x55, // push rbp
+            0x48, 0x89, 0xE5, // mov rbp, rsp
+            0x48, 0x83, 0xEC, 0x10, // sub rsp, 0x10
+            0x8B, 0x45, 0xFC, // mov eax, [rbp-4]
+            0x48, 0x8D, 0x05, 0x10, 0x00, 0x00, 0x00, // lea rax, [rip+0x10]
+            0x31, 0xC0, // xor eax, eax
+            0xE8, 0x00, 0x00, 0x00, 0x00, // call
+            0x74, 0xFE, // je (self)
+            0x0F, 0x05, // syscall
+            0xC3, // ret
+            0x06, // (invalid in 64-bit mode)
+        ];
+        assert_eq!(
+            texts(&code, Arch::X86_64),
+            vec![
+                "endbr64",
+                "push rbp",
+                "mov rbp, rsp",
+                "sub rsp, 0x10",
+                "mov eax, dword ptr [rbp - 4]",
+                "lea rax, [rip + 0x10]",
+                "xor eax, eax",
+                "call 0x101d",
+                "je 0x101d",
+                "syscall",
+                "ret",
+                ".byte 0x06",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arm64() {
+        let words: [u32; 9] = [
+            0xA9BF7BFD, // stp x29, x30, [sp, #-16]!
+            0x910003FD, // mov x29, sp
+            0x52800540, // mov w0, #0x2a
+            0x94000000, // bl (self)
+            0xF9400BE1, // ldr x1, [sp, #16]
+            0xD4000001, // svc #0
+            0xA8C17BFD, // ldp x29, x30, [sp], #16
+            0xD65F03C0, // ret
+            0x00000000, // (undefined)
+        ];
+        let code: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        assert_eq!(
+            texts(&code, Arch::Arm64),
+            vec![
+                "stp x29, x30, [sp, #-0x10]!",
+                "mov x29, sp",
+                "mov w0, #0x2a",
+                "bl #0x100c",
+                "ldr x1, [sp, #0x10]",
+                "svc #0",
+                "ldp x29, x30, [sp], #0x10",
+                "ret",
+                ".inst 0x00000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arm() {
+        let words: [u32; 6] = [
+            0xE92D4800, // push {fp, lr}
+            0xE3A0002A, // mov r0, #0x2a
+            0xEBFFFFFE, // bl (self)
+            0xE59F1004, // ldr r1, [pc, #4]
+            0xE8BD8800, // pop {fp, pc}
+            0xFFFFFFFF, // (undefined)
+        ];
+        let code: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        assert_eq!(
+            texts(&code, Arch::Arm),
+            vec![
+                "push {fp, lr}",
+                "mov r0, #0x2a",
+                "bl #0x1008",
+                "ldr r1, [pc, #4]",
+                "pop {fp, pc}",
+                ".inst 0xffffffff",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_riscv() {
+        let mut code: Vec<u8> = Vec::new();
+        code.extend_from_slice(&0xFF010113u32.to_le_bytes()); // addi sp, sp, -16
+        code.extend_from_slice(&0x00113423u32.to_le_bytes()); // sd ra, 8(sp)
+        code.extend_from_slice(&0x4501u16.to_le_bytes()); // c.li a0, 0
+        code.extend_from_slice(&0x00000073u32.to_le_bytes()); // ecall
+        code.extend_from_slice(&0xFFFFFFFFu32.to_le_bytes()); // (48ビット以上の命令)
+
+        let insts = disassemble(&code, 0x1000, Arch::RiscV64, usize::MAX);
+        let summary: Vec<_> = insts
+            .iter()
+            .map(|i| (i.offset, i.len, i.text.split(' ').next().unwrap_or_default()))
+            .collect();
+        assert_eq!(summary[..2], [(0x1000, 4, "addi"), (0x1004, 4, "sd")]);
+        assert_eq!((summary[2].0, summary[2].1), (0x1008, 2));
+        assert_eq!(summary[3], (0x100A, 4, "ecall"));
+        assert_eq!(insts[4].text, ".inst 0xffffffff");
+
+        // ld は RV64 にしかない
+        let ld = 0x00853503u32.to_le_bytes(); // ld a0, 8(a0)
+        assert!(texts(&ld, Arch::RiscV64)[0].starts_with("ld "));
+        assert_eq!(texts(&ld, Arch::RiscV32), vec![".inst 0x00853503"]);
+    }
+
+    #[test]
+    fn test_arch_names() {
+        for arch in Arch::ALL {
+            assert_eq!(Arch::from_name(arch.name()), Some(arch));
+        }
+        assert_eq!(Arch::from_name("RV64"), Some(Arch::RiscV64));
+        assert_eq!(Arch::RiscV32.next(), Arch::X86_64);
+    }
+
+    #[test]
+    fn test_truncated_instruction_is_dropped() {
+        // 命令の途中で終わっている（x86 は .byte で表す）
+        assert_eq!(
+            texts(&[0xFD, 0x7B, 0xBF], Arch::Arm64),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            texts(&[0x48, 0x89], Arch::X86_64),
+            vec![".byte 0x48", ".byte 0x89"]
+        );
+        assert_eq!(
+            texts(&[0x13, 0x01, 0x01], Arch::RiscV64),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -1,9 +1,14 @@
 #![allow(dead_code)]
 
 use encoding_rs::Encoding;
+use serde::Deserialize;
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
+mod table;
+
+pub use table::{CharTable, TableError};
+
 /// サポートする文字エンコーディング
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CharEncoding {
@@ -11,67 +16,299 @@ pub enum CharEncoding {
     Utf8,
     Utf16Le,
     Utf16Be,
+    Utf32Le,
+    Utf32Be,
     ShiftJis,
     EucJp,
     Iso2022Jp,
     Ascii,
     Latin1,
+    Cp1252,
+    Cp437,
+    Koi8R,
+    /// 読み込んだ文字テーブル（.tbl）による変換（変換自体は CharTable で行う）
+    Custom,
 }
 
+/// CP437（DOS の英語版コードページ）の 0x80〜0xFF
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
 impl CharEncoding {
+    /// F2 で切り替える順
+    pub const ALL: [Self; 14] = [
+        Self::Utf8,
+        Self::Utf16Le,
+        Self::Utf16Be,
+        Self::Utf32Le,
+        Self::Utf32Be,
+        Self::ShiftJis,
+        Self::EucJp,
+        Self::Iso2022Jp,
+        Self::Ascii,
+        Self::Latin1,
+        Self::Cp1252,
+        Self::Cp437,
+        Self::Koi8R,
+        Self::Custom,
+    ];
+
     /// エンコーディング名を取得
     pub fn name(&self) -> &'static str {
         match self {
             Self::Utf8 => "UTF-8",
             Self::Utf16Le => "UTF-16LE",
             Self::Utf16Be => "UTF-16BE",
+            Self::Utf32Le => "UTF-32LE",
+            Self::Utf32Be => "UTF-32BE",
             Self::ShiftJis => "Shift-JIS",
             Self::EucJp => "EUC-JP",
             Self::Iso2022Jp => "ISO-2022-JP",
             Self::Ascii => "ASCII",
             Self::Latin1 => "Latin-1",
+            Self::Cp1252 => "CP1252",
+            Self::Cp437 => "CP437",
+            Self::Koi8R => "KOI8-R",
+            Self::Custom => "Table",
         }
     }
 
+    /// エンコーディング名から取得（大文字小文字・記号の違いは無視、よく使う別名も受け付ける）
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalize = |s: &str| -> String {
+            s.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        };
+        let target = normalize(name);
+        let alias = match target.as_str() {
+            "utf16" => Some(Self::Utf16Le),
+            "utf32" => Some(Self::Utf32Le),
+            "sjis" | "cp932" => Some(Self::ShiftJis),
+            "iso88591" | "latin1" => Some(Self::Latin1),
+            "windows1252" => Some(Self::Cp1252),
+            "ibm437" | "dos" => Some(Self::Cp437),
+            "tbl" | "custom" => Some(Self::Custom),
+            _ => None,
+        };
+        alias.or_else(|| {
+            Self::ALL
+                .into_iter()
+                .find(|encoding| normalize(encoding.name()) == target)
+        })
+    }
+
     /// encoding_rsのEncodingを取得
-    pub fn to_encoding(&self) -> &'static Encoding {
-        match self {
+    /// encoding_rs にない UTF-32・ASCII・Latin-1・CP437 は自前で変換するので None
+    /// （文字テーブルも None、テーブルがなければどの文字にも変換しない）
+    pub fn to_encoding(&self) -> Option<&'static Encoding> {
+        Some(match self {
             Self::Utf8 => encoding_rs::UTF_8,
             Self::Utf16Le => encoding_rs::UTF_16LE,
             Self::Utf16Be => encoding_rs::UTF_16BE,
+            Self::Utf32Le | Self::Utf32Be => return None,
             Self::ShiftJis => encoding_rs::SHIFT_JIS,
             Self::EucJp => encoding_rs::EUC_JP,
             Self::Iso2022Jp => encoding_rs::ISO_2022_JP,
-            Self::Ascii | Self::Latin1 => encoding_rs::WINDOWS_1252,
+            Self::Cp1252 => encoding_rs::WINDOWS_1252,
+            Self::Koi8R => encoding_rs::KOI8_R,
+            Self::Ascii | Self::Latin1 | Self::Cp437 | Self::Custom => return None,
+        })
+    }
+
+    /// 1文字の最小のバイト数（NUL 終端の幅、UTF-16 は 2、UTF-32 は 4）
+    pub fn unit_len(&self) -> usize {
+        match self {
+            Self::Utf16Le | Self::Utf16Be => 2,
+            Self::Utf32Le | Self::Utf32Be => 4,
+            _ => 1,
         }
     }
 
     /// 次のエンコーディングに切り替え
     pub fn next(&self) -> Self {
-        match self {
-            Self::Utf8 => Self::Utf16Le,
-            Self::Utf16Le => Self::Utf16Be,
-            Self::Utf16Be => Self::ShiftJis,
-            Self::ShiftJis => Self::EucJp,
-            Self::EucJp => Self::Iso2022Jp,
-            Self::Iso2022Jp => Self::Ascii,
-            Self::Ascii => Self::Latin1,
-            Self::Latin1 => Self::Utf8,
-        }
+        let index = Self::ALL.iter().position(|e| e == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// 1バイト1文字のエンコーディングか
+    pub fn is_single_byte(&self) -> bool {
+        matches!(self, Self::Ascii | Self::Latin1 | Self::Cp1252 | Self::Cp437 | Self::Koi8R)
+    }
+}
+
+/// 1バイト1文字のエンコーディングでバイトが表す文字（割り当てがなければ None）
+fn single_byte_char(byte: u8, encoding: CharEncoding) -> Option<char> {
+    match encoding {
+        CharEncoding::Ascii => byte.is_ascii().then_some(byte as char),
+        CharEncoding::Latin1 => Some(byte as char),
+        CharEncoding::Cp437 if byte >= 0x80 => Some(CP437_HIGH[byte as usize - 0x80]),
+        CharEncoding::Cp437 => Some(byte as char),
+        _ => encoding
+            .to_encoding()?
+            .decode_without_bom_handling_and_without_replacement(&[byte])?
+            .chars()
+            .next(),
+    }
+}
+
+/// 自前で変換する1バイトのエンコーディング（ASCII・Latin-1・CP437）で文字を表すバイト
+fn single_byte_encode(ch: char, encoding: CharEncoding) -> Option<u8> {
+    match encoding {
+        CharEncoding::Ascii => ch.is_ascii().then_some(ch as u8),
+        CharEncoding::Latin1 => u8::try_from(ch as u32).ok(),
+        CharEncoding::Cp437 if ch.is_ascii() => Some(ch as u8),
+        CharEncoding::Cp437 => CP437_HIGH
+            .iter()
+            .position(|&c| c == ch)
+            .map(|i| i as u8 + 0x80),
+        _ => None,
     }
 }
 
 /// バイト列を文字列にデコード
 pub fn decode_bytes(bytes: &[u8], encoding: CharEncoding) -> String {
-    let enc = encoding.to_encoding();
+    let Some(enc) = encoding.to_encoding() else {
+        if encoding.unit_len() == 1 {
+            return bytes
+                .iter()
+                .map(|&byte| single_byte_char(byte, encoding).unwrap_or('\u{FFFD}'))
+                .collect();
+        }
+        // UTF-32: 不正な値や端数バイトは置換文字にする
+        let le = encoding == CharEncoding::Utf32Le;
+        let mut chunks = bytes.chunks_exact(4);
+        let mut result: String = chunks
+            .by_ref()
+            .map(|chunk| char::from_u32(utf32_unit(chunk, le)).unwrap_or('\u{FFFD}'))
+            .collect();
+        if !chunks.remainder().is_empty() {
+            result.push('\u{FFFD}');
+        }
+        return result;
+    };
     let (result, _, _) = enc.decode(bytes);
     result.into_owned()
 }
 
+/// バイト列を1文字ずつデコードし、文字の先頭のバイト位置と組にする
+/// （正規表現の一致などを元のバイト位置に戻すため。変換できないバイトは置換文字）
+pub fn decode_chars(bytes: &[u8], encoding: CharEncoding) -> Vec<(usize, char)> {
+    let mut result = Vec::new();
+    match encoding {
+        CharEncoding::Utf8 => {
+            let mut i = 0;
+            while i < bytes.len() {
+                let len = utf8_char_len(bytes[i]).max(1);
+                match bytes.get(i..i + len).and_then(|s| std::str::from_utf8(s).ok()) {
+                    Some(s) => {
+                        result.extend(s.chars().map(|c| (i, c)));
+                        i += len;
+                    }
+                    None => {
+                        result.push((i, '\u{FFFD}'));
+                        i += 1;
+                    }
+                }
+            }
+        }
+        CharEncoding::Utf16Le | CharEncoding::Utf16Be => {
+            let le = encoding == CharEncoding::Utf16Le;
+            let units = bytes.chunks_exact(2).map(|pair| {
+                let pair = [pair[0], pair[1]];
+                if le { u16::from_le_bytes(pair) } else { u16::from_be_bytes(pair) }
+            });
+            let mut i = 0;
+            for c in char::decode_utf16(units) {
+                let c = c.unwrap_or('\u{FFFD}');
+                result.push((i, c));
+                i += if c == '\u{FFFD}' { 2 } else { c.len_utf16() * 2 };
+            }
+            if bytes.len() % 2 == 1 {
+                result.push((bytes.len() - 1, '\u{FFFD}'));
+            }
+        }
+        CharEncoding::Utf32Le | CharEncoding::Utf32Be => {
+            let le = encoding == CharEncoding::Utf32Le;
+            for (i, chunk) in bytes.chunks(4).enumerate() {
+                let c = match chunk.len() {
+                    4 => char::from_u32(utf32_unit(chunk, le)),
+                    _ => None,
+                };
+                result.push((i * 4, c.unwrap_or('\u{FFFD}')));
+            }
+        }
+        _ => match encoding.to_encoding() {
+            // 1バイトずつ渡し、出てきた文字は前の文字の後ろのバイトから始まるものとする
+            // （ISO-2022-JP のエスケープシーケンスは次の文字に含める）
+            Some(enc) => {
+                let mut decoder = enc.new_decoder_without_bom_handling();
+                let mut out = String::with_capacity(16);
+                let mut start = 0;
+                for i in 0..bytes.len() {
+                    out.clear();
+                    let last = i + 1 == bytes.len();
+                    let _ = decoder.decode_to_string(&bytes[i..i + 1], &mut out, last);
+                    for c in out.chars() {
+                        result.push((start, c));
+                    }
+                    if !out.is_empty() {
+                        start = i + 1;
+                    }
+                }
+            }
+            None => {
+                for (i, &byte) in bytes.iter().enumerate() {
+                    result.push((i, single_byte_char(byte, encoding).unwrap_or('\u{FFFD}')));
+                }
+            }
+        },
+    }
+    result
+}
+
+/// 4バイトを UTF-32 の符号単位として読む
+fn utf32_unit(bytes: &[u8], le: bool) -> u32 {
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    if le {
+        u32::from_le_bytes(bytes)
+    } else {
+        u32::from_be_bytes(bytes)
+    }
+}
+
 /// 文字列をバイト列にエンコード
 /// エンコードできない文字は置換文字になる
 pub fn encode_string(s: &str, encoding: CharEncoding) -> Vec<u8> {
-    let enc = encoding.to_encoding();
+    // encoding_rs は UTF-16 へのエンコードを UTF-8 で出力し、UTF-32 は扱わないので自前で変換する
+    match encoding {
+        CharEncoding::Utf16Le => return s.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        CharEncoding::Utf16Be => return s.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        _ => {}
+    }
+    let Some(enc) = encoding.to_encoding() else {
+        if encoding.unit_len() == 1 {
+            return s
+                .chars()
+                .map(|ch| single_byte_encode(ch, encoding).unwrap_or(b'?'))
+                .collect();
+        }
+        // UTF-32
+        let le = encoding == CharEncoding::Utf32Le;
+        return s
+            .chars()
+            .flat_map(|c| if le { (c as u32).to_le_bytes() } else { (c as u32).to_be_bytes() })
+            .collect();
+    };
     let (result, _, _) = enc.encode(s);
     result.into_owned()
 }
@@ -79,9 +316,20 @@ pub fn encode_string(s: &str, encoding: CharEncoding) -> Vec<u8> {
 /// 文字をバイト列にエンコード
 /// エンコードできない場合は None を返す
 pub fn encode_char(ch: char, encoding: CharEncoding) -> Option<Vec<u8>> {
-    let s: String = ch.to_string();
-    let enc = encoding.to_encoding();
-    let (result, _, had_errors) = enc.encode(&s);
+    encode_string_strict(&ch.to_string(), encoding)
+}
+
+/// 文字列をバイト列にエンコード
+/// エンコードできない文字が含まれていれば None を返す
+pub fn encode_string_strict(s: &str, encoding: CharEncoding) -> Option<Vec<u8>> {
+    // UTF-16 / UTF-32 はすべての文字をエンコードできる
+    if encoding.unit_len() > 1 {
+        return Some(encode_string(s, encoding));
+    }
+    let Some(enc) = encoding.to_encoding() else {
+        return s.chars().map(|ch| single_byte_encode(ch, encoding)).collect();
+    };
+    let (result, _, had_errors) = enc.encode(s);
     if had_errors {
         // エンコードエラー（置換文字が使われた）
         None
@@ -90,6 +338,77 @@ pub fn encode_char(ch: char, encoding: CharEncoding) -> Option<Vec<u8>> {
     }
 }
 
+/// 文字列の終端の付け方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Terminator {
+    #[default]
+    None,
+    /// NUL 終端（UTF-16 では 2 バイト、UTF-32 では 4 バイト）
+    Nul,
+    /// 先頭にバイト数を付ける（width バイトの整数）
+    Length { width: usize, big_endian: bool },
+}
+
+impl Terminator {
+    /// 名前をパース（none / nul / len8 / len16 / len16be / len32 / len32be）
+    pub fn from_name(name: &str) -> Option<Self> {
+        let length = |width, big_endian| Some(Self::Length { width, big_endian });
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "nul" | "null" | "z" => Some(Self::Nul),
+            "len8" => length(1, false),
+            "len16" | "len16le" => length(2, false),
+            "len16be" => length(2, true),
+            "len32" | "len32le" => length(4, false),
+            "len32be" => length(4, true),
+            _ => None,
+        }
+    }
+
+    /// 表示名
+    pub fn describe(self) -> String {
+        match self {
+            Self::None => "no terminator".to_string(),
+            Self::Nul => "NUL-terminated".to_string(),
+            Self::Length { width, big_endian } => {
+                let order = if width == 1 {
+                    ""
+                } else if big_endian {
+                    " BE"
+                } else {
+                    " LE"
+                };
+                format!("{}-bit{} length prefix", width * 8, order)
+            }
+        }
+    }
+
+    /// エンコード済みの文字列に終端を付ける
+    /// 長さが接頭辞の幅に収まらなければ None
+    pub fn apply(self, bytes: Vec<u8>, encoding: CharEncoding) -> Option<Vec<u8>> {
+        match self {
+            Self::None => Some(bytes),
+            Self::Nul => {
+                let mut bytes = bytes;
+                bytes.resize(bytes.len() + encoding.unit_len(), 0);
+                Some(bytes)
+            }
+            Self::Length { width, big_endian } => {
+                let len = u64::try_from(bytes.len()).ok()?;
+                if width < 8 && len >> (width * 8) != 0 {
+                    return None;
+                }
+                let prefix = if big_endian {
+                    len.to_be_bytes()[8 - width..].to_vec()
+                } else {
+                    len.to_le_bytes()[..width].to_vec()
+                };
+                Some([prefix, bytes].concat())
+            }
+        }
+    }
+}
+
 /// 1バイトを表示用文字に変換（ASCII範囲外は'.'）
 pub fn byte_to_char(byte: u8) -> char {
     if byte.is_ascii_graphic() || byte == b' ' {
@@ -102,7 +421,7 @@ pub fn byte_to_char(byte: u8) -> char {
 /// 書記素クラスタの表示幅を計算
 pub fn grapheme_width(s: &str) -> usize {
     s.graphemes(true)
-        .map(|g| UnicodeWidthStr::width(g))
+        .map(UnicodeWidthStr::width)
         .sum()
 }
 
@@ -122,9 +441,49 @@ pub struct DecodedChar {
     pub width: usize,
 }
 
+/// ASCII 列での制御文字（C0 と DEL）の表示方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlChars {
+    /// '.'
+    #[default]
+    Dot,
+    /// キャレット記法（^A、^?）。2桁使うので制御文字のある行は右に伸びる
+    Caret,
+    /// Unicode の制御文字の図形（␀、␁、␡）
+    Picture,
+}
+
+/// 書記素の表示と表示幅（表示できないものは '.'、制御文字は controls に従う）
+fn display_for(s: &str, controls: ControlChars) -> (String, usize) {
+    if is_displayable(s) {
+        return (s.to_string(), UnicodeWidthStr::width(s).max(1));
+    }
+    let mut chars = s.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(ch), None) => ch as u32,
+        _ => return (".".to_string(), 1),
+    };
+    match (controls, code) {
+        (ControlChars::Caret, 0x00..=0x1F) => (format!("^{}", (code as u8 + 0x40) as char), 2),
+        (ControlChars::Caret, 0x7F) => ("^?".to_string(), 2),
+        (ControlChars::Picture, 0x00..=0x1F) => {
+            // U+2400〜U+241F は C0 制御文字の図形
+            let picture = char::from_u32(0x2400 + code).unwrap_or('.');
+            (picture.to_string(), 1)
+        }
+        (ControlChars::Picture, 0x7F) => ("\u{2421}".to_string(), 1),
+        _ => (".".to_string(), 1),
+    }
+}
+
 /// バイト列をデコードして文字ごとの情報を返す
 /// 各バイト位置に対応する表示情報を返す
-pub fn decode_for_display(bytes: &[u8], encoding: CharEncoding) -> Vec<Option<DecodedChar>> {
+pub fn decode_for_display(
+    bytes: &[u8],
+    encoding: CharEncoding,
+    controls: ControlChars,
+) -> Vec<Option<DecodedChar>> {
     if bytes.is_empty() {
         return vec![];
     }
@@ -132,30 +491,27 @@ pub fn decode_for_display(bytes: &[u8], encoding: CharEncoding) -> Vec<Option<De
     let mut result = vec![None; bytes.len()];
 
     match encoding {
-        CharEncoding::Utf8 => decode_utf8_for_display(bytes, &mut result),
+        CharEncoding::Utf8 => decode_utf8_for_display(bytes, controls, &mut result),
         CharEncoding::ShiftJis | CharEncoding::EucJp => {
-            decode_with_encoding_rs(bytes, encoding, &mut result)
+            decode_with_encoding_rs(bytes, encoding, controls, &mut result)
         }
         CharEncoding::Utf16Le | CharEncoding::Utf16Be => {
-            decode_utf16_for_display(bytes, encoding, &mut result)
+            decode_utf16_for_display(bytes, encoding, controls, &mut result)
+        }
+        CharEncoding::Utf32Le | CharEncoding::Utf32Be => {
+            decode_utf32_for_display(bytes, encoding, controls, &mut result)
         }
         _ => {
-            // ASCII, Latin1: 1バイト1文字
+            // ASCII, Latin-1, コードページ: 1バイト1文字（割り当てのないバイトは '.'）
             for (i, &byte) in bytes.iter().enumerate() {
-                let ch = if byte.is_ascii_graphic() || byte == b' ' {
-                    (byte as char).to_string()
-                } else if byte < 0x20 || byte == 0x7F {
-                    ".".to_string()
-                } else {
-                    // Latin1 extended
-                    char::from_u32(byte as u32)
-                        .map(|c| c.to_string())
-                        .unwrap_or_else(|| ".".to_string())
+                let (display, width) = match single_byte_char(byte, encoding) {
+                    Some(ch) => display_for(ch.encode_utf8(&mut [0; 4]), controls),
+                    None => (".".to_string(), 1),
                 };
                 result[i] = Some(DecodedChar {
-                    display: ch,
+                    display,
                     byte_len: 1,
-                    width: 1,
+                    width,
                 });
             }
         }
@@ -165,7 +521,11 @@ pub fn decode_for_display(bytes: &[u8], encoding: CharEncoding) -> Vec<Option<De
 }
 
 /// UTF-8デコード
-fn decode_utf8_for_display(bytes: &[u8], result: &mut [Option<DecodedChar>]) {
+fn decode_utf8_for_display(
+    bytes: &[u8],
+    controls: ControlChars,
+    result: &mut [Option<DecodedChar>],
+) {
     let mut i = 0;
     while i < bytes.len() {
         let byte = bytes[i];
@@ -187,12 +547,7 @@ fn decode_utf8_for_display(bytes: &[u8], result: &mut [Option<DecodedChar>]) {
             Ok(s) => {
                 let graphemes: Vec<&str> = s.graphemes(true).collect();
                 if let Some(g) = graphemes.first() {
-                    let width = UnicodeWidthStr::width(*g).max(1);
-                    let display = if is_displayable(g) {
-                        g.to_string()
-                    } else {
-                        ".".to_string()
-                    };
+                    let (display, width) = display_for(g, controls);
                     result[i] = Some(DecodedChar {
                         display,
                         byte_len: char_len,
@@ -226,7 +581,12 @@ fn utf8_char_len(first_byte: u8) -> usize {
 }
 
 /// UTF-16デコード
-fn decode_utf16_for_display(bytes: &[u8], encoding: CharEncoding, result: &mut [Option<DecodedChar>]) {
+fn decode_utf16_for_display(
+    bytes: &[u8],
+    encoding: CharEncoding,
+    controls: ControlChars,
+    result: &mut [Option<DecodedChar>],
+) {
     let is_le = encoding == CharEncoding::Utf16Le;
     let mut i = 0;
 
@@ -265,13 +625,7 @@ fn decode_utf16_for_display(bytes: &[u8], encoding: CharEncoding, result: &mut [
 
         // 通常の2バイト文字
         if let Some(ch) = char::from_u32(code_unit as u32) {
-            let s = ch.to_string();
-            let width = if is_displayable(&s) {
-                UnicodeWidthStr::width(s.as_str()).max(1)
-            } else {
-                1
-            };
-            let display = if is_displayable(&s) { s } else { ".".to_string() };
+            let (display, width) = display_for(&ch.to_string(), controls);
             result[i] = Some(DecodedChar {
                 display,
                 byte_len: 2,
@@ -297,52 +651,87 @@ fn decode_utf16_for_display(bytes: &[u8], encoding: CharEncoding, result: &mut [
     }
 }
 
-/// encoding_rsを使ったデコード（Shift-JIS, EUC-JP等）
-fn decode_with_encoding_rs(bytes: &[u8], encoding: CharEncoding, result: &mut [Option<DecodedChar>]) {
-    let enc = encoding.to_encoding();
+/// UTF-32デコード（4バイトずつ、範囲外やサロゲートの値は '.'）
+fn decode_utf32_for_display(
+    bytes: &[u8],
+    encoding: CharEncoding,
+    controls: ControlChars,
+    result: &mut [Option<DecodedChar>],
+) {
+    let le = encoding == CharEncoding::Utf32Le;
+    let mut i = 0;
+    while i + 3 < bytes.len() {
+        let (display, width) = match char::from_u32(utf32_unit(&bytes[i..], le)) {
+            Some(ch) => display_for(&ch.to_string(), controls),
+            None => (".".to_string(), 1),
+        };
+        result[i] = Some(DecodedChar {
+            display,
+            byte_len: 4,
+            width,
+        });
+        i += 4;
+    }
+
+    // 端数バイト
+    for slot in &mut result[i..] {
+        *slot = Some(DecodedChar {
+            display: ".".to_string(),
+            byte_len: 1,
+            width: 1,
+        });
+    }
+}
+
+/// 先頭バイトから Shift-JIS / EUC-JP の1文字のバイト数を求める
+/// （デコード結果を再エンコードして比べると、NEC/IBM 拡張の重複コードや JIS X 0212 で
+/// 長さを取り違えるため、先頭バイトの範囲で決める）
+fn japanese_char_len(lead: u8, encoding: CharEncoding) -> usize {
+    match (encoding, lead) {
+        (CharEncoding::ShiftJis, 0x81..=0x9F | 0xE0..=0xFC) => 2,
+        (CharEncoding::EucJp, 0x8F) => 3,
+        (CharEncoding::EucJp, 0x8E | 0xA1..=0xFE) => 2,
+        _ => 1,
+    }
+}
+
+/// encoding_rsを使ったデコード（Shift-JIS, EUC-JP）
+fn decode_with_encoding_rs(
+    bytes: &[u8],
+    encoding: CharEncoding,
+    controls: ControlChars,
+    result: &mut [Option<DecodedChar>],
+) {
+    let Some(enc) = encoding.to_encoding() else {
+        return;
+    };
 
     let mut i = 0;
     while i < bytes.len() {
-        // 1〜4バイトを試してデコード
-        let mut decoded = false;
-        for len in 1..=4.min(bytes.len() - i) {
-            let slice = &bytes[i..i + len];
-            let (cow, _, had_errors) = enc.decode(slice);
-
-            if !had_errors && !cow.is_empty() {
-                let s = cow.into_owned();
-                let graphemes: Vec<&str> = s.graphemes(true).collect();
-
-                if let Some(g) = graphemes.first() {
-                    // 完全な文字がデコードできたか確認
-                    let (encoded, _, _) = enc.encode(g);
-                    if encoded.len() == len {
-                        let width = UnicodeWidthStr::width(*g).max(1);
-                        let display = if is_displayable(g) {
-                            g.to_string()
-                        } else {
-                            ".".to_string()
-                        };
-                        result[i] = Some(DecodedChar {
-                            display,
-                            byte_len: len,
-                            width,
-                        });
-                        i += len;
-                        decoded = true;
-                        break;
-                    }
-                }
+        let len = japanese_char_len(bytes[i], encoding);
+        let decoded = bytes
+            .get(i..i + len)
+            .and_then(|slice| enc.decode_without_bom_handling_and_without_replacement(slice));
+
+        match decoded {
+            Some(s) if !s.is_empty() => {
+                let (display, width) = display_for(&s, controls);
+                result[i] = Some(DecodedChar {
+                    display,
+                    byte_len: len,
+                    width,
+                });
+                i += len;
+            }
+            // 不正な並びや途中で切れた文字は先頭の1バイトだけを不明として進める
+            _ => {
+                result[i] = Some(DecodedChar {
+                    display: ".".to_string(),
+                    byte_len: 1,
+                    width: 1,
+                });
+                i += 1;
             }
-        }
-
-        if !decoded {
-            result[i] = Some(DecodedChar {
-                display: ".".to_string(),
-                byte_len: 1,
-                width: 1,
-            });
-            i += 1;
         }
     }
 }
@@ -363,3 +752,134 @@ fn is_displayable(s: &str) -> bool {
     }
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encodes_text_in_target_encoding() {
+        assert_eq!(encode_string("MZ日", CharEncoding::Utf16Le), b"M\0Z\0\xE5\x65");
+        assert_eq!(encode_string("MZ日", CharEncoding::Utf16Be), b"\0M\0Z\x65\xE5");
+        assert_eq!(encode_string("MZ日", CharEncoding::ShiftJis), b"MZ\x93\xFA");
+        assert_eq!(encode_char('日', CharEncoding::Utf16Le), Some(vec![0xE5, 0x65]));
+        assert_eq!(encode_char('日', CharEncoding::Latin1), None);
+        assert_eq!(encode_string_strict("MZ日", CharEncoding::Latin1), None);
+    }
+
+    #[test]
+    fn test_terminators() {
+        let nul = Terminator::from_name("NUL").unwrap();
+        assert_eq!(nul.apply(b"MZ".to_vec(), CharEncoding::Utf8), Some(b"MZ\0".to_vec()));
+        let utf16 = encode_string("MZ", CharEncoding::Utf16Le);
+        assert_eq!(nul.apply(utf16, CharEncoding::Utf16Le), Some(b"M\0Z\0\0\0".to_vec()));
+        let len16be = Terminator::from_name("len16be").unwrap();
+        assert_eq!(len16be.apply(b"MZ".to_vec(), CharEncoding::Utf8), Some(b"\0\x02MZ".to_vec()));
+        let len8 = Terminator::from_name("len8").unwrap();
+        assert_eq!(len8.apply(vec![0; 256], CharEncoding::Utf8), None);
+        assert_eq!(Terminator::from_name("len64"), None);
+    }
+
+    #[test]
+    fn test_decode_chars() {
+        let chars = |bytes: &[u8], encoding| -> String {
+            decode_chars(bytes, encoding)
+                .into_iter()
+                .map(|(i, c)| format!("{}{}", i, c))
+                .collect()
+        };
+        assert_eq!(chars("aあ".as_bytes(), CharEncoding::Utf8), "0a1あ");
+        assert_eq!(chars(b"a\xFFb", CharEncoding::Utf8), "0a1\u{FFFD}2b");
+        assert_eq!(chars(b"\x3D\xD8\x00\xDEA", CharEncoding::Utf16Le), "0😀4\u{FFFD}");
+        assert_eq!(chars(b"a\x82\xa0b", CharEncoding::ShiftJis), "0a1あ3b");
+        assert_eq!(
+            chars(b"\x1B$B$\"\x1B(Ba", CharEncoding::Iso2022Jp),
+            "0あ5a"
+        );
+        assert_eq!(chars(b"A\xFE", CharEncoding::Cp437), "0A1■");
+    }
+
+    #[test]
+    fn test_decode_japanese_widths() {
+        let display = |bytes: &[u8], encoding, i: usize| {
+            decode_for_display(bytes, encoding, ControlChars::Dot)[i]
+                .as_ref()
+                .unwrap()
+                .display
+                .clone()
+        };
+        let layout = |bytes: &[u8], encoding| -> Vec<(usize, usize, usize)> {
+            decode_for_display(bytes, encoding, ControlChars::Dot)
+                .iter()
+                .enumerate()
+                .filter_map(|(i, dc)| dc.as_ref().map(|dc| (i, dc.byte_len, dc.width)))
+                .collect()
+        };
+        // 日本 / 半角カナ / ASCII / NEC 拡張の重複コード（纊）
+        let sjis = b"\x93\xFA\x96\x7B\xB1A\xED\x40";
+        assert_eq!(
+            layout(sjis, CharEncoding::ShiftJis),
+            vec![(0, 2, 2), (2, 2, 2), (4, 1, 1), (5, 1, 1), (6, 2, 2)]
+        );
+        assert_eq!(display(sjis, CharEncoding::ShiftJis, 6), "纊");
+        // 2バイト目が不正なら先頭バイトだけを飛ばす
+        let expected = vec![(0, 1, 1), (1, 1, 1), (2, 1, 1)];
+        assert_eq!(layout(b"\x81 A", CharEncoding::ShiftJis), expected);
+
+        // 日 / 半角カナ / JIS X 0212（丂）/ 途中で切れた文字
+        let euc = b"\xC6\xFC\x8E\xB1\x8F\xB0\xA1\xC6";
+        assert_eq!(
+            layout(euc, CharEncoding::EucJp),
+            vec![(0, 2, 2), (2, 2, 1), (4, 3, 2), (7, 1, 1)]
+        );
+        assert_eq!(display(euc, CharEncoding::EucJp, 4), "丂");
+    }
+
+    #[test]
+    fn test_utf32() {
+        assert_eq!(encode_string("A日", CharEncoding::Utf32Le), b"A\0\0\0\xE5\x65\0\0");
+        assert_eq!(encode_string("A日", CharEncoding::Utf32Be), b"\0\0\0A\0\0\x65\xE5");
+        let decoded = decode_bytes(b"A\0\0\0\x00\xD8\0\0\x01", CharEncoding::Utf32Le);
+        assert_eq!(decoded, "A\u{FFFD}\u{FFFD}");
+        let nul = Terminator::Nul.apply(b"A\0\0\0".to_vec(), CharEncoding::Utf32Le).unwrap();
+        assert_eq!(nul.len(), 8);
+        assert_eq!(CharEncoding::from_name("utf32be"), Some(CharEncoding::Utf32Be));
+
+        // 😀（U+1F600）/ サロゲートの値 / 端数バイト
+        let data = b"\0\x01\xF6\x00\0\0\xD8\0\0\0";
+        let decoded = decode_for_display(data, CharEncoding::Utf32Be, ControlChars::Dot);
+        let first = decoded[0].as_ref().unwrap();
+        assert_eq!((first.display.as_str(), first.byte_len, first.width), ("😀", 4, 2));
+        assert_eq!(decoded[4].as_ref().unwrap().display, ".");
+        assert_eq!(decoded[8].as_ref().unwrap().byte_len, 1);
+        assert_eq!(decoded[9].as_ref().unwrap().byte_len, 1);
+    }
+
+    #[test]
+    fn test_single_byte_codepages() {
+        let text = |bytes: &[u8], encoding| -> String {
+            decode_for_display(bytes, encoding, ControlChars::Dot)
+                .into_iter()
+                .map(|dc| dc.unwrap().display)
+                .collect()
+        };
+        let bytes = b"A\x01\x80\x9F\xC9\xE9";
+        assert_eq!(text(bytes, CharEncoding::Ascii), "A.....");
+        assert_eq!(text(bytes, CharEncoding::Latin1), "A...Éé");
+        assert_eq!(text(bytes, CharEncoding::Cp1252), "A.€ŸÉé");
+        assert_eq!(text(bytes, CharEncoding::Cp437), "A.Çƒ╔Θ");
+        assert_eq!(text(bytes, CharEncoding::Koi8R), "A.─÷иИ");
+
+        assert_eq!(encode_string_strict("Éé", CharEncoding::Latin1), Some(vec![0xC9, 0xE9]));
+        assert_eq!(encode_string_strict("€", CharEncoding::Latin1), None);
+        assert_eq!(encode_string_strict("€", CharEncoding::Cp1252), Some(vec![0x80]));
+        assert_eq!(encode_string_strict("╔═╗", CharEncoding::Cp437), Some(vec![0xC9, 0xCD, 0xBB]));
+        assert_eq!(encode_string("aé", CharEncoding::Ascii), b"a?");
+        assert_eq!(decode_bytes(b"\xF0\xF1", CharEncoding::Koi8R), "ПЯ");
+
+        assert_eq!(CharEncoding::from_name("ibm437"), Some(CharEncoding::Cp437));
+        assert_eq!(CharEncoding::from_name("koi8r"), Some(CharEncoding::Koi8R));
+        assert_eq!(CharEncoding::Koi8R.next(), CharEncoding::Custom);
+        assert_eq!(CharEncoding::Custom.next(), CharEncoding::Utf8);
+    }
+}
@@ -0,0 +1,237 @@
+//! 文字テーブル（.tbl、ROM などの独自の文字コードとバイト列の対応表）
+//!
+//! 1行に1つ `HEX=文字列` の形で書く（例: `00=A`、`8140=あ`、`E0=the`）。
+//! `/HEX=文字列` は文字列の終端（文字列を省くと `<end>`）、`*HEX` は改行として扱う。
+//! 空行と `#` / `;` で始まる行は無視する。
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use thiserror::Error;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use super::{display_for, is_displayable, ControlChars, DecodedChar};
+
+#[derive(Error, Debug)]
+pub enum TableError {
+    #[error("Table IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid table entry at line {0}")]
+    Parse(usize),
+    #[error("Table has no entries")]
+    Empty,
+}
+
+/// 読み込んだ文字テーブル
+#[derive(Debug, Clone, Default)]
+pub struct CharTable {
+    /// バイト列 → 文字列
+    decode: HashMap<Vec<u8>, String>,
+    /// 文字列 → バイト列（同じ文字列が複数あれば先に書いたもの）
+    encode: HashMap<String, Vec<u8>>,
+    /// 最長のバイト列の長さ
+    max_bytes: usize,
+    /// 最長の文字列の文字数
+    max_chars: usize,
+}
+
+impl CharTable {
+    /// ファイルから読み込む
+    pub fn load(path: &Path) -> Result<Self, TableError> {
+        let data = std::fs::read(path)?;
+        Self::parse(&String::from_utf8_lossy(&data))
+    }
+
+    /// テーブルの書式の文字列から読み込む
+    pub fn parse(text: &str) -> Result<Self, TableError> {
+        let mut table = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_start_matches('\u{FEFF}').trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            let (hex, value) = if let Some(rest) = line.strip_prefix('/') {
+                match rest.split_once('=') {
+                    Some((hex, value)) if !value.is_empty() => (hex, value),
+                    Some((hex, _)) => (hex, "<end>"),
+                    None => (rest, "<end>"),
+                }
+            } else if let Some(hex) = line.strip_prefix('*') {
+                (hex, "\n")
+            } else {
+                match line.split_once('=') {
+                    Some((hex, value)) if !value.is_empty() => (hex, value),
+                    _ => return Err(TableError::Parse(index + 1)),
+                }
+            };
+            let bytes = parse_hex(hex.trim()).ok_or(TableError::Parse(index + 1))?;
+            table.insert(bytes, value.to_string());
+        }
+        if table.decode.is_empty() {
+            return Err(TableError::Empty);
+        }
+        Ok(table)
+    }
+
+    fn insert(&mut self, bytes: Vec<u8>, value: String) {
+        self.max_bytes = self.max_bytes.max(bytes.len());
+        self.max_chars = self.max_chars.max(value.chars().count());
+        self.encode
+            .entry(value.clone())
+            .or_insert_with(|| bytes.clone());
+        self.decode.insert(bytes, value);
+    }
+
+    /// 登録されているバイト列の数
+    pub fn len(&self) -> usize {
+        self.decode.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decode.is_empty()
+    }
+
+    /// bytes の先頭に一致する最長のバイト列（長さと文字列）
+    fn lookup(&self, bytes: &[u8]) -> Option<(usize, &str)> {
+        (1..=self.max_bytes.min(bytes.len()))
+            .rev()
+            .find_map(|len| self.decode.get(&bytes[..len]).map(|s| (len, s.as_str())))
+    }
+
+    /// バイト列を文字列にデコード（対応のないバイトは置換文字）
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        let mut result = String::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            match self.lookup(&bytes[i..]) {
+                Some((len, s)) => {
+                    result.push_str(s);
+                    i += len;
+                }
+                None => {
+                    result.push('\u{FFFD}');
+                    i += 1;
+                }
+            }
+        }
+        result
+    }
+
+    /// 文字列をバイト列にエンコード（先頭から最長一致、対応のない文字があれば None）
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        let mut result = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let ends: Vec<usize> = rest
+                .char_indices()
+                .map(|(i, c)| i + c.len_utf8())
+                .take(self.max_chars)
+                .collect();
+            let (end, bytes) = ends
+                .iter()
+                .rev()
+                .find_map(|&end| self.encode.get(&rest[..end]).map(|bytes| (end, bytes)))?;
+            result.extend_from_slice(bytes);
+            rest = &rest[end..];
+        }
+        Some(result)
+    }
+
+    /// バイト列をデコードして文字ごとの情報を返す（decode_for_display と同じ形）
+    /// 文字列がバイト数より広い場合は収まる分だけを表示する
+    pub fn decode_for_display(
+        &self,
+        bytes: &[u8],
+        controls: ControlChars,
+    ) -> Vec<Option<DecodedChar>> {
+        let mut result = vec![None; bytes.len()];
+        let mut i = 0;
+        while i < bytes.len() {
+            let (len, (display, width)) = match self.lookup(&bytes[i..]) {
+                Some((len, s)) => match fit_width(s, len) {
+                    Some(display) => {
+                        let width = UnicodeWidthStr::width(display.as_str()).max(1);
+                        (len, (display, width))
+                    }
+                    // 改行などの制御文字1文字は他のエンコーディングと同じく controls に従う
+                    None if s.chars().count() == 1 => (len, display_for(s, controls)),
+                    None => (len, (".".to_string(), 1)),
+                },
+                None => (1, (".".to_string(), 1)),
+            };
+            result[i] = Some(DecodedChar {
+                display,
+                byte_len: len,
+                width,
+            });
+            i += len;
+        }
+        result
+    }
+}
+
+/// 表示幅 columns に収まる先頭の書記素（表示できないもの・1つも収まらなければ None）
+fn fit_width(s: &str, columns: usize) -> Option<String> {
+    let mut width = 0;
+    let mut end = 0;
+    for (i, g) in s.grapheme_indices(true) {
+        if !is_displayable(g) {
+            break;
+        }
+        width += UnicodeWidthStr::width(g);
+        if width > columns {
+            break;
+        }
+        end = i + g.len();
+    }
+    (end > 0).then(|| s[..end].to_string())
+}
+
+/// 16進数の文字列をバイト列に（空・奇数桁は None）
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_convert() {
+        let table =
+            CharTable::parse("# comment\n00=A\n01=B\n80=the\n8140=あ\n/FF\n*FE\n02==\n").unwrap();
+        assert_eq!(table.len(), 7);
+        assert_eq!(
+            table.decode(b"\x00\x80\x81\x40\x03\xFE\x02\xFF"),
+            "Atheあ\u{FFFD}\n=<end>"
+        );
+        assert_eq!(table.encode("theA=B"), Some(b"\x80\x00\x02\x01".to_vec()));
+        assert_eq!(table.encode("AC"), None);
+
+        let display: Vec<_> = table
+            .decode_for_display(b"\x80\x81\x40\x00\x03\xFE", ControlChars::Caret)
+            .into_iter()
+            .flatten()
+            .map(|dc| (dc.display, dc.byte_len))
+            .collect();
+        let expected = [("t", 1), ("あ", 2), ("A", 1), (".", 1), ("^J", 1)];
+        assert_eq!(display, expected.map(|(s, len)| (s.to_string(), len)));
+
+        assert!(matches!(
+            CharTable::parse("00=A\n0=B"),
+            Err(TableError::Parse(2))
+        ));
+        assert!(matches!(CharTable::parse("00"), Err(TableError::Parse(1))));
+        assert!(matches!(
+            CharTable::parse("# empty\n"),
+            Err(TableError::Empty)
+        ));
+    }
+}
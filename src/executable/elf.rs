@@ -0,0 +1,176 @@
+//! ELF: ヘッダー、プログラムヘッダー（セグメント）、セクションヘッダー
+//!
+//! 32/64 ビット、リトルエンディアン・ビッグエンディアンのどちらも読む。
+
+use super::{Executable, ExecutableError, ExecutableFormat, Fields, Section, c_name, rwx};
+
+pub(super) const MAGIC: &[u8] = b"\x7FELF";
+
+/// ファイルに中身のないセクション（.bss）
+const SHT_NOBITS: u64 = 8;
+const SHF_WRITE: u64 = 0x1;
+const SHF_ALLOC: u64 = 0x2;
+const SHF_EXECINSTR: u64 = 0x4;
+const PF_X: u64 = 0x1;
+const PF_W: u64 = 0x2;
+const PF_R: u64 = 0x4;
+
+pub(super) fn parse(data: &[u8]) -> Result<Executable, ExecutableError> {
+    let ident = data.get(..6).ok_or(ExecutableError::Truncated)?;
+    let wide = match ident[4] {
+        1 => false,
+        2 => true,
+        _ => return Err(ExecutableError::Invalid("unknown ELF class")),
+    };
+    let big_endian = match ident[5] {
+        1 => false,
+        2 => true,
+        _ => return Err(ExecutableError::Invalid("unknown ELF byte order")),
+    };
+    let f = Fields { data, big_endian };
+    // ELF32 と ELF64 で位置と幅が違うフィールドを読む
+    let word = |offset32: u64, offset64: u64| {
+        if wide {
+            f.u64(offset64)
+        } else {
+            f.u32(offset32)
+        }
+    };
+
+    let machine = f.u16(18)?;
+    let phoff = word(28, 32)?;
+    let shoff = word(32, 40)?;
+    let (phentsize, phnum) = if wide {
+        (f.u16(54)?, f.u16(56)?)
+    } else {
+        (f.u16(42)?, f.u16(44)?)
+    };
+    let (shentsize, shnum, shstrndx) = if wide {
+        (f.u16(58)?, f.u16(60)?, f.u16(62)?)
+    } else {
+        (f.u16(46)?, f.u16(48)?, f.u16(50)?)
+    };
+
+    let mut segments = Vec::new();
+    for i in 0..phnum {
+        let ph = phoff.saturating_add(i * phentsize);
+        let ty = f.u32(ph)?;
+        let (offset, addr, size, mem_size, flags) = if wide {
+            (
+                f.u64(ph + 8)?,
+                f.u64(ph + 16)?,
+                f.u64(ph + 32)?,
+                f.u64(ph + 40)?,
+                f.u32(ph + 4)?,
+            )
+        } else {
+            (
+                f.u32(ph + 4)?,
+                f.u32(ph + 8)?,
+                f.u32(ph + 16)?,
+                f.u32(ph + 20)?,
+                f.u32(ph + 24)?,
+            )
+        };
+        segments.push(Section {
+            name: segment_type(ty),
+            offset,
+            size,
+            addr,
+            mem_size,
+            flags: rwx(flags & PF_R != 0, flags & PF_W != 0, flags & PF_X != 0),
+        });
+    }
+
+    // セクションヘッダー: (名前の位置, 種類, フラグ, アドレス, 位置, 大きさ)
+    let mut headers = Vec::new();
+    for i in 0..shnum {
+        let sh = shoff.saturating_add(i * shentsize);
+        headers.push(if wide {
+            (
+                f.u32(sh)?,
+                f.u32(sh + 4)?,
+                f.u64(sh + 8)?,
+                f.u64(sh + 16)?,
+                f.u64(sh + 24)?,
+                f.u64(sh + 32)?,
+            )
+        } else {
+            (
+                f.u32(sh)?,
+                f.u32(sh + 4)?,
+                f.u32(sh + 8)?,
+                f.u32(sh + 12)?,
+                f.u32(sh + 16)?,
+                f.u32(sh + 20)?,
+            )
+        });
+    }
+    let strtab = match headers.get(shstrndx as usize) {
+        Some(&(_, _, _, _, offset, size)) => f.bytes(offset, size)?,
+        None => &[],
+    };
+
+    // 先頭の SHT_NULL は除く
+    let sections = headers
+        .iter()
+        .skip(1)
+        .map(|&(name, ty, flags, addr, offset, size)| Section {
+            name: strtab.get(name as usize..).map(c_name).unwrap_or_default(),
+            offset,
+            size: if ty == SHT_NOBITS { 0 } else { size },
+            addr,
+            mem_size: if flags & SHF_ALLOC != 0 { size } else { 0 },
+            flags: rwx(
+                flags & SHF_ALLOC != 0,
+                flags & SHF_WRITE != 0,
+                flags & SHF_EXECINSTR != 0,
+            ),
+        })
+        .collect();
+
+    Ok(Executable {
+        format: ExecutableFormat::Elf,
+        class: if wide { "ELF64" } else { "ELF32" },
+        arch: machine_name(machine),
+        big_endian,
+        segments,
+        sections,
+    })
+}
+
+/// プログラムヘッダーの種類
+fn segment_type(ty: u64) -> String {
+    match ty {
+        0 => "NULL",
+        1 => "LOAD",
+        2 => "DYNAMIC",
+        3 => "INTERP",
+        4 => "NOTE",
+        5 => "SHLIB",
+        6 => "PHDR",
+        7 => "TLS",
+        0x6474E550 => "GNU_EH_FRAME",
+        0x6474E551 => "GNU_STACK",
+        0x6474E552 => "GNU_RELRO",
+        0x6474E553 => "GNU_PROPERTY",
+        _ => return format!("0x{:08X}", ty),
+    }
+    .to_string()
+}
+
+/// e_machine の名前
+fn machine_name(machine: u64) -> String {
+    match machine {
+        0x03 => "x86",
+        0x08 => "mips",
+        0x14 => "ppc",
+        0x15 => "ppc64",
+        0x28 => "arm",
+        0x3E => "x86_64",
+        0xB7 => "arm64",
+        0xF3 => "riscv",
+        _ => return format!("machine 0x{:X}", machine),
+    }
+    .to_string()
+}
@@ -0,0 +1,145 @@
+//! Mach-O: ヘッダーと LC_SEGMENT / LC_SEGMENT_64 のロードコマンド
+//!
+//! 32/64 ビット、両方のエンディアンを読む。複数の命令セットをまとめた fat バイナリは
+//! Java の class ファイルとマジックナンバーが同じなので扱わない。
+
+use super::{Executable, ExecutableError, ExecutableFormat, Fields, Section, c_name, rwx};
+
+/// 32ビットと64ビットのマジックナンバー
+const MH_MAGIC: u64 = 0xFEED_FACE;
+const MH_MAGIC_64: u64 = 0xFEED_FACF;
+const LC_SEGMENT: u64 = 0x1;
+const LC_SEGMENT_64: u64 = 0x19;
+const VM_PROT_READ: u64 = 0x1;
+const VM_PROT_WRITE: u64 = 0x2;
+const VM_PROT_EXECUTE: u64 = 0x4;
+/// ファイルに中身のないセクションの種類（S_ZEROFILL, S_GB_ZEROFILL, S_THREAD_LOCAL_ZEROFILL）
+const ZEROFILL: [u64; 3] = [0x1, 0xC, 0x12];
+
+/// 先頭が Mach-O のマジックナンバーか（どちらのエンディアンでも）
+pub(super) fn is_macho(data: &[u8]) -> bool {
+    let Some(head) = data.get(..4) else {
+        return false;
+    };
+    let head: [u8; 4] = head.try_into().unwrap_or_default();
+    [u32::from_le_bytes(head), u32::from_be_bytes(head)]
+        .iter()
+        .any(|&magic| matches!(u64::from(magic), MH_MAGIC | MH_MAGIC_64))
+}
+
+pub(super) fn parse(data: &[u8]) -> Result<Executable, ExecutableError> {
+    let big_endian = matches!(data.first(), Some(0xFE));
+    let f = Fields { data, big_endian };
+    let wide = f.u32(0)? == MH_MAGIC_64;
+    let cpu = f.u32(4)?;
+    let count = f.u32(16)?;
+
+    let mut segments = Vec::new();
+    let mut sections = Vec::new();
+    let mut cmd = if wide { 32 } else { 28 };
+    for _ in 0..count {
+        let kind = f.u32(cmd)?;
+        let size = f.u32(cmd + 4)?;
+        if size < 8 {
+            return Err(ExecutableError::Invalid(
+                "load command smaller than its header",
+            ));
+        }
+        if kind == LC_SEGMENT || kind == LC_SEGMENT_64 {
+            let wide = kind == LC_SEGMENT_64;
+            // 32/64 ビットで幅が違うフィールドを読む
+            let word = |offset: u64| if wide { f.u64(offset) } else { f.u32(offset) };
+            let (addr, mem_size, offset, file_size) = if wide {
+                (
+                    word(cmd + 24)?,
+                    word(cmd + 32)?,
+                    word(cmd + 40)?,
+                    word(cmd + 48)?,
+                )
+            } else {
+                (
+                    word(cmd + 24)?,
+                    word(cmd + 28)?,
+                    word(cmd + 32)?,
+                    word(cmd + 36)?,
+                )
+            };
+            let (prot, nsects, first) = if wide {
+                (f.u32(cmd + 60)?, f.u32(cmd + 64)?, cmd + 72)
+            } else {
+                (f.u32(cmd + 44)?, f.u32(cmd + 48)?, cmd + 56)
+            };
+            let segment = c_name(f.bytes(cmd + 8, 16)?);
+            let flags = rwx(
+                prot & VM_PROT_READ != 0,
+                prot & VM_PROT_WRITE != 0,
+                prot & VM_PROT_EXECUTE != 0,
+            );
+
+            let entry = if wide { 80 } else { 68 };
+            for i in 0..nsects {
+                let sect = first + i * entry;
+                let (addr, size, offset, kind) = if wide {
+                    (
+                        f.u64(sect + 32)?,
+                        f.u64(sect + 40)?,
+                        f.u32(sect + 48)?,
+                        f.u32(sect + 64)?,
+                    )
+                } else {
+                    (
+                        f.u32(sect + 32)?,
+                        f.u32(sect + 36)?,
+                        f.u32(sect + 40)?,
+                        f.u32(sect + 56)?,
+                    )
+                };
+                let kind = kind & 0xFF;
+                sections.push(Section {
+                    name: format!(
+                        "{},{}",
+                        c_name(f.bytes(sect + 16, 16)?),
+                        c_name(f.bytes(sect, 16)?)
+                    ),
+                    offset,
+                    size: if ZEROFILL.contains(&kind) { 0 } else { size },
+                    addr,
+                    mem_size: size,
+                    flags: flags.clone(),
+                });
+            }
+            segments.push(Section {
+                name: segment,
+                offset,
+                size: file_size,
+                addr,
+                mem_size,
+                flags,
+            });
+        }
+        cmd += size;
+    }
+
+    Ok(Executable {
+        format: ExecutableFormat::MachO,
+        class: if wide { "Mach-O 64" } else { "Mach-O 32" },
+        arch: cpu_name(cpu),
+        big_endian,
+        segments,
+        sections,
+    })
+}
+
+/// cputype の名前
+fn cpu_name(cpu: u64) -> String {
+    match cpu {
+        0x7 => "x86",
+        0x0100_0007 => "x86_64",
+        0xC => "arm",
+        0x0100_000C => "arm64",
+        0x12 => "ppc",
+        0x0100_0012 => "ppc64",
+        _ => return format!("cpu 0x{:X}", cpu),
+    }
+    .to_string()
+}
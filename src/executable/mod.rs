@@ -0,0 +1,321 @@
+//! 実行ファイル（ELF / PE / Mach-O）のセグメントとセクションの一覧
+//!
+//! ヘッダーとセクション表だけを読む。シンボルや再配置は解釈しない。
+
+mod elf;
+mod macho;
+mod pe;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ExecutableError {
+    #[error("Unknown executable format (expected ELF, PE or Mach-O)")]
+    UnknownFormat,
+    #[error("Executable is truncated")]
+    Truncated,
+    #[error("Invalid executable: {0}")]
+    Invalid(&'static str),
+    #[error("Unsupported executable: {0}")]
+    Unsupported(&'static str),
+}
+
+/// 実行ファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutableFormat {
+    Elf,
+    Pe,
+    MachO,
+}
+
+impl ExecutableFormat {
+    /// 名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Elf => "ELF",
+            Self::Pe => "PE",
+            Self::MachO => "Mach-O",
+        }
+    }
+
+    /// 先頭のマジックナンバーから判定
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(elf::MAGIC) {
+            Some(Self::Elf)
+        } else if data.starts_with(pe::MAGIC) {
+            Some(Self::Pe)
+        } else if macho::is_macho(data) {
+            Some(Self::MachO)
+        } else {
+            None
+        }
+    }
+}
+
+/// セグメントまたはセクション
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// 名前（ELF のセグメントは "LOAD" などの種類、Mach-O のセクションは "__TEXT,__text"）
+    pub name: String,
+    /// ファイル上の位置
+    pub offset: u64,
+    /// ファイル上の大きさ（.bss のようにファイルに中身がなければ 0）
+    pub size: u64,
+    /// 読み込まれるアドレス
+    pub addr: u64,
+    /// メモリ上の大きさ
+    pub mem_size: u64,
+    /// 属性（"rwx" の形）
+    pub flags: String,
+}
+
+/// 解析した実行ファイル
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Executable {
+    pub format: ExecutableFormat,
+    /// "ELF64" / "PE32+" など
+    pub class: &'static str,
+    /// 命令セット（"x86_64" など、知らなければ番号）
+    pub arch: String,
+    pub big_endian: bool,
+    /// セグメント（PE にはない）
+    pub segments: Vec<Section>,
+    pub sections: Vec<Section>,
+}
+
+impl Executable {
+    /// バイト列を解析する
+    pub fn parse(data: &[u8]) -> Result<Self, ExecutableError> {
+        match ExecutableFormat::detect(data) {
+            Some(ExecutableFormat::Elf) => elf::parse(data),
+            Some(ExecutableFormat::Pe) => pe::parse(data),
+            Some(ExecutableFormat::MachO) => macho::parse(data),
+            None => Err(ExecutableError::UnknownFormat),
+        }
+    }
+
+    /// 名前でセクション（なければセグメント）を探す
+    /// Mach-O のセクションは "__text" のようにセグメント名を省いてもよい
+    pub fn find(&self, name: &str) -> Option<&Section> {
+        let matches = |section: &&Section| {
+            section.name == name
+                || section
+                    .name
+                    .split_once(',')
+                    .is_some_and(|(_, sect)| sect == name)
+        };
+        self.sections
+            .iter()
+            .find(matches)
+            .or_else(|| self.segments.iter().find(matches))
+    }
+}
+
+/// "rwx" 形式の属性
+fn rwx(read: bool, write: bool, execute: bool) -> String {
+    [(read, 'r'), (write, 'w'), (execute, 'x')]
+        .iter()
+        .map(|&(set, c)| if set { c } else { '-' })
+        .collect()
+}
+
+/// NUL で終わる名前
+fn c_name(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// エンディアンを指定してヘッダーを読む
+struct Fields<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Fields<'a> {
+    fn bytes(&self, offset: u64, len: u64) -> Result<&'a [u8], ExecutableError> {
+        usize::try_from(offset)
+            .ok()
+            .zip(usize::try_from(len).ok())
+            .and_then(|(offset, len)| self.data.get(offset..offset.checked_add(len)?))
+            .ok_or(ExecutableError::Truncated)
+    }
+
+    /// len バイトの符号なし整数
+    fn uint(&self, offset: u64, len: u64) -> Result<u64, ExecutableError> {
+        let bytes = self.bytes(offset, len)?;
+        let fold = |acc: u64, &b: &u8| (acc << 8) | u64::from(b);
+        Ok(if self.big_endian {
+            bytes.iter().fold(0, fold)
+        } else {
+            bytes.iter().rev().fold(0, fold)
+        })
+    }
+
+    fn u16(&self, offset: u64) -> Result<u64, ExecutableError> {
+        self.uint(offset, 2)
+    }
+
+    fn u32(&self, offset: u64) -> Result<u64, ExecutableError> {
+        self.uint(offset, 4)
+    }
+
+    fn u64(&self, offset: u64) -> Result<u64, ExecutableError> {
+        self.uint(offset, 8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 値を little endian で data[offset..] に書く
+    fn put(data: &mut [u8], offset: usize, value: u64, len: usize) {
+        data[offset..offset + len].copy_from_slice(&value.to_le_bytes()[..len]);
+    }
+
+    #[test]
+    fn test_elf() {
+        // ELF64 ヘッダー + プログラムヘッダー1つ + セクション3つ（NULL, .text, .shstrtab）
+        let mut data = vec![0u8; 0x200];
+        data[..6].copy_from_slice(b"\x7FELF\x02\x01");
+        put(&mut data, 18, 0x3E, 2);
+        put(&mut data, 32, 0x40, 8); // e_phoff
+        put(&mut data, 40, 0x100, 8); // e_shoff
+        put(&mut data, 54, 56, 2);
+        put(&mut data, 56, 1, 2);
+        put(&mut data, 58, 64, 2);
+        put(&mut data, 60, 3, 2);
+        put(&mut data, 62, 2, 2);
+        // PT_LOAD r-x
+        put(&mut data, 0x40, 1, 4);
+        put(&mut data, 0x44, 5, 4);
+        put(&mut data, 0x48, 0, 8);
+        put(&mut data, 0x50, 0x400000, 8);
+        put(&mut data, 0x60, 0x200, 8);
+        put(&mut data, 0x68, 0x200, 8);
+        // .text
+        put(&mut data, 0x140, 1, 4);
+        put(&mut data, 0x144, 1, 4);
+        put(&mut data, 0x148, 6, 8);
+        put(&mut data, 0x150, 0x4000F0, 8);
+        put(&mut data, 0x158, 0xF0, 8);
+        put(&mut data, 0x160, 4, 8);
+        // .shstrtab
+        put(&mut data, 0x180, 7, 4);
+        put(&mut data, 0x184, 3, 4);
+        put(&mut data, 0x198, 0x1E0, 8);
+        put(&mut data, 0x1A0, 0x11, 8);
+        data[0x1E0..0x1F1].copy_from_slice(b"\0.text\0.shstrtab\0");
+
+        let exe = Executable::parse(&data).unwrap();
+        assert_eq!(
+            (exe.format, exe.class, exe.arch.as_str()),
+            (ExecutableFormat::Elf, "ELF64", "x86_64")
+        );
+        assert_eq!(exe.segments.len(), 1);
+        assert_eq!(
+            (
+                exe.segments[0].name.as_str(),
+                exe.segments[0].flags.as_str()
+            ),
+            ("LOAD", "r-x")
+        );
+        let text = exe.find(".text").unwrap();
+        assert_eq!((text.offset, text.size, text.addr), (0xF0, 4, 0x4000F0));
+        assert_eq!(text.flags, "r-x");
+        assert_eq!(exe.sections.len(), 2);
+
+        put(&mut data, 40, 0x1000, 8);
+        assert_eq!(Executable::parse(&data), Err(ExecutableError::Truncated));
+    }
+
+    #[test]
+    fn test_pe() {
+        let mut data = vec![0u8; 0x200];
+        data[..2].copy_from_slice(b"MZ");
+        put(&mut data, 0x3C, 0x80, 4);
+        data[0x80..0x84].copy_from_slice(b"PE\0\0");
+        put(&mut data, 0x84, 0x8664, 2);
+        put(&mut data, 0x86, 1, 2);
+        put(&mut data, 0x94, 0x70, 2); // SizeOfOptionalHeader
+        put(&mut data, 0x98, 0x20B, 2);
+        put(&mut data, 0x98 + 24, 0x140000000, 8);
+        let section = 0x98 + 0x70;
+        data[section..section + 5].copy_from_slice(b".text");
+        put(&mut data, section + 8, 0x10, 4);
+        put(&mut data, section + 12, 0x1000, 4);
+        put(&mut data, section + 16, 0x20, 4);
+        put(&mut data, section + 20, 0x180, 4);
+        put(&mut data, section + 36, 0x6000_0020, 4);
+
+        let exe = Executable::parse(&data).unwrap();
+        assert_eq!((exe.class, exe.arch.as_str()), ("PE32+", "x86_64"));
+        assert!(exe.segments.is_empty());
+        let text = exe.find(".text").unwrap();
+        assert_eq!(
+            (text.offset, text.size, text.addr, text.mem_size),
+            (0x180, 0x20, 0x140001000, 0x10)
+        );
+        assert_eq!(text.flags, "r-x");
+    }
+
+    #[test]
+    fn test_macho() {
+        // 64ビット、LC_SEGMENT_64 1つ（セクション2つ、1つは zerofill）
+        let mut data = vec![0u8; 0x200];
+        put(&mut data, 0, 0xFEEDFACF, 4);
+        put(&mut data, 4, 0x0100000C, 4);
+        put(&mut data, 16, 1, 4);
+        put(&mut data, 20, 72 + 2 * 80, 4);
+        let cmd = 32;
+        put(&mut data, cmd, 0x19, 4);
+        put(&mut data, cmd + 4, 72 + 2 * 80, 4);
+        data[cmd + 8..cmd + 14].copy_from_slice(b"__TEXT");
+        put(&mut data, cmd + 24, 0x100000000, 8);
+        put(&mut data, cmd + 32, 0x4000, 8);
+        put(&mut data, cmd + 48, 0x200, 8);
+        put(&mut data, cmd + 60, 5, 4);
+        put(&mut data, cmd + 64, 2, 4);
+        let sect = cmd + 72;
+        data[sect..sect + 6].copy_from_slice(b"__text");
+        data[sect + 16..sect + 22].copy_from_slice(b"__TEXT");
+        put(&mut data, sect + 32, 0x100000180, 8);
+        put(&mut data, sect + 40, 0x40, 8);
+        put(&mut data, sect + 48, 0x180, 4);
+        let bss = sect + 80;
+        data[bss..bss + 5].copy_from_slice(b"__bss");
+        data[bss + 16..bss + 22].copy_from_slice(b"__TEXT");
+        put(&mut data, bss + 40, 0x100, 8);
+        put(&mut data, bss + 64, 1, 4);
+
+        let exe = Executable::parse(&data).unwrap();
+        assert_eq!((exe.class, exe.arch.as_str()), ("Mach-O 64", "arm64"));
+        assert_eq!(
+            (
+                exe.segments[0].name.as_str(),
+                exe.segments[0].flags.as_str()
+            ),
+            ("__TEXT", "r-x")
+        );
+        let text = exe.find("__text").unwrap();
+        assert_eq!(text.name, "__TEXT,__text");
+        assert_eq!(
+            (text.offset, text.size, text.addr),
+            (0x180, 0x40, 0x100000180)
+        );
+        let bss = exe.find("__TEXT,__bss").unwrap();
+        assert_eq!((bss.size, bss.mem_size), (0, 0x100));
+    }
+
+    #[test]
+    fn test_unknown() {
+        assert_eq!(
+            Executable::parse(b"PATCH"),
+            Err(ExecutableError::UnknownFormat)
+        );
+        assert_eq!(
+            Executable::parse(b"\x7FELF\x02"),
+            Err(ExecutableError::Truncated)
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! PE（Windows の EXE / DLL）: DOS ヘッダー、COFF ヘッダー、セクション表
+//!
+//! PE にはセグメントがないので、セクションだけを返す。
+
+use super::{Executable, ExecutableError, ExecutableFormat, Fields, Section, c_name, rwx};
+
+pub(super) const MAGIC: &[u8] = b"MZ";
+
+/// セクション表の1項目の大きさ
+const SECTION_SIZE: u64 = 40;
+const IMAGE_SCN_MEM_EXECUTE: u64 = 0x2000_0000;
+const IMAGE_SCN_MEM_READ: u64 = 0x4000_0000;
+const IMAGE_SCN_MEM_WRITE: u64 = 0x8000_0000;
+
+pub(super) fn parse(data: &[u8]) -> Result<Executable, ExecutableError> {
+    let f = Fields {
+        data,
+        big_endian: false,
+    };
+    let pe = f.u32(0x3C)?;
+    if f.bytes(pe, 4)? != b"PE\0\0" {
+        return Err(ExecutableError::Unsupported(
+            "MS-DOS executable without a PE header",
+        ));
+    }
+    let machine = f.u16(pe + 4)?;
+    let count = f.u16(pe + 6)?;
+    let optional_size = f.u16(pe + 20)?;
+    let optional = pe + 24;
+    let (class, image_base) = match f.u16(optional)? {
+        0x10B => ("PE32", f.u32(optional + 28)?),
+        0x20B => ("PE32+", f.u64(optional + 24)?),
+        _ => return Err(ExecutableError::Invalid("unknown optional header")),
+    };
+
+    let table = optional + optional_size;
+    let mut sections = Vec::new();
+    for i in 0..count {
+        let header = table + i * SECTION_SIZE;
+        let flags = f.u32(header + 36)?;
+        sections.push(Section {
+            // "/4" のような長い名前（文字列表への参照）はそのまま表示する
+            name: c_name(f.bytes(header, 8)?),
+            offset: f.u32(header + 20)?,
+            size: f.u32(header + 16)?,
+            addr: image_base.wrapping_add(f.u32(header + 12)?),
+            mem_size: f.u32(header + 8)?,
+            flags: rwx(
+                flags & IMAGE_SCN_MEM_READ != 0,
+                flags & IMAGE_SCN_MEM_WRITE != 0,
+                flags & IMAGE_SCN_MEM_EXECUTE != 0,
+            ),
+        });
+    }
+
+    Ok(Executable {
+        format: ExecutableFormat::Pe,
+        class,
+        arch: machine_name(machine),
+        big_endian: false,
+        segments: Vec::new(),
+        sections,
+    })
+}
+
+/// COFF の Machine の名前
+fn machine_name(machine: u64) -> String {
+    match machine {
+        0x014C => "x86",
+        0x01C0 | 0x01C4 => "arm",
+        0x5064 => "riscv64",
+        0x8664 => "x86_64",
+        0xAA64 => "arm64",
+        _ => return format!("machine 0x{:04X}", machine),
+    }
+    .to_string()
+}
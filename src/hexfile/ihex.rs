@@ -0,0 +1,87 @@
+//! Intel HEX: ":" + 長さ + アドレス（16ビット）+ 種類 + データ + チェックサム
+//!
+//! 16ビットを超えるアドレスは拡張リニアアドレス（種類 04）で上位16ビットを指定する。
+
+use super::{HexFileError, Record, hex, parse_bytes, sum};
+
+const DATA: u8 = 0x00;
+const END_OF_FILE: u8 = 0x01;
+const EXTENDED_SEGMENT_ADDRESS: u8 = 0x02;
+const START_SEGMENT_ADDRESS: u8 = 0x03;
+const EXTENDED_LINEAR_ADDRESS: u8 = 0x04;
+const START_LINEAR_ADDRESS: u8 = 0x05;
+
+pub(super) fn encode(data: &[u8], address: u64, record_len: usize) -> String {
+    let mut text = String::new();
+    let mut upper = 0;
+    let mut offset = 0;
+    while offset < data.len() {
+        let addr = address + offset as u64;
+        if addr >> 16 != upper {
+            upper = addr >> 16;
+            text.push_str(&record(
+                0,
+                EXTENDED_LINEAR_ADDRESS,
+                &(upper as u16).to_be_bytes(),
+            ));
+        }
+        // 1レコードが 64 KiB の境界をまたがないように
+        let len = record_len
+            .min(data.len() - offset)
+            .min(0x10000 - (addr & 0xFFFF) as usize);
+        text.push_str(&record(addr as u16, DATA, &data[offset..offset + len]));
+        offset += len;
+    }
+    text.push_str(&record(0, END_OF_FILE, &[]));
+    text
+}
+
+fn record(address: u16, kind: u8, data: &[u8]) -> String {
+    let mut bytes = vec![data.len() as u8];
+    bytes.extend_from_slice(&address.to_be_bytes());
+    bytes.push(kind);
+    bytes.extend_from_slice(data);
+    bytes.push(sum(&bytes).wrapping_neg());
+    format!(":{}\n", hex(&bytes))
+}
+
+pub(super) fn decode(text: &str) -> Result<Vec<Record>, HexFileError> {
+    let mut records = Vec::new();
+    // 拡張アドレスのレコードで決まる、後に続くレコードのアドレスの基準
+    let mut base = 0;
+    for (i, line) in text.lines().enumerate() {
+        let n = i + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some(digits) = line.strip_prefix(':') else {
+            return Err(HexFileError::Invalid(n, "missing ':'"));
+        };
+        let bytes = parse_bytes(n, digits)?;
+        if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+            return Err(HexFileError::Invalid(n, "wrong record length"));
+        }
+        if sum(&bytes) != 0 {
+            return Err(HexFileError::Checksum(n));
+        }
+        let address = u64::from(u16::from_be_bytes([bytes[1], bytes[2]]));
+        let data = &bytes[4..bytes.len() - 1];
+        let word = || match data {
+            &[high, low] => Ok(u64::from(u16::from_be_bytes([high, low]))),
+            _ => Err(HexFileError::Invalid(n, "address record must have 2 bytes")),
+        };
+        match bytes[3] {
+            DATA => records.push(Record {
+                address: base + address,
+                data: data.to_vec(),
+            }),
+            END_OF_FILE => break,
+            EXTENDED_SEGMENT_ADDRESS => base = word()? << 4,
+            EXTENDED_LINEAR_ADDRESS => base = word()? << 16,
+            START_SEGMENT_ADDRESS | START_LINEAR_ADDRESS => {}
+            _ => return Err(HexFileError::Invalid(n, "unknown record type")),
+        }
+    }
+    Ok(records)
+}
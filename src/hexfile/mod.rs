@@ -0,0 +1,227 @@
+//! Intel HEX / Motorola S-record（ファームウェアの書き込みに使うテキスト形式）
+//!
+//! どちらもアドレス付きのレコードを1行ずつ並べた形式。読むときはデータレコードだけを
+//! 取り出し、開始アドレス（エントリーポイント）などのレコードは無視する。
+
+mod ihex;
+mod srec;
+
+use thiserror::Error;
+
+/// 並べたときに許す大きさ（レコードのアドレスが離れすぎていると巨大な穴埋めになるため）
+const MAX_SPAN: u64 = 1 << 30;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum HexFileError {
+    #[error("Line {0}: {1}")]
+    Invalid(usize, &'static str),
+    #[error("Line {0}: checksum mismatch")]
+    Checksum(usize),
+    #[error("Address 0x{0:X} does not fit in 32 bits")]
+    AddressTooLarge(u64),
+    #[error("Record length must be 1-{0}")]
+    RecordLength(usize),
+    #[error("Data at 0x{0:X} is below the base address 0x{1:X}")]
+    BelowBase(u64, u64),
+    #[error("Records span 0x{0:X} bytes (more than 1 GiB)")]
+    TooLarge(u64),
+}
+
+/// 形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexFormat {
+    IntelHex,
+    SRecord,
+}
+
+impl HexFormat {
+    /// 入力に使う名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::IntelHex => "ihex",
+            Self::SRecord => "srec",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ihex" => Some(Self::IntelHex),
+            "srec" | "s19" | "mot" => Some(Self::SRecord),
+            _ => None,
+        }
+    }
+
+    /// 1レコードに入れられるデータの最大バイト数
+    pub fn max_record_len(self) -> usize {
+        match self {
+            Self::IntelHex => 255,
+            // 長さのバイトにアドレス（最大4バイト）とチェックサムも含まれる
+            Self::SRecord => 250,
+        }
+    }
+
+    /// address から始まる data をレコードの並びにする（1レコードに record_len バイトまで）
+    pub fn encode(
+        self,
+        data: &[u8],
+        address: u64,
+        record_len: usize,
+    ) -> Result<String, HexFileError> {
+        if record_len == 0 || record_len > self.max_record_len() {
+            return Err(HexFileError::RecordLength(self.max_record_len()));
+        }
+        let end = address.saturating_add(data.len() as u64);
+        if end > 1 << 32 {
+            return Err(HexFileError::AddressTooLarge(end - 1));
+        }
+        Ok(match self {
+            Self::IntelHex => ihex::encode(data, address, record_len),
+            Self::SRecord => srec::encode(data, address, record_len),
+        })
+    }
+
+    /// データレコードを取り出す（空行は無視）
+    pub fn decode(self, text: &str) -> Result<Vec<Record>, HexFileError> {
+        match self {
+            Self::IntelHex => ihex::decode(text),
+            Self::SRecord => srec::decode(text),
+        }
+    }
+}
+
+/// データレコード
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub address: u64,
+    pub data: Vec<u8>,
+}
+
+/// レコードを1つのバイト列に並べる
+/// base のアドレスが先頭になり（None なら最も小さいアドレス）、隙間は fill で埋める
+pub fn to_binary(records: &[Record], base: Option<u64>, fill: u8) -> Result<Vec<u8>, HexFileError> {
+    let Some(lowest) = records.iter().map(|r| r.address).min() else {
+        return Ok(Vec::new());
+    };
+    let base = base.unwrap_or(lowest);
+    if lowest < base {
+        return Err(HexFileError::BelowBase(lowest, base));
+    }
+    let end = records
+        .iter()
+        .map(|r| r.address + r.data.len() as u64)
+        .max()
+        .unwrap_or(base);
+    if end - base > MAX_SPAN {
+        return Err(HexFileError::TooLarge(end - base));
+    }
+
+    let mut image = vec![fill; (end - base) as usize];
+    for record in records {
+        let start = (record.address - base) as usize;
+        image[start..start + record.data.len()].copy_from_slice(&record.data);
+    }
+    Ok(image)
+}
+
+/// レコードの HEX 部分をバイト列に
+fn parse_bytes(line: usize, hex: &str) -> Result<Vec<u8>, HexFileError> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(HexFileError::Invalid(line, "odd number of hex digits"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or(HexFileError::Invalid(line, "invalid hex digit"))
+        })
+        .collect()
+}
+
+/// バイト列を HEX 文字列に（大文字）
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// バイトの合計（下位8ビット）
+fn sum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |acc, &b| acc.wrapping_add(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_known_records() {
+        let ihex = ":10010000214601360121470136007EFE09D2190140\n\
+                    :020000040001F9\n\
+                    :0200100055AAEF\n\
+                    :00000001FF\n";
+        let records = HexFormat::IntelHex.decode(ihex).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].address, 0x100);
+        assert_eq!(records[0].data[..4], [0x21, 0x46, 0x01, 0x36]);
+        assert_eq!(
+            records[1],
+            Record {
+                address: 0x10010,
+                data: vec![0x55, 0xAA]
+            }
+        );
+        assert_eq!(
+            HexFormat::IntelHex.decode(":0200100055AAEE"),
+            Err(HexFileError::Checksum(1))
+        );
+        assert_eq!(
+            HexFormat::IntelHex.decode("0200100055AAEF"),
+            Err(HexFileError::Invalid(1, "missing ':'"))
+        );
+
+        let srec = "S00F000068656C6C6F202020202000003C\n\
+                    S11F00007C0802A6900100049421FFF07C6C1B787C8C23783C6000003863000026\n\
+                    S5030001FB\n\
+                    S9030000FC\n";
+        let records = HexFormat::SRecord.decode(srec).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].address, 0);
+        assert_eq!(records[0].data.len(), 28);
+        assert_eq!(
+            HexFormat::SRecord.decode("S9030000FD"),
+            Err(HexFileError::Checksum(1))
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        for format in [HexFormat::IntelHex, HexFormat::SRecord] {
+            // 64 KiB の境界をまたぐ
+            let text = format.encode(&data, 0xFFF0_FF00, 32).unwrap();
+            let records = format.decode(&text).unwrap();
+            assert!(records.iter().all(|r| r.data.len() <= 32));
+            assert_eq!(to_binary(&records, None, 0xFF).unwrap(), data);
+            assert_eq!(
+                to_binary(&records, Some(0xFFF0_FEFE), 0xFF).unwrap()[..3],
+                [0xFF, 0xFF, 0x00]
+            );
+            assert_eq!(
+                to_binary(&records, Some(0xFFF0_FF01), 0xFF),
+                Err(HexFileError::BelowBase(0xFFF0_FF00, 0xFFF0_FF01))
+            );
+        }
+
+        let text = HexFormat::IntelHex.encode(b"\x01\x02", 0x1000, 16).unwrap();
+        assert_eq!(text, ":021000000102EB\n:00000001FF\n");
+        let text = HexFormat::SRecord.encode(b"\x01\x02", 0x1000, 16).unwrap();
+        assert_eq!(text, "S0030000FC\nS10510000102E7\nS9031000EC\n");
+        assert_eq!(
+            HexFormat::SRecord.encode(b"\x01", 0x1_0000_0000, 16),
+            Err(HexFileError::AddressTooLarge(0x1_0000_0000))
+        );
+        assert_eq!(
+            HexFormat::IntelHex.encode(b"\x01", 0, 256),
+            Err(HexFileError::RecordLength(255))
+        );
+    }
+}
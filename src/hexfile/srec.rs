@@ -0,0 +1,73 @@
+//! Motorola S-record: "S" + 種類 + 長さ + アドレス + データ + チェックサム
+//!
+//! アドレスの幅は種類で決まる（S1/S9: 16ビット、S2/S8: 24ビット、S3/S7: 32ビット）。
+//! 書き出すときは、最後のアドレスが収まる最も短い幅を使う。
+
+use super::{HexFileError, Record, hex, parse_bytes, sum};
+
+pub(super) fn encode(data: &[u8], address: u64, record_len: usize) -> String {
+    let end = address + data.len() as u64;
+    let (width, kind, termination) = if end <= 1 << 16 {
+        (2, 1, 9)
+    } else if end <= 1 << 24 {
+        (3, 2, 8)
+    } else {
+        (4, 3, 7)
+    };
+
+    // S0（ヘッダー）は中身なし
+    let mut text = record(0, 2, 0, &[]);
+    for (i, chunk) in data.chunks(record_len).enumerate() {
+        let addr = address + (i * record_len) as u64;
+        text.push_str(&record(kind, width, addr, chunk));
+    }
+    // 終わりのレコードのアドレスは開始アドレス（読み込んだ先頭）
+    text.push_str(&record(termination, width, address, &[]));
+    text
+}
+
+fn record(kind: u8, width: usize, address: u64, data: &[u8]) -> String {
+    let mut bytes = vec![(width + data.len() + 1) as u8];
+    bytes.extend_from_slice(&address.to_be_bytes()[8 - width..]);
+    bytes.extend_from_slice(data);
+    bytes.push(!sum(&bytes));
+    format!("S{}{}\n", kind, hex(&bytes))
+}
+
+pub(super) fn decode(text: &str) -> Result<Vec<Record>, HexFileError> {
+    let mut records = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let n = i + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut chars = line.chars();
+        let (Some('S' | 's'), Some(kind)) = (chars.next(), chars.next()) else {
+            return Err(HexFileError::Invalid(n, "missing 'S'"));
+        };
+        let width = match kind {
+            '0' | '1' | '5' | '9' => 2,
+            '2' | '6' | '8' => 3,
+            '3' | '7' => 4,
+            _ => return Err(HexFileError::Invalid(n, "unknown record type")),
+        };
+        let bytes = parse_bytes(n, chars.as_str())?;
+        if bytes.len() < width + 2 || bytes.len() != bytes[0] as usize + 1 {
+            return Err(HexFileError::Invalid(n, "wrong record length"));
+        }
+        if sum(&bytes) != 0xFF {
+            return Err(HexFileError::Checksum(n));
+        }
+        if matches!(kind, '1' | '2' | '3') {
+            let address = bytes[1..=width]
+                .iter()
+                .fold(0u64, |acc, &b| acc << 8 | u64::from(b));
+            records.push(Record {
+                address,
+                data: bytes[width + 1..bytes.len() - 1].to_vec(),
+            });
+        }
+    }
+    Ok(records)
+}
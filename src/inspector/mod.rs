@@ -0,0 +1,287 @@
+//! データインスペクタ（カーソル位置のバイト列を数値型として解釈・書き換え）
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum InspectorError {
+    #[error("Invalid {0} value: {1}")]
+    Invalid(String, String),
+    #[error("{1} is out of range for {0}")]
+    OutOfRange(String, String),
+    #[error("Unknown type: {0} (u8..u64, i8..i64, f32, f64 with le/be)")]
+    UnknownType(String),
+}
+
+/// 数値型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+}
+
+impl NumType {
+    /// バイト数
+    pub fn size(self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::U64 | Self::I64 | Self::F64 => 8,
+        }
+    }
+
+    /// 型名から（"u32" など）
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::U8,
+            Self::I8,
+            Self::U16,
+            Self::I16,
+            Self::U32,
+            Self::I32,
+            Self::U64,
+            Self::I64,
+            Self::F32,
+            Self::F64,
+        ]
+        .into_iter()
+        .find(|ty| ty.name() == name)
+    }
+
+    /// 型名
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::U8 => "u8",
+            Self::I8 => "i8",
+            Self::U16 => "u16",
+            Self::I16 => "i16",
+            Self::U32 => "u32",
+            Self::I32 => "i32",
+            Self::U64 => "u64",
+            Self::I64 => "i64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        }
+    }
+}
+
+/// インスペクタの1項目（型とエンディアン）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub ty: NumType,
+    pub big_endian: bool,
+}
+
+/// インスペクタに表示する項目
+pub const FIELDS: &[Field] = &[
+    Field { ty: NumType::U8, big_endian: false },
+    Field { ty: NumType::I8, big_endian: false },
+    Field { ty: NumType::U16, big_endian: false },
+    Field { ty: NumType::U16, big_endian: true },
+    Field { ty: NumType::I16, big_endian: false },
+    Field { ty: NumType::I16, big_endian: true },
+    Field { ty: NumType::U32, big_endian: false },
+    Field { ty: NumType::U32, big_endian: true },
+    Field { ty: NumType::I32, big_endian: false },
+    Field { ty: NumType::I32, big_endian: true },
+    Field { ty: NumType::U64, big_endian: false },
+    Field { ty: NumType::U64, big_endian: true },
+    Field { ty: NumType::I64, big_endian: false },
+    Field { ty: NumType::I64, big_endian: true },
+    Field { ty: NumType::F32, big_endian: false },
+    Field { ty: NumType::F32, big_endian: true },
+    Field { ty: NumType::F64, big_endian: false },
+    Field { ty: NumType::F64, big_endian: true },
+];
+
+impl Field {
+    /// 型の指定から（"u32" / "u32le" / "u32be"、エンディアン省略時は LE）
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.to_ascii_lowercase();
+        let (name, big_endian) = match (spec.strip_suffix("le"), spec.strip_suffix("be")) {
+            (Some(name), _) => (name, false),
+            (_, Some(name)) => (name, true),
+            _ => (spec.as_str(), false),
+        };
+        let ty = NumType::from_name(name)?;
+        Some(Self { ty, big_endian })
+    }
+
+    /// 表示名（例: "u32 LE"、1バイト型はエンディアンなし）
+    pub fn name(&self) -> String {
+        if self.ty.size() == 1 {
+            self.ty.name().to_string()
+        } else {
+            format!("{} {}", self.ty.name(), if self.big_endian { "BE" } else { "LE" })
+        }
+    }
+
+    /// バイト列をエンディアンに従って u64 に読む（長さが足りなければ None）
+    fn read_raw(&self, bytes: &[u8]) -> Option<u64> {
+        let bytes = bytes.get(..self.ty.size())?;
+        // 読みやすさのため常にビッグエンディアンの順に並べてから u64 にする
+        let mut be = [0u8; 8];
+        let start = 8 - bytes.len();
+        be[start..].copy_from_slice(bytes);
+        if !self.big_endian {
+            be[start..].reverse();
+        }
+        Some(u64::from_be_bytes(be))
+    }
+
+    /// u64 の下位を型の大きさ・エンディアンのバイト列にする
+    fn write_raw(&self, raw: u64) -> Vec<u8> {
+        let size = self.ty.size();
+        let be = raw.to_be_bytes();
+        let mut bytes = be[8 - size..].to_vec();
+        if !self.big_endian {
+            bytes.reverse();
+        }
+        bytes
+    }
+
+    /// バイト列を値として解釈（長さが足りなければ None）
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        let raw = self.read_raw(bytes)?;
+
+        Some(match self.ty {
+            NumType::U8 | NumType::U16 | NumType::U32 | NumType::U64 => raw.to_string(),
+            NumType::I8 => (raw as u8 as i8).to_string(),
+            NumType::I16 => (raw as u16 as i16).to_string(),
+            NumType::I32 => (raw as u32 as i32).to_string(),
+            NumType::I64 => (raw as i64).to_string(),
+            NumType::F32 => f32::from_bits(raw as u32).to_string(),
+            NumType::F64 => f64::from_bits(raw).to_string(),
+        })
+    }
+
+    /// 入力された値をバイト列に変換（整数は 0x 接頭辞の16進も可）
+    pub fn encode(&self, input: &str) -> Result<Vec<u8>, InspectorError> {
+        let input = input.trim();
+        let invalid = || InspectorError::Invalid(self.name(), input.to_string());
+        let out_of_range = || InspectorError::OutOfRange(self.name(), input.to_string());
+
+        let raw: u64 = match self.ty {
+            NumType::F32 => input.parse::<f32>().map_err(|_| invalid())?.to_bits() as u64,
+            NumType::F64 => input.parse::<f64>().map_err(|_| invalid())?.to_bits(),
+            ty => {
+                let value = parse_integer(input).ok_or_else(invalid)?;
+                let bits = ty.size() as u32 * 8;
+                let signed = matches!(ty, NumType::I8 | NumType::I16 | NumType::I32 | NumType::I64);
+                let (min, max) = if signed {
+                    (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+                } else {
+                    (0, (1i128 << bits) - 1)
+                };
+                if value < min || value > max {
+                    return Err(out_of_range());
+                }
+                value as u64
+            }
+        };
+        Ok(self.write_raw(raw))
+    }
+
+    /// 値に delta を足したバイト列（整数は型の範囲で折り返す。長さが足りなければ None）
+    pub fn step(&self, bytes: &[u8], delta: i64) -> Option<Vec<u8>> {
+        let raw = self.read_raw(bytes)?;
+        let raw = match self.ty {
+            NumType::F32 => (f32::from_bits(raw as u32) + delta as f32).to_bits() as u64,
+            NumType::F64 => (f64::from_bits(raw) + delta as f64).to_bits(),
+            // 2の補数なので符号の有無に関係なく同じ足し算で済む
+            _ => raw.wrapping_add(delta as u64),
+        };
+        Some(self.write_raw(raw))
+    }
+}
+
+/// 値と型（"500 u16"、"0x1F4 u32be"、"1.5 f64le" など）をバイト列に変換
+pub fn parse_typed_value(input: &str) -> Result<(Field, Vec<u8>), InspectorError> {
+    let mut words = input.split_whitespace();
+    let (value, spec) = match (words.next(), words.next(), words.next()) {
+        (Some(value), Some(spec), None) => (value, spec),
+        _ => return Err(InspectorError::UnknownType(input.trim().to_string())),
+    };
+    let field = Field::parse(spec).ok_or_else(|| InspectorError::UnknownType(spec.to_string()))?;
+    Ok((field, field.encode(value)?))
+}
+
+/// 整数をパース（符号付き、0x 接頭辞で16進）
+fn parse_integer(s: &str) -> Option<i128> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i128>().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_encode_roundtrip() {
+        let u32_le = Field { ty: NumType::U32, big_endian: false };
+        assert_eq!(u32_le.encode("123456").unwrap(), [0x40, 0xE2, 0x01, 0x00]);
+        assert_eq!(u32_le.decode(&[0x40, 0xE2, 0x01, 0x00, 0xFF]).unwrap(), "123456");
+        assert_eq!(u32_le.decode(&[0x40, 0xE2]), None);
+
+        let i16_be = Field { ty: NumType::I16, big_endian: true };
+        assert_eq!(i16_be.encode("-2").unwrap(), [0xFF, 0xFE]);
+        assert_eq!(i16_be.decode(&[0xFF, 0xFE]).unwrap(), "-2");
+        assert_eq!(i16_be.encode("0x7FFF").unwrap(), [0x7F, 0xFF]);
+
+        let f32_le = Field { ty: NumType::F32, big_endian: false };
+        assert_eq!(f32_le.encode("1.5").unwrap(), 1.5f32.to_le_bytes());
+        assert_eq!(f32_le.decode(&1.5f32.to_le_bytes()).unwrap(), "1.5");
+    }
+
+    #[test]
+    fn test_step_wraps_within_type() {
+        let u8_field = Field { ty: NumType::U8, big_endian: false };
+        assert_eq!(u8_field.step(&[0xFF], 1).unwrap(), [0x00]);
+        assert_eq!(u8_field.step(&[0x00], -2).unwrap(), [0xFE]);
+
+        let u16_be = Field { ty: NumType::U16, big_endian: true };
+        assert_eq!(u16_be.step(&[0x00, 0xFF, 0x12], 1).unwrap(), [0x01, 0x00]);
+        let i32_le = Field { ty: NumType::I32, big_endian: false };
+        assert_eq!(i32_le.step(&[0, 0, 0, 0], -1).unwrap(), [0xFF; 4]);
+        assert_eq!(i32_le.step(&[0, 0], 1), None);
+
+        let f64_le = Field { ty: NumType::F64, big_endian: false };
+        assert_eq!(f64_le.step(&1.5f64.to_le_bytes(), 2).unwrap(), 3.5f64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        let u8_field = Field { ty: NumType::U8, big_endian: false };
+        assert!(matches!(u8_field.encode("256"), Err(InspectorError::OutOfRange(..))));
+        assert!(matches!(u8_field.encode("-1"), Err(InspectorError::OutOfRange(..))));
+        assert!(matches!(u8_field.encode("abc"), Err(InspectorError::Invalid(..))));
+    }
+
+    #[test]
+    fn test_parse_typed_value() {
+        let (field, bytes) = parse_typed_value("0x1F4 u16").unwrap();
+        assert_eq!(field, Field { ty: NumType::U16, big_endian: false });
+        assert_eq!(bytes, [0xF4, 0x01]);
+        assert_eq!(parse_typed_value("500 U32BE").unwrap().1, [0, 0, 0x01, 0xF4]);
+        assert_eq!(parse_typed_value("-1 i8").unwrap().1, [0xFF]);
+        assert_eq!(parse_typed_value("1.5 f64le").unwrap().1, 1.5f64.to_le_bytes());
+        assert!(matches!(parse_typed_value("500"), Err(InspectorError::UnknownType(_))));
+        assert!(matches!(parse_typed_value("5 u24"), Err(InspectorError::UnknownType(_))));
+        assert!(matches!(parse_typed_value("70000 u16"), Err(InspectorError::OutOfRange(..))));
+    }
+}
@@ -4,6 +4,19 @@
 
 pub mod app;
 pub mod buffer;
+pub mod checksum;
 pub mod clipboard;
+pub mod config;
+#[cfg(feature = "disasm")]
+pub mod disasm;
 pub mod encoding;
+pub mod executable;
+pub mod hexfile;
+pub mod inspector;
+pub mod patch;
+pub mod search;
+pub mod session;
+pub mod strings;
+pub mod template;
+pub mod transform;
 pub mod ui;
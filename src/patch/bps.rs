@@ -0,0 +1,234 @@
+//! BPS: "BPS1" + 元・結果・メタデータの長さ + 操作の列 + CRC-32 ×3
+//!
+//! 操作は可変長整数 (長さ - 1) << 2 | 種類 で、種類は
+//! 0: 元ファイルの同じ位置から、1: パッチから、2: 元ファイルの相対位置から、3: 結果の相対位置から。
+
+use super::{PatchError, Reader};
+
+pub(super) const MAGIC: &[u8] = b"BPS1";
+/// 末尾の CRC-32 ×3
+const FOOTER: usize = 12;
+
+impl Reader<'_> {
+    /// BPS の可変長整数（続きのバイトがあるたびに 1 を足す形）
+    fn bps_number(&mut self) -> Result<u64, PatchError> {
+        let mut value: u64 = 0;
+        let mut shift: u64 = 1;
+        loop {
+            let byte = self.byte()?;
+            value = (u64::from(byte & 0x7F))
+                .checked_mul(shift)
+                .and_then(|v| v.checked_add(value))
+                .ok_or(PatchError::Invalid("number too large"))?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift
+                .checked_shl(7)
+                .ok_or(PatchError::Invalid("number too large"))?;
+            value = value
+                .checked_add(shift)
+                .ok_or(PatchError::Invalid("number too large"))?;
+        }
+    }
+
+    /// 相対位置（最下位ビットが符号）
+    fn bps_offset(&mut self) -> Result<i64, PatchError> {
+        let value = self.bps_number()?;
+        let magnitude = (value >> 1) as i64;
+        Ok(if value & 1 != 0 {
+            -magnitude
+        } else {
+            magnitude
+        })
+    }
+}
+
+pub(super) fn apply(patch: &[u8], source: &[u8], verify: bool) -> Result<Vec<u8>, PatchError> {
+    if patch.len() < MAGIC.len() + FOOTER || !patch.starts_with(MAGIC) {
+        return Err(PatchError::Invalid("missing BPS1 header"));
+    }
+    let footer = &patch[patch.len() - FOOTER..];
+    let crc = |i: usize| u32::from_le_bytes(footer[i * 4..i * 4 + 4].try_into().unwrap());
+    let (source_crc, target_crc, patch_crc) = (crc(0), crc(1), crc(2));
+
+    let actual = crc32fast::hash(&patch[..patch.len() - 4]);
+    if actual != patch_crc {
+        return Err(PatchError::PatchChecksum {
+            expected: patch_crc,
+            actual,
+        });
+    }
+
+    let mut reader = Reader::new(&patch[..patch.len() - FOOTER]);
+    reader.bytes(MAGIC.len())?;
+    let source_size = reader.bps_number()?;
+    let target_size = reader.bps_number()?;
+    let metadata_size = reader.bps_number()?;
+    reader.bytes(metadata_size as usize)?;
+    if verify {
+        if source_size != source.len() as u64 {
+            return Err(PatchError::SourceSize {
+                expected: source_size,
+                actual: source.len() as u64,
+            });
+        }
+        let actual = crc32fast::hash(source);
+        if actual != source_crc {
+            return Err(PatchError::SourceChecksum {
+                expected: source_crc,
+                actual,
+            });
+        }
+    }
+
+    let target_size = usize::try_from(target_size).map_err(|_| PatchError::Invalid("size"))?;
+    let mut output: Vec<u8> = Vec::with_capacity(target_size.min(1 << 30));
+    let mut source_offset: i64 = 0;
+    let mut target_offset: i64 = 0;
+    while !reader.is_empty() {
+        let data = reader.bps_number()?;
+        let len = (data >> 2) as usize + 1;
+        if output.len() + len > target_size {
+            return Err(PatchError::Invalid("output exceeds the target size"));
+        }
+        match data & 3 {
+            0 => {
+                let start = output.len();
+                let bytes = source
+                    .get(start..start + len)
+                    .ok_or(PatchError::Invalid("source read"))?;
+                output.extend_from_slice(bytes);
+            }
+            1 => output.extend_from_slice(reader.bytes(len)?),
+            2 => {
+                source_offset += reader.bps_offset()?;
+                let start = usize::try_from(source_offset)
+                    .map_err(|_| PatchError::Invalid("source copy"))?;
+                let bytes = source
+                    .get(start..start + len)
+                    .ok_or(PatchError::Invalid("source copy"))?;
+                output.extend_from_slice(bytes);
+                source_offset += len as i64;
+            }
+            _ => {
+                target_offset += reader.bps_offset()?;
+                let start = usize::try_from(target_offset)
+                    .map_err(|_| PatchError::Invalid("target copy"))?;
+                if start >= output.len() {
+                    return Err(PatchError::Invalid("target copy"));
+                }
+                // 書いたばかりのバイトを読むこともあるので1バイトずつ
+                for i in start..start + len {
+                    output.push(output[i]);
+                }
+                target_offset += len as i64;
+            }
+        }
+    }
+    if output.len() != target_size {
+        return Err(PatchError::Invalid(
+            "output is shorter than the target size",
+        ));
+    }
+    let actual = crc32fast::hash(&output);
+    if verify && actual != target_crc {
+        return Err(PatchError::TargetChecksum {
+            expected: target_crc,
+            actual,
+        });
+    }
+    Ok(output)
+}
+
+/// BPS の可変長整数を書く
+fn number(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let x = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(0x80 | x);
+            return;
+        }
+        out.push(x);
+        value -= 1;
+    }
+}
+
+/// 同じ位置のバイトを比べるだけの単純な差分（元ファイルの同じ位置から / パッチから）
+/// 挿入や削除で後ろがずれたファイルではパッチが大きくなる
+pub(super) fn create(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut patch = MAGIC.to_vec();
+    for value in [source.len() as u64, target.len() as u64, 0] {
+        number(&mut patch, value);
+    }
+    let same = |i: usize| source.get(i) == Some(&target[i]);
+    let mut i = 0;
+    while i < target.len() {
+        let start = i;
+        if same(i) {
+            while i < target.len() && same(i) {
+                i += 1;
+            }
+            number(&mut patch, ((i - start - 1) as u64) << 2);
+        } else {
+            while i < target.len() && !same(i) {
+                i += 1;
+            }
+            number(&mut patch, (((i - start - 1) as u64) << 2) | 1);
+            patch.extend_from_slice(&target[start..i]);
+        }
+    }
+    patch.extend_from_slice(&crc32fast::hash(source).to_le_bytes());
+    patch.extend_from_slice(&crc32fast::hash(target).to_le_bytes());
+    patch.extend_from_slice(&crc32fast::hash(&patch).to_le_bytes());
+    patch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let source = b"Hello, world";
+        let target = b"Hello, BPS world world!";
+        let mut patch = MAGIC.to_vec();
+        for value in [source.len() as u64, target.len() as u64, 0] {
+            number(&mut patch, value);
+        }
+        number(&mut patch, (7 - 1) << 2); // "Hello, " を元の同じ位置から
+        number(&mut patch, ((4 - 1) << 2) | 1); // "BPS " をパッチから
+        patch.extend_from_slice(b"BPS ");
+        number(&mut patch, ((5 - 1) << 2) | 2); // "world" を元の 7 から
+        number(&mut patch, 7 << 1);
+        number(&mut patch, ((6 - 1) << 2) | 3); // " world" を結果の 10 から
+        number(&mut patch, 10 << 1);
+        number(&mut patch, 1); // "!" をパッチから
+        patch.push(b'!');
+        patch.extend_from_slice(&crc32fast::hash(source).to_le_bytes());
+        patch.extend_from_slice(&crc32fast::hash(target).to_le_bytes());
+        patch.extend_from_slice(&crc32fast::hash(&patch).to_le_bytes());
+
+        assert_eq!(apply(&patch, source, true).unwrap(), target);
+        assert!(matches!(
+            apply(&patch, b"Hello, World", true),
+            Err(PatchError::SourceChecksum { .. })
+        ));
+        let mut corrupted = patch.clone();
+        corrupted[6] ^= 1;
+        assert!(matches!(
+            apply(&corrupted, source, true),
+            Err(PatchError::PatchChecksum { .. })
+        ));
+    }
+
+    #[test]
+    fn test_create() {
+        let source = b"Hello, world";
+        for target in [&b"Hello, World!!"[..], b"Hell", b""] {
+            let patch = create(source, target);
+            assert_eq!(apply(&patch, source, true).unwrap(), target);
+        }
+    }
+}
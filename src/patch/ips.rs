@@ -0,0 +1,147 @@
+//! IPS: "PATCH" + レコード（3バイトのオフセット、2バイトの長さ、データ）+ "EOF"
+//!
+//! 長さ 0 のレコードは RLE（2バイトの回数と1バイトの値）。"EOF" の後ろに3バイトの
+//! 長さがあれば、結果をその長さに切り詰める（Lunar IPS の拡張）。
+
+use super::{PatchError, Reader, changed_ranges};
+
+pub(super) const MAGIC: &[u8] = b"PATCH";
+const EOF: u64 = 0x454F46;
+/// オフセットは3バイトまで
+const MAX_OFFSET: usize = 0xFF_FFFF;
+/// 1レコードの最大長
+const MAX_RECORD: usize = 0xFFFF;
+/// この長さ以上同じバイトが続けば RLE レコードにする
+const MIN_RLE: usize = 9;
+
+pub(super) fn apply(patch: &[u8], source: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut reader = Reader::new(patch);
+    if reader.bytes(MAGIC.len())? != MAGIC {
+        return Err(PatchError::Invalid("missing PATCH header"));
+    }
+    let mut output = source.to_vec();
+    loop {
+        let offset = reader.uint_be(3)?;
+        if offset == EOF {
+            break;
+        }
+        let offset = offset as usize;
+        let size = reader.uint_be(2)? as usize;
+        let (len, data) = if size == 0 {
+            let count = reader.uint_be(2)? as usize;
+            (count, None)
+        } else {
+            (size, Some(reader.bytes(size)?))
+        };
+        if output.len() < offset + len {
+            output.resize(offset + len, 0);
+        }
+        match data {
+            Some(data) => output[offset..offset + len].copy_from_slice(data),
+            None => output[offset..offset + len].fill(reader.byte()?),
+        }
+    }
+    if !reader.is_empty() {
+        let len = reader.uint_be(3)? as usize;
+        output.truncate(len);
+    }
+    Ok(output)
+}
+
+pub(super) fn create(source: &[u8], target: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut patch = MAGIC.to_vec();
+    // レコードの見出しは5バイトなので、それより短い一致はまたいでしまう
+    for range in changed_ranges(source, target, 6) {
+        let mut start = range.start;
+        while start < range.end {
+            if start > MAX_OFFSET {
+                return Err(PatchError::Unsupported("offsets above 16 MiB in IPS"));
+            }
+            // "EOF" と同じオフセットは終端と区別できないので、1バイト前からの2バイトで書く
+            if start == EOF as usize {
+                patch.extend_from_slice(&(start as u32 - 1).to_be_bytes()[1..]);
+                patch.extend_from_slice(&[0, 2]);
+                patch.extend_from_slice(&target[start - 1..=start]);
+                start += 1;
+                continue;
+            }
+            let byte = target[start];
+            let run = target[start..range.end]
+                .iter()
+                .take_while(|&&b| b == byte)
+                .count();
+            let run = run.min(MAX_RECORD);
+            patch.extend_from_slice(&(start as u32).to_be_bytes()[1..]);
+            if run >= MIN_RLE {
+                patch.extend_from_slice(&[0, 0]);
+                patch.extend_from_slice(&(run as u16).to_be_bytes());
+                patch.push(byte);
+                start += run;
+                continue;
+            }
+            // 次に長く同じバイトが続くところまでを1レコードにする
+            let mut end = start;
+            while end < range.end && end - start < MAX_RECORD {
+                let byte = target[end];
+                let run = target[end..range.end]
+                    .iter()
+                    .take_while(|&&b| b == byte)
+                    .count();
+                if run >= MIN_RLE {
+                    break;
+                }
+                end = (end + run).min(start + MAX_RECORD);
+            }
+            patch.extend_from_slice(&((end - start) as u16).to_be_bytes());
+            patch.extend_from_slice(&target[start..end]);
+            start = end;
+        }
+    }
+    patch.extend_from_slice(b"EOF");
+    if target.len() < source.len() {
+        if target.len() > MAX_OFFSET {
+            return Err(PatchError::Unsupported("truncating above 16 MiB in IPS"));
+        }
+        patch.extend_from_slice(&(target.len() as u32).to_be_bytes()[1..]);
+    }
+    Ok(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let patch = b"PATCH\x00\x00\x02\x00\x02XY\x00\x00\x08\x00\x00\x00\x03ZEOF";
+        assert_eq!(apply(patch, b"abcdef").unwrap(), b"abXYef\0\0ZZZ");
+        // 切り詰め
+        assert_eq!(apply(b"PATCHEOF\x00\x00\x02", b"abcdef").unwrap(), b"ab");
+        assert_eq!(
+            apply(b"PATCH\x00\x00\x02", b"abc"),
+            Err(PatchError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_create() {
+        let source = b"0123456789abcdef".repeat(8);
+        let mut target = source.clone();
+        target[3] = b'X';
+        target[20..40].fill(0xFF);
+        target.extend_from_slice(b"tail");
+        let patch = create(&source, &target).unwrap();
+        assert_eq!(apply(&patch, &source).unwrap(), target);
+        // 0xFF の連続は RLE になる（リテラルなら 48 バイト）
+        assert_eq!(patch.len(), 31);
+
+        let patch = create(&source, &source[..10]).unwrap();
+        assert_eq!(apply(&patch, &source).unwrap(), &source[..10]);
+
+        let source = vec![0; EOF as usize + 4];
+        let mut target = source.clone();
+        target[EOF as usize..].copy_from_slice(b"ABCD");
+        let patch = create(&source, &target).unwrap();
+        assert_eq!(apply(&patch, &source).unwrap(), target);
+    }
+}
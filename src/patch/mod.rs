@@ -0,0 +1,185 @@
+//! バイナリパッチファイル（IPS / BPS / VCDIFF）の適用
+//!
+//! ROM ハックやファームウェアの配布で使われる形式。BPS は元ファイル・結果・パッチの
+//! CRC-32 を、xdelta3 の VCDIFF は各ウィンドウの Adler-32 を持つので、適用時に確かめる。
+
+mod bps;
+mod ips;
+mod vcdiff;
+
+use std::ops::Range;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PatchError {
+    #[error("Unknown patch format (expected IPS, BPS or VCDIFF)")]
+    UnknownFormat,
+    #[error("Patch is truncated")]
+    Truncated,
+    #[error("Invalid patch: {0}")]
+    Invalid(&'static str),
+    #[error("Unsupported patch feature: {0}")]
+    Unsupported(&'static str),
+    #[error("Source size is {actual} bytes, patch expects {expected}")]
+    SourceSize { expected: u64, actual: u64 },
+    #[error("Source checksum {actual:08X} does not match {expected:08X} (wrong file or version?)")]
+    SourceChecksum { expected: u32, actual: u32 },
+    #[error("Result checksum {actual:08X} does not match {expected:08X}")]
+    TargetChecksum { expected: u32, actual: u32 },
+    #[error("Patch checksum {actual:08X} does not match {expected:08X} (corrupted patch?)")]
+    PatchChecksum { expected: u32, actual: u32 },
+}
+
+/// パッチの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchFormat {
+    Ips,
+    Bps,
+    Vcdiff,
+}
+
+impl PatchFormat {
+    /// 名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ips => "IPS",
+            Self::Bps => "BPS",
+            Self::Vcdiff => "VCDIFF",
+        }
+    }
+
+    /// 名前から（大文字・小文字を区別しない、xdelta は VCDIFF）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ips" => Some(Self::Ips),
+            "bps" => Some(Self::Bps),
+            "vcdiff" | "xdelta" | "xdelta3" => Some(Self::Vcdiff),
+            _ => None,
+        }
+    }
+
+    /// 先頭のマジックナンバーから判定
+    pub fn detect(patch: &[u8]) -> Option<Self> {
+        if patch.starts_with(ips::MAGIC) {
+            Some(Self::Ips)
+        } else if patch.starts_with(bps::MAGIC) {
+            Some(Self::Bps)
+        } else if patch.starts_with(vcdiff::MAGIC) {
+            Some(Self::Vcdiff)
+        } else {
+            None
+        }
+    }
+}
+
+/// パッチを source に適用した結果を返す
+/// verify = false なら元ファイルと結果のサイズ・チェックサムを確かめない
+/// （パッチ自体の CRC-32 は常に確かめる）
+pub fn apply(
+    patch: &[u8],
+    source: &[u8],
+    format: PatchFormat,
+    verify: bool,
+) -> Result<Vec<u8>, PatchError> {
+    match format {
+        PatchFormat::Ips => ips::apply(patch, source),
+        PatchFormat::Bps => bps::apply(patch, source, verify),
+        PatchFormat::Vcdiff => vcdiff::apply(patch, source, verify),
+    }
+}
+
+/// source を target にするパッチを作る（VCDIFF の作成には対応しない）
+pub fn create(source: &[u8], target: &[u8], format: PatchFormat) -> Result<Vec<u8>, PatchError> {
+    match format {
+        PatchFormat::Ips => ips::create(source, target),
+        PatchFormat::Bps => Ok(bps::create(source, target)),
+        PatchFormat::Vcdiff => Err(PatchError::Unsupported("creating VCDIFF patches")),
+    }
+}
+
+/// target のうち source と違うバイトの範囲（source より後ろは全部違うとみなす）
+/// 間の一致が gap バイト未満なら1つの範囲にまとめる
+pub fn changed_ranges(source: &[u8], target: &[u8], gap: usize) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut i = 0;
+    while i < target.len() {
+        if source.get(i) == Some(&target[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < target.len() && source.get(i) != Some(&target[i]) {
+            i += 1;
+        }
+        match ranges.last_mut() {
+            Some(last) if start - last.end < gap => last.end = i,
+            _ => ranges.push(start..i),
+        }
+    }
+    ranges
+}
+
+/// パッチを読み進めるカーソル
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], PatchError> {
+        let end = self.pos.checked_add(len).ok_or(PatchError::Truncated)?;
+        let bytes = self.data.get(self.pos..end).ok_or(PatchError::Truncated)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, PatchError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// ビッグエンディアンの整数（len バイト）
+    fn uint_be(&mut self, len: usize) -> Result<u64, PatchError> {
+        Ok(self
+            .bytes(len)?
+            .iter()
+            .fold(0, |acc, &b| (acc << 8) | u64::from(b)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(PatchFormat::detect(b"PATCHEOF"), Some(PatchFormat::Ips));
+        assert_eq!(PatchFormat::detect(b"BPS1"), Some(PatchFormat::Bps));
+        assert_eq!(
+            PatchFormat::detect(b"\xD6\xC3\xC4\x00\x00"),
+            Some(PatchFormat::Vcdiff)
+        );
+        assert_eq!(PatchFormat::detect(b"MZ"), None);
+        assert_eq!(PatchFormat::from_name("xdelta"), Some(PatchFormat::Vcdiff));
+    }
+
+    #[test]
+    fn test_changed_ranges() {
+        let source = b"abcdefgh";
+        assert_eq!(
+            changed_ranges(source, b"aXcdeYgZ", 1),
+            vec![1..2, 5..6, 7..8]
+        );
+        assert_eq!(changed_ranges(source, b"aXcdeYgZ", 2), vec![1..2, 5..8]);
+        assert_eq!(changed_ranges(source, b"abcdefghij", 1), vec![8..10]);
+        assert!(changed_ranges(source, b"abc", 1).is_empty());
+    }
+}
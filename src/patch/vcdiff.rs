@@ -0,0 +1,318 @@
+//! VCDIFF (RFC 3284): xdelta3 や open-vcdiff が出力する差分形式
+//!
+//! 既定のコード表とアドレスキャッシュ（near 4 / same 3）だけに対応する。二次圧縮や
+//! 独自のコード表を使うパッチは Unsupported を返す。
+
+use super::{PatchError, Reader};
+use crate::checksum::adler32;
+
+pub(super) const MAGIC: &[u8] = b"\xD6\xC3\xC4\x00";
+
+const HDR_DECOMPRESS: u8 = 0x01;
+const HDR_CODETABLE: u8 = 0x02;
+/// xdelta3 の拡張: アプリケーション固有のヘッダー
+const HDR_APPHEADER: u8 = 0x04;
+
+const WIN_SOURCE: u8 = 0x01;
+const WIN_TARGET: u8 = 0x02;
+/// xdelta3 の拡張: ターゲットウィンドウの Adler-32
+const WIN_ADLER32: u8 = 0x04;
+
+const NEAR_SIZE: usize = 4;
+const SAME_SIZE: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Inst {
+    Noop,
+    Add,
+    Run,
+    Copy,
+}
+
+/// コード表の1命令（size 0 は命令セクションから長さを読む）
+#[derive(Debug, Clone, Copy)]
+struct Half {
+    inst: Inst,
+    size: u8,
+    mode: u8,
+}
+
+const NOOP: Half = Half {
+    inst: Inst::Noop,
+    size: 0,
+    mode: 0,
+};
+
+/// RFC 3284 5.6 の既定のコード表
+fn default_code_table() -> Vec<[Half; 2]> {
+    let add = |size| Half {
+        inst: Inst::Add,
+        size,
+        mode: 0,
+    };
+    let copy = |size, mode| Half {
+        inst: Inst::Copy,
+        size,
+        mode,
+    };
+    let mut table = vec![[
+        Half {
+            inst: Inst::Run,
+            size: 0,
+            mode: 0,
+        },
+        NOOP,
+    ]];
+    table.extend((0..=17).map(|size| [add(size), NOOP]));
+    for mode in 0..9 {
+        table.push([copy(0, mode), NOOP]);
+        table.extend((4..=18).map(|size| [copy(size, mode), NOOP]));
+    }
+    for mode in 0..6 {
+        for add_size in 1..=4 {
+            table.extend((4..=6).map(|size| [add(add_size), copy(size, mode)]));
+        }
+    }
+    for mode in 6..9 {
+        table.extend((1..=4).map(|add_size| [add(add_size), copy(4, mode)]));
+    }
+    table.extend((0..9).map(|mode| [copy(4, mode), add(1)]));
+    table
+}
+
+impl Reader<'_> {
+    /// VCDIFF の整数（ビッグエンディアンの 7 ビットずつ、最上位ビットが継続）
+    fn vcdiff_number(&mut self) -> Result<u64, PatchError> {
+        let mut value: u64 = 0;
+        loop {
+            let byte = self.byte()?;
+            if value >> 57 != 0 {
+                return Err(PatchError::Invalid("number too large"));
+            }
+            value = (value << 7) | u64::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn vcdiff_size(&mut self) -> Result<usize, PatchError> {
+        usize::try_from(self.vcdiff_number()?).map_err(|_| PatchError::Invalid("size"))
+    }
+}
+
+/// COPY のアドレスキャッシュ（ウィンドウごとに初期化）
+struct AddressCache {
+    near: [usize; NEAR_SIZE],
+    next_slot: usize,
+    same: [usize; SAME_SIZE * 256],
+}
+
+impl AddressCache {
+    fn new() -> Self {
+        Self {
+            near: [0; NEAR_SIZE],
+            next_slot: 0,
+            same: [0; SAME_SIZE * 256],
+        }
+    }
+
+    /// here は元セグメントとターゲットを連結したアドレス空間での現在位置
+    fn decode(&mut self, addrs: &mut Reader, here: usize, mode: u8) -> Result<usize, PatchError> {
+        let mode = usize::from(mode);
+        let addr = match mode {
+            0 => addrs.vcdiff_size()?,
+            1 => here
+                .checked_sub(addrs.vcdiff_size()?)
+                .ok_or(PatchError::Invalid("copy address"))?,
+            m if m < 2 + NEAR_SIZE => self.near[m - 2]
+                .checked_add(addrs.vcdiff_size()?)
+                .ok_or(PatchError::Invalid("copy address"))?,
+            m => self.same[(m - 2 - NEAR_SIZE) * 256 + usize::from(addrs.byte()?)],
+        };
+        if addr >= here {
+            return Err(PatchError::Invalid("copy address"));
+        }
+        self.near[self.next_slot] = addr;
+        self.next_slot = (self.next_slot + 1) % NEAR_SIZE;
+        self.same[addr % (SAME_SIZE * 256)] = addr;
+        Ok(addr)
+    }
+}
+
+pub(super) fn apply(patch: &[u8], source: &[u8], verify: bool) -> Result<Vec<u8>, PatchError> {
+    let mut reader = Reader::new(patch);
+    if reader.bytes(MAGIC.len())? != MAGIC {
+        return Err(PatchError::Invalid("missing VCDIFF header"));
+    }
+    let indicator = reader.byte()?;
+    if indicator & HDR_DECOMPRESS != 0 {
+        return Err(PatchError::Unsupported("secondary compression"));
+    }
+    if indicator & HDR_CODETABLE != 0 {
+        return Err(PatchError::Unsupported("custom code table"));
+    }
+    if indicator & HDR_APPHEADER != 0 {
+        let len = reader.vcdiff_size()?;
+        reader.bytes(len)?;
+    }
+
+    let table = default_code_table();
+    let mut output = Vec::new();
+    while !reader.is_empty() {
+        let window = reader.byte()?;
+        let segment: &[u8] = if window & (WIN_SOURCE | WIN_TARGET) != 0 {
+            let len = reader.vcdiff_size()?;
+            let pos = reader.vcdiff_size()?;
+            let base: &[u8] = if window & WIN_SOURCE != 0 {
+                source
+            } else {
+                &output
+            };
+            let segment = pos.checked_add(len).and_then(|end| base.get(pos..end));
+            match segment {
+                Some(segment) => segment,
+                None if window & WIN_SOURCE != 0 => {
+                    return Err(PatchError::SourceSize {
+                        expected: (pos + len) as u64,
+                        actual: source.len() as u64,
+                    });
+                }
+                None => return Err(PatchError::Invalid("target segment")),
+            }
+        } else {
+            &[]
+        };
+        // 前のウィンドウの出力を参照することがあるので、ここで複製する
+        let segment = segment.to_vec();
+
+        let _delta_len = reader.vcdiff_number()?;
+        let target_len = reader.vcdiff_size()?;
+        if reader.byte()? != 0 {
+            return Err(PatchError::Unsupported("compressed delta sections"));
+        }
+        let data_len = reader.vcdiff_size()?;
+        let inst_len = reader.vcdiff_size()?;
+        let addr_len = reader.vcdiff_size()?;
+        let checksum = if window & WIN_ADLER32 != 0 {
+            Some(reader.uint_be(4)? as u32)
+        } else {
+            None
+        };
+        let mut data = Reader::new(reader.bytes(data_len)?);
+        let mut insts = Reader::new(reader.bytes(inst_len)?);
+        let mut addrs = Reader::new(reader.bytes(addr_len)?);
+
+        let mut target: Vec<u8> = Vec::with_capacity(target_len.min(1 << 30));
+        let mut cache = AddressCache::new();
+        while !insts.is_empty() {
+            for half in table[usize::from(insts.byte()?)] {
+                if half.inst == Inst::Noop {
+                    continue;
+                }
+                let size = match half.size {
+                    0 => insts.vcdiff_size()?,
+                    size => usize::from(size),
+                };
+                if target.len() + size > target_len {
+                    return Err(PatchError::Invalid("window exceeds the target size"));
+                }
+                match half.inst {
+                    Inst::Add => target.extend_from_slice(data.bytes(size)?),
+                    Inst::Run => {
+                        let byte = data.byte()?;
+                        target.resize(target.len() + size, byte);
+                    }
+                    _ => {
+                        let here = segment.len() + target.len();
+                        let addr = cache.decode(&mut addrs, here, half.mode)?;
+                        // 元セグメントからターゲットにまたがることも、重なることもある
+                        for pos in addr..addr + size {
+                            let byte = match segment.get(pos) {
+                                Some(&byte) => byte,
+                                None => target[pos - segment.len()],
+                            };
+                            target.push(byte);
+                        }
+                    }
+                }
+            }
+        }
+        if target.len() != target_len {
+            return Err(PatchError::Invalid(
+                "window is shorter than the target size",
+            ));
+        }
+        if verify && let Some(expected) = checksum {
+            let actual = adler32(&target);
+            if actual != expected {
+                return Err(PatchError::TargetChecksum { expected, actual });
+            }
+        }
+        output.extend_from_slice(&target);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_table() {
+        let table = default_code_table();
+        assert_eq!(table.len(), 256);
+        assert_eq!(table[19][0].inst, Inst::Copy);
+        assert_eq!((table[163][0].size, table[163][1].size), (1, 4));
+        assert_eq!((table[255][0].mode, table[255][1].inst), (8, Inst::Add));
+    }
+
+    #[test]
+    fn test_apply() {
+        let source = b"Hello, world";
+        let target = b"Hello, VCDIFF world world!";
+        // COPY 7 @0 (self) / ADD "VCDIFF " / COPY 5 @7 (self) / COPY 6 @25 (here 31 - 6) / ADD "!"
+        let data = b"VCDIFF !";
+        let insts = [
+            19, 7, // COPY size 7 mode 0
+            8, // ADD size 7
+            19, 5, // COPY size 5 mode 0
+            35, 6, // COPY size 6 mode 1
+            2, // ADD size 1
+        ];
+        let addrs = [0, 7, 6];
+        let mut window = vec![WIN_SOURCE | WIN_ADLER32, source.len() as u8, 0];
+        let mut delta = vec![
+            target.len() as u8,
+            0,
+            data.len() as u8,
+            insts.len() as u8,
+            3,
+        ];
+        delta.extend_from_slice(&adler32(target).to_be_bytes());
+        delta.extend_from_slice(data);
+        delta.extend_from_slice(&insts);
+        delta.extend_from_slice(&addrs);
+        window.push(delta.len() as u8);
+        window.extend_from_slice(&delta);
+        let mut patch = MAGIC.to_vec();
+        patch.push(0);
+        patch.extend_from_slice(&window);
+
+        assert_eq!(apply(&patch, source, true).unwrap(), target);
+        assert!(matches!(
+            apply(&patch, b"Hello, World", true),
+            Err(PatchError::TargetChecksum { .. })
+        ));
+        assert_eq!(
+            apply(&patch, b"Hello, World", false).unwrap(),
+            b"Hello, VCDIFF World World!"
+        );
+
+        patch[4] = HDR_DECOMPRESS;
+        assert!(matches!(
+            apply(&patch, source, true),
+            Err(PatchError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,407 @@
+//! バイト列のパターン検索（hx と bx で共通）
+//!
+//! memchr の memmem（SIMD を使う Two-Way）で探すので、GB 単位のファイルでも速い。
+//! 時間のかかる検索は SearchJob で別スレッドに任せ、進捗を見ながら中断できる。
+
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use memchr::memmem;
+
+use crate::buffer::{Document, Snapshot};
+
+mod multi;
+pub use multi::{MaskedPattern, MultiFinder, MultiMatch, PatternError};
+
+/// 一度に調べるバイト数（この単位で進捗を報告し、中断を確認する）
+const CHUNK: usize = 16 * 1024 * 1024;
+
+/// start 以降で最初に見つかった位置
+pub fn find(data: &[u8], pattern: &[u8], start: usize) -> Option<usize> {
+    if pattern.is_empty() || start + pattern.len() > data.len() {
+        return None;
+    }
+    memmem::find(&data[start..], pattern).map(|pos| pos + start)
+}
+
+/// data[..end] の中に収まる最後の位置
+pub fn rfind(data: &[u8], pattern: &[u8], end: usize) -> Option<usize> {
+    let end = end.min(data.len());
+    if pattern.is_empty() || end < pattern.len() {
+        return None;
+    }
+    memmem::rfind(&data[..end], pattern)
+}
+
+/// すべての位置（重なり合う一致も含む）
+pub fn find_all(data: &[u8], pattern: &[u8]) -> Vec<usize> {
+    let mut results = Vec::new();
+    if pattern.is_empty() {
+        return results;
+    }
+    let finder = memmem::Finder::new(pattern);
+    let mut start = 0;
+    while let Some(pos) = data.get(start..).and_then(|rest| finder.find(rest)) {
+        results.push(start + pos);
+        start += pos + 1;
+    }
+    results
+}
+
+/// 検索対象のバイト列（連続していなくてもよく、CHUNK ずつ取り出して調べる）
+pub trait Haystack {
+    /// 全体の長さ
+    fn len(&self) -> usize;
+
+    /// 範囲 [range.start, range.end) のバイト列（len() 以内の範囲だけ渡す）
+    fn window(&self, range: Range<usize>) -> Cow<'_, [u8]>;
+
+    /// 空かどうか
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Haystack for [u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    fn window(&self, range: Range<usize>) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self[range])
+    }
+}
+
+impl Haystack for Vec<u8> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn window(&self, range: Range<usize>) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self[range])
+    }
+}
+
+impl Haystack for Document {
+    fn len(&self) -> usize {
+        Document::len(self)
+    }
+
+    fn window(&self, range: Range<usize>) -> Cow<'_, [u8]> {
+        self.get_range(range.start, range.end).unwrap_or_default()
+    }
+}
+
+impl Haystack for Snapshot {
+    fn len(&self) -> usize {
+        Snapshot::len(self)
+    }
+
+    fn window(&self, range: Range<usize>) -> Cow<'_, [u8]> {
+        self.get_range(range.start, range.end).unwrap_or_default()
+    }
+}
+
+/// 検索の向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+/// 範囲内を折り返して探す検索
+#[derive(Debug, Clone)]
+pub struct Query {
+    /// 探すバイト列
+    pub pattern: Vec<u8>,
+    /// 検索する範囲
+    pub range: Range<usize>,
+    /// 前方検索はここ以降に始まる一致、後方検索はここまでに終わる一致から探す
+    pub from: usize,
+    /// 検索の向き
+    pub direction: Direction,
+}
+
+/// 検索結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// 見つかった位置
+    Found(usize),
+    /// 範囲の端で折り返して見つかった位置
+    Wrapped(usize),
+    NotFound,
+}
+
+/// 検索を実行する
+/// 調べる範囲だけを CHUNK ずつ読むので、data 全体を連結しなくてよい
+/// progress には調べたバイト数を渡し、false が返ったら中断して None を返す
+pub fn run<H: Haystack + ?Sized>(
+    data: &H,
+    query: &Query,
+    mut progress: impl FnMut(usize) -> bool,
+) -> Option<Outcome> {
+    let len = query.pattern.len();
+    let last = query.range.end.min(data.len());
+    let first = query.range.start.min(last);
+    let from = query.from.clamp(first, last);
+    if len == 0 {
+        return Some(Outcome::NotFound);
+    }
+    let result = match query.direction {
+        Direction::Forward => {
+            let finder = memmem::Finder::new(&query.pattern);
+            match scan_forward(data, &finder, from..last, &mut progress)? {
+                Some(pos) => Outcome::Found(pos),
+                // 折り返して from より前に始まる一致を探す
+                None => {
+                    let end = (from + len - 1).min(last);
+                    match scan_forward(data, &finder, first..end, &mut progress)? {
+                        Some(pos) => Outcome::Wrapped(pos),
+                        None => Outcome::NotFound,
+                    }
+                }
+            }
+        }
+        Direction::Backward => {
+            let finder = memmem::FinderRev::new(&query.pattern);
+            match scan_backward(data, &finder, first..from, &mut progress)? {
+                Some(pos) => Outcome::Found(pos),
+                // 折り返して from より後ろで終わる一致を探す
+                None => {
+                    let start = (from + 1).saturating_sub(len).max(first);
+                    match scan_backward(data, &finder, start..last, &mut progress)? {
+                        Some(pos) => Outcome::Wrapped(pos),
+                        None => Outcome::NotFound,
+                    }
+                }
+            }
+        }
+    };
+    Some(result)
+}
+
+/// data[range] を先頭から CHUNK ずつ調べる
+fn scan_forward<H: Haystack + ?Sized>(
+    data: &H,
+    finder: &memmem::Finder,
+    range: Range<usize>,
+    progress: &mut impl FnMut(usize) -> bool,
+) -> Option<Option<usize>> {
+    let len = finder.needle().len();
+    let mut start = range.start;
+    while start + len <= range.end {
+        // 境界をまたぐ一致も見つかるようにパターン長 - 1 だけ重ねる
+        let end = (start + CHUNK + len - 1).min(range.end);
+        if let Some(pos) = finder.find(&data.window(start..end)) {
+            return Some(Some(start + pos));
+        }
+        if !progress(end - start) {
+            return None;
+        }
+        start += CHUNK;
+    }
+    Some(None)
+}
+
+/// data[range] を末尾から CHUNK ずつ調べる
+fn scan_backward<H: Haystack + ?Sized>(
+    data: &H,
+    finder: &memmem::FinderRev,
+    range: Range<usize>,
+    progress: &mut impl FnMut(usize) -> bool,
+) -> Option<Option<usize>> {
+    let len = finder.needle().len();
+    let mut end = range.end;
+    while end >= range.start + len {
+        let start = end.saturating_sub(CHUNK + len - 1).max(range.start);
+        if let Some(pos) = finder.rfind(data.window(start..end)) {
+            return Some(Some(start + pos));
+        }
+        if !progress(end - start) {
+            return None;
+        }
+        if start == range.start {
+            break;
+        }
+        end -= CHUNK;
+    }
+    Some(None)
+}
+
+/// 別スレッドの検索からの通知
+enum Message {
+    Progress(usize),
+    Done(Outcome),
+}
+
+/// 別スレッドで実行中の検索（破棄すると中断する）
+pub struct SearchJob {
+    receiver: Receiver<Message>,
+    cancel: Arc<AtomicBool>,
+    scanned: usize,
+    total: usize,
+}
+
+impl SearchJob {
+    /// data を別スレッドで検索する
+    pub fn spawn<D: Haystack + Send + 'static>(data: D, query: Query) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let total = query.range.len().max(1);
+        let cancelled = cancel.clone();
+        thread::spawn(move || {
+            let progress = |scanned| {
+                !cancelled.load(Ordering::Relaxed)
+                    && sender.send(Message::Progress(scanned)).is_ok()
+            };
+            if let Some(outcome) = run(&data, &query, progress) {
+                let _ = sender.send(Message::Done(outcome));
+            }
+        });
+        Self {
+            receiver,
+            cancel,
+            scanned: 0,
+            total,
+        }
+    }
+
+    /// 届いた進捗を反映し、終わっていれば結果を返す
+    pub fn poll(&mut self) -> Option<Outcome> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(Message::Progress(scanned)) => self.scanned += scanned,
+                Ok(Message::Done(outcome)) => return Some(outcome),
+                Err(TryRecvError::Empty) => return None,
+                // スレッドが結果を返さずに終わった
+                Err(TryRecvError::Disconnected) => return Some(Outcome::NotFound),
+            }
+        }
+    }
+
+    /// 進捗（%）
+    pub fn percent(&self) -> usize {
+        (self.scanned * 100 / self.total).min(100)
+    }
+}
+
+impl Drop for SearchJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_forward_backward_and_overlapping() {
+        let data = b"\x00AAA\x00AA";
+        assert_eq!(find(data, b"AA", 0), Some(1));
+        assert_eq!(find(data, b"AA", 3), Some(5));
+        assert_eq!(find(data, b"AA", 6), None);
+        assert_eq!(rfind(data, b"AA", data.len()), Some(5));
+        assert_eq!(rfind(data, b"AA", 6), Some(2));
+        assert_eq!(rfind(data, b"AA", 1), None);
+        assert_eq!(find_all(data, b"AA"), vec![1, 2, 5]);
+        assert!(find_all(data, b"").is_empty());
+    }
+
+    #[test]
+    fn test_run_wraps_within_range_and_across_chunks() {
+        let query = |from, direction| Query {
+            pattern: b"AB".to_vec(),
+            range: 1..9,
+            from,
+            direction,
+        };
+        let data: &[u8] = b"ABxABxxABxAB";
+        assert_eq!(
+            run(data, &query(4, Direction::Forward), |_| true),
+            Some(Outcome::Found(7))
+        );
+        assert_eq!(
+            run(data, &query(8, Direction::Forward), |_| true),
+            Some(Outcome::Wrapped(3))
+        );
+        assert_eq!(
+            run(data, &query(7, Direction::Backward), |_| true),
+            Some(Outcome::Found(3))
+        );
+        assert_eq!(
+            run(data, &query(4, Direction::Backward), |_| true),
+            Some(Outcome::Wrapped(7))
+        );
+        assert_eq!(
+            run(data, &query(4, Direction::Forward), |_| false),
+            Some(Outcome::Found(7))
+        );
+
+        // チャンクの境界をまたぐ一致も見つけ、見つからなければ中断できる
+        let mut data = vec![0u8; CHUNK + 8];
+        data[CHUNK - 1..CHUNK + 1].copy_from_slice(b"AB");
+        let mut query = query(0, Direction::Forward);
+        query.range = 0..data.len();
+        assert_eq!(
+            run(&data, &query, |_| true),
+            Some(Outcome::Found(CHUNK - 1))
+        );
+        query.direction = Direction::Backward;
+        query.from = data.len();
+        assert_eq!(
+            run(&data, &query, |_| true),
+            Some(Outcome::Found(CHUNK - 1))
+        );
+        query.pattern = b"XY".to_vec();
+        assert_eq!(run(&data, &query, |_| false), None);
+    }
+
+    #[test]
+    fn test_job_reports_result_from_thread() {
+        let query = Query {
+            pattern: b"AB".to_vec(),
+            range: 0..6,
+            from: 0,
+            direction: Direction::Forward,
+        };
+        let mut job = SearchJob::spawn(b"xxxxAB".to_vec(), query);
+        let outcome = loop {
+            if let Some(outcome) = job.poll() {
+                break outcome;
+            }
+            thread::yield_now();
+        };
+        assert_eq!(outcome, Outcome::Found(4));
+    }
+
+    #[test]
+    fn test_run_reads_pieces_without_flattening() {
+        let mut doc = Document::from_bytes(b"xxABxx".to_vec());
+        doc.insert(3, b'C').unwrap();
+        doc.insert(0, b'A').unwrap();
+        let query = |pattern: &[u8], direction| Query {
+            pattern: pattern.to_vec(),
+            range: 0..doc.len(),
+            from: 0,
+            direction,
+        };
+        let snapshot = doc.snapshot();
+        assert_eq!(
+            run(&doc, &query(b"xxAC", Direction::Forward), |_| true),
+            Some(Outcome::Found(1))
+        );
+        assert_eq!(
+            run(&snapshot, &query(b"CB", Direction::Backward), |_| true),
+            Some(Outcome::Wrapped(4))
+        );
+        assert_eq!(
+            run(&snapshot, &query(b"AB", Direction::Forward), |_| true),
+            Some(Outcome::NotFound)
+        );
+    }
+}
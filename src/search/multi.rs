@@ -0,0 +1,307 @@
+//! ワイルドカード付きの HEX パターンを複数まとめて探す
+//!
+//! パターンは "DE AD ?? EF" のように書き、`??` は任意のバイト、`4?` や `?F` は片方の
+//! ニブルだけを決める。各パターンのワイルドカードを含まない最長の部分（アンカー）を
+//! Aho-Corasick のオートマトンでまとめて探し、見つかった位置でパターン全体を確かめる。
+//! 入力を1回なめるだけで、パターンの数に関係なく探せる。
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PatternError {
+    #[error("Invalid hex pattern: {0}")]
+    Invalid(String),
+    #[error("Hex pattern must have an even number of digits: {0}")]
+    OddLength(String),
+    #[error("Empty pattern")]
+    Empty,
+}
+
+/// ワイルドカード付きのバイト列（mask のビットが立っている所だけを比べる）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskedPattern {
+    pub bytes: Vec<u8>,
+    pub mask: Vec<u8>,
+}
+
+impl MaskedPattern {
+    /// HEX 文字列から（空白・`,` `:` `-` の区切りと 0x 接頭辞は無視、`?` はワイルドカード）
+    pub fn parse(text: &str) -> Result<Self, PatternError> {
+        let mut digits = Vec::new();
+        for word in text.split(|c: char| c.is_whitespace() || matches!(c, ',' | ':' | '-')) {
+            let word = word
+                .strip_prefix("0x")
+                .or_else(|| word.strip_prefix("0X"))
+                .unwrap_or(word);
+            for c in word.chars() {
+                match c {
+                    '?' => digits.push(None),
+                    c => match c.to_digit(16) {
+                        Some(d) => digits.push(Some(d as u8)),
+                        None => return Err(PatternError::Invalid(text.to_string())),
+                    },
+                }
+            }
+        }
+        if digits.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        if !digits.len().is_multiple_of(2) {
+            return Err(PatternError::OddLength(text.to_string()));
+        }
+
+        let mut bytes = Vec::with_capacity(digits.len() / 2);
+        let mut mask = Vec::with_capacity(digits.len() / 2);
+        for pair in digits.chunks_exact(2) {
+            let (high, high_mask) = pair[0].map_or((0, 0), |d| (d << 4, 0xF0));
+            let (low, low_mask) = pair[1].map_or((0, 0), |d| (d, 0x0F));
+            bytes.push(high | low);
+            mask.push(high_mask | low_mask);
+        }
+        Ok(Self { bytes, mask })
+    }
+
+    /// 完全に決まったバイト列から
+    pub fn exact(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            mask: vec![0xFF; bytes.len()],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// ワイルドカードを含まないか
+    pub fn is_exact(&self) -> bool {
+        self.mask.iter().all(|&m| m == 0xFF)
+    }
+
+    /// data が先頭からこのパターンに一致するか
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.len() >= self.len()
+            && data
+                .iter()
+                .zip(&self.bytes)
+                .zip(&self.mask)
+                .all(|((&d, &b), &m)| d & m == b)
+    }
+
+    /// ワイルドカードを含まない最長の範囲 (開始位置, 長さ)
+    fn anchor(&self) -> (usize, usize) {
+        let mut best = (0, 0);
+        let mut start = 0;
+        for (i, &m) in self.mask.iter().chain([&0]).enumerate() {
+            if m != 0xFF {
+                if i - start > best.1 {
+                    best = (start, i - start);
+                }
+                start = i + 1;
+            }
+        }
+        best
+    }
+}
+
+/// 一致（パターンの番号と開始位置）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultiMatch {
+    pub offset: usize,
+    pub pattern: usize,
+}
+
+/// オートマトンの状態（遷移は 256 通りすべてを持つ DFA にしてある）
+struct State {
+    next: [u32; 256],
+    /// この状態で終わるアンカー（失敗リンクの先の分も含む）のパターン番号
+    outputs: Vec<usize>,
+}
+
+/// 複数のパターンをまとめて探す
+pub struct MultiFinder {
+    patterns: Vec<MaskedPattern>,
+    /// 各パターンのアンカーの開始位置と長さ
+    anchors: Vec<(usize, usize)>,
+    states: Vec<State>,
+    /// アンカーを持たない（すべてワイルドカードを含む）パターン。全位置で確かめる
+    unanchored: Vec<usize>,
+}
+
+impl MultiFinder {
+    pub fn new(patterns: Vec<MaskedPattern>) -> Self {
+        let anchors: Vec<(usize, usize)> = patterns.iter().map(MaskedPattern::anchor).collect();
+        let mut states = vec![State {
+            next: [0; 256],
+            outputs: Vec::new(),
+        }];
+        // 遷移が 0 なら未定義（ルートへの遷移は後で失敗リンクから埋める）
+        let mut unanchored = Vec::new();
+        for (index, (pattern, &(start, len))) in patterns.iter().zip(&anchors).enumerate() {
+            if len == 0 {
+                unanchored.push(index);
+                continue;
+            }
+            let mut state = 0;
+            for &byte in &pattern.bytes[start..start + len] {
+                state = match states[state].next[byte as usize] {
+                    0 => {
+                        states.push(State {
+                            next: [0; 256],
+                            outputs: Vec::new(),
+                        });
+                        let new = states.len() - 1;
+                        states[state].next[byte as usize] = new as u32;
+                        new
+                    }
+                    next => next as usize,
+                };
+            }
+            states[state].outputs.push(index);
+        }
+
+        // 幅優先で失敗リンクを求め、未定義の遷移を失敗先の遷移で埋める
+        let mut fail = vec![0usize; states.len()];
+        let mut queue: std::collections::VecDeque<usize> = states[0]
+            .next
+            .iter()
+            .filter(|&&next| next != 0)
+            .map(|&next| next as usize)
+            .collect();
+        while let Some(state) = queue.pop_front() {
+            let inherited = states[fail[state]].outputs.clone();
+            states[state].outputs.extend(inherited);
+            for byte in 0..256 {
+                let next = states[state].next[byte] as usize;
+                let fallback = states[fail[state]].next[byte];
+                if next == 0 {
+                    states[state].next[byte] = fallback;
+                } else {
+                    fail[next] = fallback as usize;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        Self {
+            patterns,
+            anchors,
+            states,
+            unanchored,
+        }
+    }
+
+    /// すべての一致（重なり合う一致も含む）を開始位置、パターンの番号の順に
+    pub fn find_all(&self, data: &[u8]) -> Vec<MultiMatch> {
+        let mut results = Vec::new();
+        let mut check = |offset: usize, index: usize| {
+            if data
+                .get(offset..)
+                .is_some_and(|rest| self.patterns[index].matches(rest))
+            {
+                results.push(MultiMatch {
+                    offset,
+                    pattern: index,
+                });
+            }
+        };
+
+        let mut state = 0;
+        for (pos, &byte) in data.iter().enumerate() {
+            state = self.states[state].next[byte as usize] as usize;
+            for &index in &self.states[state].outputs {
+                let (start, len) = self.anchors[index];
+                // アンカーの末尾が pos にあるので、パターンの開始位置を逆算する
+                if let Some(offset) = (pos + 1).checked_sub(start + len) {
+                    check(offset, index);
+                }
+            }
+        }
+        for &index in &self.unanchored {
+            for offset in 0..data.len() {
+                check(offset, index);
+            }
+        }
+
+        results.sort_unstable_by_key(|m| (m.offset, m.pattern));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_masked_pattern() {
+        let pattern = MaskedPattern::parse("DE ?? 4? 0x?F").unwrap();
+        assert_eq!(pattern.bytes, [0xDE, 0x00, 0x40, 0x0F]);
+        assert_eq!(pattern.mask, [0xFF, 0x00, 0xF0, 0x0F]);
+        assert!(pattern.matches(&[0xDE, 0x12, 0x4A, 0x3F, 0x00]));
+        assert!(!pattern.matches(&[0xDE, 0x12, 0x5A, 0x3F]));
+        assert!(!pattern.matches(&[0xDE, 0x12, 0x4A]));
+        assert_eq!(pattern.anchor(), (0, 1));
+        assert_eq!(MaskedPattern::parse("??AABB??CC").unwrap().anchor(), (1, 2));
+
+        assert_eq!(
+            MaskedPattern::parse("ABC"),
+            Err(PatternError::OddLength("ABC".into()))
+        );
+        assert_eq!(
+            MaskedPattern::parse("GG"),
+            Err(PatternError::Invalid("GG".into()))
+        );
+        assert_eq!(MaskedPattern::parse(" "), Err(PatternError::Empty));
+    }
+
+    #[test]
+    fn test_multi_finder_reports_each_pattern() {
+        let finder = MultiFinder::new(vec![
+            MaskedPattern::parse("6162").unwrap(),
+            MaskedPattern::parse("62??64").unwrap(),
+            MaskedPattern::parse("????").unwrap(),
+            MaskedPattern::exact(b"bcd"),
+        ]);
+        let data = b"abcdab";
+        let found: Vec<(usize, usize)> = finder
+            .find_all(data)
+            .iter()
+            .map(|m| (m.offset, m.pattern))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (0, 0),
+                (0, 2),
+                (1, 1),
+                (1, 2),
+                (1, 3),
+                (2, 2),
+                (3, 2),
+                (4, 0),
+                (4, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multi_finder_shared_suffixes() {
+        // "he" / "she" / "hers" のように、失敗リンクを辿って見つかる一致
+        let finder = MultiFinder::new(
+            ["he", "she", "hers", "his"]
+                .iter()
+                .map(|p| MaskedPattern::exact(p.as_bytes()))
+                .collect(),
+        );
+        let found: Vec<(usize, usize)> = finder
+            .find_all(b"ushers")
+            .iter()
+            .map(|m| (m.offset, m.pattern))
+            .collect();
+        assert_eq!(found, vec![(1, 1), (2, 0), (2, 2)]);
+    }
+}
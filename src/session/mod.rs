@@ -0,0 +1,136 @@
+//! ファイルごとの状態の保存 (~/.local/share/hx/sessions)
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// 記録するファイル数の上限（古いものから捨てる）
+const MAX_FILES: usize = 100;
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("Session IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid session file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Failed to write session: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+/// 1ファイル分の状態
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileState {
+    /// ファイルパス（正規化済み）
+    pub path: PathBuf,
+    /// カーソル位置
+    pub cursor: usize,
+    /// 表示オフセット
+    pub offset: usize,
+    /// 1行あたりのバイト数
+    pub bytes_per_row: usize,
+    /// 文字エンコーディング名
+    pub encoding: String,
+    /// ブックマークしたオフセット
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bookmarks: Vec<usize>,
+}
+
+/// 全ファイルの状態（新しく開いた順）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sessions {
+    #[serde(rename = "file")]
+    files: Vec<FileState>,
+}
+
+impl Sessions {
+    /// データディレクトリ ($XDG_DATA_HOME/hx または ~/.local/share/hx)
+    pub fn dir() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+            return Some(PathBuf::from(dir).join("hx"));
+        }
+        std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".local").join("share").join("hx"))
+    }
+
+    /// セッションファイルのパス
+    pub fn path() -> Option<PathBuf> {
+        Self::dir().map(|dir| dir.join("sessions").join("files.toml"))
+    }
+
+    /// 読み込む（ファイルがなければ空）
+    pub fn load() -> Result<Self, SessionError> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Ok(toml::from_str(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 書き出す
+    pub fn save(&self) -> Result<(), SessionError> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// ファイルの状態を取得
+    pub fn get(&self, path: &Path) -> Option<&FileState> {
+        let path = normalize(path);
+        self.files.iter().find(|state| state.path == path)
+    }
+
+    /// ファイルの状態を記録（最近開いたファイルの先頭に移す）
+    pub fn update(&mut self, mut state: FileState) {
+        state.path = normalize(&state.path);
+        self.files.retain(|s| s.path != state.path);
+        self.files.insert(0, state);
+        self.files.truncate(MAX_FILES);
+    }
+
+    /// 最近開いたファイル（新しい順）
+    pub fn recent(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|state| state.path.as_path())
+    }
+}
+
+/// 記録用にパスを正規化（存在しなければそのまま）
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_and_roundtrip() {
+        let mut sessions = Sessions::default();
+        for name in ["/nonexistent/a", "/nonexistent/b", "/nonexistent/a"] {
+            sessions.update(FileState {
+                path: PathBuf::from(name),
+                cursor: 16,
+                bookmarks: vec![4, 32],
+                ..Default::default()
+            });
+        }
+        let recent: Vec<_> = sessions.recent().collect();
+        assert_eq!(recent, [Path::new("/nonexistent/a"), Path::new("/nonexistent/b")]);
+
+        let text = toml::to_string(&sessions).unwrap();
+        let loaded: Sessions = toml::from_str(&text).unwrap();
+        let state = loaded.get(Path::new("/nonexistent/b")).unwrap();
+        assert_eq!(state.cursor, 16);
+        assert_eq!(state.bookmarks, [4, 32]);
+    }
+}
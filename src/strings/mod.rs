@@ -0,0 +1,396 @@
+//! 文字列の抽出（ASCII / UTF-8 / UTF-16 の表示可能な文字の並びを探す）
+//!
+//! 巨大なファイルでも画面が固まらないよう、StringsJob で別スレッドに任せ、
+//! 見つかった分から順に受け取れる。
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crate::search::Haystack;
+
+/// 文字列とみなす最小の文字数
+pub const MIN_LENGTH: usize = 4;
+
+/// 別スレッドで一度に調べるバイト数（この単位で結果を送り、中断を確認する）
+const CHUNK: usize = 4 * 1024 * 1024;
+
+/// 一覧に表示する文字列の最大文字数（これより長い分は省略）
+const MAX_TEXT: usize = 256;
+
+/// 文字列の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringKind {
+    Ascii,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl StringKind {
+    /// 表示名
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ascii => "ascii",
+            Self::Utf8 => "utf8",
+            Self::Utf16Le => "u16le",
+            Self::Utf16Be => "u16be",
+        }
+    }
+}
+
+/// 見つかった文字列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundString {
+    /// 先頭のオフセット
+    pub offset: usize,
+    /// バイト数
+    pub len: usize,
+    pub kind: StringKind,
+    /// 内容（長いものは MAX_TEXT 文字まで）
+    pub text: String,
+}
+
+/// 途中まで見つかっている文字の並び
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    start: usize,
+    chars: usize,
+    multibyte: bool,
+}
+
+/// 文字列の抽出の進行状況
+#[derive(Debug)]
+pub struct Scanner {
+    min_len: usize,
+    /// 次に調べるオフセット（ASCII / UTF-8）
+    pos: usize,
+    /// ASCII / UTF-8 の並び
+    run: Option<Run>,
+    /// UTF-16 の並び [LE, BE][オフセットの偶奇]
+    utf16: [[Option<Run>; 2]; 2],
+    /// 最後に記録した UTF-16 文字列の末尾（LE と BE で同じ文字列を二重に拾わないため）
+    utf16_end: usize,
+    found: Vec<FoundString>,
+}
+
+impl Scanner {
+    pub fn new(min_len: usize) -> Self {
+        Self {
+            min_len: min_len.max(1),
+            pos: 0,
+            run: None,
+            utf16: [[None; 2]; 2],
+            utf16_end: 0,
+            found: Vec::new(),
+        }
+    }
+
+    /// 調べ終わったバイト数
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// 見つかった文字列（先頭のオフセット順）
+    pub fn found(&self) -> &[FoundString] {
+        &self.found
+    }
+
+    /// これまでに見つかった文字列を取り出す（取り出した分は found() から消える）
+    pub fn take_found(&mut self) -> Vec<FoundString> {
+        std::mem::take(&mut self.found)
+    }
+
+    /// 抽出を最大 budget バイト進める。最後まで調べたら true を返す
+    pub fn scan(&mut self, data: &[u8], budget: usize) -> bool {
+        let end = self.pos.saturating_add(budget).min(data.len());
+        while self.pos < end {
+            let i = self.pos;
+            // UTF-16 は各オフセットから始まる2バイトを調べる（偶奇で別の並び）
+            for big_endian in [false, true] {
+                self.step_utf16(data, i, big_endian);
+            }
+            let step = match utf8_char(data, i) {
+                Some((n, multibyte)) => {
+                    let run = self.run.get_or_insert(Run { start: i, chars: 0, multibyte: false });
+                    run.chars += 1;
+                    run.multibyte |= multibyte;
+                    n
+                }
+                None => {
+                    self.finish_run(data, i);
+                    1
+                }
+            };
+            // 複数バイトの文字の途中の UTF-16 も調べる
+            for j in i + 1..(i + step).min(data.len()) {
+                for big_endian in [false, true] {
+                    self.step_utf16(data, j, big_endian);
+                }
+            }
+            self.pos = i + step;
+        }
+        if self.pos < data.len() {
+            return false;
+        }
+        self.finish_run(data, data.len());
+        for big_endian in [false, true] {
+            for parity in 0..2 {
+                self.finish_utf16(data, big_endian, parity);
+            }
+        }
+        true
+    }
+
+    /// オフセット i から始まる UTF-16 の1文字を並びに加える（文字でなければ並びを終える）
+    fn step_utf16(&mut self, data: &[u8], i: usize, big_endian: bool) {
+        let parity = i % 2;
+        if i + 1 >= data.len() {
+            self.finish_utf16(data, big_endian, parity);
+            return;
+        }
+        let pair = [data[i], data[i + 1]];
+        let unit = if big_endian {
+            u16::from_be_bytes(pair)
+        } else {
+            u16::from_le_bytes(pair)
+        };
+        if is_printable_ascii(unit) {
+            let run = &mut self.utf16[big_endian as usize][parity];
+            run.get_or_insert(Run { start: i, chars: 0, multibyte: false }).chars += 1;
+        } else {
+            self.finish_utf16(data, big_endian, parity);
+        }
+    }
+
+    /// オフセット順に記録（並びは終わった順に届くので、末尾近くに挿入する）
+    fn push(&mut self, found: FoundString) {
+        let index = self.found.partition_point(|s| s.offset <= found.offset);
+        self.found.insert(index, found);
+    }
+
+    /// ASCII / UTF-8 の並びを end で終える（十分長ければ記録）
+    fn finish_run(&mut self, data: &[u8], end: usize) {
+        let Some(run) = self.run.take() else {
+            return;
+        };
+        if run.chars < self.min_len {
+            return;
+        }
+        let text = String::from_utf8_lossy(&data[run.start..end]);
+        self.push(FoundString {
+            offset: run.start,
+            len: end - run.start,
+            kind: if run.multibyte { StringKind::Utf8 } else { StringKind::Ascii },
+            text: text.chars().take(MAX_TEXT).collect(),
+        });
+    }
+
+    /// UTF-16 の並びを終える（十分長く、他方のエンディアンと重ならなければ記録）
+    fn finish_utf16(&mut self, data: &[u8], big_endian: bool, parity: usize) {
+        let Some(run) = self.utf16[big_endian as usize][parity].take() else {
+            return;
+        };
+        if run.chars < self.min_len || run.start < self.utf16_end {
+            return;
+        }
+        // 1バイト前から始まる他方のエンディアンの並びの方が正しい読み方
+        if self.utf16[!big_endian as usize][1 - parity].is_some_and(|other| other.start < run.start)
+        {
+            return;
+        }
+        let end = run.start + run.chars * 2;
+        let text = data[run.start..end]
+            .chunks_exact(2)
+            .take(MAX_TEXT)
+            .map(|pair| {
+                let unit = if big_endian { pair[1] } else { pair[0] };
+                unit as char
+            })
+            .collect();
+        self.utf16_end = end;
+        self.push(FoundString {
+            offset: run.start,
+            len: end - run.start,
+            kind: if big_endian { StringKind::Utf16Be } else { StringKind::Utf16Le },
+            text,
+        });
+    }
+}
+
+/// 別スレッドの抽出からの通知（見つかった文字列と、調べ終わったバイト数）
+struct Message {
+    found: Vec<FoundString>,
+    position: usize,
+    done: bool,
+}
+
+/// 別スレッドで実行中の文字列の抽出（破棄すると中断する）
+pub struct StringsJob {
+    receiver: Receiver<Message>,
+    cancel: Arc<AtomicBool>,
+    found: Vec<FoundString>,
+    position: usize,
+    total: usize,
+    done: bool,
+}
+
+impl StringsJob {
+    /// data から min_len 文字以上の文字列を別スレッドで抽出する
+    pub fn spawn<D: Haystack + Send + 'static>(data: D, min_len: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let total = data.len();
+        let cancelled = cancel.clone();
+        thread::spawn(move || {
+            // ピースの連結もこのスレッドで行う
+            let data = data.window(0..total);
+            let mut scanner = Scanner::new(min_len);
+            loop {
+                if cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+                let done = scanner.scan(&data, CHUNK);
+                let message = Message {
+                    found: scanner.take_found(),
+                    position: scanner.position(),
+                    done,
+                };
+                if sender.send(message).is_err() || done {
+                    return;
+                }
+            }
+        });
+        Self {
+            receiver,
+            cancel,
+            found: Vec::new(),
+            position: 0,
+            total,
+            done: total == 0,
+        }
+    }
+
+    /// 届いた結果を反映する。新しい文字列が届いたら true を返す
+    pub fn poll(&mut self) -> bool {
+        let mut received = false;
+        while !self.done {
+            match self.receiver.try_recv() {
+                Ok(message) => {
+                    // チャンクの境目をまたぐ並びは後から届くので、オフセット順に挿入する
+                    for found in message.found {
+                        let index = self.found.partition_point(|s| s.offset <= found.offset);
+                        self.found.insert(index, found);
+                        received = true;
+                    }
+                    self.position = message.position;
+                    self.done = message.done;
+                }
+                Err(TryRecvError::Empty) => break,
+                // スレッドが最後まで調べずに終わった
+                Err(TryRecvError::Disconnected) => self.done = true,
+            }
+        }
+        received
+    }
+
+    /// 見つかった文字列（先頭のオフセット順）
+    pub fn found(&self) -> &[FoundString] {
+        &self.found
+    }
+
+    /// 最後まで調べ終わったか
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// 進捗（%）
+    pub fn percent(&self) -> usize {
+        (self.position as u128 * 100 / self.total.max(1) as u128).min(100) as usize
+    }
+}
+
+impl Drop for StringsJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// バイト列全体から文字列を抽出（先頭のオフセット順）
+pub fn extract(data: &[u8], min_len: usize) -> Vec<FoundString> {
+    let mut scanner = Scanner::new(min_len);
+    scanner.scan(data, usize::MAX);
+    scanner.found
+}
+
+/// 表示可能な ASCII 文字（タブを含む）か
+fn is_printable_ascii(unit: u16) -> bool {
+    matches!(unit, 0x20..=0x7E | 0x09)
+}
+
+/// オフセット i の表示可能な1文字のバイト数と、複数バイトの文字かどうか
+fn utf8_char(data: &[u8], i: usize) -> Option<(usize, bool)> {
+    let lead = data[i];
+    if is_printable_ascii(lead as u16) {
+        return Some((1, false));
+    }
+    let n = match lead {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return None,
+    };
+    let ch = std::str::from_utf8(data.get(i..i + n)?).ok()?.chars().next()?;
+    (!ch.is_control() && ch != '\u{FEFF}').then_some((n, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let mut data = vec![0u8, 1, 2];
+        data.extend_from_slice(b"hello\0ab\0");
+        data.extend_from_slice("日本語です".as_bytes());
+        data.push(0xFF);
+        data.extend_from_slice(&[b'w', 0, b'i', 0, b'd', 0, b'e', 0, 0xFF]);
+        data.extend_from_slice(&[0, b'b', 0, b'i', 0, b'g', 0, b'!']);
+
+        let found = extract(&data, 4);
+        let summary: Vec<_> = found.iter().map(|s| (s.offset, s.kind, s.text.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (3, StringKind::Ascii, "hello"),
+                (12, StringKind::Utf8, "日本語です"),
+                (28, StringKind::Utf16Le, "wide"),
+                (37, StringKind::Utf16Be, "big!"),
+            ]
+        );
+        assert_eq!(found[2].len, 8);
+    }
+
+    #[test]
+    fn test_scan_in_chunks() {
+        let data = b"\x01first string\x00\x02second\x03".repeat(100);
+        let mut scanner = Scanner::new(MIN_LENGTH);
+        while !scanner.scan(&data, 7) {}
+        assert_eq!(scanner.found().len(), 200);
+        assert_eq!(scanner.found()[1].offset, 15);
+        assert_eq!(scanner.found()[1].text, "second");
+    }
+
+    #[test]
+    fn test_job_reports_strings_from_thread() {
+        let data = b"\x01first string\x00\x02second\x03".repeat(100);
+        let mut job = StringsJob::spawn(data.clone(), MIN_LENGTH);
+        while !job.is_done() {
+            job.poll();
+            thread::yield_now();
+        }
+        assert_eq!(job.found(), extract(&data, MIN_LENGTH).as_slice());
+        assert_eq!(job.percent(), 100);
+    }
+}
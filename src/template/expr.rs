@@ -0,0 +1,279 @@
+//! テンプレートの式（count と if に書く、先に読んだフィールドを使った計算と比較）
+//!
+//! 整数（10進・0x の16進）、フィールド名（入れ子の構造体のフィールドは "header.version"）、
+//! 括弧と、C と同じ優先順位の演算子 `* / % + - << >> < <= > >= == != & ^ | && ||`、
+//! 単項の `- ! ~` を使える。比較と論理演算の結果は 1 か 0 で、0 以外は真。
+//! `&&` と `||` は右辺を評価しないことがある（まだ読んでいないフィールドを参照できる）。
+
+use super::TemplateError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i128),
+    Name(String),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+/// 2文字の演算子を先に試す
+const OPERATORS: [&str; 19] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "<", ">", "+", "-", "*", "/", "%", "&", "^",
+    "|", "!",
+];
+
+/// 式を評価する（lookup はフィールドの値を返す）
+pub(super) fn eval(
+    expr: &str,
+    lookup: impl Fn(&str) -> Option<i128>,
+) -> Result<i128, TemplateError> {
+    let error = |reason: &str| TemplateError::Expr(expr.to_string(), reason.to_string());
+    let tokens = tokenize(expr).ok_or_else(|| error("unexpected character"))?;
+    let mut parser = ExprParser {
+        expr,
+        tokens: &tokens,
+        pos: 0,
+        lookup: &lookup,
+    };
+    let value = parser.binary(1, true)?;
+    if parser.pos < tokens.len() {
+        return Err(error("unexpected token"));
+    }
+    value.ok_or_else(|| error("syntax error"))
+}
+
+/// 10進または 0x の16進の整数（テンプレートの enum の値にも使う）
+pub(super) fn parse_int(text: &str) -> Option<i128> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i128>().ok()?,
+    };
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(if negative { -value } else { value })
+}
+
+fn tokenize(expr: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = expr.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            tokens.push(Token::Number(parse_int(&rest[..len])?));
+            len
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..len].to_string()));
+            len
+        } else if c == '(' {
+            tokens.push(Token::Open);
+            1
+        } else if c == ')' {
+            tokens.push(Token::Close);
+            1
+        } else if c == '~' {
+            tokens.push(Token::Op("~"));
+            1
+        } else {
+            let op = OPERATORS.iter().find(|op| rest.starts_with(*op))?;
+            tokens.push(Token::Op(op));
+            op.len()
+        };
+        rest = rest[len..].trim_start();
+    }
+    Some(tokens)
+}
+
+/// 二項演算子の優先順位（大きいほど強く結び付く）
+fn precedence(op: &str) -> Option<u8> {
+    Some(match op {
+        "||" => 1,
+        "&&" => 2,
+        "|" => 3,
+        "^" => 4,
+        "&" => 5,
+        "==" | "!=" => 6,
+        "<" | "<=" | ">" | ">=" => 7,
+        "<<" | ">>" => 8,
+        "+" | "-" => 9,
+        "*" | "/" | "%" => 10,
+        _ => return None,
+    })
+}
+
+/// 構文を読みながら評価する（active でない部分はフィールドを参照せず 0 とする）
+struct ExprParser<'a, F> {
+    expr: &'a str,
+    tokens: &'a [Token],
+    pos: usize,
+    lookup: &'a F,
+}
+
+impl<F: Fn(&str) -> Option<i128>> ExprParser<'_, F> {
+    fn error(&self, reason: &str) -> TemplateError {
+        TemplateError::Expr(self.expr.to_string(), reason.to_string())
+    }
+
+    /// min 以上の優先順位の演算子をまとめて評価する（式が空なら None）
+    fn binary(&mut self, min: u8, active: bool) -> Result<Option<i128>, TemplateError> {
+        let Some(mut left) = self.unary(active)? else {
+            return Ok(None);
+        };
+        while let Some(Token::Op(op)) = self.tokens.get(self.pos) {
+            let Some(prec) = precedence(op).filter(|&prec| prec >= min) else {
+                break;
+            };
+            self.pos += 1;
+            // 左辺で結果が決まる論理演算は右辺を評価しない
+            let right_active = match *op {
+                "&&" => active && left != 0,
+                "||" => active && left == 0,
+                _ => active,
+            };
+            let right = self
+                .binary(prec + 1, right_active)?
+                .ok_or_else(|| self.error("missing operand"))?;
+            left = if active {
+                self.apply(op, left, right)?
+            } else {
+                0
+            };
+        }
+        Ok(Some(left))
+    }
+
+    fn unary(&mut self, active: bool) -> Result<Option<i128>, TemplateError> {
+        let Some(token) = self.tokens.get(self.pos) else {
+            return Ok(None);
+        };
+        self.pos += 1;
+        let value = match token {
+            Token::Number(n) => *n,
+            Token::Name(name) if active => {
+                (self.lookup)(name).ok_or_else(|| TemplateError::UnknownField(name.clone()))?
+            }
+            Token::Name(_) => 0,
+            Token::Op(op @ ("-" | "!" | "~")) => {
+                let value = self
+                    .unary(active)?
+                    .ok_or_else(|| self.error("missing operand"))?;
+                match *op {
+                    "-" => value.checked_neg().ok_or_else(|| self.error("overflow"))?,
+                    "!" => i128::from(value == 0),
+                    _ => !value,
+                }
+            }
+            Token::Open => {
+                let value = self
+                    .binary(1, active)?
+                    .ok_or_else(|| self.error("empty parentheses"))?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err(self.error("missing ')'"));
+                }
+                self.pos += 1;
+                value
+            }
+            Token::Op(_) | Token::Close => {
+                self.pos -= 1;
+                return Ok(None);
+            }
+        };
+        Ok(Some(value))
+    }
+
+    fn apply(&self, op: &str, left: i128, right: i128) -> Result<i128, TemplateError> {
+        let shift = || u32::try_from(right).ok().filter(|&s| s < 128);
+        let value = match op {
+            "||" => Some(i128::from(left != 0 || right != 0)),
+            "&&" => Some(i128::from(left != 0 && right != 0)),
+            "|" => Some(left | right),
+            "^" => Some(left ^ right),
+            "&" => Some(left & right),
+            "==" => Some(i128::from(left == right)),
+            "!=" => Some(i128::from(left != right)),
+            "<" => Some(i128::from(left < right)),
+            "<=" => Some(i128::from(left <= right)),
+            ">" => Some(i128::from(left > right)),
+            ">=" => Some(i128::from(left >= right)),
+            "<<" => shift().and_then(|s| left.checked_shl(s)),
+            ">>" => shift().and_then(|s| left.checked_shr(s)),
+            "+" => left.checked_add(right),
+            "-" => left.checked_sub(right),
+            "*" => left.checked_mul(right),
+            "/" if right == 0 => return Err(self.error("division by zero")),
+            "%" if right == 0 => return Err(self.error("division by zero")),
+            "/" => left.checked_div(right),
+            "%" => left.checked_rem(right),
+            _ => None,
+        };
+        value.ok_or_else(|| self.error("overflow"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_with(expr: &str) -> Result<i128, TemplateError> {
+        eval(expr, |name| match name {
+            "count" => Some(3),
+            "header.version" => Some(2),
+            "flags" => Some(0x14),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(eval_with("count").unwrap(), 3);
+        assert_eq!(eval_with("count * 2 + 1").unwrap(), 7);
+        assert_eq!(eval_with("(count + 1) * 2").unwrap(), 8);
+        assert_eq!(eval_with("0x10 - count - 1").unwrap(), 12);
+        assert_eq!(eval_with("header.version >= 2 && flags & 0x4").unwrap(), 1);
+        assert_eq!(eval_with("!(flags & 0x8) || missing").unwrap(), 1);
+        assert_eq!(eval_with("1 << 4 | 1 == 1").unwrap(), 17);
+        assert_eq!(eval_with("-count % 2").unwrap(), -1);
+        assert_eq!(eval_with("~0").unwrap(), -1);
+        // 評価しない右辺の未知のフィールドはエラーにならない
+        assert_eq!(eval_with("count == 0 && missing > 1").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_eval_errors() {
+        assert!(matches!(
+            eval_with("missing + 1"),
+            Err(TemplateError::UnknownField(_))
+        ));
+        for bad in [
+            "",
+            "count +",
+            "(count",
+            "count )",
+            "1 / 0",
+            "count $ 2",
+            "1 << 200",
+        ] {
+            assert!(
+                matches!(eval_with(bad), Err(TemplateError::Expr(..))),
+                "{}",
+                bad
+            );
+        }
+        assert_eq!(parse_int("0x1F"), Some(31));
+        assert_eq!(parse_int("-5"), Some(-5));
+        assert_eq!(parse_int("rgb"), None);
+    }
+}
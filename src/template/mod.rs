@@ -0,0 +1,571 @@
+//! バイナリテンプレート（TOML で書いた構造体の定義に従ってバイト列を解析する）
+//!
+//! ```toml
+//! root = "header"        # 解析を始める構造体（省略時は "main"）
+//! endian = "little"      # 既定のエンディアン（"little" / "big"）
+//!
+//! [enums.kind]          # 整数の値に付ける名前
+//! 1 = "data"
+//! 0x10 = "index"
+//!
+//! [structs.header]
+//! fields = [
+//!   { name = "magic", type = "char", count = 4 },
+//!   { name = "kind", type = "u8", enum = "kind" },
+//!   { name = "count", type = "u16" },
+//!   { name = "entries", type = "entry", count = "count" },
+//!   { name = "index", type = "u32", count = "count * 2", if = "kind == 0x10" },
+//! ]
+//! ```
+//!
+//! count と if には先に読んだ整数フィールドを使った式を書ける（書き方は expr.rs を参照）。
+
+mod expr;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::clipboard::{self, HexFormat};
+use crate::config::Config;
+use crate::inspector::{self, NumType};
+
+/// 構造体の入れ子の上限（再帰的な定義で止まらなくなるのを防ぐ）
+const MAX_DEPTH: usize = 64;
+
+/// 配列の要素数の上限（これを超える配列は木にしない）
+const MAX_ELEMENTS: u64 = 1 << 16;
+
+#[derive(Error, Debug)]
+pub enum TemplateError {
+    #[error("Template IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid template: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Unknown type: {0}")]
+    UnknownType(String),
+    #[error("Unknown field in expression: {0}")]
+    UnknownField(String),
+    #[error("Invalid expression '{0}': {1}")]
+    Expr(String, String),
+    #[error("Unknown enum: {0}")]
+    UnknownEnum(String),
+    #[error("Invalid value in enum {0}: {1}")]
+    InvalidEnum(String, String),
+    #[error("{0} at {1:08X} runs past the end of data")]
+    Eof(String, usize),
+    #[error("Too many elements in {0}: {1}")]
+    TooMany(String, u64),
+    #[error("Structs nested too deeply at {0}")]
+    TooDeep(String),
+}
+
+/// エンディアン
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+/// 配列の要素数（固定値、または先に読んだフィールドを使った式）
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Count {
+    Fixed(u64),
+    Field(String),
+}
+
+/// フィールドの定義
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldDef {
+    pub name: String,
+    /// 型（u8〜u64, i8〜i64, f32, f64, char, bytes, または構造体名）
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// 指定すると配列（char は文字列、bytes はバイト列）
+    #[serde(default)]
+    pub count: Option<Count>,
+    /// このフィールドだけエンディアンを変える
+    #[serde(default)]
+    pub endian: Option<Endian>,
+    /// 整数の値に enums の名前を付ける
+    #[serde(default, rename = "enum")]
+    pub enumeration: Option<String>,
+    /// 式が真（0 以外）のときだけ読む
+    #[serde(default, rename = "if")]
+    pub condition: Option<String>,
+}
+
+/// 構造体の定義
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StructDef {
+    pub fields: Vec<FieldDef>,
+}
+
+/// テンプレート
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    #[serde(default = "default_root")]
+    pub root: String,
+    #[serde(default)]
+    pub endian: Endian,
+    pub structs: BTreeMap<String, StructDef>,
+    /// 列挙型（値 → 名前、値は10進か 0x の16進）
+    #[serde(default)]
+    pub enums: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+fn default_root() -> String {
+    "main".to_string()
+}
+
+/// 解析したフィールド（木を行きがけ順に並べたもの）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// フィールド名（配列の要素は "[0]" など）
+    pub name: String,
+    /// 型の表示（"u32", "char[4]", "entry[3]" など）
+    pub ty: String,
+    pub offset: usize,
+    pub size: usize,
+    /// 値の表示（構造体と配列は空）
+    pub value: String,
+    /// 値に付いた enum の名前（なければ空）
+    pub label: String,
+    /// 木の深さ（ルートが 0）
+    pub depth: usize,
+    /// 子孫の数（このノードの後に続く、子孫のノードの数）
+    pub descendants: usize,
+}
+
+impl Template {
+    /// TOML文字列から読み込む
+    pub fn parse(text: &str) -> Result<Self, TemplateError> {
+        let template: Self = toml::from_str(text)?;
+        for (name, values) in &template.enums {
+            if let Some(value) = values.keys().find(|value| expr::parse_int(value).is_none()) {
+                return Err(TemplateError::InvalidEnum(name.clone(), value.clone()));
+            }
+        }
+        Ok(template)
+    }
+
+    /// ファイルから読み込む
+    pub fn load(path: &Path) -> Result<Self, TemplateError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// バイト列を解析してフィールドの一覧を作る（先頭はルートの構造体）
+    pub fn apply(&self, data: &[u8]) -> Result<Vec<Node>, TemplateError> {
+        let mut parser = Parser {
+            template: self,
+            data,
+            nodes: Vec::new(),
+            scopes: Vec::new(),
+        };
+        parser.parse_struct(&self.root, &self.root, 0, 0)?;
+        Ok(parser.nodes)
+    }
+}
+
+/// 設定ディレクトリの templates/名前.toml
+pub fn user_template_path(name: &str) -> Option<std::path::PathBuf> {
+    Config::dir().map(|dir| dir.join("templates").join(format!("{}.toml", name)))
+}
+
+/// 解析の途中の状態
+struct Parser<'a> {
+    template: &'a Template,
+    data: &'a [u8],
+    nodes: Vec<Node>,
+    /// 構造体ごとに読んだ整数フィールドの値（count と if から参照する）
+    scopes: Vec<HashMap<String, i128>>,
+}
+
+impl<'a> Parser<'a> {
+    /// 構造体を offset から解析し、そのバイト数を返す
+    fn parse_struct(
+        &mut self,
+        name: &str,
+        ty: &str,
+        offset: usize,
+        depth: usize,
+    ) -> Result<usize, TemplateError> {
+        let template = self.template;
+        let def = template
+            .structs
+            .get(ty)
+            .ok_or_else(|| TemplateError::UnknownType(ty.to_string()))?;
+        if depth > MAX_DEPTH {
+            return Err(TemplateError::TooDeep(name.to_string()));
+        }
+
+        let index = self.push(name, ty.to_string(), offset, depth);
+        self.scopes.push(HashMap::new());
+        let mut pos = offset;
+        for field in &def.fields {
+            pos += self.parse_field(field, pos, depth + 1)?;
+        }
+        // 外側からは "名前.フィールド" で参照できる（配列の要素は除く）
+        let scope = self.scopes.pop().unwrap_or_default();
+        if !name.starts_with('[')
+            && let Some(parent) = self.scopes.last_mut()
+        {
+            for (field, value) in scope {
+                parent.insert(format!("{}.{}", name, field), value);
+            }
+        }
+        self.finish(index, pos - offset);
+        Ok(pos - offset)
+    }
+
+    /// フィールドを offset から解析し、そのバイト数を返す
+    fn parse_field(
+        &mut self,
+        field: &FieldDef,
+        offset: usize,
+        depth: usize,
+    ) -> Result<usize, TemplateError> {
+        if let Some(ref condition) = field.condition
+            && self.eval(condition)? == 0
+        {
+            return Ok(0);
+        }
+        let big_endian = field.endian.unwrap_or(self.template.endian) == Endian::Big;
+        let labels = match field.enumeration {
+            Some(ref name) => Some(
+                self.template
+                    .enums
+                    .get(name)
+                    .ok_or_else(|| TemplateError::UnknownEnum(name.clone()))?,
+            ),
+            None => None,
+        };
+        let count = match field.count {
+            Some(ref count) => Some(self.resolve(count)?),
+            None => None,
+        };
+        let name = field.name.as_str();
+
+        match (field.ty.as_str(), count) {
+            // 文字列・バイト列は1つのノードにまとめる
+            ("char", count) => {
+                let len = count.unwrap_or(1);
+                let bytes = self.bytes(name, offset, len)?;
+                let ty = match count {
+                    Some(n) => format!("char[{}]", n),
+                    None => "char".to_string(),
+                };
+                let value = format!("\"{}\"", text(bytes));
+                self.leaf(name, ty, offset, bytes.len(), value, depth);
+                Ok(bytes.len())
+            }
+            ("bytes", count) => {
+                let len = count.unwrap_or(1);
+                let bytes = self.bytes(name, offset, len)?;
+                let value = preview(bytes);
+                self.leaf(name, format!("bytes[{}]", len), offset, bytes.len(), value, depth);
+                Ok(bytes.len())
+            }
+            (ty, None) => match num_type(ty) {
+                Some(num) => self.parse_number(name, num, big_endian, labels, offset, depth),
+                None => self.parse_struct(name, ty, offset, depth),
+            },
+            (ty, Some(n)) => {
+                if n > MAX_ELEMENTS {
+                    return Err(TemplateError::TooMany(name.to_string(), n));
+                }
+                let num = num_type(ty);
+                if num.is_none() && !self.template.structs.contains_key(ty) {
+                    return Err(TemplateError::UnknownType(ty.to_string()));
+                }
+                let index = self.push(name, format!("{}[{}]", ty, n), offset, depth);
+                let mut pos = offset;
+                for i in 0..n {
+                    let element = format!("[{}]", i);
+                    pos += match num {
+                        Some(num) => {
+                            self.parse_number(&element, num, big_endian, labels, pos, depth + 1)?
+                        }
+                        None => self.parse_struct(&element, ty, pos, depth + 1)?,
+                    };
+                }
+                self.finish(index, pos - offset);
+                Ok(pos - offset)
+            }
+        }
+    }
+
+    /// 数値のフィールドを解析（整数なら count と if から参照できるよう値を覚える）
+    fn parse_number(
+        &mut self,
+        name: &str,
+        num: NumType,
+        big_endian: bool,
+        labels: Option<&BTreeMap<String, String>>,
+        offset: usize,
+        depth: usize,
+    ) -> Result<usize, TemplateError> {
+        let field = inspector::Field { ty: num, big_endian };
+        let bytes = self.bytes(name, offset, num.size() as u64)?;
+        let value = field.decode(bytes).unwrap_or_default();
+        let mut label = String::new();
+        if let Ok(n) = value.parse::<i128>() {
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.insert(name.to_string(), n);
+            }
+            if let Some((_, name)) =
+                labels.into_iter().flatten().find(|(key, _)| expr::parse_int(key) == Some(n))
+            {
+                label = name.clone();
+            }
+        }
+        self.leaf(name, num.name().to_string(), offset, num.size(), value, depth);
+        if let Some(node) = self.nodes.last_mut() {
+            node.label = label;
+        }
+        Ok(num.size())
+    }
+
+    /// 要素数を求める
+    fn resolve(&self, count: &Count) -> Result<u64, TemplateError> {
+        match count {
+            Count::Fixed(n) => Ok(*n),
+            Count::Field(text) => u64::try_from(self.eval(text)?)
+                .map_err(|_| TemplateError::Expr(text.clone(), "negative count".to_string())),
+        }
+    }
+
+    /// 式を評価（フィールド名は内側の構造体から順に探す）
+    fn eval(&self, text: &str) -> Result<i128, TemplateError> {
+        expr::eval(text, |name| {
+            self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+        })
+    }
+
+    /// offset から len バイト（足りなければエラー）
+    fn bytes(&self, name: &str, offset: usize, len: u64) -> Result<&'a [u8], TemplateError> {
+        usize::try_from(len)
+            .ok()
+            .and_then(|len| self.data.get(offset..offset.checked_add(len)?))
+            .ok_or_else(|| TemplateError::Eof(name.to_string(), offset))
+    }
+
+    /// 子を持つノードを追加（大きさと子孫の数は finish で埋める）
+    fn push(&mut self, name: &str, ty: String, offset: usize, depth: usize) -> usize {
+        self.nodes.push(Node {
+            name: name.to_string(),
+            ty,
+            offset,
+            size: 0,
+            value: String::new(),
+            label: String::new(),
+            depth,
+            descendants: 0,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn finish(&mut self, index: usize, size: usize) {
+        let descendants = self.nodes.len() - index - 1;
+        let node = &mut self.nodes[index];
+        node.size = size;
+        node.descendants = descendants;
+    }
+
+    /// 子を持たないノードを追加
+    fn leaf(
+        &mut self,
+        name: &str,
+        ty: String,
+        offset: usize,
+        size: usize,
+        value: String,
+        depth: usize,
+    ) {
+        let index = self.push(name, ty, offset, depth);
+        let node = &mut self.nodes[index];
+        node.size = size;
+        node.value = value;
+    }
+}
+
+/// 型名から数値型を探す
+fn num_type(name: &str) -> Option<NumType> {
+    inspector::FIELDS.iter().map(|field| field.ty).find(|ty| ty.name() == name)
+}
+
+/// 文字列として表示（NUL で終わり、表示できない文字は '.'）
+fn text(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|&&b| b != 0)
+        .map(|&b| if (0x20..=0x7E).contains(&b) { b as char } else { '.' })
+        .collect()
+}
+
+/// バイト列の短い表示（長い場合は省略）
+fn preview(bytes: &[u8]) -> String {
+    const MAX: usize = 8;
+    let hex = clipboard::bytes_to_hex(&bytes[..bytes.len().min(MAX)], HexFormat::Spaced);
+    if bytes.len() > MAX { format!("{} ..", hex) } else { hex }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = r#"
+        root = "file"
+
+        [structs.file]
+        fields = [
+          { name = "magic", type = "char", count = 4 },
+          { name = "count", type = "u16", endian = "big" },
+          { name = "entries", type = "entry", count = "count" },
+          { name = "rest", type = "bytes", count = 2 },
+        ]
+
+        [structs.entry]
+        fields = [
+          { name = "id", type = "u8" },
+          { name = "value", type = "i16" },
+        ]
+    "#;
+
+    #[test]
+    fn test_apply() {
+        let template = Template::parse(TEMPLATE).unwrap();
+        let data = [b'D', b'E', b'M', b'O', 0, 2, 1, 0xFE, 0xFF, 2, 0x10, 0, 0xAA, 0xBB];
+        let nodes = template.apply(&data).unwrap();
+
+        let summary: Vec<_> = nodes
+            .iter()
+            .map(|n| (n.depth, n.name.as_str(), n.ty.as_str(), n.offset, n.value.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, "file", "file", 0, ""),
+                (1, "magic", "char[4]", 0, "\"DEMO\""),
+                (1, "count", "u16", 4, "2"),
+                (1, "entries", "entry[2]", 6, ""),
+                (2, "[0]", "entry", 6, ""),
+                (3, "id", "u8", 6, "1"),
+                (3, "value", "i16", 7, "-2"),
+                (2, "[1]", "entry", 9, ""),
+                (3, "id", "u8", 9, "2"),
+                (3, "value", "i16", 10, "16"),
+                (1, "rest", "bytes[2]", 12, "AA BB"),
+            ]
+        );
+        assert_eq!((nodes[0].size, nodes[0].descendants), (14, 10));
+        assert_eq!((nodes[3].size, nodes[3].descendants), (6, 6));
+
+        assert!(matches!(template.apply(&data[..10]), Err(TemplateError::Eof(..))));
+    }
+
+    #[test]
+    fn test_conditions_and_enums() {
+        let template = Template::parse(
+            r#"
+            root = "file"
+
+            [enums.kind]
+            1 = "data"
+            0x10 = "index"
+
+            [structs.file]
+            fields = [
+              { name = "hdr", type = "header" },
+              { name = "index", type = "u16", count = "hdr.count * 2", if = "hdr.kind == 0x10" },
+              { name = "data", type = "bytes", count = "hdr.count + 1", if = "hdr.kind == 1" },
+              { name = "extra", type = "u8", if = "hdr.version >= 2 && hdr.flags & 0x80" },
+            ]
+
+            [structs.header]
+            fields = [
+              { name = "kind", type = "u8", enum = "kind" },
+              { name = "version", type = "u8" },
+              { name = "flags", type = "u8", if = "version >= 2" },
+              { name = "count", type = "u8" },
+            ]
+            "#,
+        )
+        .unwrap();
+        let summary = |data: &[u8]| -> Vec<(String, String, String)> {
+            let nodes = template.apply(data).unwrap();
+            nodes.into_iter().map(|n| (n.name, n.value, n.label)).collect()
+        };
+        let row = |name: &str, value: &str, label: &str| {
+            (name.to_string(), value.to_string(), label.to_string())
+        };
+
+        // 版 1 には flags がなく、&& の右辺の hdr.flags は評価しない
+        let index = summary(&[0x10, 1, 2, 0, 1, 0, 2, 0, 3, 0, 4]);
+        assert_eq!(index[2], row("kind", "16", "index"));
+        assert_eq!(index[5], row("index", "", ""));
+        assert_eq!(index.len(), 10);
+
+        let data = summary(&[1, 2, 0x80, 1, b'a', b'b', 7]);
+        assert_eq!(
+            data[1..],
+            [
+                row("hdr", "", ""),
+                row("kind", "1", "data"),
+                row("version", "2", ""),
+                row("flags", "128", ""),
+                row("count", "1", ""),
+                row("data", "61 62", ""),
+                row("extra", "7", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template_errors() {
+        let unknown = Template::parse(
+            r#"
+            [structs.main]
+            fields = [{ name = "x", type = "u8", count = "missing" }]
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(unknown.apply(&[0]), Err(TemplateError::UnknownField(_))));
+
+        let recursive = Template::parse(
+            r#"
+            [structs.main]
+            fields = [{ name = "again", type = "main" }]
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(recursive.apply(&[0]), Err(TemplateError::TooDeep(_))));
+
+        assert!(matches!(Template::parse("root = 1"), Err(TemplateError::Parse(_))));
+
+        let invalid = Template::parse(
+            r#"
+            [structs.main]
+            fields = [
+              { name = "n", type = "u8" },
+              { name = "x", type = "u8", count = "n - 2" },
+              { name = "y", type = "u8", enum = "missing" },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(invalid.apply(&[1]), Err(TemplateError::Expr(..))));
+        assert!(matches!(invalid.apply(&[2, 0]), Err(TemplateError::UnknownEnum(_))));
+
+        let enums = "[structs.main]\nfields = []\n[enums.kind]\none = \"1\"";
+        assert!(matches!(Template::parse(enums), Err(TemplateError::InvalidEnum(..))));
+    }
+}
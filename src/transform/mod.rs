@@ -0,0 +1,660 @@
+//! 選択範囲のバイト列の変換（ビット演算・エンコード・圧縮など）
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+use base64::Engine;
+use base64::alphabet;
+use base64::engine::DecodePaddingMode;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD};
+use flate2::Compression;
+use flate2::bufread::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+use thiserror::Error;
+
+/// パディングの有無を問わない base64 デコーダ
+const BASE64_DECODER: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TransformError {
+    #[error("Invalid key: {0} (hex bytes, e.g. 5A or DE AD BE EF)")]
+    InvalidKey(String),
+    #[error("Invalid bit count: {0} (1-7)")]
+    InvalidBits(String),
+    #[error("Selection length {0} is not a multiple of {1}")]
+    Misaligned(usize, usize),
+    #[error("Invalid base64: {0}")]
+    InvalidBase64(String),
+    #[error("Invalid base32: {0}")]
+    InvalidBase32(String),
+    #[error("Unknown format: {0} (zlib, gzip, raw or zstd)")]
+    UnknownCodec(String),
+    #[error("Decompression failed: {0}")]
+    Decompress(String),
+    #[error("Decompressed data exceeds {0} bytes")]
+    TooLarge(usize),
+}
+
+/// キーを使うビット演算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOp {
+    Xor,
+    And,
+    Or,
+}
+
+impl BitOp {
+    /// 表示名
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Xor => "XOR",
+            Self::And => "AND",
+            Self::Or => "OR",
+        }
+    }
+}
+
+/// バイトごとのシフト・ローテート
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shift {
+    Left,
+    Right,
+    RotateLeft,
+    RotateRight,
+}
+
+/// 16進のキーをパース（空白・カンマ区切り、各語の 0x 接頭辞も可）
+pub fn parse_key(input: &str) -> Result<Vec<u8>, TransformError> {
+    let invalid = || TransformError::InvalidKey(input.trim().to_string());
+    let mut key = Vec::new();
+    for word in input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty())
+    {
+        let digits = word
+            .strip_prefix("0x")
+            .or_else(|| word.strip_prefix("0X"))
+            .unwrap_or(word);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        // 奇数桁は先頭に 0 を補う（0x5 = 05）
+        let padded = if digits.len() % 2 == 1 {
+            format!("0{}", digits)
+        } else {
+            digits.to_string()
+        };
+        for pair in padded.as_bytes().chunks(2) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            key.push(u8::from_str_radix(pair, 16).map_err(|_| invalid())?);
+        }
+    }
+    if key.is_empty() {
+        return Err(invalid());
+    }
+    Ok(key)
+}
+
+/// シフトするビット数をパース（1-7）
+pub fn parse_bits(input: &str) -> Result<u32, TransformError> {
+    input
+        .trim()
+        .parse()
+        .ok()
+        .filter(|bits| (1..8).contains(bits))
+        .ok_or_else(|| TransformError::InvalidBits(input.trim().to_string()))
+}
+
+/// キーを繰り返しながらビット演算する
+pub fn apply_key(data: &mut [u8], key: &[u8], op: BitOp) {
+    for (byte, &k) in data.iter_mut().zip(key.iter().cycle()) {
+        match op {
+            BitOp::Xor => *byte ^= k,
+            BitOp::And => *byte &= k,
+            BitOp::Or => *byte |= k,
+        }
+    }
+}
+
+/// 各バイトのビットを反転
+pub fn not(data: &mut [u8]) {
+    for byte in data {
+        *byte = !*byte;
+    }
+}
+
+/// 各バイトの上位・下位ニブルを入れ替える（0x12 → 0x21）
+pub fn swap_nibbles(data: &mut [u8]) {
+    shift(data, Shift::RotateLeft, 4);
+}
+
+/// 各バイトを bits ビットだけシフト・ローテート
+pub fn shift(data: &mut [u8], shift: Shift, bits: u32) {
+    for byte in data {
+        *byte = match shift {
+            Shift::Left => *byte << bits,
+            Shift::Right => *byte >> bits,
+            Shift::RotateLeft => byte.rotate_left(bits),
+            Shift::RotateRight => byte.rotate_right(bits),
+        };
+    }
+}
+
+/// size バイトごとにバイト順を反転（エンディアンの入れ替え）
+pub fn swap_bytes(data: &mut [u8], size: usize) -> Result<(), TransformError> {
+    if !data.len().is_multiple_of(size) {
+        return Err(TransformError::Misaligned(data.len(), size));
+    }
+    for group in data.chunks_exact_mut(size) {
+        group.reverse();
+    }
+    Ok(())
+}
+
+/// base64 にエンコードする（パディングあり）
+pub fn base64_encode(data: &[u8]) -> Vec<u8> {
+    STANDARD.encode(data).into_bytes()
+}
+
+/// base64 テキストをデコードする（改行・空白は無視、パディングは省略可）
+pub fn base64_decode(text: &[u8]) -> Result<Vec<u8>, TransformError> {
+    let text: Vec<u8> = text
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    if text.is_empty() {
+        return Err(TransformError::InvalidBase64("empty input".to_string()));
+    }
+    BASE64_DECODER
+        .decode(&text)
+        .map_err(|e| TransformError::InvalidBase64(e.to_string()))
+}
+
+/// base64 / base32 の種類（RFC 4648）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseEncoding {
+    Base64,
+    /// URL・ファイル名に使える base64（`+` `/` の代わりに `-` `_`）
+    Base64Url,
+    Base32,
+    /// 0-9A-V を使う base32（並べ替えても順序が変わらない）
+    Base32Hex,
+}
+
+impl BaseEncoding {
+    pub const ALL: [Self; 4] = [Self::Base64, Self::Base64Url, Self::Base32, Self::Base32Hex];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Base64 => "base64",
+            Self::Base64Url => "base64url",
+            Self::Base32 => "base32",
+            Self::Base32Hex => "base32hex",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|e| e.name().eq_ignore_ascii_case(name))
+    }
+
+    /// パディングなしで区切れるバイト数（この倍数ごとに分けてエンコードできる）
+    pub fn block_bytes(self) -> usize {
+        match self {
+            Self::Base64 | Self::Base64Url => 3,
+            Self::Base32 | Self::Base32Hex => 5,
+        }
+    }
+
+    /// block_bytes をエンコードした文字数（この倍数ごとに分けてデコードできる）
+    pub fn block_chars(self) -> usize {
+        match self {
+            Self::Base64 | Self::Base64Url => 4,
+            Self::Base32 | Self::Base32Hex => 8,
+        }
+    }
+
+    fn base64_engine(self, pad: bool) -> GeneralPurpose {
+        let alphabet = match self {
+            Self::Base64Url => &alphabet::URL_SAFE,
+            _ => &alphabet::STANDARD,
+        };
+        GeneralPurpose::new(
+            alphabet,
+            GeneralPurposeConfig::new()
+                .with_encode_padding(pad)
+                .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+        )
+    }
+
+    fn base32_alphabet(self) -> &'static [u8; 32] {
+        match self {
+            Self::Base32Hex => b"0123456789ABCDEFGHIJKLMNOPQRSTUV",
+            _ => b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+        }
+    }
+
+    /// エンコードする（pad が false なら末尾の `=` を付けない）
+    pub fn encode(self, data: &[u8], pad: bool) -> String {
+        if matches!(self, Self::Base64 | Self::Base64Url) {
+            return self.base64_engine(pad).encode(data);
+        }
+        let alphabet = self.base32_alphabet();
+        let mut text = String::with_capacity(data.len().div_ceil(5) * 8);
+        for group in data.chunks(5) {
+            let mut block = [0u8; 5];
+            block[..group.len()].copy_from_slice(group);
+            let bits = block.iter().fold(0u64, |acc, &b| acc << 8 | u64::from(b));
+            // 5 バイトを 5 ビットずつ 8 文字に。端数のバイトなら必要な文字数だけ
+            let chars = (group.len() * 8).div_ceil(5);
+            for i in 0..chars {
+                text.push(alphabet[(bits >> (35 - i * 5)) as usize & 0x1F] as char);
+            }
+            if pad {
+                text.extend(std::iter::repeat_n('=', 8 - chars));
+            }
+        }
+        text
+    }
+
+    /// デコードする（改行・空白は無視、パディングは省略可、base32 は小文字も可）
+    pub fn decode(self, text: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let text: Vec<u8> = text
+            .iter()
+            .copied()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect();
+        if matches!(self, Self::Base64 | Self::Base64Url) {
+            return self
+                .base64_engine(true)
+                .decode(&text)
+                .map_err(|e| TransformError::InvalidBase64(e.to_string()));
+        }
+
+        let end = text.iter().rposition(|&b| b != b'=').map_or(0, |i| i + 1);
+        let text = &text[..end];
+        // 端数の文字数は 2 / 4 / 5 / 7 のどれか（1 / 2 / 3 / 4 バイト）
+        if matches!(text.len() % 8, 1 | 3 | 6) {
+            return Err(TransformError::InvalidBase32(format!(
+                "invalid length {}",
+                text.len()
+            )));
+        }
+        let alphabet = self.base32_alphabet();
+        let mut data = Vec::with_capacity(text.len() * 5 / 8);
+        for group in text.chunks(8) {
+            let mut bits = 0u64;
+            for (i, &c) in group.iter().enumerate() {
+                let Some(value) = alphabet.iter().position(|&a| a == c.to_ascii_uppercase()) else {
+                    return Err(TransformError::InvalidBase32(format!(
+                        "invalid character '{}'",
+                        c as char
+                    )));
+                };
+                bits |= (value as u64) << (35 - i * 5);
+            }
+            let bytes = group.len() * 5 / 8;
+            data.extend_from_slice(&bits.to_be_bytes()[3..3 + bytes]);
+        }
+        Ok(data)
+    }
+}
+
+/// 圧縮形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Zlib,
+    Gzip,
+    /// ヘッダなしの deflate ストリーム
+    Raw,
+    Zstd,
+}
+
+impl Codec {
+    /// 表示名
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Zlib => "zlib",
+            Self::Gzip => "gzip",
+            Self::Raw => "raw deflate",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// 形式名をパース（空なら None = 既定値・自動判定）
+    pub fn parse(input: &str) -> Result<Option<Self>, TransformError> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "" => Ok(None),
+            "zlib" => Ok(Some(Self::Zlib)),
+            "gzip" | "gz" => Ok(Some(Self::Gzip)),
+            "raw" | "deflate" => Ok(Some(Self::Raw)),
+            "zstd" | "zst" => Ok(Some(Self::Zstd)),
+            other => Err(TransformError::UnknownCodec(other.to_string())),
+        }
+    }
+
+    /// 先頭のヘッダから形式を判定する（どれでもなければ raw deflate とみなす）
+    pub fn detect(data: &[u8]) -> Result<Self, TransformError> {
+        if data.starts_with(&[0x1F, 0x8B]) {
+            return Ok(Self::Gzip);
+        }
+        if data.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            return Ok(Self::Zstd);
+        }
+        // zlib: CM = 8 (deflate) かつ CMF/FLG が 31 の倍数
+        if let [cmf, flg, ..] = *data
+            && cmf & 0x0F == 8
+            && (u16::from(cmf) << 8 | u16::from(flg)).is_multiple_of(31)
+        {
+            return Ok(Self::Zlib);
+        }
+        Ok(Self::Raw)
+    }
+}
+
+/// data を codec で圧縮する
+pub fn compress(data: &[u8], codec: Codec) -> Vec<u8> {
+    let level = Compression::default();
+    // Vec への書き込みは失敗しない
+    let result = match codec {
+        Codec::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), level);
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        Codec::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), level);
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        Codec::Raw => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), level);
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        Codec::Zstd => zstd::stream::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL),
+    };
+    result.unwrap_or_default()
+}
+
+/// data 先頭の圧縮ストリームを展開する（展開後が limit バイトを超えたらエラー）
+/// 戻り値: (展開後のバイト列, ストリームが占めていたバイト数)
+pub fn decompress(
+    data: &[u8],
+    codec: Codec,
+    limit: usize,
+) -> Result<(Vec<u8>, usize), TransformError> {
+    // 圧縮率の極端なデータでメモリを使い果たさないよう、limit を1バイト超えたところで止める
+    let read_all = |decoder: &mut dyn Read| -> io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        decoder.take(limit as u64 + 1).read_to_end(&mut output)?;
+        Ok(output)
+    };
+    let read_all = |decoder: &mut dyn Read| match read_all(decoder) {
+        Ok(output) if output.len() > limit => Err(TransformError::TooLarge(limit)),
+        Ok(output) => Ok(output),
+        Err(e) => Err(TransformError::Decompress(e.to_string())),
+    };
+    // 展開後に残った入力から、ストリームの後ろに続くデータの長さがわかる
+    let (output, rest) = match codec {
+        Codec::Zlib => {
+            let mut decoder = ZlibDecoder::new(data);
+            (read_all(&mut decoder)?, decoder.into_inner().len())
+        }
+        Codec::Gzip => {
+            let mut decoder = GzDecoder::new(data);
+            (read_all(&mut decoder)?, decoder.into_inner().len())
+        }
+        Codec::Raw => {
+            let mut decoder = DeflateDecoder::new(data);
+            (read_all(&mut decoder)?, decoder.into_inner().len())
+        }
+        Codec::Zstd => {
+            // 1フレームで止める（後ろに続くデータはフレームとして読まない）
+            let mut decoder = zstd::stream::read::Decoder::with_buffer(data)
+                .map_err(|e| TransformError::Decompress(e.to_string()))?
+                .single_frame();
+            (read_all(&mut decoder)?, decoder.finish().len())
+        }
+    };
+    Ok((output, data.len() - rest))
+}
+
+/// OS の暗号論的乱数源から count バイトを読む
+/// メモリを確保できなければエラー（入力された巨大な値で異常終了しないように）
+pub fn random_bytes(count: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    bytes
+        .try_reserve_exact(count)
+        .map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+    bytes.resize(count, 0);
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// De Bruijn 列（alphabet の n 文字の並びがどれも一度だけ現れる列）の先頭 len バイト
+/// 長さの上限は alphabet.len() の n 乗で、それを超える分は返さない
+pub fn de_bruijn(alphabet: &[u8], n: usize, len: usize) -> Vec<u8> {
+    /// FKM アルゴリズム（辞書順に Lyndon 語をつなげる）。len に達したら false を返して止める
+    fn generate(
+        t: usize,
+        p: usize,
+        k: usize,
+        a: &mut [usize],
+        out: &mut Vec<usize>,
+        len: usize,
+    ) -> bool {
+        let n = a.len() - 1;
+        if t > n {
+            if n.is_multiple_of(p) {
+                out.extend_from_slice(&a[1..=p]);
+            }
+            return out.len() < len;
+        }
+        a[t] = a[t - p];
+        if !generate(t + 1, p, k, a, out, len) {
+            return false;
+        }
+        for j in a[t - p] + 1..k {
+            a[t] = j;
+            if !generate(t + 1, t, k, a, out, len) {
+                return false;
+            }
+        }
+        true
+    }
+
+    if alphabet.is_empty() || n == 0 {
+        return Vec::new();
+    }
+    let mut a = vec![0; n + 1];
+    let mut out = Vec::new();
+    generate(1, 1, alphabet.len(), &mut a, &mut out, len);
+    out.truncate(len);
+    out.into_iter().map(|i| alphabet[i]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xor_with_repeating_key() {
+        assert_eq!(parse_key("5a"), Ok(vec![0x5A]));
+        assert_eq!(
+            parse_key("0xDE 0xAD, BEEF"),
+            Ok(vec![0xDE, 0xAD, 0xBE, 0xEF])
+        );
+        assert_eq!(parse_key("0x5"), Ok(vec![0x05]));
+        assert!(parse_key("xyz").is_err());
+        assert!(parse_key("  ").is_err());
+
+        let mut data = vec![0x00, 0xFF, 0x10, 0x20, 0x30];
+        apply_key(&mut data, &[0x01, 0x02], BitOp::Xor);
+        assert_eq!(data, [0x01, 0xFD, 0x11, 0x22, 0x31]);
+    }
+
+    #[test]
+    fn test_bitwise_ops_per_byte() {
+        let mut data = vec![0xF0, 0x3C];
+        apply_key(&mut data, &[0x0F], BitOp::And);
+        assert_eq!(data, [0x00, 0x0C]);
+        apply_key(&mut data, &[0x80, 0x01], BitOp::Or);
+        assert_eq!(data, [0x80, 0x0D]);
+        not(&mut data);
+        assert_eq!(data, [0x7F, 0xF2]);
+        shift(&mut data, Shift::RotateLeft, 4);
+        assert_eq!(data, [0xF7, 0x2F]);
+        shift(&mut data, Shift::Right, 1);
+        assert_eq!(data, [0x7B, 0x17]);
+        shift(&mut data, Shift::Left, 7);
+        assert_eq!(data, [0x80, 0x80]);
+        let mut data = vec![0x12, 0xAB];
+        swap_nibbles(&mut data);
+        assert_eq!(data, [0x21, 0xBA]);
+        assert_eq!(parse_bits(" 3 "), Ok(3));
+        assert!(parse_bits("8").is_err());
+        assert!(parse_bits("0").is_err());
+    }
+
+    #[test]
+    fn test_swap_bytes_in_groups() {
+        let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        swap_bytes(&mut data, 2).unwrap();
+        assert_eq!(data, [2, 1, 4, 3, 6, 5, 8, 7]);
+        swap_bytes(&mut data, 4).unwrap();
+        assert_eq!(data, [3, 4, 1, 2, 7, 8, 5, 6]);
+        swap_bytes(&mut data, 8).unwrap();
+        assert_eq!(data, [6, 5, 8, 7, 2, 1, 4, 3]);
+        assert_eq!(
+            swap_bytes(&mut data[..6], 4),
+            Err(TransformError::Misaligned(6, 4))
+        );
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        assert_eq!(base64_encode(b"hx\x00\xFF"), b"aHgA/w==");
+        assert_eq!(base64_decode(b"aHgA/w==").unwrap(), b"hx\x00\xFF");
+        assert_eq!(base64_decode(b"aHgA\r\n/w").unwrap(), b"hx\x00\xFF");
+        assert!(base64_decode(b"aHg*").is_err());
+        assert!(base64_decode(b" \n").is_err());
+    }
+
+    #[test]
+    fn test_base_encodings() {
+        // RFC 4648 のテストベクター
+        let vectors = [
+            ("", "", ""),
+            ("f", "MY======", "CO======"),
+            ("fo", "MZXQ====", "CPNG===="),
+            ("foo", "MZXW6===", "CPNMU==="),
+            ("foob", "MZXW6YQ=", "CPNMUOG="),
+            ("fooba", "MZXW6YTB", "CPNMUOJ1"),
+            ("foobar", "MZXW6YTBOI======", "CPNMUOJ1E8======"),
+        ];
+        for (data, base32, base32hex) in vectors {
+            assert_eq!(BaseEncoding::Base32.encode(data.as_bytes(), true), base32);
+            assert_eq!(
+                BaseEncoding::Base32Hex.encode(data.as_bytes(), true),
+                base32hex
+            );
+            assert_eq!(
+                BaseEncoding::Base32.decode(base32.as_bytes()).unwrap(),
+                data.as_bytes()
+            );
+            let unpadded = base32hex.trim_end_matches('=');
+            assert_eq!(
+                BaseEncoding::Base32Hex.encode(data.as_bytes(), false),
+                unpadded
+            );
+            assert_eq!(
+                BaseEncoding::Base32Hex
+                    .decode(unpadded.to_lowercase().as_bytes())
+                    .unwrap(),
+                data.as_bytes()
+            );
+        }
+        assert!(BaseEncoding::Base32.decode(b"MZXW6Y").is_err());
+        assert!(BaseEncoding::Base32.decode(b"MZ1W6YQ=").is_err());
+
+        let data = b"\xFB\xFF\xBF";
+        assert_eq!(BaseEncoding::Base64.encode(data, true), "+/+/");
+        assert_eq!(BaseEncoding::Base64Url.encode(data, true), "-_-_");
+        assert_eq!(
+            BaseEncoding::Base64Url.encode(b"hx\x00\xFF", false),
+            "aHgA_w"
+        );
+        assert_eq!(
+            BaseEncoding::Base64Url.decode(b"aHgA_w==").unwrap(),
+            b"hx\x00\xFF"
+        );
+        assert!(BaseEncoding::Base64.decode(b"aHgA_w").is_err());
+        assert_eq!(
+            BaseEncoding::from_name("Base64URL"),
+            Some(BaseEncoding::Base64Url)
+        );
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let data = b"hx hx hx hx hx hx hx hx".repeat(8);
+        for codec in [Codec::Zlib, Codec::Gzip, Codec::Raw, Codec::Zstd] {
+            let packed = compress(&data, codec);
+            assert!(packed.len() < data.len());
+            assert_eq!(Codec::detect(&packed), Ok(codec));
+            // 後続データは展開対象に含めない
+            let mut embedded = packed.clone();
+            embedded.extend_from_slice(b"TAIL");
+            assert_eq!(
+                decompress(&embedded, codec, data.len()),
+                Ok((data.clone(), packed.len()))
+            );
+            // 展開後が上限を超えるものは途中で止める
+            assert_eq!(
+                decompress(&packed, codec, data.len() - 1),
+                Err(TransformError::TooLarge(data.len() - 1))
+            );
+        }
+        assert!(decompress(b"not zlib", Codec::Zlib, 1024).is_err());
+        assert!(decompress(b"not zstd", Codec::Zstd, 1024).is_err());
+    }
+
+    #[test]
+    fn test_codec_parse_and_detect() {
+        assert_eq!(Codec::parse(" GZ "), Ok(Some(Codec::Gzip)));
+        assert_eq!(Codec::parse(""), Ok(None));
+        assert_eq!(Codec::parse("zst"), Ok(Some(Codec::Zstd)));
+        assert!(matches!(
+            Codec::parse("lz4"),
+            Err(TransformError::UnknownCodec(_))
+        ));
+        assert_eq!(Codec::detect(&[0x28, 0xB5, 0x2F, 0xFD, 0x00]), Ok(Codec::Zstd));
+    }
+
+    #[test]
+    fn test_random_bytes_are_filled() {
+        let bytes = random_bytes(64).unwrap();
+        assert_eq!(bytes.len(), 64);
+        assert!(bytes.iter().any(|&b| b != 0));
+        // 確保できない長さは異常終了せずにエラー
+        let err = random_bytes(usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+    }
+
+    #[test]
+    fn test_de_bruijn_sequence() {
+        assert_eq!(de_bruijn(b"ab", 3, 100), b"aaababbb");
+        let cyclic = de_bruijn(b"abcdefghijklmnopqrstuvwxyz", 4, 40);
+        assert_eq!(&cyclic[..20], b"aaaabaaacaaadaaaeaaa");
+        // 4文字の並びはどれも一度だけ現れる
+        let cyclic = de_bruijn(b"abcd", 4, 1000);
+        assert_eq!(cyclic.len(), 256);
+        let mut windows: Vec<&[u8]> = cyclic.windows(4).collect();
+        windows.sort();
+        windows.dedup();
+        assert_eq!(windows.len(), 253);
+    }
+}
@@ -0,0 +1,75 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use super::Theme;
+
+/// 画面中央に重ねて表示するヘルプ（スクロール可能）
+pub struct HelpView<'a> {
+    /// 表示する行
+    lines: &'a [String],
+    /// 先頭に表示する行
+    scroll: usize,
+    /// 配色
+    theme: Theme,
+}
+
+impl<'a> HelpView<'a> {
+    pub fn new(lines: &'a [String]) -> Self {
+        Self {
+            lines,
+            scroll: 0,
+            theme: Theme::DARK,
+        }
+    }
+
+    pub fn scroll(mut self, scroll: usize) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl Widget for HelpView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // 幅は最大 80 桁、上下左右に1文字ずつ余白
+        let width = area.width.saturating_sub(2).min(80);
+        let height = area.height.saturating_sub(2);
+        if width < 4 || height < 3 {
+            return;
+        }
+        let area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + 1,
+            width,
+            height,
+        };
+        Clear.render(area, buf);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Help (Up/Down: scroll  q: close) ")
+            .title_style(Style::default().fg(self.theme.header).add_modifier(Modifier::BOLD));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let rows = inner.height as usize;
+        let scroll = self.scroll.min(self.lines.len().saturating_sub(rows));
+        for (row, line) in self.lines.iter().skip(scroll).take(rows).enumerate() {
+            // 字下げのない行は見出し
+            let style = if line.starts_with(' ') {
+                Style::default()
+            } else {
+                Style::default().fg(self.theme.header)
+            };
+            let y = inner.y + row as u16;
+            buf.set_stringn(inner.x + 1, y, line, inner.width.saturating_sub(1) as usize, style);
+        }
+    }
+}
@@ -5,8 +5,8 @@ use ratatui::{
     widgets::Widget,
 };
 
-use super::Colors;
-use crate::encoding::{decode_for_display, CharEncoding};
+use super::Theme;
+use crate::encoding::{decode_for_display, CharEncoding, CharTable, ControlChars, DecodedChar};
 
 /// 表示モード
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -16,40 +16,152 @@ pub enum ViewMode {
     Ascii,
 }
 
+/// バイト値の表示形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteFormat {
+    #[default]
+    Hex,
+    Octal,
+    Binary,
+}
+
+impl ByteFormat {
+    /// 名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hex => "HEX",
+            Self::Octal => "OCT",
+            Self::Binary => "BIN",
+        }
+    }
+
+    /// 次の形式
+    pub fn next(self) -> Self {
+        match self {
+            Self::Hex => Self::Octal,
+            Self::Octal => Self::Binary,
+            Self::Binary => Self::Hex,
+        }
+    }
+
+    /// 基数
+    pub fn radix(self) -> u32 {
+        match self {
+            Self::Hex => 16,
+            Self::Octal => 8,
+            Self::Binary => 2,
+        }
+    }
+
+    /// 1バイトの桁数
+    pub fn digits(self) -> usize {
+        match self {
+            Self::Hex => 2,
+            Self::Octal => 3,
+            Self::Binary => 8,
+        }
+    }
+
+    /// バイト値を文字列にする
+    pub fn format(self, byte: u8) -> String {
+        match self {
+            Self::Hex => format!("{:02X}", byte),
+            Self::Octal => format!("{:03o}", byte),
+            Self::Binary => format!("{:08b}", byte),
+        }
+    }
+}
+
+/// 選べるグループのバイト数（xxd -g と同じく、グループ内は詰めてグループ間を空ける）
+pub const GROUP_SIZES: &[usize] = &[1, 2, 4, 8];
+
 /// HEX/ASCII表示ウィジェット
 pub struct HexView<'a> {
-    /// 表示するデータ
+    /// 表示するデータ（data_start から始まる表示範囲の分だけ）
     data: &'a [u8],
+    /// data の先頭のファイルオフセット
+    data_start: usize,
+    /// データ全体の長さ
+    len: usize,
     /// 表示開始オフセット
     offset: usize,
     /// 1行あたりのバイト数
     bytes_per_row: usize,
     /// カーソル位置
     cursor: usize,
+    /// 複数カーソルの主カーソル以外の位置（昇順）
+    cursors: &'a [usize],
+    /// カーソル位置のバイトで次に入力する桁
+    digit: usize,
     /// 選択範囲（開始, 終了）
     selection: Option<(usize, usize)>,
     /// 現在の表示モード
     mode: ViewMode,
+    /// バイト値の表示形式
+    format: ByteFormat,
+    /// 空白で区切るまとまりのバイト数
+    group: usize,
     /// 文字エンコーディング
     encoding: CharEncoding,
+    /// 文字テーブル（エンコーディングが Custom のときに使う）
+    char_table: Option<&'a CharTable>,
+    /// 制御文字の表示方法
+    control_chars: ControlChars,
     /// アドレス表示の基数（16進数 or 10進数）
     addr_radix: u8,
+    /// 基準アドレス（表示するアドレス = ファイルオフセット + base）
+    base: Option<i128>,
+    /// 基準アドレスがあるときにファイルオフセットの列も表示するか
+    absolute: bool,
+    /// 比較対象のデータ（data と同じ位置から、異なるバイトをハイライト）
+    compare: Option<&'a [u8]>,
+    /// ブックマークしたオフセット（昇順、アドレス欄に印を付ける）
+    bookmarks: &'a [usize],
+    /// 未保存の変更範囲 [start, end)（昇順）
+    modified: &'a [(usize, usize)],
+    /// 配色
+    theme: Theme,
+    /// HEX 列のバイト値ごとの色（なければテーマの色で分類）
+    byte_colors: Option<&'a [Color; 256]>,
 }
 
 impl<'a> HexView<'a> {
     pub fn new(data: &'a [u8]) -> Self {
         Self {
             data,
+            data_start: 0,
+            len: data.len(),
             offset: 0,
             bytes_per_row: 16,
             cursor: 0,
+            cursors: &[],
+            digit: 0,
             selection: None,
             mode: ViewMode::Hex,
+            format: ByteFormat::Hex,
+            group: 1,
             encoding: CharEncoding::Utf8,
+            char_table: None,
+            control_chars: ControlChars::Dot,
             addr_radix: 16,
+            base: None,
+            absolute: true,
+            compare: None,
+            bookmarks: &[],
+            modified: &[],
+            theme: Theme::DARK,
+            byte_colors: None,
         }
     }
 
+    /// data が全体の一部のとき: data はファイルオフセット start から始まり、全体は len バイト
+    /// 表示する行の前後 4 バイトまで含めておくと行をまたぐ文字も正しく表示される
+    pub fn window(mut self, start: usize, len: usize) -> Self {
+        self.data_start = start;
+        self.len = len;
+        self
+    }
+
     pub fn offset(mut self, offset: usize) -> Self {
         self.offset = offset;
         self
@@ -65,6 +177,16 @@ impl<'a> HexView<'a> {
         self
     }
 
+    pub fn cursors(mut self, cursors: &'a [usize]) -> Self {
+        self.cursors = cursors;
+        self
+    }
+
+    pub fn digit(mut self, digit: usize) -> Self {
+        self.digit = digit;
+        self
+    }
+
     pub fn selection(mut self, selection: Option<(usize, usize)>) -> Self {
         self.selection = selection;
         self
@@ -75,11 +197,119 @@ impl<'a> HexView<'a> {
         self
     }
 
+    pub fn format(mut self, format: ByteFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn addr_radix(mut self, radix: u8) -> Self {
+        self.addr_radix = radix;
+        self
+    }
+
+    pub fn base(mut self, base: Option<i128>, absolute: bool) -> Self {
+        self.base = base;
+        self.absolute = absolute;
+        self
+    }
+
+    pub fn group(mut self, group: usize) -> Self {
+        self.group = group.max(1);
+        self
+    }
+
     pub fn encoding(mut self, encoding: CharEncoding) -> Self {
         self.encoding = encoding;
         self
     }
 
+    pub fn char_table(mut self, table: Option<&'a CharTable>) -> Self {
+        self.char_table = table;
+        self
+    }
+
+    pub fn control_chars(mut self, control_chars: ControlChars) -> Self {
+        self.control_chars = control_chars;
+        self
+    }
+
+    /// ASCII 列に表示するためにデコード
+    fn decode(&self, bytes: &[u8]) -> Vec<Option<DecodedChar>> {
+        match (self.encoding, self.char_table) {
+            (CharEncoding::Custom, Some(table)) => {
+                table.decode_for_display(bytes, self.control_chars)
+            }
+            _ => decode_for_display(bytes, self.encoding, self.control_chars),
+        }
+    }
+
+    pub fn compare(mut self, other: Option<&'a [u8]>) -> Self {
+        self.compare = other;
+        self
+    }
+
+    pub fn bookmarks(mut self, bookmarks: &'a [usize]) -> Self {
+        self.bookmarks = bookmarks;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn byte_colors(mut self, colors: &'a [Color; 256]) -> Self {
+        self.byte_colors = Some(colors);
+        self
+    }
+
+    pub fn modified(mut self, ranges: &'a [(usize, usize)]) -> Self {
+        self.modified = ranges;
+        self
+    }
+
+    /// start..end にカーソル（複数カーソルを含む）があるかどうか
+    fn has_cursor(&self, start: usize, end: usize) -> bool {
+        if (start..end).contains(&self.cursor) {
+            return true;
+        }
+        let index = self.cursors.partition_point(|&pos| pos < start);
+        self.cursors.get(index).is_some_and(|&pos| pos < end)
+    }
+
+    /// カーソルの表示（入力中でない側の列は控えめに示す）
+    fn cursor_style(&self, style: Style, column: ViewMode) -> Style {
+        if self.mode == column {
+            style.bg(self.theme.cursor_bg).fg(self.theme.cursor)
+        } else {
+            style.fg(self.theme.cursor_bg).add_modifier(Modifier::UNDERLINED | Modifier::BOLD)
+        }
+    }
+
+    /// 未保存の変更があるバイトかどうか
+    fn is_modified(&self, pos: usize) -> bool {
+        let index = self.modified.partition_point(|&(_, end)| end <= pos);
+        self.modified.get(index).is_some_and(|&(start, _)| start <= pos)
+    }
+
+    /// 比較対象と異なるバイトかどうか
+    fn is_diff(&self, pos: usize) -> bool {
+        match self.compare {
+            Some(other) => {
+                let index = pos.wrapping_sub(self.data_start);
+                self.data.get(index) != other.get(index)
+            }
+            None => false,
+        }
+    }
+
+    /// ファイルオフセット [start, end) のバイト列（data の範囲外は切り詰める）
+    fn bytes(&self, start: usize, end: usize) -> &[u8] {
+        let start = start.saturating_sub(self.data_start).min(self.data.len());
+        let end = end.saturating_sub(self.data_start).clamp(start, self.data.len());
+        &self.data[start..end]
+    }
+
     /// アドレス文字列を生成
     fn format_addr(&self, addr: usize) -> String {
         if self.addr_radix == 16 {
@@ -89,37 +319,69 @@ impl<'a> HexView<'a> {
         }
     }
 
+    /// 基準アドレスからのアドレス文字列（負の値は '-' を付けて右寄せ）
+    fn format_relative(&self, addr: usize, base: i128) -> String {
+        let value = addr as i128 + base;
+        let digits = if self.addr_radix == 16 {
+            format!("{:08X}", value.unsigned_abs())
+        } else {
+            format!("{:010}", value.unsigned_abs())
+        };
+        let sign = if value < 0 { "-" } else { "" };
+        format!("{:>width$}", format!("{}{}", sign, digits), width = digits.len() + 1)
+    }
+
+    /// アドレス1列分の幅
+    fn addr_digits(&self) -> usize {
+        if self.addr_radix == 16 { 8 } else { 10 }
+    }
+
+    /// アドレス欄のヘッダー
+    fn addr_header(&self) -> String {
+        let digits = self.addr_digits();
+        match self.base {
+            Some(_) if self.absolute => {
+                format!("{:<width$} {:<digits$}", "Address", "Offset", width = digits + 1)
+            }
+            Some(_) => format!("{:<width$}", "Address", width = digits + 1),
+            None => format!("{:<digits$}", "Offset"),
+        }
+    }
+
     /// バイト値に応じた色を取得
     fn byte_color(&self, byte: u8) -> Color {
+        if let Some(colors) = self.byte_colors {
+            return colors[byte as usize];
+        }
         match byte {
-            0x00 => Colors::HEX_ZERO,
-            0xFF => Colors::HEX_HIGH,
-            0x20..=0x7E => Colors::HEX_PRINTABLE,
-            _ => Colors::HEX_NORMAL,
+            0x00 => self.theme.hex_zero,
+            0xFF => self.theme.hex_high,
+            0x20..=0x7E => self.theme.hex_printable,
+            _ => self.theme.hex_normal,
         }
     }
 
     /// 前の行からはみ出した文字の継続バイト数を計算
     fn count_continuation_bytes(&self, row_start: usize) -> usize {
-        if row_start == 0 || self.data.is_empty() {
+        if row_start == 0 || self.len == 0 {
             return 0;
         }
 
         // 前の数バイトを調べて、行境界をまたぐ文字があるかチェック
         let lookahead = 4;
         let check_start = row_start.saturating_sub(lookahead);
-        let end = row_start.min(self.data.len());
+        let end = row_start.min(self.len);
         if check_start >= end {
             return 0;
         }
-        let check_bytes = &self.data[check_start..end];
+        let check_bytes = self.bytes(check_start, end);
 
         if check_bytes.is_empty() {
             return 0;
         }
 
         // デコードして最後の文字が行をまたぐかチェック
-        let decoded = decode_for_display(check_bytes, self.encoding);
+        let decoded = self.decode(check_bytes);
 
         let mut pos = 0;
         let mut last_char_end = 0;
@@ -133,31 +395,28 @@ impl<'a> HexView<'a> {
         }
 
         // 最後の文字が row_start を超えていれば、その分が継続バイト
-        if last_char_end > row_start {
-            last_char_end - row_start
-        } else {
-            0
-        }
+        last_char_end.saturating_sub(row_start)
     }
 
     /// 1行分のデータを描画
     fn render_row(&self, row_offset: usize, area: Rect, buf: &mut Buffer) {
         let row_start = self.offset + row_offset * self.bytes_per_row;
-        let row_end = (row_start + self.bytes_per_row).min(self.data.len());
+        let row_bytes = self.bytes(row_start, row_start + self.bytes_per_row);
+        let row_end = row_start + row_bytes.len();
 
         // 前の行からはみ出した文字の継続バイト数
         let skip_bytes = self.count_continuation_bytes(row_start);
 
         // EOF行も描画可能にする（カーソルがEOF位置にある場合）
-        let eof_pos = self.data.len();
+        let eof_pos = self.len;
         let cursor_at_eof = self.cursor == eof_pos;
 
-        if row_start > self.data.len() {
+        if row_start > self.len {
             return;
         }
 
         // データがなく、かつカーソルもこの行にない場合はスキップ
-        if row_start >= self.data.len() && !cursor_at_eof {
+        if row_start >= self.len && !cursor_at_eof {
             return;
         }
 
@@ -165,51 +424,95 @@ impl<'a> HexView<'a> {
         let y = area.y;
 
         // アドレス表示
-        let addr_str = self.format_addr(row_start);
-        buf.set_string(x, y, &addr_str, Style::default().fg(Colors::ADDR));
-        x += addr_str.len() as u16 + 2;
+        let addr_style = Style::default().fg(self.theme.addr);
+        if let Some(base) = self.base {
+            let relative = self.format_relative(row_start, base);
+            put(buf, area, x, y, &relative, addr_style);
+            x += relative.len() as u16;
+            // ファイルオフセットは控えめに表示
+            if self.absolute {
+                let addr_str = self.format_addr(row_start);
+                put(buf, area, x + 1, y, &addr_str, addr_style.add_modifier(Modifier::DIM));
+                x += addr_str.len() as u16 + 1;
+            }
+        } else {
+            let addr_str = self.format_addr(row_start);
+            put(buf, area, x, y, &addr_str, addr_style);
+            x += addr_str.len() as u16;
+        }
 
-        // HEX表示
+        // ブックマークのある行はアドレスの後ろに印を付ける
+        let row_range = row_start..row_start + self.bytes_per_row;
+        let first = self.bookmarks.partition_point(|&pos| pos < row_start);
+        if self.bookmarks.get(first).is_some_and(|pos| row_range.contains(pos)) {
+            put(buf, area, x, y, "*", Style::default().fg(self.theme.bookmark));
+        }
+        x += 2;
+
+        // HEX表示（2進・8進の場合も同じ列）
+        let digits = self.format.digits();
         for i in row_start..row_start + self.bytes_per_row {
             if i < row_end {
-                let byte = self.data[i];
-                let hex = format!("{:02X}", byte);
+                let byte = row_bytes[i - row_start];
+                let hex = self.format.format(byte);
 
                 let mut style = Style::default().fg(self.byte_color(byte));
 
                 // カーソル位置のハイライト
-                if i == self.cursor && self.mode == ViewMode::Hex {
-                    style = style.bg(Colors::CURSOR_BG).fg(Colors::CURSOR);
+                let cursor = self.has_cursor(i, i + 1);
+                if cursor {
+                    style = self.cursor_style(style, ViewMode::Hex);
                 }
                 // 選択範囲のハイライト
-                else if let Some((start, end)) = self.selection {
-                    if i >= start && i <= end {
-                        style = style.bg(Colors::SELECTION_BG);
-                    }
+                else if let Some((start, end)) = self.selection
+                    && i >= start
+                    && i <= end
+                {
+                    style = style.bg(self.theme.selection_bg);
+                }
+                // 比較対象と異なるバイト
+                else if self.is_diff(i) {
+                    style = style.bg(self.theme.diff_bg).fg(self.theme.diff);
+                }
+                // 未保存の変更
+                else if self.is_modified(i) {
+                    style = style.fg(self.theme.modified);
+                }
+                // ブックマークしたバイトは下線
+                if self.bookmarks.binary_search(&i).is_ok() {
+                    style = style.add_modifier(Modifier::UNDERLINED);
                 }
 
-                buf.set_string(x, y, &hex, style);
+                if cursor && self.mode == ViewMode::Hex {
+                    // 次に入力する桁だけをカーソルにし、他の桁は控えめに示す
+                    let ghost = self.cursor_style(Style::default(), ViewMode::Ascii);
+                    for (d, ch) in hex.chars().enumerate() {
+                        let style = if d == self.digit { style } else { ghost };
+                        put(buf, area, x + d as u16, y, &ch.to_string(), style);
+                    }
+                } else {
+                    put(buf, area, x, y, &hex, style);
+                }
             } else if i == eof_pos && i == self.cursor && self.mode == ViewMode::Hex {
                 // EOF位置のカーソル（HEXモード）
-                buf.set_string(x, y, "__", Style::default().bg(Colors::CURSOR_BG).fg(Colors::CURSOR));
+                put(buf, area, x, y, &"_".repeat(digits), Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor));
             } else {
-                buf.set_string(x, y, "  ", Style::default());
+                put(buf, area, x, y, &" ".repeat(digits), Style::default());
+            }
+            x += digits as u16;
+            // グループの区切り（行末のグループは途中でも区切る）
+            let col = i - row_start + 1;
+            if col.is_multiple_of(self.group) || col == self.bytes_per_row {
+                x += 1;
             }
-            x += 3; // "XX "
         }
 
         x += 1; // 区切りスペース
 
         // ASCII表示（エンコーディングに従ってデコード）
         // 行末のマルチバイト文字を正しく表示するため、次の行のバイトも含めてデコード
-        let lookahead = 4; // UTF-8/UTF-16の最大バイト数
-        let decode_end = (row_end + lookahead).min(self.data.len());
-        let row_bytes = if decode_end > row_start {
-            &self.data[row_start..decode_end]
-        } else {
-            &[]
-        };
-        let decoded = decode_for_display(row_bytes, self.encoding);
+        let lookahead = 4; // UTF-8/UTF-16/UTF-32の最大バイト数
+        let decoded = self.decode(self.bytes(row_start, row_end + lookahead));
 
         let mut byte_idx = 0;
         // 前の行からはみ出した文字の継続バイトをスキップ
@@ -224,36 +527,39 @@ impl<'a> HexView<'a> {
             if byte_idx < decoded.len() {
                 if let Some(ref dc) = decoded[byte_idx] {
                     // この位置に文字がある
-                    let mut style = Style::default().fg(Colors::ASCII_NORMAL);
+                    let mut style = Style::default().fg(self.theme.ascii_normal);
 
                     // カーソル位置のハイライト
-                    let cursor_in_char = self.cursor >= abs_idx
-                        && self.cursor < abs_idx + dc.byte_len;
-                    if cursor_in_char && self.mode == ViewMode::Ascii {
-                        style = style.bg(Colors::CURSOR_BG).fg(Colors::CURSOR);
+                    let cursor_in_char = self.has_cursor(abs_idx, abs_idx + dc.byte_len);
+                    if cursor_in_char {
+                        style = self.cursor_style(style, ViewMode::Ascii);
                     }
                     // 選択範囲のハイライト
-                    else if let Some((start, end)) = self.selection {
-                        if abs_idx >= start && abs_idx <= end {
-                            style = style.bg(Colors::SELECTION_BG);
-                        }
+                    else if let Some((start, end)) = self.selection
+                        && abs_idx >= start
+                        && abs_idx <= end
+                    {
+                        style = style.bg(self.theme.selection_bg);
+                    }
+                    // 比較対象と異なるバイトを含む文字
+                    else if (abs_idx..abs_idx + dc.byte_len).any(|pos| self.is_diff(pos)) {
+                        style = style.bg(self.theme.diff_bg).fg(self.theme.diff);
+                    }
+                    // 未保存の変更を含む文字
+                    else if (abs_idx..abs_idx + dc.byte_len).any(|pos| self.is_modified(pos)) {
+                        style = style.fg(self.theme.modified);
                     }
 
                     // 文字を表示
-                    buf.set_string(x, y, &dc.display, style);
+                    put(buf, area, x, y, &dc.display, style);
 
                     // この行内のバイト数を計算
                     let bytes_in_row = dc.byte_len.min(self.bytes_per_row - byte_idx);
 
                     // 表示幅分進める
-                    // 行をはみ出す文字は表示幅だけ進める（はみ出し表示）
-                    let advance = if dc.byte_len <= bytes_in_row {
-                        // 行内に収まる場合
-                        dc.width.min(dc.byte_len)
-                    } else {
-                        // 行をはみ出す場合：文字の表示幅を使用
-                        dc.width
-                    };
+                    // 行をはみ出す文字や、キャレット記法の制御文字（^A）のようにバイト数より
+                    // 広い表示は、その分だけ後ろの文字が右にずれる
+                    let advance = dc.width;
                     x += advance as u16;
 
                     // 残りのバイト分（行内）はスペースで埋める
@@ -271,7 +577,7 @@ impl<'a> HexView<'a> {
                 }
             } else if abs_idx == eof_pos && abs_idx == self.cursor && self.mode == ViewMode::Ascii {
                 // EOF位置のカーソル（ASCIIモード）
-                buf.set_string(x, y, "_", Style::default().bg(Colors::CURSOR_BG).fg(Colors::CURSOR));
+                put(buf, area, x, y, "_", Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor));
                 x += 1;
                 byte_idx += 1;
             } else {
@@ -287,20 +593,26 @@ impl Widget for HexView<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         // ヘッダー行を描画
         let header = format!(
-            "{:8}  {:}  {:}",
-            "Offset",
+            "{}  {:}  {:}",
+            self.addr_header(),
             (0..self.bytes_per_row)
-                .map(|i| format!("{:02X}", i))
+                .step_by(self.group)
+                .map(|i| {
+                    // グループの先頭の列番号をグループの幅で表示
+                    let width = self.format.digits() * self.group.min(self.bytes_per_row - i);
+                    format!("{:<width$}", format!("{:02X}", i))
+                })
                 .collect::<Vec<_>>()
                 .join(" "),
             "ASCII"
         );
-        buf.set_string(
+        buf.set_stringn(
             area.x,
             area.y,
             &header,
+            area.width as usize,
             Style::default()
-                .fg(Colors::HEADER)
+                .fg(self.theme.header)
                 .add_modifier(Modifier::BOLD),
         );
 
@@ -317,3 +629,50 @@ impl Widget for HexView<'_> {
         }
     }
 }
+
+/// 描画領域の右端で切り詰めて文字列を書き込む（左右分割時に隣へはみ出さないように）
+fn put(buf: &mut Buffer, area: Rect, x: u16, y: u16, text: &str, style: Style) {
+    if x < area.right() {
+        buf.set_stringn(x, y, text, (area.right() - x) as usize, style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 描画結果の各行
+    fn render(view: HexView, height: u16) -> Vec<String> {
+        let area = Rect::new(0, 0, 80, height);
+        let mut buf = Buffer::empty(area);
+        view.render(area, &mut buf);
+        (0..height)
+            .map(|y| (0..80).map(|x| buf[(x, y)].symbol()).collect::<String>())
+            .collect()
+    }
+
+    #[test]
+    fn test_window_renders_like_full_data() {
+        // 行をまたぐ UTF-8 文字を含むデータ
+        let mut data = b"0123456789abcd".to_vec();
+        data.extend_from_slice("日本語".as_bytes());
+        data.extend_from_slice(&[0; 40]);
+        let full = render(HexView::new(&data).offset(16).cursor(20), 3);
+
+        let (start, end) = (16 - 4, 16 + 2 * 16 + 4);
+        let window = HexView::new(&data[start..end]).window(start, data.len());
+        assert_eq!(render(window.offset(16).cursor(20), 3), full);
+    }
+
+    #[test]
+    fn test_control_chars() {
+        let data = b"A\x01\x7FB";
+        let text = |controls| {
+            let row = render(HexView::new(data).control_chars(controls), 2).remove(1);
+            row.split_whitespace().last().unwrap().to_string()
+        };
+        assert_eq!(text(ControlChars::Dot), "A..B");
+        assert_eq!(text(ControlChars::Caret), "A^A^?B");
+        assert_eq!(text(ControlChars::Picture), "A\u{2401}\u{2421}B");
+    }
+}
@@ -0,0 +1,204 @@
+use std::borrow::Cow;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+use super::Theme;
+
+/// 1ブロックあたりに調べる最大バイト数（巨大ファイルでは間引いて調べる）
+const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// ミニマップの色分け
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MinimapMode {
+    /// ブロックごとのエントロピー（圧縮・暗号化された領域ほど赤い）
+    #[default]
+    Entropy,
+    /// ブロック内で最も多いバイトの種類（0x00 / 0xFF / 表示可能文字 / その他）
+    ByteClass,
+}
+
+/// バイト列のシャノンエントロピー（ビット/バイト、0〜8）
+pub fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0u64; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    counts_entropy(&counts)
+}
+
+/// バイトごとの出現回数からエントロピーを求める（少しずつ読みながら数えた場合に）
+pub fn counts_entropy(counts: &[u64; 256]) -> f64 {
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let total = total as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / total;
+            -p * p.log2()
+        })
+        .sum::<f64>()
+        // 1種類だけのときの -0.0 を 0.0 に
+        .abs()
+}
+
+/// ファイル全体を blocks 個のブロックに分け、それぞれの色を求める
+/// read(start, end) で長さ len のデータの一部を読む（全体を連続したバイト列にしなくてよい）
+pub fn block_colors<'a>(
+    len: usize,
+    read: impl Fn(usize, usize) -> Cow<'a, [u8]>,
+    blocks: usize,
+    mode: MinimapMode,
+    theme: &Theme,
+) -> Vec<Color> {
+    (0..blocks)
+        .map(|i| {
+            let (start, end) = block_range(len, blocks, i);
+            // 大きいブロックは先頭から SAMPLE_SIZE バイトだけ調べる
+            let sample = read(start, end.min(start + SAMPLE_SIZE));
+            if sample.is_empty() {
+                return Color::Reset;
+            }
+            match mode {
+                MinimapMode::Entropy => entropy_color(entropy(&sample)),
+                MinimapMode::ByteClass => class_color(&sample, theme),
+            }
+        })
+        .collect()
+}
+
+/// ブロック i のバイト範囲 [start, end)
+pub fn block_range(len: usize, blocks: usize, i: usize) -> (usize, usize) {
+    let blocks = blocks.max(1) as u128;
+    let len = len as u128;
+    let i = i as u128;
+    ((len * i / blocks) as usize, (len * (i + 1) / blocks) as usize)
+}
+
+/// オフセットを含むブロックの番号
+pub fn block_of(len: usize, blocks: usize, offset: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    ((offset.min(len - 1) as u128 * blocks as u128 / len as u128) as usize).min(blocks.saturating_sub(1))
+}
+
+/// エントロピーを色にする（0: 暗い灰色、低: 青 → 中: 緑 → 高: 赤）
+fn entropy_color(entropy: f64) -> Color {
+    if entropy < 0.5 {
+        return Color::Rgb(48, 48, 48);
+    }
+    let t = (entropy / 8.0).clamp(0.0, 1.0);
+    if t < 0.5 {
+        let u = t * 2.0;
+        Color::Rgb(0, (255.0 * u) as u8, (255.0 * (1.0 - u)) as u8)
+    } else {
+        let u = (t - 0.5) * 2.0;
+        Color::Rgb((255.0 * u) as u8, (255.0 * (1.0 - u)) as u8, 0)
+    }
+}
+
+/// 最も多いバイトの種類の色
+fn class_color(bytes: &[u8], theme: &Theme) -> Color {
+    // 0x00, 0xFF, 表示可能文字, その他
+    let mut counts = [0usize; 4];
+    for &b in bytes {
+        let class = match b {
+            0x00 => 0,
+            0xFF => 1,
+            0x20..=0x7E => 2,
+            _ => 3,
+        };
+        counts[class] += 1;
+    }
+    let colors = [theme.hex_zero, theme.hex_high, theme.hex_printable, theme.hex_normal];
+    let (class, _) = counts.iter().enumerate().max_by_key(|&(_, c)| c).unwrap_or((3, &0));
+    colors[class]
+}
+
+/// 画面右端のファイル全体の概観（1行が1ブロック、表示中の範囲に印を付ける）
+pub struct Minimap<'a> {
+    /// ブロックごとの色
+    colors: &'a [Color],
+    /// 表示中の範囲のブロック（先頭, 末尾）
+    view: (usize, usize),
+    /// 選択中のブロック（キー操作中のみ）
+    selected: Option<usize>,
+    /// 配色
+    theme: Theme,
+}
+
+impl<'a> Minimap<'a> {
+    pub fn new(colors: &'a [Color]) -> Self {
+        Self {
+            colors,
+            view: (0, 0),
+            selected: None,
+            theme: Theme::DARK,
+        }
+    }
+
+    pub fn view(mut self, first: usize, last: usize) -> Self {
+        self.view = (first, last);
+        self
+    }
+
+    pub fn selected(mut self, selected: Option<usize>) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl Widget for Minimap<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < 2 {
+            return;
+        }
+        for (row, &color) in self.colors.iter().enumerate().take(area.height as usize) {
+            let y = area.y + row as u16;
+            let marker = if self.selected == Some(row) {
+                Some(Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor))
+            } else if (self.view.0..=self.view.1).contains(&row) {
+                Some(Style::default().fg(self.theme.header))
+            } else {
+                None
+            };
+            if let Some(style) = marker {
+                buf.set_string(area.x, y, ">", style);
+            }
+            let strip = " ".repeat(area.width as usize - 1);
+            buf.set_string(area.x + 1, y, &strip, Style::default().bg(color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entropy_and_blocks() {
+        assert_eq!(entropy(&[0; 64]), 0.0);
+        assert_eq!(entropy(&[0, 1, 0, 1]), 1.0);
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(entropy(&all), 8.0);
+
+        assert_eq!(block_range(100, 4, 0), (0, 25));
+        assert_eq!(block_range(100, 4, 3), (75, 100));
+        assert_eq!(block_of(100, 4, 74), 2);
+        assert_eq!(block_of(100, 4, 100), 3);
+    }
+}
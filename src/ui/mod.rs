@@ -1,25 +1,21 @@
 #![allow(dead_code)]
 
+mod help;
 mod hex_view;
+mod minimap;
+mod palette;
+mod panel;
+mod status;
+mod tab_bar;
+mod theme;
 
-pub use hex_view::{HexView, ViewMode};
-
-use ratatui::style::Color;
-
-/// デフォルトカラー設定
-pub struct Colors;
-
-impl Colors {
-    pub const ADDR: Color = Color::Cyan;
-    pub const HEX_NORMAL: Color = Color::White;
-    pub const HEX_ZERO: Color = Color::DarkGray;
-    pub const HEX_HIGH: Color = Color::Red;
-    pub const HEX_PRINTABLE: Color = Color::Green;
-    pub const ASCII_NORMAL: Color = Color::White;
-    pub const ASCII_CONTROL: Color = Color::DarkGray;
-    pub const CURSOR: Color = Color::Black;
-    pub const CURSOR_BG: Color = Color::Yellow;
-    pub const SELECTION_BG: Color = Color::Blue;
-    pub const MODIFIED: Color = Color::Magenta;
-    pub const HEADER: Color = Color::Yellow;
-}
+pub use help::HelpView;
+pub use hex_view::{ByteFormat, HexView, ViewMode, GROUP_SIZES};
+pub use minimap::{
+    block_colors, block_of, block_range, counts_entropy, entropy, Minimap, MinimapMode,
+};
+pub use palette::CommandPalette;
+pub use panel::SidePanel;
+pub use status::{parse_status_format, StatusField, StatusPart, DEFAULT_STATUS_FORMAT};
+pub use tab_bar::TabBar;
+pub use theme::{ByteColorRule, Palette, Theme, ThemeError, BUILTIN_THEMES, BYTE_CLASSES};
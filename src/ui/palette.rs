@@ -0,0 +1,81 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    widgets::{Block, Borders, Clear, Widget},
+};
+
+use super::Theme;
+
+/// 一度に表示する候補の最大数
+const MAX_ROWS: usize = 10;
+
+/// M-x の候補一覧（ステータスバーの直上に重ねて表示）
+pub struct CommandPalette<'a> {
+    /// 候補（コマンド名と説明）
+    items: &'a [(String, &'a str)],
+    /// 選択中の候補
+    selected: usize,
+    /// 配色
+    theme: Theme,
+}
+
+impl<'a> CommandPalette<'a> {
+    pub fn new(items: &'a [(String, &'a str)]) -> Self {
+        Self {
+            items,
+            selected: 0,
+            theme: Theme::DARK,
+        }
+    }
+
+    pub fn selected(mut self, selected: usize) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// 描画に必要な高さ（上の罫線を含む）
+    pub fn height(&self) -> u16 {
+        if self.items.is_empty() {
+            0
+        } else {
+            self.items.len().min(MAX_ROWS) as u16 + 1
+        }
+    }
+}
+
+impl Widget for CommandPalette<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.items.is_empty() || area.height < 2 {
+            return;
+        }
+        Clear.render(area, buf);
+        let block = Block::default()
+            .borders(Borders::TOP)
+            .title(" Commands (Up/Down: select  Tab: complete) ")
+            .title_style(Style::default().fg(self.theme.header));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // 選択中の候補が見えるようにずらす
+        let rows = inner.height as usize;
+        let first = self.selected.saturating_sub(rows.saturating_sub(1));
+        let label_width = self.items.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        for (row, (label, help)) in self.items.iter().skip(first).take(rows).enumerate() {
+            let style = if first + row == self.selected {
+                Style::default().fg(self.theme.cursor).bg(self.theme.cursor_bg)
+            } else {
+                Style::default()
+            };
+            let y = inner.y + row as u16;
+            let line = format!(" {:<width$}  {}", label, help, width = label_width);
+            buf.set_style(Rect { y, height: 1, ..inner }, style);
+            buf.set_stringn(inner.x, y, line, inner.width as usize, style);
+        }
+    }
+}
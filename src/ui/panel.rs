@@ -0,0 +1,70 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Widget},
+};
+
+use super::Theme;
+
+/// 一覧表示用のサイドパネル（選択行をハイライト）
+pub struct SidePanel<'a> {
+    /// タイトル
+    title: &'a str,
+    /// 表示する行
+    items: &'a [String],
+    /// 選択中の行
+    selected: usize,
+    /// 配色
+    theme: Theme,
+}
+
+impl<'a> SidePanel<'a> {
+    pub fn new(title: &'a str, items: &'a [String]) -> Self {
+        Self {
+            title,
+            items,
+            selected: 0,
+            theme: Theme::DARK,
+        }
+    }
+
+    pub fn selected(mut self, selected: usize) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+impl Widget for SidePanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::LEFT)
+            .title(self.title)
+            .title_style(Style::default().fg(self.theme.header).add_modifier(Modifier::BOLD));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height == 0 || self.items.is_empty() {
+            return;
+        }
+
+        // 選択行が見えるようにスクロール
+        let height = inner.height as usize;
+        let scroll = self.selected.saturating_sub(height - 1);
+
+        for (row, item) in self.items.iter().skip(scroll).take(height).enumerate() {
+            let style = if scroll + row == self.selected {
+                Style::default().bg(self.theme.cursor_bg).fg(self.theme.cursor)
+            } else {
+                Style::default()
+            };
+            let y = inner.y + row as u16;
+            buf.set_stringn(inner.x, y, item, inner.width as usize, style);
+        }
+    }
+}
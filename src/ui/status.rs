@@ -0,0 +1,109 @@
+//! ステータスバーの書式（設定の status_format）
+
+/// ステータスバーの既定の書式
+pub const DEFAULT_STATUS_FORMAT: &str = " %f%m | %o/%len (%pct%) | %mode %edit | %enc%cmp";
+
+/// 書式の中の項目（%名前）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusField {
+    /// ファイル名
+    File,
+    /// 変更あり [+] / 読み取り専用 [RO]
+    Modified,
+    /// カーソル位置
+    Offset,
+    /// ファイルの長さ
+    Length,
+    /// カーソル位置の割合
+    Percent,
+    /// 表示形式 (HEX / OCT / BIN / ASC)
+    Mode,
+    /// 入力モード (OVR / INS)
+    Edit,
+    /// 文字エンコーディング
+    Encoding,
+    /// 比較中の相手 (" | vs 名前")
+    Compare,
+    /// カーソル位置のバイト値
+    Value,
+    /// 選択範囲のバイト数（選択していなければ空）
+    Selection,
+}
+
+impl StatusField {
+    /// 書式での名前（長い名前を先に調べるため、前方が重なるものは長い順）
+    const NAMES: &[(&str, Self)] = &[
+        ("len", Self::Length),
+        ("pct", Self::Percent),
+        ("mode", Self::Mode),
+        ("edit", Self::Edit),
+        ("enc", Self::Encoding),
+        ("cmp", Self::Compare),
+        ("val", Self::Value),
+        ("sel", Self::Selection),
+        ("f", Self::File),
+        ("m", Self::Modified),
+        ("o", Self::Offset),
+    ];
+}
+
+/// 書式を分解した要素
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusPart {
+    Text(String),
+    Field(StatusField),
+}
+
+/// 書式を分解する（%% は %、知らない %名前 はそのまま表示）
+pub fn parse_status_format(format: &str) -> Vec<StatusPart> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = format;
+    while let Some(pos) = rest.find('%') {
+        text.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+        if let Some(after) = rest.strip_prefix('%') {
+            text.push('%');
+            rest = after;
+            continue;
+        }
+        match StatusField::NAMES.iter().find(|(name, _)| rest.starts_with(name)) {
+            Some(&(name, field)) => {
+                if !text.is_empty() {
+                    parts.push(StatusPart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(StatusPart::Field(field));
+                rest = &rest[name.len()..];
+            }
+            None => text.push('%'),
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(StatusPart::Text(text));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status_format() {
+        use StatusField::*;
+        let text = |s: &str| StatusPart::Text(s.to_string());
+        assert_eq!(
+            parse_status_format("%f%m [%len] 100%% %x %val"),
+            vec![
+                StatusPart::Field(File),
+                StatusPart::Field(Modified),
+                text(" ["),
+                StatusPart::Field(Length),
+                text("] 100% %x "),
+                StatusPart::Field(Value),
+            ]
+        );
+        assert!(parse_status_format(DEFAULT_STATUS_FORMAT).contains(&StatusPart::Field(Percent)));
+    }
+}
@@ -0,0 +1,110 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Span,
+    widgets::Widget,
+};
+
+use super::Theme;
+
+/// 開いているバッファのタブ（画面の一番上の行）
+pub struct TabBar<'a> {
+    /// タブの名前（変更ありの印を含む）
+    titles: &'a [String],
+    /// アクティブなタブ
+    selected: usize,
+    /// 配色
+    theme: Theme,
+}
+
+impl<'a> TabBar<'a> {
+    pub fn new(titles: &'a [String]) -> Self {
+        Self {
+            titles,
+            selected: 0,
+            theme: Theme::DARK,
+        }
+    }
+
+    pub fn selected(mut self, selected: usize) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// 幅 width に表示するタブ（番号, 左端からの位置, 幅）
+    /// 収まらなければアクティブなタブが見えるよう左のタブを省く
+    fn layout(&self, width: u16) -> Vec<(usize, u16, u16)> {
+        let widths: Vec<u16> =
+            self.titles.iter().map(|title| Span::raw(title).width() as u16 + 2).collect();
+        let mut first = 0;
+        while first < self.selected
+            && widths[first..=self.selected].iter().sum::<u16>() > width
+        {
+            first += 1;
+        }
+        let mut x = 0;
+        let mut tabs = Vec::new();
+        for (i, &w) in widths.iter().enumerate().skip(first) {
+            if x >= width {
+                break;
+            }
+            tabs.push((i, x, w.min(width - x)));
+            x += w;
+        }
+        tabs
+    }
+
+    /// 幅 width のタブバーの x 桁目にあるタブ
+    pub fn tab_at(&self, width: u16, x: u16) -> Option<usize> {
+        self.layout(width)
+            .into_iter()
+            .find(|&(_, start, w)| (start..start + w).contains(&x))
+            .map(|(i, _, _)| i)
+    }
+}
+
+impl Widget for TabBar<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let base = Style::default().bg(self.theme.status_bg).fg(self.theme.status);
+        buf.set_style(area, base);
+        for (i, x, width) in self.layout(area.width) {
+            let style = if i == self.selected {
+                Style::default()
+                    .bg(self.theme.cursor_bg)
+                    .fg(self.theme.cursor)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                base
+            };
+            let label = format!(" {} ", self.titles[i]);
+            buf.set_stringn(area.x + x, area.y, label, width as usize, style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tab_at() {
+        let titles = ["a.bin".to_string(), "b.bin[+]".to_string(), "c".to_string()];
+        // " a.bin " は 0..7、" b.bin[+] " は 7..17、" c " は 17..20
+        let bar = TabBar::new(&titles);
+        assert_eq!(bar.tab_at(80, 0), Some(0));
+        assert_eq!(bar.tab_at(80, 7), Some(1));
+        assert_eq!(bar.tab_at(80, 19), Some(2));
+        assert_eq!(bar.tab_at(80, 20), None);
+
+        // 狭ければアクティブなタブが見えるよう左を省く
+        let bar = TabBar::new(&titles).selected(2);
+        assert_eq!(bar.tab_at(14, 0), Some(1));
+        assert_eq!(bar.tab_at(14, 10), Some(2));
+    }
+}
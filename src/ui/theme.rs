@@ -0,0 +1,325 @@
+//! 配色テーマ（組み込みテーマと設定ファイルで定義するパレット）
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ThemeError {
+    #[error("Unknown theme: {0}")]
+    Unknown(String),
+    #[error("Invalid color for {0}: {1}")]
+    InvalidColor(String, String),
+    #[error("Unknown byte class: {0}")]
+    UnknownClass(String),
+    #[error("Invalid byte range: {0}")]
+    InvalidBytes(String),
+}
+
+/// 組み込みテーマの名前
+pub const BUILTIN_THEMES: &[&str] = &["dark", "light", "solarized"];
+
+/// バイトの分類の判定
+type ByteClass = fn(u8) -> bool;
+
+/// バイトの分類の名前と判定
+pub const BYTE_CLASSES: &[(&str, ByteClass)] = &[
+    ("zero", |b| b == 0x00),
+    ("ff", |b| b == 0xFF),
+    ("printable", |b| matches!(b, 0x20..=0x7E)),
+    ("control", |b| matches!(b, 0x01..=0x1F | 0x7F)),
+    ("whitespace", |b| matches!(b, 0x09..=0x0D | 0x20)),
+    ("high", |b| b >= 0x80),
+    ("utf8-lead", |b| matches!(b, 0xC2..=0xF4)),
+    ("utf8-cont", |b| matches!(b, 0x80..=0xBF)),
+];
+
+/// 設定ファイルの [[byte_colors]]：分類やバイト値ごとの HEX の色（先に書いたものが優先）
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ByteColorRule {
+    /// 分類の名前（BYTE_CLASSES）
+    pub class: Option<String>,
+    /// バイト値（16進、"01-08 0E-1F 7F" のように範囲と値を空白で区切る）
+    pub bytes: Option<String>,
+    pub color: String,
+}
+
+impl ByteColorRule {
+    /// 対象のバイトの一覧（256個の真偽値）
+    fn matches(&self) -> Result<[bool; 256], ThemeError> {
+        let mut matches = [false; 256];
+        if let Some(ref name) = self.class {
+            let &(_, contains) = BYTE_CLASSES
+                .iter()
+                .find(|(class, _)| class == name)
+                .ok_or_else(|| ThemeError::UnknownClass(name.clone()))?;
+            for (byte, matched) in matches.iter_mut().enumerate() {
+                *matched |= contains(byte as u8);
+            }
+        }
+        for range in self.bytes.iter().flat_map(|bytes| bytes.split_whitespace()) {
+            let invalid = || ThemeError::InvalidBytes(range.to_string());
+            let parse = |s: &str| {
+                let s = s.strip_prefix("0x").unwrap_or(s);
+                u8::from_str_radix(s, 16).map_err(|_| invalid())
+            };
+            let (start, end) = match range.split_once('-') {
+                Some((start, end)) => (parse(start)?, parse(end)?),
+                None => (parse(range)?, parse(range)?),
+            };
+            if start > end {
+                return Err(invalid());
+            }
+            matches[start as usize..=end as usize].fill(true);
+        }
+        Ok(matches)
+    }
+}
+
+/// 配色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub addr: Color,
+    pub hex_normal: Color,
+    pub hex_zero: Color,
+    pub hex_high: Color,
+    pub hex_printable: Color,
+    pub ascii_normal: Color,
+    pub ascii_control: Color,
+    pub cursor: Color,
+    pub cursor_bg: Color,
+    pub selection_bg: Color,
+    pub modified: Color,
+    pub diff: Color,
+    pub diff_bg: Color,
+    pub bookmark: Color,
+    pub header: Color,
+    pub status: Color,
+    pub status_bg: Color,
+}
+
+impl Theme {
+    /// 暗い背景の端末向け（デフォルト）
+    pub const DARK: Self = Self {
+        addr: Color::Cyan,
+        hex_normal: Color::White,
+        hex_zero: Color::DarkGray,
+        hex_high: Color::Red,
+        hex_printable: Color::Green,
+        ascii_normal: Color::White,
+        ascii_control: Color::DarkGray,
+        cursor: Color::Black,
+        cursor_bg: Color::Yellow,
+        selection_bg: Color::Blue,
+        modified: Color::Magenta,
+        diff: Color::White,
+        diff_bg: Color::Red,
+        bookmark: Color::LightGreen,
+        header: Color::Yellow,
+        status: Color::White,
+        status_bg: Color::DarkGray,
+    };
+
+    /// 明るい背景の端末向け
+    pub const LIGHT: Self = Self {
+        addr: Color::Blue,
+        hex_normal: Color::Black,
+        hex_zero: Color::Gray,
+        hex_high: Color::Red,
+        hex_printable: Color::Green,
+        ascii_normal: Color::Black,
+        ascii_control: Color::Gray,
+        cursor: Color::White,
+        cursor_bg: Color::Blue,
+        selection_bg: Color::LightCyan,
+        modified: Color::Magenta,
+        diff: Color::White,
+        diff_bg: Color::Red,
+        bookmark: Color::Green,
+        header: Color::Blue,
+        status: Color::Black,
+        status_bg: Color::Gray,
+    };
+
+    /// Solarized (dark)
+    pub const SOLARIZED: Self = Self {
+        addr: Color::Rgb(0x2a, 0xa1, 0x98),
+        hex_normal: Color::Rgb(0x83, 0x94, 0x96),
+        hex_zero: Color::Rgb(0x58, 0x6e, 0x75),
+        hex_high: Color::Rgb(0xdc, 0x32, 0x2f),
+        hex_printable: Color::Rgb(0x85, 0x99, 0x00),
+        ascii_normal: Color::Rgb(0x83, 0x94, 0x96),
+        ascii_control: Color::Rgb(0x58, 0x6e, 0x75),
+        cursor: Color::Rgb(0x00, 0x2b, 0x36),
+        cursor_bg: Color::Rgb(0xb5, 0x89, 0x00),
+        selection_bg: Color::Rgb(0x07, 0x36, 0x42),
+        modified: Color::Rgb(0xd3, 0x36, 0x82),
+        diff: Color::Rgb(0xfd, 0xf6, 0xe3),
+        diff_bg: Color::Rgb(0xcb, 0x4b, 0x16),
+        bookmark: Color::Rgb(0x6c, 0x71, 0xc4),
+        header: Color::Rgb(0x26, 0x8b, 0xd2),
+        status: Color::Rgb(0x93, 0xa1, 0xa1),
+        status_bg: Color::Rgb(0x07, 0x36, 0x42),
+    };
+
+    /// 組み込みテーマを名前で取得
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::DARK),
+            "light" => Some(Self::LIGHT),
+            "solarized" => Some(Self::SOLARIZED),
+            _ => None,
+        }
+    }
+
+    /// HEX 列のバイト値ごとの色（規則に当てはまらないバイトはテーマの色）
+    pub fn byte_colors(&self, rules: &[ByteColorRule]) -> Result<[Color; 256], ThemeError> {
+        let mut colors = [self.hex_normal; 256];
+        colors[0x20..=0x7E].fill(self.hex_printable);
+        colors[0x00] = self.hex_zero;
+        colors[0xFF] = self.hex_high;
+        // 先に書いた規則が優先されるよう後ろから塗る
+        for rule in rules.iter().rev() {
+            let color = Color::from_str(&rule.color).map_err(|_| {
+                ThemeError::InvalidColor("byte_colors".to_string(), rule.color.clone())
+            })?;
+            for (byte, matched) in rule.matches()?.into_iter().enumerate() {
+                if matched {
+                    colors[byte] = color;
+                }
+            }
+        }
+        Ok(colors)
+    }
+
+    /// 名前からテーマを作る（設定ファイルのパレットを組み込みテーマより優先）
+    pub fn resolve(name: &str, palettes: &BTreeMap<String, Palette>) -> Result<Self, ThemeError> {
+        match palettes.get(name) {
+            Some(palette) => palette.to_theme(),
+            None => Self::builtin(name).ok_or_else(|| ThemeError::Unknown(name.to_string())),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+/// パレットの定義（色のフィールドごとに Option<String> を持つ）
+macro_rules! palette {
+    ($($field:ident),* $(,)?) => {
+        /// 設定ファイルの [themes.名前] で定義する配色
+        /// 指定しなかった色は base の組み込みテーマ（省略時は dark）を引き継ぐ
+        #[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        pub struct Palette {
+            pub base: Option<String>,
+            $(pub $field: Option<String>,)*
+        }
+
+        impl Palette {
+            /// テーマに変換
+            pub fn to_theme(&self) -> Result<Theme, ThemeError> {
+                let base = self.base.as_deref().unwrap_or("dark");
+                let mut theme =
+                    Theme::builtin(base).ok_or_else(|| ThemeError::Unknown(base.to_string()))?;
+                $(
+                    if let Some(ref value) = self.$field {
+                        theme.$field = Color::from_str(value).map_err(|_| {
+                            ThemeError::InvalidColor(stringify!($field).to_string(), value.clone())
+                        })?;
+                    }
+                )*
+                Ok(theme)
+            }
+        }
+    };
+}
+
+palette!(
+    addr,
+    hex_normal,
+    hex_zero,
+    hex_high,
+    hex_printable,
+    ascii_normal,
+    ascii_control,
+    cursor,
+    cursor_bg,
+    selection_bg,
+    modified,
+    diff,
+    diff_bg,
+    bookmark,
+    header,
+    status,
+    status_bg,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_overrides_base() {
+        let palette = Palette {
+            base: Some("light".to_string()),
+            addr: Some("#ff8000".to_string()),
+            cursor_bg: Some("magenta".to_string()),
+            ..Default::default()
+        };
+        let mut palettes = BTreeMap::new();
+        palettes.insert("mine".to_string(), palette);
+
+        let theme = Theme::resolve("mine", &palettes).unwrap();
+        assert_eq!(theme.addr, Color::Rgb(0xff, 0x80, 0x00));
+        assert_eq!(theme.cursor_bg, Color::Magenta);
+        assert_eq!(theme.hex_normal, Theme::LIGHT.hex_normal);
+
+        assert_eq!(Theme::resolve("solarized", &palettes).unwrap(), Theme::SOLARIZED);
+        assert!(matches!(Theme::resolve("none", &palettes), Err(ThemeError::Unknown(_))));
+
+        palettes.get_mut("mine").unwrap().header = Some("not-a-color".to_string());
+        assert!(matches!(
+            Theme::resolve("mine", &palettes),
+            Err(ThemeError::InvalidColor(..))
+        ));
+    }
+
+    #[test]
+    fn test_byte_colors() {
+        let rule = |class: Option<&str>, bytes: Option<&str>, color: &str| ByteColorRule {
+            class: class.map(str::to_string),
+            bytes: bytes.map(str::to_string),
+            color: color.to_string(),
+        };
+        let colors = Theme::DARK.byte_colors(&[]).unwrap();
+        assert_eq!(colors[0x00], Theme::DARK.hex_zero);
+        assert_eq!(colors[b'A' as usize], Theme::DARK.hex_printable);
+        assert_eq!(colors[0x80], Theme::DARK.hex_normal);
+
+        let rules = [
+            rule(None, Some("0A 0x7F-7f"), "blue"),
+            rule(Some("control"), None, "yellow"),
+            rule(Some("utf8-lead"), Some("80"), "magenta"),
+        ];
+        let colors = Theme::DARK.byte_colors(&rules).unwrap();
+        assert_eq!(colors[0x0A], Color::Blue);
+        assert_eq!(colors[0x7F], Color::Blue);
+        assert_eq!(colors[0x01], Color::Yellow);
+        assert_eq!(colors[0xC3], Color::Magenta);
+        assert_eq!(colors[0x80], Color::Magenta);
+        assert_eq!(colors[0x00], Theme::DARK.hex_zero);
+
+        let colors = Theme::DARK.byte_colors(&[rule(Some("utf8"), None, "red")]);
+        assert_eq!(colors, Err(ThemeError::UnknownClass("utf8".to_string())));
+        let colors = Theme::DARK.byte_colors(&[rule(None, Some("20-10"), "red")]);
+        assert_eq!(colors, Err(ThemeError::InvalidBytes("20-10".to_string())));
+    }
+}